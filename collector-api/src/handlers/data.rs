@@ -1,4 +1,4 @@
-use collector_core::core::point::{PointId, Val};
+use collector_core::core::point::{DataPoint, PointId, Val};
 use salvo::{Depot, Request, handler};
 use validator::Validate;
 
@@ -29,3 +29,51 @@ pub async fn set(req: &mut Request, depot: &mut Depot) -> ApiResult<ObjResponse<
     service.set(depot, params).await?;
     Ok(ObjResponse::ok(()))
 }
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Validate)]
+pub struct RefreshParams {
+    #[validate(length(min = 1, message = "设备ID不能为空"))]
+    pub dev_id: String,
+    #[serde(default)]
+    pub point_ids: Vec<PointId>,
+    #[serde(default)]
+    pub point_keys: Vec<String>,
+}
+
+/// 按需读取到的点位
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RefreshedPoint {
+    pub id: PointId,
+    pub key: &'static str,
+    pub name: &'static str,
+    pub value: Val,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<&'static str>,
+}
+
+impl From<DataPoint> for RefreshedPoint {
+    fn from(point: DataPoint) -> Self {
+        Self {
+            id: point.id,
+            key: point.key,
+            name: point.name,
+            value: point.value,
+            unit: point.unit,
+        }
+    }
+}
+
+/// 立即读取设备上的指定点位并返回最新值，用于控制前刷新
+#[handler]
+pub async fn refresh(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> ApiResult<ObjResponse<Vec<RefreshedPoint>>> {
+    let params = req.parse_json::<RefreshParams>().await?;
+    params.validate()?;
+    let service = DataService::new()?;
+    let points = service.refresh(depot, params).await?;
+    Ok(ObjResponse::ok(
+        points.into_iter().map(RefreshedPoint::from).collect(),
+    ))
+}
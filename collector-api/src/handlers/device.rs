@@ -0,0 +1,90 @@
+use collector_core::config::reload::{self, ConfigDiff};
+use collector_core::config::revision::{self, RegisterFileRevision};
+use collector_core::dev::diagnostics::{self as diag, DiagnosticsSnapshot};
+use collector_core::dev::identity::{self, DeviceIdentity};
+use collector_core::dev::maintenance::{Maintenance, MaintenanceOutput};
+use salvo::{Depot, Request, handler};
+use validator::Validate;
+
+use crate::{
+    core::{
+        ApiResult,
+        response::{ListResponse, ObjResponse},
+    },
+    services::device::DeviceService,
+};
+
+/// 已加载点位表的摘要与版本，可用 `dev_id` 只查询单个设备
+#[handler]
+pub async fn revisions(req: &mut Request) -> ApiResult<ListResponse<RegisterFileRevision>> {
+    let list = match req.query::<String>("dev_id") {
+        Some(dev_id) => revision::device_revisions(&dev_id),
+        None => revision::revisions(),
+    };
+    let total = list.len();
+    Ok(ListResponse::ok(list, total))
+}
+
+/// 最近一次配置热更新新增、删除、重建的设备，尚未热更新时为 `null`
+#[handler]
+pub async fn last_reload() -> ApiResult<ObjResponse<Option<ConfigDiff>>> {
+    Ok(ObjResponse::ok(reload::last_diff()))
+}
+
+/// 设备连接后读到的厂商、产品代码与版本，可用 `dev_id` 只查询单个设备
+#[handler]
+pub async fn identities(req: &mut Request) -> ApiResult<ListResponse<DeviceIdentity>> {
+    let list = match req.query::<String>("dev_id") {
+        Some(dev_id) => identity::device_identity(&dev_id).into_iter().collect(),
+        None => identity::identities(),
+    };
+    let total = list.len();
+    Ok(ListResponse::ok(list, total))
+}
+
+/// 设备的通讯诊断计数，可用 `dev_id` 只查询单个设备
+#[handler]
+pub async fn diagnostics(req: &mut Request) -> ApiResult<ListResponse<DiagnosticsSnapshot>> {
+    let list = match req.query::<String>("dev_id") {
+        Some(dev_id) => diag::device_diagnostics(&dev_id).into_iter().collect(),
+        None => diag::diagnostics(),
+    };
+    let total = list.len();
+    Ok(ListResponse::ok(list, total))
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Validate)]
+pub struct MaintainParams {
+    #[validate(length(min = 1, message = "设备ID不能为空"))]
+    pub dev_id: String,
+    pub op: Maintenance,
+}
+
+/// 对设备执行维护操作：立即重连、清零诊断计数、立即轮询或读取原始寄存器
+#[handler]
+pub async fn maintain(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> ApiResult<ObjResponse<MaintenanceOutput>> {
+    let params = req.parse_json::<MaintainParams>().await?;
+    params.validate()?;
+    let service = DeviceService::new()?;
+    let output = service.maintain(depot, params).await?;
+    Ok(ObjResponse::ok(output))
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Validate)]
+pub struct RestartParams {
+    #[validate(length(min = 1, message = "设备ID不能为空"))]
+    pub dev_id: String,
+}
+
+/// 重启设备，按当前配置重建后重新连接
+#[handler]
+pub async fn restart(req: &mut Request, depot: &mut Depot) -> ApiResult<ObjResponse<()>> {
+    let params = req.parse_json::<RestartParams>().await?;
+    params.validate()?;
+    let service = DeviceService::new()?;
+    service.restart(depot, params).await?;
+    Ok(ObjResponse::ok(()))
+}
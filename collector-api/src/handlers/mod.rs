@@ -2,6 +2,7 @@ use salvo::Request;
 use serde::Deserialize;
 
 pub(crate) mod data;
+pub(crate) mod device;
 #[cfg(target_os = "linux")]
 pub(crate) mod network;
 pub(crate) mod planned_curve;
@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use collector_core::{
     center::SharedPointCenter,
-    core::point::{DataPoint, Val, Words},
+    core::point::{DataPoint, Quality, Val, Words},
 };
 use salvo::{
     Depot, Request, Response, handler,
@@ -58,6 +58,8 @@ struct Point<'a> {
     words: Option<&'static Words>,
     #[serde(skip_serializing_if = "Option::is_none")]
     unit: Option<&'static str>,
+    #[serde(skip_serializing_if = "Quality::is_good")]
+    quality: Quality,
 }
 
 impl<'a> Point<'a> {
@@ -73,6 +75,7 @@ impl<'a> Point<'a> {
             value: &data_point.value,
             words: data_point.words,
             unit: data_point.unit,
+            quality: data_point.quality,
         }
     }
 }
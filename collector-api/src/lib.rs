@@ -1,5 +1,10 @@
-use collector_core::{center::SharedPointCenter, shutdown::ShutdownManager};
+use std::sync::Arc;
+
+use collector_core::{
+    center::SharedPointCenter, dev::manager::DevManager, shutdown::ShutdownManager,
+};
 use salvo::{Listener, Server, conn::TcpListener};
+use tokio::sync::Mutex;
 use tracing::info;
 
 use crate::routes::root_router;
@@ -16,11 +21,22 @@ pub struct ApiApp {
     ip: String,
     port: u16,
     center: SharedPointCenter,
+    manager: Arc<Mutex<DevManager>>,
 }
 
 impl ApiApp {
-    pub fn new(ip: String, port: u16, center: SharedPointCenter) -> Self {
-        Self { ip, port, center }
+    pub fn new(
+        ip: String,
+        port: u16,
+        center: SharedPointCenter,
+        manager: Arc<Mutex<DevManager>>,
+    ) -> Self {
+        Self {
+            ip,
+            port,
+            center,
+            manager,
+        }
     }
 
     pub async fn start(self, shutdown: ShutdownManager) {
@@ -37,7 +53,7 @@ impl ApiApp {
             shutdown_handle.stop_graceful(None);
         });
 
-        server.serve(root_router(self.center)).await;
+        server.serve(root_router(self.center, self.manager)).await;
         info!("API 服务器已关闭");
     }
 }
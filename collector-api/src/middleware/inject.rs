@@ -1,5 +1,9 @@
+use std::sync::Arc;
+
 use collector_core::center::SharedPointCenter;
+use collector_core::dev::manager::DevManager;
 use salvo::{Depot, FlowCtrl, Handler, Request, Response, async_trait};
+use tokio::sync::Mutex;
 
 #[derive(Clone)]
 pub struct InjectCenter {
@@ -24,3 +28,27 @@ impl Handler for InjectCenter {
         depot.insert("center", self.center.clone());
     }
 }
+
+#[derive(Clone)]
+pub struct InjectManager {
+    manager: Arc<Mutex<DevManager>>,
+}
+
+impl InjectManager {
+    pub fn new(manager: Arc<Mutex<DevManager>>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl Handler for InjectManager {
+    async fn handle(
+        &self,
+        _req: &mut Request,
+        depot: &mut Depot,
+        _res: &mut Response,
+        _ctrl: &mut FlowCtrl,
+    ) {
+        depot.insert("manager", self.manager.clone());
+    }
+}
@@ -7,4 +7,5 @@ pub(crate) fn router() -> Router {
     Router::with_path("data")
         .hoop(auth_handler())
         .push(Router::with_path("set").post(handlers::data::set))
+        .push(Router::with_path("refresh").post(handlers::data::refresh))
 }
@@ -0,0 +1,15 @@
+use salvo::Router;
+
+use crate::{handlers, middleware::auth::auth_handler};
+
+/// 设备相关api
+pub(crate) fn router() -> Router {
+    Router::with_path("device")
+        .hoop(auth_handler())
+        .push(Router::with_path("revisions").get(handlers::device::revisions))
+        .push(Router::with_path("reload").get(handlers::device::last_reload))
+        .push(Router::with_path("identity").get(handlers::device::identities))
+        .push(Router::with_path("diagnostics").get(handlers::device::diagnostics))
+        .push(Router::with_path("maintain").post(handlers::device::maintain))
+        .push(Router::with_path("restart").post(handlers::device::restart))
+}
@@ -1,20 +1,27 @@
 mod data;
+mod device;
 #[cfg(target_os = "linux")]
 mod network;
 mod planned_curve;
 mod user;
 mod ws;
 
-use crate::middleware::inject::InjectCenter;
+use std::sync::Arc;
+
+use crate::middleware::inject::{InjectCenter, InjectManager};
 use collector_core::center::SharedPointCenter;
+use collector_core::dev::manager::DevManager;
 use salvo::Router;
+use tokio::sync::Mutex;
 
-pub(crate) fn root_router(center: SharedPointCenter) -> Router {
+pub(crate) fn root_router(center: SharedPointCenter, manager: Arc<Mutex<DevManager>>) -> Router {
     let v1 = Router::new()
         .hoop(InjectCenter::new(center))
+        .hoop(InjectManager::new(manager))
         .path("v1")
         .push(user::router())
         .push(data::router())
+        .push(device::router())
         .push(planned_curve::router())
         .push(ws::router());
     #[cfg(target_os = "linux")]
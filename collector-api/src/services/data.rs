@@ -1,11 +1,17 @@
+use std::time::Duration;
+
+use collector_core::core::point::{DataPoint, PointRef};
 use collector_core::down;
 use salvo::Depot;
 
 use crate::{
-    handlers::data::RequestDataParams,
+    handlers::data::{RefreshParams, RequestDataParams},
     services::{Service, ServiceError, ServiceResult},
 };
 
+/// 等待设备回报按需读取结果的最长时间，设备断线时命令会一直排队
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct DataService {}
 
 impl Service for DataService {}
@@ -53,4 +59,33 @@ impl DataService {
         }
         Ok(())
     }
+
+    pub async fn refresh(
+        &self,
+        depot: &mut Depot,
+        params: RefreshParams,
+    ) -> ServiceResult<Vec<DataPoint>> {
+        let points: Vec<PointRef> = params
+            .point_ids
+            .into_iter()
+            .map(PointRef::Id)
+            .chain(params.point_keys.into_iter().map(PointRef::Key))
+            .collect();
+        if points.is_empty() {
+            return Err(ServiceError::InvalidParameter(
+                "point_ids和point_keys不能同时为空".to_string(),
+            ));
+        }
+        let center = self.center(depot)?;
+        if !center.has_downlink(&params.dev_id) {
+            return Err(ServiceError::InvalidParameter(format!(
+                "设备ID {} 不存在",
+                params.dev_id
+            )));
+        }
+        tokio::time::timeout(REFRESH_TIMEOUT, center.refresh(&params.dev_id, points))
+            .await
+            .map_err(|_| ServiceError::InternalError("等待设备读取超时".to_string()))?
+            .map_err(|e| ServiceError::InternalError(e.to_string()))
+    }
 }
@@ -0,0 +1,48 @@
+use collector_core::dev::DeviceError;
+use collector_core::dev::maintenance::MaintenanceOutput;
+use salvo::Depot;
+
+use crate::{
+    handlers::device::{MaintainParams, RestartParams},
+    services::{Service, ServiceError, ServiceResult},
+};
+
+pub struct DeviceService {}
+
+impl Service for DeviceService {}
+
+impl DeviceService {
+    pub fn new() -> ServiceResult<Self> {
+        Ok(Self {})
+    }
+
+    pub async fn maintain(
+        &self,
+        depot: &mut Depot,
+        params: MaintainParams,
+    ) -> ServiceResult<MaintenanceOutput> {
+        let manager = self.manager(depot)?;
+        tracing::info!("维护设备 {}: {:?}", params.dev_id, params.op);
+        // 只在取得句柄时持有设备管理器的锁，等待设备应答期间不阻塞其他请求
+        let handle = manager.lock().await.get(&params.dev_id);
+        let handle = handle.ok_or_else(|| device_error(DeviceError::NotFound(params.dev_id)))?;
+        handle.maintain(params.op).await.map_err(device_error)
+    }
+
+    pub async fn restart(&self, depot: &mut Depot, params: RestartParams) -> ServiceResult<()> {
+        let manager = self.manager(depot)?;
+        tracing::info!("重启设备 {}", params.dev_id);
+        let mut manager = manager.lock().await;
+        manager
+            .restart_device(&params.dev_id)
+            .await
+            .map_err(device_error)
+    }
+}
+
+fn device_error(err: DeviceError) -> ServiceError {
+    match err {
+        DeviceError::NotFound(_) => ServiceError::NotFound(err.to_string()),
+        err => ServiceError::BusinessLogic(err.to_string()),
+    }
+}
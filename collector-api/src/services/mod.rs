@@ -1,14 +1,19 @@
 pub mod data;
+pub mod device;
 pub mod error;
 #[cfg(target_os = "linux")]
 pub mod network;
 pub mod planned_curve;
 pub mod user;
 
+use std::sync::Arc;
+
 use collector_core::center::SharedPointCenter;
+use collector_core::dev::manager::DevManager;
 // Service 层使用独立的错误类型
 pub use error::{ServiceError, ServiceResult};
 use salvo::Depot;
+use tokio::sync::Mutex;
 
 trait Service {
     fn center(&self, depot: &mut Depot) -> ServiceResult<SharedPointCenter> {
@@ -18,4 +23,12 @@ trait Service {
             .clone();
         Ok(center)
     }
+
+    fn manager(&self, depot: &mut Depot) -> ServiceResult<Arc<Mutex<DevManager>>> {
+        let manager = depot
+            .get::<Arc<Mutex<DevManager>>>("manager")
+            .map_err(|_| ServiceError::InternalError(String::from("DevManager not found")))?
+            .clone();
+        Ok(manager)
+    }
 }
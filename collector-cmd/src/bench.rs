@@ -0,0 +1,148 @@
+//! `collector bench`：对设备或按点位表启动的模拟器连续轮询，输出轮询速率、请求往返时间分布与解码吞吐
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::Args;
+use collector_core::config::modbus_conf::ByteOrder;
+use collector_core::config::{ComType, ConfigFormat, Device};
+use collector_core::dev::bench::{self, BenchReport};
+use collector_core::dev::capture::Recorder;
+use collector_core::dev::oneshot::Session;
+use collector_core::dev::simulator::{Simulator, SimulatorOptions};
+use collector_core::shutdown::ShutdownManager;
+use tokio::net::TcpListener;
+
+use crate::link;
+
+#[derive(Args, Debug)]
+pub(crate) struct BenchArgs {
+    /// 设备的键或 ID
+    #[arg(long)]
+    device: String,
+    /// 轮询时长(s)
+    #[arg(long, default_value_t = 10)]
+    duration: u64,
+    /// 不连接设备，按设备的点位表在本机启动模拟器并对其轮询
+    #[arg(long)]
+    simulate: bool,
+}
+
+/// 按设备的点位表在本机随机端口启动模拟器，设备改为连接模拟器
+async fn simulate(dev: &mut Device, shutdown: &ShutdownManager) -> Result<(), String> {
+    let config = &mut dev.config;
+    let register_file = config.register_file.clone().ok_or("设备没有配置点位表")?;
+    let byte_order = config
+        .byte_order
+        .as_deref()
+        .map(|order| ByteOrder::try_from(Some(order)).map_err(|_| format!("无效的字节序 {order}")))
+        .transpose()?;
+    let simulator = Simulator::load(
+        &register_file,
+        config.address_base.unwrap_or(0),
+        byte_order,
+        SimulatorOptions::default(),
+    )
+    .await
+    .map_err(|err| err.to_string())?;
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|err| format!("启动模拟器失败: {err}"))?;
+    let port = listener
+        .local_addr()
+        .map_err(|err| format!("启动模拟器失败: {err}"))?
+        .port();
+    tokio::spawn(simulator.serve(listener, shutdown.token()));
+    config.com_type = Some(ComType::ModbusTCP);
+    config.ip = Some("127.0.0.1".to_owned());
+    config.port = Some(port);
+    config.tls = None;
+    config.backup_ip = None;
+    Ok(())
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn print_report(report: &BenchReport, interval: Duration) {
+    println!(
+        "轮询: {}圈, {:.1}圈/s; {}个请求, {:.1}个/s, 其中{}个失败",
+        report.cycles,
+        report.cycles_per_sec(),
+        report.requests,
+        report.requests_per_sec(),
+        report.errors
+    );
+    match &report.latency {
+        Some(latency) => println!(
+            "往返时间: 最小 {}, 平均 {}, p50 {}, p90 {}, p99 {}, 最大 {}",
+            millis(latency.min),
+            millis(latency.mean),
+            millis(latency.p50),
+            millis(latency.p90),
+            millis(latency.p99),
+            millis(latency.max)
+        ),
+        None => println!("往返时间: 没有成功的请求"),
+    }
+    if report.decoded > 0 {
+        println!(
+            "解码: 每圈{}个点位, {:.0}个点位/s",
+            report.points_per_cycle,
+            report.decode_per_sec()
+        );
+    }
+    // 一圈的用时占轮询周期的比例，接近或超过 100% 时按配置的周期已轮询不过来
+    if report.cycles > 0 && !interval.is_zero() {
+        let cycle = report.elapsed / report.cycles as u32;
+        println!(
+            "每圈用时 {}, 占轮询周期 {}ms 的 {:.1}%",
+            millis(cycle),
+            interval.as_millis(),
+            cycle.as_secs_f64() / interval.as_secs_f64() * 100.0
+        );
+    }
+}
+
+pub(crate) async fn bench(path: &str, format: Option<ConfigFormat>, args: BenchArgs) -> ExitCode {
+    let mut dev = match link::load_devices(path, format, std::slice::from_ref(&args.device)).await {
+        Ok(mut devices) => devices.remove(0),
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let shutdown = ShutdownManager::new();
+    if args.simulate
+        && let Err(err) = simulate(&mut dev, &shutdown).await
+    {
+        eprintln!("{err}");
+        return ExitCode::FAILURE;
+    }
+    let recorder = match Session::connect(dev).await {
+        Ok(session) => Recorder::new(session).map_err(|err| err.to_string()),
+        Err(err) => Err(format!("连接设备{}失败: {}", args.device, err)),
+    };
+    let mut recorder = match recorder {
+        Ok(recorder) => recorder,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "设备{}: 每圈{}个请求, 连续轮询{}s...",
+        recorder.id(),
+        recorder.request_count(),
+        args.duration
+    );
+    let report = bench::run(&mut recorder, Duration::from_secs(args.duration)).await;
+    print_report(&report, recorder.interval());
+    shutdown.token().cancel();
+    if report.errors == report.requests {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
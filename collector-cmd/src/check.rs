@@ -0,0 +1,101 @@
+//! `collector check`：校验配置文件与所有点位表，不连接设备
+//!
+//! 依次解析配置文件、校验设备配置、按严格模式读取点位表并试建各设备的读取块（含读取块重叠检查），
+//! 汇总全部错误后输出报告；存在错误时以非零状态退出，供 CI 流水线使用。
+//! 点位表错误带工作表与行号，JSON/YAML 解析错误带行号与列号。
+
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use collector_core::config::{ConfigFormat, ConfigurationError};
+use collector_core::dev::manager::{DevManager, DryRunReport};
+
+use crate::reload::load_config;
+
+/// 按设备汇总的错误，键为 `None` 的是与具体设备无关的错误
+#[derive(Default)]
+struct Findings(BTreeMap<Option<String>, Vec<String>>);
+
+impl Findings {
+    fn push(&mut self, device: Option<String>, message: String) {
+        self.0.entry(device).or_default().push(message);
+    }
+
+    fn has(&self, device: &str) -> bool {
+        self.0.keys().flatten().any(|key| key == device)
+    }
+
+    fn count(&self) -> usize {
+        self.0.values().map(Vec::len).sum()
+    }
+}
+
+pub(crate) async fn check(path: &str, format: Option<ConfigFormat>) -> ExitCode {
+    let mut findings = Findings::default();
+    let mut p = match load_config(path, format).await {
+        Ok(p) => p,
+        Err(err) => {
+            findings.push(None, err.to_string());
+            return report(path, &findings, None);
+        }
+    };
+    if let Err(errors) = p.validate() {
+        for err in errors {
+            findings.push(Some(err.device), err.message);
+        }
+    }
+    // 严格模式下点位表的错误行作为错误报告，而不是跳过该行
+    for dev in p.project.devices.values_mut() {
+        dev.config.strict_point_tables = Some(true);
+    }
+    // 已报告错误的设备读取点位表与试建时多半因同一原因失败，不再重复报告
+    if let Err(errors) = p.load_device_configs().await {
+        for err in errors {
+            match err {
+                ConfigurationError::PointTable(id, _) if findings.has(&id) => {}
+                ConfigurationError::PointTable(id, message) => findings.push(Some(id), message),
+                err => findings.push(None, err.to_string()),
+            }
+        }
+    }
+    let dry_run = DevManager::dry_run(p.project.devices);
+    for dev in &dry_run.devices {
+        if let Some(err) = &dev.error
+            && !findings.has(&dev.id)
+        {
+            findings.push(Some(dev.id.clone()), err.clone());
+        }
+    }
+    report(path, &findings, Some(&dry_run))
+}
+
+fn report(path: &str, findings: &Findings, dry_run: Option<&DryRunReport>) -> ExitCode {
+    println!("检查配置: {path}");
+    for dev in dry_run.iter().flat_map(|report| &report.devices) {
+        match &dev.plan {
+            Some(plan) if !findings.has(&dev.id) => println!(
+                "  {}: {}个点位, {}个读取块",
+                dev.id, plan.points, plan.blocks
+            ),
+            _ => {}
+        }
+    }
+    if let Some(report) = dry_run
+        && !report.disabled.is_empty()
+    {
+        println!("  已禁用: {}", report.disabled.join(", "));
+    }
+    for (device, messages) in &findings.0 {
+        let device = device.as_deref().unwrap_or("配置");
+        for message in messages {
+            println!("  [{device}] {message}");
+        }
+    }
+    if findings.count() > 0 {
+        println!("检查失败, 共{}处错误", findings.count());
+        return ExitCode::FAILURE;
+    }
+    let devices = dry_run.map_or(0, |report| report.devices.len());
+    println!("检查通过, 共{devices}个设备");
+    ExitCode::SUCCESS
+}
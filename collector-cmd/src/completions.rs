@@ -0,0 +1,27 @@
+//! `collector completions`：输出命令行补全脚本，运维机器上按 Tab 即可发现各子命令与参数
+
+use std::io::Write;
+use std::process::ExitCode;
+
+use clap::Args;
+use clap_complete::Shell;
+
+#[derive(Args, Debug)]
+pub(crate) struct CompletionsArgs {
+    /// 目标 shell：bash、zsh、fish、elvish、powershell
+    shell: Shell,
+}
+
+pub(crate) fn completions(mut cmd: clap::Command, args: CompletionsArgs) -> ExitCode {
+    let name = cmd.get_name().to_owned();
+    // 先生成到缓冲区，标准输出被提前关闭(如接 `head`)时报告错误而不是 panic
+    let mut script = Vec::new();
+    clap_complete::generate(args.shell, &mut cmd, name, &mut script);
+    match std::io::stdout().write_all(&script) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("输出补全脚本失败: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
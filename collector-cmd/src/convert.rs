@@ -0,0 +1,42 @@
+//! `collector convert`：在 Excel、CSV 与 JSON 点表之间转换，报告解析失败而跳过的行
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use collector_core::config::convert;
+
+#[derive(Args, Debug)]
+pub(crate) struct ConvertArgs {
+    /// 源点表，格式按扩展名判断：`.xlsx`/`.xls`/`.ods` 等、`.csv`、`.json`/`.json5`
+    input: PathBuf,
+    /// 目标点表，格式按扩展名判断，Excel 只能写出 `.xlsx`
+    output: PathBuf,
+}
+
+pub(crate) fn convert(args: ConvertArgs) -> ExitCode {
+    let conversion = match convert::convert(&args.input, &args.output) {
+        Ok(conversion) => conversion,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    for sheet in &conversion.skipped_sheets {
+        println!("  跳过工作表: {sheet}");
+    }
+    for err in &conversion.errors {
+        println!("  [跳过] {err}");
+    }
+    println!(
+        "已写出{}个点位到{}",
+        conversion.points,
+        args.output.display()
+    );
+    if conversion.errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        println!("共{}行解析失败", conversion.errors.len());
+        ExitCode::FAILURE
+    }
+}
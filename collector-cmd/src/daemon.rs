@@ -0,0 +1,175 @@
+//! `--daemon`/`--pid-file`：在没有 init 管理的系统上脱离终端后台运行
+//!
+//! 指定其中任一项时还会在临时目录下锁定一个按配置路径命名的实例锁文件，同一配置的第二个实例因拿不到锁而拒绝启动；
+//! 进程退出(包括异常退出)时锁随之释放，残留的文件不影响下次启动。
+//! 由 systemd 等进程管理器启动时两项都不指定，不在临时目录下留下文件。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum DaemonError {
+    #[error("打开pid文件{0}失败: {1}")]
+    Open(String, io::Error),
+    #[error("配置{config}已有采集程序在运行(pid {pid}), pid文件{path}")]
+    Running {
+        config: String,
+        pid: String,
+        path: String,
+    },
+    #[error("写入pid文件{0}失败: {1}")]
+    Write(String, io::Error),
+    #[error("转入后台失败: {0}")]
+    Detach(io::Error),
+}
+
+/// 已加锁的 pid 文件，释放时解锁
+#[derive(Debug)]
+struct PidFile {
+    path: PathBuf,
+    file: File,
+    /// 释放时删除文件；实例锁文件保留，避免删除后另一个实例锁定的是已脱离路径的旧文件
+    remove: bool,
+}
+
+impl PidFile {
+    /// 打开并锁定 `path`，已被其他进程锁定时报告其 pid
+    fn lock(path: PathBuf, config: &str, remove: bool) -> Result<Self, DaemonError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|err| DaemonError::Open(path.display().to_string(), err))?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { path, file, remove }),
+            Err(fs::TryLockError::WouldBlock) => Err(DaemonError::Running {
+                config: config.to_owned(),
+                pid: fs::read_to_string(&path)
+                    .map(|pid| pid.trim().to_owned())
+                    .unwrap_or_default(),
+                path: path.display().to_string(),
+            }),
+            Err(fs::TryLockError::Error(err)) => {
+                Err(DaemonError::Open(path.display().to_string(), err))
+            }
+        }
+    }
+
+    /// 以当前进程号覆盖文件内容，须在转入后台之后调用
+    fn write_pid(&self) -> Result<(), DaemonError> {
+        let write = || {
+            self.file.set_len(0)?;
+            (&self.file).write_all(format!("{}\n", std::process::id()).as_bytes())
+        };
+        write().map_err(|err| DaemonError::Write(self.path.display().to_string(), err))
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if self.remove {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 运行实例持有的 pid 文件锁，进程退出前一直持有
+#[derive(Debug)]
+pub struct Instance {
+    _pid_files: Vec<PidFile>,
+}
+
+/// 锁定 pid 文件，`daemon` 为真时转入后台
+///
+/// fork 后子进程只保留调用线程，须在创建日志线程与 tokio 运行时之前调用
+pub(crate) fn start(
+    config: &str,
+    pid_file: Option<&Path>,
+    daemon: bool,
+) -> Result<Instance, DaemonError> {
+    let instance = instance_path(config);
+    let mut paths = vec![(instance.clone(), false)];
+    if let Some(path) = pid_file
+        && path != instance
+    {
+        paths.push((path.to_path_buf(), true));
+    }
+    // 先加锁再 fork，重复启动的错误仍能输出到终端；flock 锁随文件描述符由子进程继承
+    let pid_files = paths
+        .into_iter()
+        .map(|(path, remove)| PidFile::lock(path, config, remove))
+        .collect::<Result<Vec<_>, _>>()?;
+    if daemon {
+        detach().map_err(DaemonError::Detach)?;
+    }
+    for pid_file in &pid_files {
+        pid_file.write_pid()?;
+    }
+    if daemon {
+        redirect_stdio().map_err(DaemonError::Detach)?;
+    }
+    Ok(Instance {
+        _pid_files: pid_files,
+    })
+}
+
+/// 按配置路径命名的实例锁文件，如 `/tmp/collector-etc_collector_config.json.pid`
+fn instance_path(config: &str) -> PathBuf {
+    let config = fs::canonicalize(config)
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| config.to_owned());
+    let name: String = config
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    std::env::temp_dir().join(format!("collector-{}.pid", name.trim_matches('_')))
+}
+
+/// 两次 fork 并建立新会话：启动进程立即返回，守护进程不再属于任何终端
+///
+/// 不切换工作目录，配置中的相对路径与 `logs` 目录仍相对于启动时的目录
+fn detach() -> io::Result<()> {
+    // SAFETY: 调用时进程内只有主线程，子进程继续执行不受 fork 对多线程的限制
+    unsafe {
+        fork_and_exit_parent()?;
+        if libc::setsid() < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // 会话首进程退出，守护进程之后打开终端设备也不会成为控制终端
+        fork_and_exit_parent()
+    }
+}
+
+unsafe fn fork_and_exit_parent() -> io::Result<()> {
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(()),
+        // 父进程不执行析构，pid 文件留给子进程
+        _ => unsafe { libc::_exit(0) },
+    }
+}
+
+/// 标准输入输出重定向到 `/dev/null`，日志仍写入 `logs` 目录
+fn redirect_stdio() -> io::Result<()> {
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        // SAFETY: 两个描述符都有效，dup2 只替换标准描述符
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
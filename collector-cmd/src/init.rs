@@ -0,0 +1,173 @@
+//! `collector init`：按选定的通信类型生成起步用的项目配置与示例点位表
+//!
+//! 未通过参数指定的项在终端中逐项询问，直接回车取缺省值；加 `--yes` 或标准输入不是终端时全部取缺省值。
+
+use std::fmt::Display;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use clap::Args;
+use collector_core::config::ComType;
+use collector_core::config::scaffold::Scaffold;
+
+#[derive(Args, Debug)]
+pub(crate) struct InitArgs {
+    /// 生成文件的目录
+    #[arg(default_value = ".")]
+    dir: PathBuf,
+    /// 设备的通信类型：ModbusTCP、ModbusRTU、CAN、GPIO
+    #[arg(long, value_parser = com_type)]
+    com_type: Option<ComType>,
+    /// 项目名称
+    #[arg(long)]
+    project: Option<String>,
+    /// 设备键与 ID
+    #[arg(long)]
+    device: Option<String>,
+    /// Modbus TCP 设备的 IP
+    #[arg(long)]
+    ip: Option<String>,
+    /// Modbus TCP 设备的端口
+    #[arg(long)]
+    port: Option<u16>,
+    /// Modbus 从站地址
+    #[arg(long)]
+    slave: Option<u8>,
+    /// Modbus RTU 的串口设备
+    #[arg(long)]
+    serial_tty: Option<String>,
+    /// Modbus RTU 或 CAN 的波特率
+    #[arg(long)]
+    baud_rate: Option<u32>,
+    /// CAN 接口
+    #[arg(long)]
+    interface: Option<String>,
+    /// 不询问，未指定的项取缺省值
+    #[arg(short, long)]
+    yes: bool,
+    /// 覆盖已存在的文件
+    #[arg(long)]
+    force: bool,
+}
+
+/// 通信类型，不区分大小写
+fn com_type(value: &str) -> Result<ComType, String> {
+    [
+        ComType::ModbusTCP,
+        ComType::ModbusRTU,
+        ComType::CAN,
+        ComType::GPIO,
+        ComType::IEC104,
+        ComType::IEC61850,
+    ]
+    .into_iter()
+    .find(|com| format!("{com:?}").eq_ignore_ascii_case(value))
+    .ok_or_else(|| format!("无效的通信类型 {value}"))
+}
+
+/// 终端询问，输入无效时重新询问
+struct Prompt {
+    interactive: bool,
+}
+
+impl Prompt {
+    fn ask<T: FromStr + Display>(&self, label: &str, given: Option<T>, default: T) -> T {
+        self.ask_with(label, given, default, |input| input.parse().ok())
+    }
+
+    fn ask_with<T: Display>(
+        &self,
+        label: &str,
+        given: Option<T>,
+        default: T,
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> T {
+        if let Some(value) = given {
+            return value;
+        }
+        if !self.interactive {
+            return default;
+        }
+        let stdin = std::io::stdin();
+        loop {
+            print!("{label} [{default}]: ");
+            let _ = std::io::stdout().flush();
+            let mut line = String::new();
+            // 输入结束(Ctrl+D)时取缺省值
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                println!();
+                return default;
+            }
+            let input = line.trim();
+            if input.is_empty() {
+                return default;
+            }
+            match parse(input) {
+                Some(value) => return value,
+                None => println!("  无效的输入: {input}"),
+            }
+        }
+    }
+}
+
+pub(crate) fn init(args: InitArgs) -> ExitCode {
+    let prompt = Prompt {
+        interactive: !args.yes && std::io::stdin().is_terminal(),
+    };
+    let com = prompt.ask_with(
+        "通信类型(ModbusTCP/ModbusRTU/CAN/GPIO)",
+        args.com_type.map(ComTypeName),
+        ComTypeName(ComType::ModbusTCP),
+        |input| com_type(input).ok().map(ComTypeName),
+    );
+    let defaults = Scaffold::new(com.0);
+    let mut scaffold = Scaffold {
+        project: prompt.ask("项目名称", args.project, defaults.project.clone()),
+        device: prompt.ask("设备ID", args.device, defaults.device.clone()),
+        ..defaults.clone()
+    };
+    match com.0 {
+        ComType::ModbusTCP => {
+            scaffold.ip = prompt.ask("IP", args.ip, defaults.ip);
+            scaffold.port = prompt.ask("端口", args.port, defaults.port);
+            scaffold.slave = prompt.ask("从站地址", args.slave, defaults.slave);
+        }
+        ComType::ModbusRTU => {
+            scaffold.serial_tty = prompt.ask("串口设备", args.serial_tty, defaults.serial_tty);
+            scaffold.baud_rate = prompt.ask("波特率", args.baud_rate, defaults.baud_rate);
+            scaffold.slave = prompt.ask("从站地址", args.slave, defaults.slave);
+        }
+        ComType::CAN => {
+            scaffold.interface = prompt.ask("CAN接口", args.interface, defaults.interface);
+            scaffold.baud_rate = prompt.ask("波特率", args.baud_rate, defaults.baud_rate);
+        }
+        _ => {}
+    }
+    match scaffold.write(&args.dir, args.force) {
+        Ok(files) => {
+            for file in &files {
+                println!("已生成 {}", file.display());
+            }
+            println!(
+                "按现场修改后运行 collector check -c {} 校验",
+                files[0].display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 询问时显示通信类型，变体名与配置中的写法相同
+struct ComTypeName(ComType);
+
+impl Display for ComTypeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
@@ -1,10 +1,17 @@
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
 use collector_api::ApiApp;
 use collector_core::center::DataCenter;
 use collector_core::center::SharedPointCenter;
 use collector_core::config;
+#[cfg(unix)]
+use collector_core::control::{self, ControlServer};
 use collector_core::dev::can_bus::SharedCanBus;
 use collector_core::dev::manager::DevManager;
 use collector_core::dock::modbus::ModbusServer;
@@ -15,7 +22,7 @@ use collector_core::utils::database::close_database;
 use collector_core::utils::database::{DatabaseConfig, init_database};
 use collector_engine::emu::core::Emu;
 use collector_engine::mod_engine::ScriptManager;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, watch};
 use tracing::error;
 use tracing_error::ErrorLayer;
 use tracing_log::LogTracer;
@@ -23,6 +30,44 @@ use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{EnvFilter, Layer, Registry, fmt};
 
+mod bench;
+mod check;
+mod completions;
+mod convert;
+#[cfg(unix)]
+mod daemon;
+mod init;
+mod link;
+#[cfg(unix)]
+mod maintain;
+mod man;
+mod read;
+mod record;
+mod reload;
+mod replay;
+#[cfg(unix)]
+mod restart;
+mod scan;
+#[cfg(windows)]
+mod service;
+mod simulate;
+#[cfg(unix)]
+mod status;
+mod supervise;
+mod write;
+
+#[cfg(unix)]
+pub use daemon::Instance;
+#[cfg(windows)]
+pub use service::run_service;
+
+/// KV 配置源在本地缓存目录下的镜像目录
+const KV_MIRROR_DIR: &str = "kv";
+/// 检查 KV 配置变化的间隔
+const KV_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// 收到关闭信号后等待设备停止的缺省期限，超时后不再等待直接退出
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[inline]
 pub fn init_tracing() -> Vec<tracing_appender::non_blocking::WorkerGuard> {
     let _ = LogTracer::builder().init();
@@ -84,25 +129,221 @@ pub fn init_tracing() -> Vec<tracing_appender::non_blocking::WorkerGuard> {
     guards
 }
 
+/// 子命令的日志：只把警告与错误写到标准错误，不创建 `logs` 目录，标准输出留给命令的结果
+///
+/// 可用 `RUST_LOG` 调整级别
+pub fn init_command_tracing() {
+    let _ = LogTracer::builder().init();
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+    let collector = Registry::default().with(env_filter).with(
+        fmt::layer()
+            .with_timer(fmt::time::ChronoLocal::rfc_3339())
+            .with_writer(std::io::stderr),
+    );
+    tracing::subscriber::set_global_default(collector).expect("Tracing collect error");
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
-struct Args {
-    #[arg(short, long, value_name = "collector配置文件")]
-    config: String,
+pub struct Args {
+    #[arg(short, long, value_name = "collector配置文件", global = true)]
+    config: Option<String>,
+    /// 配置文件格式(json/yaml/toml)，缺省时按扩展名识别
+    #[arg(long, global = true)]
+    format: Option<config::ConfigFormat>,
+    /// 监听配置文件及点位表变化，只重启配置变化的设备；
+    /// `consul://`/`etcd://` 配置源改为轮询 KV 的变化
+    #[arg(long)]
+    watch: bool,
+    /// 脱离终端在后台运行，标准输入输出重定向到 /dev/null
+    #[cfg(unix)]
+    #[arg(long)]
+    daemon: bool,
+    /// 写入进程号并加锁的 pid 文件，文件被锁定时拒绝启动；
+    /// 指定该项或 `--daemon` 时，同一配置文件只允许运行一个实例
+    #[cfg(unix)]
+    #[arg(long)]
+    pid_file: Option<PathBuf>,
+    /// 作为 Windows 服务运行，由服务控制管理器启动时使用
+    #[cfg(windows)]
+    #[arg(long)]
+    service: bool,
+    /// 缺省时按配置文件运行采集
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 校验配置文件与所有点位表后退出，存在错误时返回非零状态
+    Check,
+    /// 探测 IP 段或串口总线上响应的 Modbus 从站，输出从站地址与往返时间
+    Scan(scan::ScanArgs),
+    /// 按设备配置连接设备，读取一次点位，输出原始值与按点位表解码的工程值
+    Read(read::ReadArgs),
+    /// 按设备配置连接设备，下发一次点位并回读校验，需加 --confirm 才会写入
+    Write(write::WriteArgs),
+    /// 对设备或按其点位表启动的模拟器连续轮询，输出轮询速率、请求往返时间分布与解码吞吐
+    Bench(bench::BenchArgs),
+    /// 按设备的读取计划轮询指定设备，把每个请求的应答与解码值写入抓包文件
+    Record(record::RecordArgs),
+    /// 按当前的点位表重新解码抓包并写入数据中心，报告与抓取时解码结果不同的点位
+    Replay(replay::ReplayArgs),
+    /// 在 Excel、CSV 与 JSON 点表之间转换，保留全部列，报告解析失败的行
+    Convert(convert::ConvertArgs),
+    /// 按点位表启动 Modbus TCP 从站模拟器，预置寄存器并可按随机或斜坡规律刷新
+    Simulate(simulate::SimulateArgs),
+    /// 经控制套接字查询运行中的采集程序，输出各设备的状态、最近读取时间与错误计数
+    #[cfg(unix)]
+    Status(status::StatusArgs),
+    /// 经控制套接字对运行中的单个设备执行维护操作：立即重连、清零诊断计数、立即轮询或读取原始寄存器
+    #[cfg(unix)]
+    Maintain(maintain::MaintainArgs),
+    /// 经控制套接字重启运行中的单个设备，按当前配置重建后重新连接
+    #[cfg(unix)]
+    Restart(restart::RestartArgs),
+    /// 按选定的通信类型生成起步用的项目配置与示例点位表，未指定的项在终端中询问
+    Init(init::InitArgs),
+    /// 输出指定 shell 的命令行补全脚本
+    Completions(completions::CompletionsArgs),
+    /// 生成 man 手册
+    Man(man::ManArgs),
+}
+
+impl Args {
+    /// 按配置运行且指定了 `--pid-file` 或 `--daemon` 时锁定 pid 文件，指定 `--daemon` 时转入后台；
+    /// 运行子命令或两者都未指定时什么也不做
+    ///
+    /// 须在创建任何线程(日志线程、tokio 运行时)之前调用，失败时报告错误并退出
+    #[cfg(unix)]
+    pub fn start_instance(&self) -> Option<Instance> {
+        if !self.is_run() || (self.pid_file.is_none() && !self.daemon) {
+            return None;
+        }
+        match daemon::start(&self.config(), self.pid_file.as_deref(), self.daemon) {
+            Ok(instance) => Some(instance),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// 按配置运行采集，而不是执行子命令
+    pub fn is_run(&self) -> bool {
+        self.command.is_none()
+    }
+
+    /// 指定了 `--service` 且按配置运行
+    #[cfg(windows)]
+    pub fn is_service(&self) -> bool {
+        self.service && self.is_run()
+    }
+
+    /// 配置文件路径，未指定时报告参数错误并退出
+    fn config(&self) -> String {
+        self.config.clone().unwrap_or_else(|| {
+            Args::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "the following required arguments were not provided: --config <collector配置文件>",
+                )
+                .exit()
+        })
+    }
+}
+
+pub async fn cmd(mut args: Args) -> ExitCode {
+    match args.command.take() {
+        Some(Command::Check) => check::check(&args.config(), args.format).await,
+        Some(Command::Scan(scan)) => scan::scan(scan).await,
+        Some(Command::Read(read)) => read::read(&args.config(), args.format, read).await,
+        Some(Command::Write(write)) => write::write(&args.config(), args.format, write).await,
+        Some(Command::Bench(bench)) => bench::bench(&args.config(), args.format, bench).await,
+        Some(Command::Record(record)) => record::record(&args.config(), args.format, record).await,
+        Some(Command::Replay(replay)) => replay::replay(&args.config(), args.format, replay).await,
+        Some(Command::Convert(convert)) => convert::convert(convert),
+        Some(Command::Simulate(simulate)) => simulate::simulate(simulate).await,
+        #[cfg(unix)]
+        Some(Command::Status(status)) => {
+            status::status(args.config.as_deref(), args.format, status).await
+        }
+        #[cfg(unix)]
+        Some(Command::Maintain(maintain)) => {
+            maintain::maintain(args.config.as_deref(), args.format, maintain).await
+        }
+        #[cfg(unix)]
+        Some(Command::Restart(restart)) => {
+            restart::restart(args.config.as_deref(), args.format, restart).await
+        }
+        Some(Command::Init(init)) => init::init(init),
+        Some(Command::Completions(completions)) => {
+            completions::completions(Args::command(), completions)
+        }
+        Some(Command::Man(man)) => man::man(Args::command(), man),
+        None => run(args, ShutdownManager::new(), supervise::from_env()).await,
+    }
 }
 
-pub async fn cmd() {
-    let args = Args::parse();
-    match config::Configuration::new(args.config).await {
+/// 按配置运行采集直到 `shutdown` 取消，`notifier` 为报告运行状态的进程管理器
+///
+/// 配置同步、加载或校验失败而未能启动时返回 [`ExitCode::FAILURE`]
+async fn run(
+    mut args: Args,
+    shutdown: ShutdownManager,
+    notifier: Option<Box<dyn supervise::Notifier>>,
+) -> ExitCode {
+    let mut path = args.config();
+    // KV 配置源先同步到本地镜像，之后按本地配置文件加载与监听
+    // 镜像统一写成 JSON，用户指定的格式只用于解析 KV 中的配置
+    let kv_source = config::kv::KvSource::parse(&path);
+    let kv_format = args.format;
+    if let Some(source) = &kv_source {
+        match source
+            .sync(&config::cache_dir().join(KV_MIRROR_DIR), kv_format)
+            .await
+        {
+            Ok(mirror) => {
+                path = mirror.display().to_string();
+                args.format = Some(config::ConfigFormat::Json);
+            }
+            Err(err) => {
+                error!("同步KV配置失败: {}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    match reload::load_config(&path, args.format).await {
         Ok(mut p) => {
-            p.load_device_configs().await;
-            // 创建统一的关闭管理器
-            let shutdown = ShutdownManager::new();
+            if let Err(errors) = p.validate() {
+                for err in &errors {
+                    error!("配置错误 {}", err);
+                }
+                error!("配置校验失败, 共{}处错误", errors.len());
+                return ExitCode::FAILURE;
+            }
+            // 热更新比较用的设备配置快照，不含点位表
+            let watch = args.watch && !config::remote::is_remote(&path);
+            if args.watch && !watch {
+                tracing::warn!("远程配置不支持文件监听, 热更新未启用");
+            }
+            let snapshot = watch.then(|| (p.project.devices.clone(), p.included_files.clone()));
+            if let Err(errors) = p.load_device_configs().await {
+                for err in &errors {
+                    error!("{}", err);
+                }
+                error!("点位表加载失败, 共{}个设备", errors.len());
+                return ExitCode::FAILURE;
+            }
+            // 尽早监听关闭信号，分阶段启动设备期间收到信号也能停止已启动的设备
+            tokio::spawn(shutdown.clone().listen_shutdown_signal());
 
             let emu_enable = p.project.emu_enable.unwrap_or(false);
             let mqtt_enable = p.project.mqtt_enable.unwrap_or(false);
 
-            let center: SharedPointCenter = Arc::new(DataCenter::new(32));
+            let center: SharedPointCenter = Arc::new(
+                DataCenter::new(32).with_float_epsilon(p.project.float_epsilon.unwrap_or(0.0)),
+            );
             let can_bus = SharedCanBus::default();
 
             let mqtt_client = if mqtt_enable {
@@ -118,6 +359,15 @@ pub async fn cmd() {
             };
 
             let mut manager = DevManager::new(p.project.devices, center.clone(), can_bus.clone());
+            if let Some(spacing) = p.project.start_spacing {
+                manager.set_start_spacing(Duration::from_millis(spacing));
+            }
+            manager.set_max_connecting(p.project.max_connecting);
+            let stop_deadline = p
+                .project
+                .stop_deadline
+                .map_or(SHUTDOWN_TIMEOUT, Duration::from_millis);
+            manager.set_stop_deadline(Some(stop_deadline));
 
             if emu_enable {
                 // 数据库连接池需要在设备管理器（含虚拟设备引擎）启动前初始化好，
@@ -129,12 +379,59 @@ pub async fn cmd() {
                     tracing::error!("EMU运行时配置错误: {}", e);
                 }
                 let emu = Emu::new(center.clone()).await;
-                manager
-                    .add_device(Arc::new(Mutex::new(Box::new(emu))))
-                    .await;
+                manager.add_device(Box::new(emu));
             }
 
-            manager.start_all().await;
+            // 启动设备前开始报告，分阶段启动较慢时进程管理器仍能收到状态与心跳
+            let (started_tx, started_rx) = watch::channel(false);
+            let handles = manager.handles();
+            let manager = Arc::new(Mutex::new(manager));
+            if let Some(notifier) = notifier {
+                tokio::spawn(supervise::supervise(
+                    manager.clone(),
+                    handles,
+                    started_rx,
+                    notifier,
+                    shutdown.clone(),
+                ));
+            }
+            manager.lock().await.start_all().await;
+            let _ = started_tx.send(true);
+
+            // 启动控制套接字，供 `collector status` 查询
+            #[cfg(unix)]
+            {
+                let socket = p
+                    .project
+                    .control_socket
+                    .clone()
+                    .unwrap_or_else(|| control::DEFAULT_SOCKET.to_owned());
+                match ControlServer::bind(socket, manager.clone()).await {
+                    Ok(server) => {
+                        tokio::spawn(server.run(shutdown.token()));
+                    }
+                    Err(err) => error!("控制套接字启动失败: {}", err),
+                }
+            }
+
+            if let Some((devices, included_files)) = snapshot {
+                let reloader = reload::ConfigReloader::new(
+                    path.clone(),
+                    args.format,
+                    devices,
+                    included_files,
+                    manager.clone(),
+                );
+                tokio::spawn(reloader.run(shutdown.clone()));
+                if let Some(source) = kv_source {
+                    tokio::spawn(source.watch(
+                        config::cache_dir().join(KV_MIRROR_DIR),
+                        kv_format,
+                        KV_POLL_INTERVAL,
+                        shutdown.clone(),
+                    ));
+                }
+            }
 
             // 启动北向 Modbus TCP 服务器
             if let (Some(host), Some(port), Some(conf)) = (
@@ -162,6 +459,7 @@ pub async fn cmd() {
                     .unwrap_or_else(|| "0.0.0.0".to_string()),
                 p.project.http_port.unwrap_or(9091),
                 center.clone(),
+                manager.clone(),
             );
 
             tokio::spawn(api_server.start(shutdown.clone()));
@@ -176,23 +474,32 @@ pub async fn cmd() {
                 }
             });
 
-            // 在后台监听关闭信号
-            tokio::spawn(shutdown.clone().listen_shutdown_signal());
-
             // 等待关闭信号
             shutdown.wait_for_shutdown().await;
 
-            // 优雅关闭所有组件
-            manager.stop_all().await;
+            // 优雅关闭所有组件，关闭串口与 TCP 连接；管理器按期限停止设备，外层只兜底等待管理器的锁
+            let stop = async { manager.lock().await.stop_all().await };
+            match tokio::time::timeout(stop_deadline * 2, stop).await {
+                Ok(aborted) if !aborted.is_empty() => {
+                    error!("{} 台设备未能正常停止: {:?}", aborted.len(), aborted);
+                }
+                Ok(_) => {}
+                Err(_) => error!(
+                    "{}ms 内未能停止所有设备, 强制退出",
+                    (stop_deadline * 2).as_millis()
+                ),
+            }
             close_database().await;
             if let Some(client) = mqtt_client.as_ref()
                 && let Err(err) = client.stop().await
             {
                 error!("failed to stop mqtt client: {}", err);
             }
+            ExitCode::SUCCESS
         }
         Err(e) => {
             error!("{}", e);
+            ExitCode::FAILURE
         }
     }
 }
@@ -0,0 +1,76 @@
+//! 调试子命令共用的 Modbus 链路参数与设备连接
+
+use clap::Args;
+use collector_core::config::modbus_conf::RegisterType;
+use collector_core::config::{ConfigFormat, Device};
+use collector_core::dev::oneshot::Session;
+
+use crate::reload::load_config;
+
+/// 串口参数
+#[derive(Args, Debug, Clone)]
+pub(crate) struct SerialArgs {
+    /// 波特率
+    #[arg(long, default_value_t = 9600)]
+    pub(crate) baud_rate: u32,
+    /// 数据位(5-8)
+    #[arg(long, default_value_t = 8)]
+    pub(crate) data_bits: u8,
+    /// 校验位(None/Even/Odd)
+    #[arg(long, default_value = "None")]
+    pub(crate) parity: String,
+    /// 停止位(1/2)
+    #[arg(long, default_value_t = 1)]
+    pub(crate) stop_bits: u8,
+}
+
+/// 解析寄存器类型，可写作类型名或功能码：1 线圈、2 离散输入、3 保持寄存器、4 输入寄存器
+pub(crate) fn register_type(value: &str) -> Result<RegisterType, String> {
+    match value {
+        "1" => Ok(RegisterType::Coils),
+        "2" => Ok(RegisterType::DiscreteInputs),
+        "3" => Ok(RegisterType::HoldingRegisters),
+        "4" => Ok(RegisterType::InputRegisters),
+        _ => RegisterType::try_from(value).map_err(|_| {
+            format!("无效的寄存器类型 {value}, 可选 1/2/3/4 或 Coils/DiscreteInputs/HoldingRegisters/InputRegisters")
+        }),
+    }
+}
+
+/// 读取配置文件中的设备配置并加载点位表，`devices` 为设备的键或 ID，按给定顺序返回
+pub(crate) async fn load_devices(
+    path: &str,
+    format: Option<ConfigFormat>,
+    devices: &[String],
+) -> Result<Vec<Device>, String> {
+    let p = load_config(path, format)
+        .await
+        .map_err(|err| err.to_string())?;
+    let mut loaded = Vec::with_capacity(devices.len());
+    for device in devices {
+        let mut dev = p
+            .project
+            .devices
+            .iter()
+            .find(|(key, dev)| *key == device || dev.id.as_ref() == Some(device))
+            .map(|(_, dev)| dev.clone())
+            .ok_or_else(|| format!("设备{device}不存在"))?;
+        dev.load_protocol_configs()
+            .await
+            .map_err(|err| err.to_string())?;
+        loaded.push(dev);
+    }
+    Ok(loaded)
+}
+
+/// 按配置文件中的设备配置加载点位表并连接设备，`device` 为设备的键或 ID
+pub(crate) async fn connect(
+    path: &str,
+    format: Option<ConfigFormat>,
+    device: &str,
+) -> Result<Session, String> {
+    let mut devices = load_devices(path, format, &[device.to_owned()]).await?;
+    Session::connect(devices.remove(0))
+        .await
+        .map_err(|err| format!("连接设备{device}失败: {err}"))
+}
@@ -1,11 +1,32 @@
-use collector_cmd::{cmd, init_tracing};
+use std::process::ExitCode;
+
+use clap::Parser;
+use collector_cmd::{Args, cmd, init_command_tracing, init_tracing};
 use mimalloc::MiMalloc;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-#[tokio::main]
-async fn main() {
-    let _log = init_tracing();
-    let _ = cmd().await;
+fn main() -> ExitCode {
+    let args = Args::parse();
+    // 服务入口在服务控制管理器的线程上初始化日志与运行时
+    #[cfg(windows)]
+    if args.is_service() {
+        return collector_cmd::run_service(args);
+    }
+    // 转入后台需要 fork，须在创建日志线程与 tokio 运行时之前完成
+    #[cfg(unix)]
+    let _instance = args.start_instance();
+    // 子命令的输出常被管道或重定向，日志只写到标准错误
+    let log = if args.is_run() {
+        init_tracing()
+    } else {
+        init_command_tracing();
+        Vec::new()
+    };
+    let runtime = tokio::runtime::Runtime::new().expect("failed to build tokio runtime");
+    let code = runtime.block_on(cmd(args));
+    // 退出前写完缓冲中的日志
+    drop(log);
+    code
 }
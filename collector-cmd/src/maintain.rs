@@ -0,0 +1,117 @@
+//! `collector maintain`：经控制套接字对运行中的单个设备执行维护操作，如立即重连、立即轮询与读取原始寄存器
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Subcommand};
+use collector_core::config::ConfigFormat;
+use collector_core::config::modbus_conf::RegisterType;
+use collector_core::control::{self, Reply, Request};
+use collector_core::dev::maintenance::{Maintenance, MaintenanceOutput};
+
+use crate::link;
+use crate::status::socket_path;
+
+#[derive(Args, Debug)]
+pub(crate) struct MaintainArgs {
+    /// 设备 ID
+    device: String,
+    #[command(subcommand)]
+    op: Op,
+    /// 控制套接字，缺省为配置文件中的 control_socket，再缺省为工作目录下的 collector.sock
+    #[arg(long, global = true)]
+    socket: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Op {
+    /// 断开当前连接并立即重连，不等待退避
+    Reconnect,
+    /// 清零通讯诊断计数
+    ResetDiagnostics,
+    /// 所有扫描等级立即开始新一轮读取
+    PollNow,
+    /// 读取原始寄存器或线圈
+    Dump {
+        /// 寄存器类型，可写作功能码
+        #[arg(long, default_value = "HoldingRegisters", value_parser = link::register_type)]
+        register_type: RegisterType,
+        /// 起始协议地址
+        #[arg(long)]
+        address: u16,
+        /// 读取数量
+        #[arg(long, default_value_t = 1)]
+        count: u16,
+    },
+}
+
+impl From<Op> for Maintenance {
+    fn from(op: Op) -> Self {
+        match op {
+            Op::Reconnect => Maintenance::Reconnect,
+            Op::ResetDiagnostics => Maintenance::ResetDiagnostics,
+            Op::PollNow => Maintenance::PollNow,
+            Op::Dump {
+                register_type,
+                address,
+                count,
+            } => Maintenance::DumpRegisters {
+                register_type,
+                address,
+                count,
+            },
+        }
+    }
+}
+
+pub(crate) async fn maintain(
+    config: Option<&str>,
+    format: Option<ConfigFormat>,
+    args: MaintainArgs,
+) -> ExitCode {
+    let socket = match socket_path(args.socket, config, format).await {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let address = match &args.op {
+        Op::Dump { address, .. } => *address,
+        _ => 0,
+    };
+    let request = Request::Maintain {
+        id: args.device,
+        op: args.op.into(),
+    };
+    let output = match control::request(&socket, &request).await {
+        Ok(Reply::Maintenance(output)) => output,
+        Ok(Reply::Error(err)) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+        Ok(reply) => {
+            eprintln!("意外的应答: {reply:?}");
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match output {
+        MaintenanceOutput::Done => println!("完成"),
+        MaintenanceOutput::Registers(values) => {
+            for (offset, value) in values.iter().enumerate() {
+                let addr = address as usize + offset;
+                println!("{addr:>5}  {value:>5}  0x{value:04X}");
+            }
+        }
+        MaintenanceOutput::Bits(values) => {
+            for (offset, value) in values.iter().enumerate() {
+                println!("{:>5}  {}", address as usize + offset, *value as u8);
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
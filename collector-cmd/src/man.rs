@@ -0,0 +1,33 @@
+//! `collector man`：按命令行定义生成 roff 格式的 man 手册
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use clap_mangen::Man;
+
+#[derive(Args, Debug)]
+pub(crate) struct ManArgs {
+    /// 为主命令及每个子命令各生成一页手册写入该目录；缺省时只把主命令的手册输出到标准输出
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+}
+
+pub(crate) fn man(cmd: clap::Command, args: ManArgs) -> ExitCode {
+    let result = match &args.out_dir {
+        Some(dir) => std::fs::create_dir_all(dir).and_then(|()| clap_mangen::generate_to(cmd, dir)),
+        None => Man::new(cmd).render(&mut std::io::stdout()),
+    };
+    match result {
+        Ok(()) => {
+            if let Some(dir) = &args.out_dir {
+                println!("已生成手册到{}", dir.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("生成手册失败: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! `collector read`：按设备配置连接设备，读取一次点位并输出原始值与工程值
+
+use std::process::ExitCode;
+
+use clap::Args;
+use collector_core::config::ConfigFormat;
+use collector_core::config::modbus_conf::{ModbusDataType, RegisterType};
+use collector_core::dev::maintenance::MaintenanceOutput;
+use collector_core::dev::oneshot::Reading;
+
+use crate::link;
+
+#[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("target").required(true))]
+pub(crate) struct ReadArgs {
+    /// 设备的键或 ID
+    #[arg(long)]
+    device: String,
+    /// 点位的键名或名称
+    #[arg(long, group = "target")]
+    point: Option<String>,
+    /// 点位表之外的地址，按设备点位表的编号：`4x0100` 为保持寄存器 100，
+    /// 前缀 0x/1x/3x/4x 分别为线圈、离散输入、输入寄存器、保持寄存器，不带前缀时为保持寄存器
+    #[arg(long, group = "target", value_parser = address)]
+    addr: Option<(RegisterType, u16)>,
+    /// `--addr` 的数据类型，如 U16、I32、F32、String(8)
+    #[arg(long = "type", default_value = "U16", value_parser = data_type)]
+    data_type: ModbusDataType,
+}
+
+fn address(value: &str) -> Result<(RegisterType, u16), String> {
+    let invalid = || format!("无效的地址 {value}, 应为 4x0100 或 100");
+    let (register_type, address) = match value.split_once(['x', 'X']) {
+        Some((prefix, address)) => {
+            let register_type = match prefix {
+                "0" => RegisterType::Coils,
+                "1" => RegisterType::DiscreteInputs,
+                "3" => RegisterType::InputRegisters,
+                "4" => RegisterType::HoldingRegisters,
+                _ => return Err(invalid()),
+            };
+            (register_type, address)
+        }
+        None => (RegisterType::HoldingRegisters, value),
+    };
+    let address = address.parse().map_err(|_| invalid())?;
+    Ok((register_type, address))
+}
+
+fn data_type(value: &str) -> Result<ModbusDataType, String> {
+    ModbusDataType::try_from(value).map_err(|_| format!("无效的数据类型 {value}"))
+}
+
+pub(crate) async fn read(path: &str, format: Option<ConfigFormat>, args: ReadArgs) -> ExitCode {
+    let mut session = match link::connect(path, format, &args.device).await {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let cfg = match (&args.point, args.addr) {
+        (Some(name), _) => session
+            .point(name)
+            .ok_or_else(|| format!("设备{}没有点位{name}", args.device)),
+        (None, Some((register_type, address))) => session
+            .adhoc_point(register_type, address, args.data_type)
+            .map_err(|err| err.to_string()),
+        (None, None) => unreachable!("clap requires --point or --addr"),
+    };
+    let cfg = match cfg {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match session.read(&cfg).await {
+        Ok(reading) => {
+            println!(
+                "{} {:?} {}..+{} ({:?})",
+                cfg.name, cfg.register_type, cfg.register_address, cfg.quantity, cfg.data_type
+            );
+            print_reading(&reading);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+pub(crate) fn print_reading(reading: &Reading) {
+    match &reading.raw {
+        MaintenanceOutput::Registers(registers) => {
+            let words: Vec<String> = registers
+                .iter()
+                .map(|word| format!("{word:#06x}"))
+                .collect();
+            println!("原始值: [{}]", words.join(", "));
+        }
+        MaintenanceOutput::Bits(bits) => {
+            let bits: Vec<&str> = bits
+                .iter()
+                .map(|bit| if *bit { "1" } else { "0" })
+                .collect();
+            println!("原始值: [{}]", bits.join(", "));
+        }
+        MaintenanceOutput::Done => {}
+    }
+    let Some(point) = &reading.point else {
+        println!("工程值: 无法解码");
+        return;
+    };
+    println!("工程值: {} {}", point.value, point.unit.unwrap_or(""));
+    if let Some(status) = point.current_status() {
+        println!("状态: {}", status.zh);
+    }
+    for bit in point.warning() {
+        println!("告警: {}", bit.zh);
+    }
+}
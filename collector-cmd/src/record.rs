@@ -0,0 +1,134 @@
+//! `collector record`：按设备的读取计划轮询，把每个请求的应答与解码值逐行写入抓包文件
+//!
+//! 抓包文件为 JSON Lines，每行一个请求，可用 `collector replay` 按点位表重新解码。
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::Args;
+use collector_core::config::ConfigFormat;
+use collector_core::dev::capture::{Frame, Recorder, Response};
+use collector_core::dev::oneshot::Session;
+use collector_core::shutdown::ShutdownManager;
+use tokio::sync::mpsc;
+
+use crate::link;
+
+#[derive(Args, Debug)]
+pub(crate) struct RecordArgs {
+    /// 设备的键或 ID，可重复指定
+    #[arg(long, required = true)]
+    device: Vec<String>,
+    /// 抓包文件
+    #[arg(short, long)]
+    output: PathBuf,
+    /// 抓取时长(s)，缺省时一直抓取到 Ctrl+C
+    #[arg(long)]
+    duration: Option<u64>,
+    /// 每个设备抓取的轮询圈数
+    #[arg(long)]
+    cycles: Option<u64>,
+}
+
+pub(crate) async fn record(path: &str, format: Option<ConfigFormat>, args: RecordArgs) -> ExitCode {
+    let devices = match link::load_devices(path, format, &args.device).await {
+        Ok(devices) => devices,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut recorders = Vec::with_capacity(devices.len());
+    for (name, dev) in args.device.iter().zip(devices) {
+        let recorder = match Session::connect(dev).await {
+            Ok(session) => Recorder::new(session).map_err(|err| err.to_string()),
+            Err(err) => Err(format!("连接设备{name}失败: {err}")),
+        };
+        match recorder {
+            Ok(recorder) => recorders.push(recorder),
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let mut file = match File::create(&args.output) {
+        Ok(file) => BufWriter::new(file),
+        Err(err) => {
+            eprintln!("创建抓包文件{}失败: {}", args.output.display(), err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let shutdown = ShutdownManager::new();
+    tokio::spawn(shutdown.clone().listen_shutdown_signal());
+    if let Some(duration) = args.duration {
+        let token = shutdown.token();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(duration)).await;
+            token.cancel();
+        });
+    }
+    let (tx, mut rx) = mpsc::channel::<Vec<Frame>>(64);
+    for mut recorder in recorders {
+        println!(
+            "抓取设备{}, 每圈{}个请求, 周期{}ms",
+            recorder.id(),
+            recorder.request_count(),
+            recorder.interval().as_millis()
+        );
+        let tx = tx.clone();
+        let token = shutdown.token();
+        let cycles = args.cycles;
+        tokio::spawn(async move {
+            let mut ticker =
+                tokio::time::interval(recorder.interval().max(Duration::from_millis(1)));
+            let mut done = 0;
+            while cycles.is_none_or(|cycles| done < cycles) {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                let frames = tokio::select! {
+                    _ = token.cancelled() => break,
+                    frames = recorder.cycle() => frames,
+                };
+                if tx.send(frames).await.is_err() {
+                    break;
+                }
+                done += 1;
+            }
+        });
+    }
+    drop(tx);
+
+    // 所有设备抓完或收到关闭信号时结束，每圈写完即刷新，中途退出也不丢失已抓取的帧
+    let (mut total, mut errors) = (0usize, 0usize);
+    while let Some(frames) = rx.recv().await {
+        for frame in &frames {
+            if matches!(frame.response, Response::Error(_)) {
+                errors += 1;
+            }
+            let line = serde_json::to_string(frame).expect("frame is serializable");
+            if let Err(err) = writeln!(file, "{line}") {
+                eprintln!("写入抓包文件{}失败: {}", args.output.display(), err);
+                return ExitCode::FAILURE;
+            }
+        }
+        total += frames.len();
+        if let Err(err) = file.flush() {
+            eprintln!("写入抓包文件{}失败: {}", args.output.display(), err);
+            return ExitCode::FAILURE;
+        }
+    }
+    println!(
+        "共抓取{}个请求, 其中{}个失败, 已写入{}",
+        total,
+        errors,
+        args.output.display()
+    );
+    ExitCode::SUCCESS
+}
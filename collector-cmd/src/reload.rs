@@ -0,0 +1,163 @@
+//! 配置热更新：监听项目配置及点位表，只重建发生变化的设备
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use collector_core::config::{ConfigFormat, Configuration, ConfigurationError, Device, reload};
+use collector_core::dev::manager::DevManager;
+use collector_core::shutdown::ShutdownManager;
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::{Mutex, mpsc};
+use tracing::{error, info, warn};
+
+/// 收到文件变化后等待的时间，合并编辑器保存时产生的多次事件
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 按指定格式或扩展名读取项目配置
+pub(crate) async fn load_config(
+    path: &str,
+    format: Option<ConfigFormat>,
+) -> Result<Configuration, ConfigurationError> {
+    match format {
+        Some(format) => Configuration::with_format(path.to_owned(), format).await,
+        None => Configuration::new(path.to_owned()).await,
+    }
+}
+
+pub(crate) struct ConfigReloader {
+    path: String,
+    format: Option<ConfigFormat>,
+    /// 最近一次读取的设备配置（不含点位表），据此监听点位表
+    devices: HashMap<String, Device>,
+    /// 通过 `includes` 合并的设备文件
+    included_files: Vec<PathBuf>,
+    manager: Arc<Mutex<DevManager>>,
+}
+
+impl ConfigReloader {
+    pub(crate) fn new(
+        path: String,
+        format: Option<ConfigFormat>,
+        devices: HashMap<String, Device>,
+        included_files: Vec<PathBuf>,
+        manager: Arc<Mutex<DevManager>>,
+    ) -> Self {
+        Self {
+            path,
+            format,
+            devices,
+            included_files,
+            manager,
+        }
+    }
+
+    pub(crate) async fn run(mut self, shutdown: ShutdownManager) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+        let watcher = RecommendedWatcher::new(
+            move |res: notify::Result<Event>| match res {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths {
+                            let _ = tx.send(path);
+                        }
+                    }
+                }
+                Err(err) => warn!("配置文件监听错误: {}", err),
+            },
+            Config::default(),
+        );
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                error!("配置文件监听启动失败: {}", err);
+                return;
+            }
+        };
+        let mut watched = HashSet::new();
+        self.watch_dirs(&mut watcher, &mut watched);
+        info!("配置热更新已启用: {}", self.path);
+
+        loop {
+            let first = tokio::select! {
+                _ = shutdown.wait_for_shutdown() => break,
+                path = rx.recv() => match path {
+                    Some(path) => path,
+                    None => break,
+                },
+            };
+            let mut changed = HashSet::from([first]);
+            tokio::time::sleep(DEBOUNCE).await;
+            while let Ok(path) = rx.try_recv() {
+                changed.insert(path);
+            }
+            self.reload(&changed).await;
+            self.watch_dirs(&mut watcher, &mut watched);
+        }
+    }
+
+    /// 监听配置文件及所有点位表所在目录
+    ///
+    /// 监听目录而不是文件本身，编辑器以“写临时文件再改名”的方式保存时也能收到事件
+    fn watch_dirs(&self, watcher: &mut RecommendedWatcher, watched: &mut HashSet<PathBuf>) {
+        let mut files = reload::register_files(&self.devices);
+        files.extend(self.config_files());
+        for dir in files.iter().filter_map(|file| file.parent()) {
+            if watched.contains(dir) {
+                continue;
+            }
+            match watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    watched.insert(dir.to_path_buf());
+                }
+                Err(err) => warn!("监听目录 {} 失败: {}", dir.display(), err),
+            }
+        }
+    }
+
+    /// 项目配置文件及其包含的设备文件
+    fn config_files(&self) -> HashSet<PathBuf> {
+        let mut files: HashSet<PathBuf> = self
+            .included_files
+            .iter()
+            .map(|file| reload::absolute_path(file))
+            .collect();
+        files.insert(reload::absolute_path(Path::new(&self.path)));
+        files
+    }
+
+    /// 重新读取并校验配置，交由设备管理器按差异重建设备；配置无效时保持当前配置
+    ///
+    /// 只有点位表变化时沿用当前设备配置，设备管理器比较点位表内容决定是否重建
+    async fn reload(&mut self, changed: &HashSet<PathBuf>) {
+        let config_changed = !self.config_files().is_disjoint(changed);
+        let devices = if config_changed {
+            let conf = match load_config(&self.path, self.format).await {
+                Ok(conf) => conf,
+                Err(err) => {
+                    error!("重新加载配置失败, 保持当前配置: {}", err);
+                    return;
+                }
+            };
+            if let Err(errors) = conf.validate() {
+                for err in &errors {
+                    error!("配置错误 {}", err);
+                }
+                error!("配置校验失败, 共{}处错误, 保持当前配置", errors.len());
+                return;
+            }
+            self.included_files = conf.included_files;
+            conf.project.devices
+        } else {
+            self.devices.clone()
+        };
+
+        // 加载点位表或新建失败的设备由设备管理器保持原状，下次文件变化时重试
+        let report = self.manager.lock().await.apply(devices.clone()).await;
+        self.devices = devices;
+        if !report.diff.is_empty() {
+            reload::record_diff(report.diff);
+        }
+    }
+}
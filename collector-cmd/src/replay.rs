@@ -0,0 +1,150 @@
+//! `collector replay`：按当前的点位表重新解码抓包并写入数据中心，报告与抓取时解码结果不同的点位
+//!
+//! 不连接设备，现场抓包带回后即可排查；修改点位表后回放同一份抓包可核对字节序与缩放。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::Args;
+use collector_core::center::{DataCenter, PointCenter};
+use collector_core::config::ConfigFormat;
+use collector_core::core::point::Val;
+use collector_core::dev::capture::{Frame, Replayer, Response};
+
+use crate::link;
+
+#[derive(Args, Debug)]
+pub(crate) struct ReplayArgs {
+    /// `collector record` 生成的抓包文件
+    capture: PathBuf,
+    /// 只回放指定设备的键或 ID，可重复指定，缺省时回放抓包中的全部设备
+    #[arg(long)]
+    device: Vec<String>,
+    /// 输出每一帧回放解码的点位值
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+fn read_frames(path: &Path) -> Result<Vec<Frame>, String> {
+    let file =
+        File::open(path).map_err(|err| format!("打开抓包文件{}失败: {}", path.display(), err))?;
+    let mut frames = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| format!("读取抓包文件{}失败: {}", path.display(), err))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = serde_json::from_str(&line)
+            .map_err(|err| format!("抓包文件{}第{}行无效: {}", path.display(), index + 1, err))?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+fn show(value: Option<&Val>) -> String {
+    value.map_or_else(|| "-".to_owned(), Val::to_string)
+}
+
+pub(crate) async fn replay(path: &str, format: Option<ConfigFormat>, args: ReplayArgs) -> ExitCode {
+    let frames = match read_frames(&args.capture) {
+        Ok(frames) => frames,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let devices: Vec<String> = if args.device.is_empty() {
+        let devices: BTreeSet<&str> = frames.iter().map(|frame| frame.device.as_str()).collect();
+        devices.into_iter().map(str::to_owned).collect()
+    } else {
+        args.device.clone()
+    };
+    let loaded = match link::load_devices(path, format, &devices).await {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let center = Arc::new(DataCenter::new(loaded.len()));
+    let replayer = match Replayer::new(loaded, center.clone()) {
+        Ok(replayer) => replayer,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (mut replayed, mut errors, mut mismatches) = (0usize, 0usize, 0usize);
+    let mut replayed_devices = BTreeMap::new();
+    for frame in &frames {
+        let Some(result) = replayer.replay(frame) else {
+            continue;
+        };
+        replayed += 1;
+        *replayed_devices
+            .entry(frame.device.as_str())
+            .or_insert(0usize) += 1;
+        if let Response::Error(err) = &frame.response {
+            errors += 1;
+            if args.verbose {
+                println!(
+                    "[{}] {} {:?} {}..+{} 失败: {}",
+                    frame.at, frame.device, frame.register_type, frame.address, frame.quantity, err
+                );
+            }
+        }
+        if args.verbose {
+            for point in &result.points {
+                println!(
+                    "[{}] {} {} = {} {}",
+                    frame.at,
+                    frame.device,
+                    point.key,
+                    point.value,
+                    point.unit.unwrap_or("")
+                );
+            }
+        }
+        for mismatch in &result.mismatches {
+            mismatches += 1;
+            println!(
+                "[{}] {} {}: 抓取时 {}, 回放 {}",
+                frame.at,
+                frame.device,
+                mismatch.key,
+                show(mismatch.recorded.as_ref()),
+                show(mismatch.replayed.as_ref())
+            );
+        }
+    }
+
+    for (device, count) in &replayed_devices {
+        println!("设备{device}: 回放{count}帧, 最终值:");
+        for point in center.read_all(device).iter() {
+            println!(
+                "  {} ({}) = {} {}",
+                point.key,
+                point.name,
+                point.value,
+                point.unit.unwrap_or("")
+            );
+        }
+    }
+    println!(
+        "共回放{}帧(其中{}帧为失败的请求), 跳过{}帧, {}处解码结果与抓取时不同",
+        replayed,
+        errors,
+        frames.len() - replayed,
+        mismatches
+    );
+    if mismatches > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
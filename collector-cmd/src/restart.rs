@@ -0,0 +1,52 @@
+//! `collector restart`：经控制套接字重启运行中的单个设备，按当前配置重建后重新连接
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use collector_core::config::ConfigFormat;
+use collector_core::control::{self, Reply, Request};
+
+use crate::status::socket_path;
+
+#[derive(Args, Debug)]
+pub(crate) struct RestartArgs {
+    /// 设备 ID
+    device: String,
+    /// 控制套接字，缺省为配置文件中的 control_socket，再缺省为工作目录下的 collector.sock
+    #[arg(long)]
+    socket: Option<PathBuf>,
+}
+
+pub(crate) async fn restart(
+    config: Option<&str>,
+    format: Option<ConfigFormat>,
+    args: RestartArgs,
+) -> ExitCode {
+    let socket = match socket_path(args.socket, config, format).await {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let request = Request::Restart { id: args.device };
+    match control::request(&socket, &request).await {
+        Ok(Reply::Done) => {
+            println!("已重启");
+            ExitCode::SUCCESS
+        }
+        Ok(Reply::Error(err)) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+        Ok(reply) => {
+            eprintln!("意外的应答: {reply:?}");
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! `collector scan`：探测 IP 段或串口总线上的 Modbus 从站，输出响应的从站与往返时间
+
+use std::ops::RangeInclusive;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::Args;
+use collector_core::config::modbus_conf::RegisterType;
+use collector_core::dev::discovery::{self, Probe, ScanTarget};
+
+use crate::link::{SerialArgs, register_type};
+
+#[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("target").required(true))]
+pub(crate) struct ScanArgs {
+    /// 探测的主机范围：`192.168.0.10`、`192.168.0.10-20`、`192.168.0.10-192.168.0.20` 或 `192.168.0.0/24`
+    #[arg(long, group = "target")]
+    hosts: Option<String>,
+    /// Modbus TCP 端口
+    #[arg(long, default_value_t = 502)]
+    port: u16,
+    /// 探测的串口
+    #[arg(long, group = "target")]
+    tty: Option<String>,
+    #[command(flatten)]
+    serial: SerialArgs,
+    /// 探测读取的寄存器类型，可写作功能码
+    #[arg(long, default_value = "HoldingRegisters", value_parser = register_type)]
+    register_type: RegisterType,
+    /// 探测寄存器的协议地址
+    #[arg(long, default_value_t = 0)]
+    address: u16,
+    /// 探测的从站地址范围，如 `1-247` 或 `5`
+    #[arg(long, default_value = "1-247", value_parser = slave_range)]
+    slaves: RangeInclusive<u8>,
+    /// 单个从站的响应超时(ms)
+    #[arg(long, default_value_t = 200)]
+    timeout: u64,
+}
+
+fn slave_range(value: &str) -> Result<RangeInclusive<u8>, String> {
+    let invalid = || format!("无效的从站地址范围 {value}");
+    let (first, last) = value.split_once('-').unwrap_or((value, value));
+    let first: u8 = first.trim().parse().map_err(|_| invalid())?;
+    let last: u8 = last.trim().parse().map_err(|_| invalid())?;
+    if first > last {
+        return Err(invalid());
+    }
+    Ok(first..=last)
+}
+
+pub(crate) async fn scan(args: ScanArgs) -> ExitCode {
+    let target = match (args.hosts, args.tty) {
+        (Some(hosts), _) => match discovery::parse_hosts(&hosts) {
+            Ok(hosts) => ScanTarget::Tcp {
+                hosts,
+                port: args.port,
+            },
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitCode::FAILURE;
+            }
+        },
+        (None, Some(tty)) => ScanTarget::Serial {
+            tty,
+            baud_rate: args.serial.baud_rate,
+            data_bits: args.serial.data_bits,
+            parity: args.serial.parity,
+            stop_bits: args.serial.stop_bits,
+        },
+        (None, None) => unreachable!("clap requires --hosts or --tty"),
+    };
+    let probe = Probe {
+        register_type: args.register_type,
+        address: args.address,
+        slaves: args.slaves,
+        timeout: Duration::from_millis(args.timeout),
+    };
+    let found = match discovery::scan(&target, &probe).await {
+        Ok(found) => found,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if found.is_empty() {
+        println!("未发现从站");
+        return ExitCode::SUCCESS;
+    }
+    println!("{:<24} {:>4} {:>10}  说明", "链路", "从站", "往返(ms)");
+    for responder in &found {
+        println!(
+            "{:<24} {:>4} {:>10.1}  {}",
+            responder.endpoint.to_string(),
+            responder.slave,
+            responder.rtt.as_secs_f64() * 1000.0,
+            responder.exception.as_deref().unwrap_or("")
+        );
+    }
+    println!("共发现{}个从站", found.len());
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,163 @@
+//! `--service`：由 Windows 服务控制管理器启动时按服务运行
+//!
+//! 注册服务时在命令行中带上 `--service` 与配置文件，如
+//! `sc create collector binPath= "C:\collector\collector-cmd.exe --service -c C:\collector\config.json"`；
+//! 服务的工作目录切换到可执行文件所在目录，配置中的相对路径与 `logs` 目录都相对于该目录。
+
+use std::ffi::OsString;
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use collector_core::shutdown::ShutdownManager;
+use windows_service::service::{
+    ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{
+    self, ServiceControlHandlerResult, ServiceStatusHandle,
+};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::supervise::Notifier;
+use crate::{Args, init_tracing, run};
+
+/// 服务名，与注册服务时的名称相同
+const SERVICE_NAME: &str = "collector";
+/// 报告启动中或停止中后，服务控制管理器等待下一次报告的期限
+const WAIT_HINT: Duration = Duration::from_secs(60);
+/// 采集未能启动时向服务控制管理器报告的服务专用退出码
+const RUN_FAILED: u32 = 1;
+
+/// 服务入口在服务控制管理器的线程上调用，参数经此传入
+static ARGS: Mutex<Option<Args>> = Mutex::new(None);
+/// 服务入口中的采集是否失败，服务停止后作为进程退出码
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// 连接服务控制管理器并阻塞到服务停止，不是由服务控制管理器启动或采集失败时返回错误
+pub fn run_service(args: Args) -> ExitCode {
+    if let Some(dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        && let Err(err) = std::env::set_current_dir(&dir)
+    {
+        eprintln!("切换工作目录到{}失败: {}", dir.display(), err);
+        return ExitCode::FAILURE;
+    }
+    *ARGS.lock().expect("service args lock poisoned") = Some(args);
+    match service_dispatcher::start(SERVICE_NAME, ffi_service_main) {
+        Ok(()) if FAILED.load(Ordering::Acquire) => ExitCode::FAILURE,
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("连接服务控制管理器失败: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    let Some(args) = ARGS.lock().expect("service args lock poisoned").take() else {
+        return;
+    };
+    let shutdown = ShutdownManager::new();
+    let token = shutdown.token();
+    let handler = move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            token.cancel();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let Ok(handle) = service_control_handler::register(SERVICE_NAME, handler) else {
+        return;
+    };
+    let mut scm = ServiceManager::new(handle);
+    scm.report(ServiceState::StartPending);
+
+    let log = init_tracing();
+    let code = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => {
+            let notifier: Box<dyn Notifier> = Box::new(scm);
+            runtime.block_on(run(args, shutdown, Some(notifier)))
+        }
+        Err(err) => {
+            tracing::error!("创建运行时失败: {}", err);
+            ExitCode::FAILURE
+        }
+    };
+    drop(log);
+    let mut scm = ServiceManager::new(handle);
+    if code != ExitCode::SUCCESS {
+        FAILED.store(true, Ordering::Release);
+        scm.exit_code = ServiceExitCode::ServiceSpecific(RUN_FAILED);
+    }
+    scm.report(ServiceState::Stopped);
+}
+
+/// 向服务控制管理器报告服务状态
+struct ServiceManager {
+    handle: ServiceStatusHandle,
+    state: ServiceState,
+    /// 启动中、停止中每次报告递增，服务控制管理器据此判断服务仍在推进
+    checkpoint: u32,
+    /// 报告停止时的退出码
+    exit_code: ServiceExitCode,
+}
+
+impl ServiceManager {
+    fn new(handle: ServiceStatusHandle) -> Self {
+        Self {
+            handle,
+            state: ServiceState::StartPending,
+            checkpoint: 0,
+            exit_code: ServiceExitCode::NO_ERROR,
+        }
+    }
+
+    fn report(&mut self, state: ServiceState) {
+        let pending = matches!(
+            state,
+            ServiceState::StartPending | ServiceState::StopPending
+        );
+        self.checkpoint = if pending { self.checkpoint + 1 } else { 0 };
+        self.state = state;
+        let status = ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == ServiceState::StartPending {
+                ServiceControlAccept::empty()
+            } else {
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+            },
+            exit_code: self.exit_code,
+            checkpoint: self.checkpoint,
+            wait_hint: if pending { WAIT_HINT } else { Duration::ZERO },
+            process_id: None,
+        };
+        if let Err(err) = self.handle.set_service_status(status) {
+            tracing::warn!("报告服务状态失败: {}", err);
+        }
+    }
+}
+
+impl Notifier for ServiceManager {
+    fn ready(&mut self, _status: &str) {
+        self.report(ServiceState::Running);
+    }
+
+    fn status(&mut self, _status: &str) {}
+
+    /// 就绪前每次心跳推进启动检查点，设备较多、启动较慢时不被判为启动超时
+    fn watchdog(&mut self) {
+        if self.state == ServiceState::StartPending {
+            self.report(ServiceState::StartPending);
+        }
+    }
+
+    fn stopping(&mut self) {
+        self.report(ServiceState::StopPending);
+    }
+}
@@ -0,0 +1,90 @@
+//! `collector simulate`：按点位表启动 Modbus TCP 从站模拟器，不需要设备即可跑通采集流程
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::Args;
+use collector_core::config::modbus_conf::ByteOrder;
+use collector_core::dev::simulator::{Simulator, SimulatorOptions, Waveform};
+use collector_core::shutdown::ShutdownManager;
+use tokio::net::TcpListener;
+
+#[derive(Args, Debug)]
+pub(crate) struct SimulateArgs {
+    /// 点位表，与设备配置的 register_file 相同，可以是通配符
+    register_file: String,
+    /// 监听地址
+    #[arg(long, default_value = "127.0.0.1:5020")]
+    listen: SocketAddr,
+    /// 点位表地址的起始编号，与设备配置的 address_base 相同
+    #[arg(long, default_value_t = 0)]
+    address_base: u16,
+    /// 点位表中未指定字节序的点位使用的字节序，与设备配置的 byte_order 相同
+    #[arg(long, value_parser = byte_order)]
+    byte_order: Option<ByteOrder>,
+    /// 取值规律：fixed 固定值、random 随机值、ramp 斜坡
+    #[arg(long, default_value = "fixed")]
+    mode: Waveform,
+    /// 数值点位的最小工程值
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    min: f64,
+    /// 数值点位的最大工程值
+    #[arg(long, default_value_t = 100.0, allow_hyphen_values = true)]
+    max: f64,
+    /// 斜坡的周期(s)
+    #[arg(long, default_value_t = 60)]
+    period: u64,
+    /// 随机值与斜坡的刷新间隔(ms)
+    #[arg(long, default_value_t = 1000)]
+    interval: u64,
+}
+
+fn byte_order(value: &str) -> Result<ByteOrder, String> {
+    ByteOrder::try_from(Some(value)).map_err(|_| format!("无效的字节序 {value}"))
+}
+
+pub(crate) async fn simulate(args: SimulateArgs) -> ExitCode {
+    let options = SimulatorOptions {
+        waveform: args.mode,
+        min: args.min,
+        max: args.max,
+        period: Duration::from_secs(args.period),
+        interval: Duration::from_millis(args.interval.max(1)),
+    };
+    let simulator = match Simulator::load(
+        &args.register_file,
+        args.address_base,
+        args.byte_order,
+        options,
+    )
+    .await
+    {
+        Ok(simulator) => simulator,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let listener = match TcpListener::bind(args.listen).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("监听{}失败: {}", args.listen, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "模拟器已在{}上运行, 共{}个点位, 按 Ctrl+C 退出",
+        args.listen,
+        simulator.points().len()
+    );
+    let shutdown = ShutdownManager::new();
+    tokio::spawn(shutdown.clone().listen_shutdown_signal());
+    match simulator.serve(listener, shutdown.token()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
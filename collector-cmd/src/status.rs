@@ -0,0 +1,134 @@
+//! `collector status`：经控制套接字查询运行中的采集程序，输出各设备的状态、最近读取时间与错误计数
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+use collector_core::config::ConfigFormat;
+use collector_core::control::{self, Reply, Request};
+use collector_core::dev::LifecycleState;
+use collector_core::dev::manager::DeviceStatus;
+
+use crate::reload::load_config;
+
+#[derive(Args, Debug)]
+pub(crate) struct StatusArgs {
+    /// 控制套接字，缺省为配置文件中的 control_socket，再缺省为工作目录下的 collector.sock
+    #[arg(long)]
+    socket: Option<PathBuf>,
+    /// 以 JSON 输出
+    #[arg(long)]
+    json: bool,
+}
+
+/// 控制套接字：`--socket`，缺省为配置文件中的 control_socket，再缺省为工作目录下的 collector.sock
+pub(crate) async fn socket_path(
+    socket: Option<PathBuf>,
+    config: Option<&str>,
+    format: Option<ConfigFormat>,
+) -> Result<PathBuf, String> {
+    match (socket, config) {
+        (Some(socket), _) => Ok(socket),
+        (None, Some(path)) => match load_config(path, format).await {
+            Ok(p) => Ok(PathBuf::from(
+                p.project
+                    .control_socket
+                    .unwrap_or_else(|| control::DEFAULT_SOCKET.to_owned()),
+            )),
+            Err(err) => Err(err.to_string()),
+        },
+        (None, None) => Ok(PathBuf::from(control::DEFAULT_SOCKET)),
+    }
+}
+
+pub(crate) async fn status(
+    config: Option<&str>,
+    format: Option<ConfigFormat>,
+    args: StatusArgs,
+) -> ExitCode {
+    let socket = match socket_path(args.socket, config, format).await {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let devices = match control::request(&socket, &Request::Status).await {
+        Ok(Reply::Status(devices)) => devices,
+        Ok(Reply::Error(err)) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+        Ok(reply) => {
+            eprintln!("意外的应答: {reply:?}");
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if args.json {
+        match serde_json::to_string_pretty(&devices) {
+            Ok(text) => println!("{text}"),
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitCode::FAILURE;
+            }
+        }
+        return ExitCode::SUCCESS;
+    }
+    print_table(&devices);
+    ExitCode::SUCCESS
+}
+
+fn print_table(devices: &[DeviceStatus]) {
+    println!(
+        "{:<16} {:<10} {:<8} {:>10} {:>6} {:>6} {:>6} {:>6} {:>8}  最近失败",
+        "设备", "类型", "状态", "最近读取", "超时", "异常", "重连", "重启", "连续失败"
+    );
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64);
+    for dev in devices {
+        let com_type = dev
+            .com_type
+            .map_or_else(|| "-".to_owned(), |com| format!("{com:?}"));
+        let state = if dev.backup_active {
+            format!("{}(备用)", dev.state)
+        } else {
+            dev.state.to_string()
+        };
+        println!(
+            "{:<16} {:<10} {:<8} {:>10} {:>6} {:>6} {:>6} {:>6} {:>8}  {}",
+            dev.id,
+            com_type,
+            state,
+            dev.last_poll_ms
+                .map_or_else(|| "-".to_owned(), |at| age(now.saturating_sub(at))),
+            dev.timeouts,
+            dev.exceptions,
+            dev.reconnects,
+            dev.restarts,
+            dev.consecutive_failures,
+            dev.last_failure.as_deref().unwrap_or("")
+        );
+    }
+    let count = |state: LifecycleState| devices.iter().filter(|dev| dev.state == state).count();
+    println!(
+        "共{}个设备, {}个运行中, {}个失败",
+        devices.len(),
+        count(LifecycleState::Running),
+        count(LifecycleState::Failed)
+    );
+}
+
+/// 距今的时间，如 `3秒前`、`5分前`
+fn age(ms: u64) -> String {
+    match ms / 1000 {
+        secs if secs < 60 => format!("{secs}秒前"),
+        secs if secs < 3600 => format!("{}分前", secs / 60),
+        secs => format!("{}时前", secs / 3600),
+    }
+}
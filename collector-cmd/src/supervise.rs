@@ -0,0 +1,299 @@
+//! 向进程管理器报告运行状态：systemd 按 sd_notify 协议，Windows 服务向服务控制管理器报告
+//!
+//! 所有设备完成初始化(已运行、失败或停止)后报告就绪，之后定期报告各状态的设备数。
+//! 看门狗心跳在取得所有设备的状态后才发送，设备管理器或设备任务卡死时心跳随之停止，
+//! 进程管理器由此能发现挂起的采集程序，而不只是已退出的进程。
+//! 分阶段启动期间设备管理器被占用，改为直接查询启动前取得的设备句柄，启动较慢时状态与心跳照常报告。
+
+use std::fmt::{self, Display};
+use std::sync::Arc;
+use std::time::Duration;
+
+use collector_core::dev::LifecycleState;
+use collector_core::dev::handle::DeviceHandle;
+use collector_core::dev::manager::{DevManager, DeviceStatus};
+use collector_core::shutdown::ShutdownManager;
+use tokio::sync::{Mutex, watch};
+use tokio::time::Instant;
+use tracing::warn;
+
+/// 没有看门狗时报告状态的间隔
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+/// 启动后超过该时间仍有设备在连接时也报告就绪，避免进程管理器因启动超时而终止采集程序
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 进程管理器
+pub(crate) trait Notifier: Send {
+    /// 报告状态与发送心跳的间隔
+    fn interval(&self) -> Duration {
+        STATUS_INTERVAL
+    }
+
+    /// 所有设备已完成初始化
+    fn ready(&mut self, status: &str);
+
+    /// 各状态的设备数，就绪前也会定期调用
+    fn status(&mut self, status: &str);
+
+    /// 看门狗心跳
+    fn watchdog(&mut self) {}
+
+    /// 开始停止
+    fn stopping(&mut self);
+}
+
+/// 各状态的设备数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Counts {
+    running: usize,
+    starting: usize,
+    paused: usize,
+    failed: usize,
+    stopped: usize,
+}
+
+impl Counts {
+    fn new(statuses: &[DeviceStatus]) -> Self {
+        let mut counts = Self::default();
+        for status in statuses {
+            match status.state {
+                LifecycleState::Connected | LifecycleState::Running => counts.running += 1,
+                LifecycleState::Paused => counts.paused += 1,
+                LifecycleState::Failed => counts.failed += 1,
+                LifecycleState::Stopping | LifecycleState::Stopped => counts.stopped += 1,
+                LifecycleState::New
+                | LifecycleState::Initializing
+                | LifecycleState::Ready
+                | LifecycleState::Starting
+                | LifecycleState::Connecting => counts.starting += 1,
+            }
+        }
+        counts
+    }
+
+    fn initialized(&self) -> bool {
+        self.starting == 0
+    }
+}
+
+impl Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.running + self.starting + self.paused + self.failed + self.stopped;
+        write!(
+            f,
+            "设备{}台: 运行{}, 启动中{}, 暂停{}, 故障{}, 停止{}",
+            total, self.running, self.starting, self.paused, self.failed, self.stopped
+        )
+    }
+}
+
+/// 是否报告就绪：所有设备已完成初始化，或启动后已超过 [`READY_TIMEOUT`]
+fn ready_to_report(counts: &Counts, elapsed: Duration) -> bool {
+    counts.initialized() || elapsed >= READY_TIMEOUT
+}
+
+/// 按启动环境选择进程管理器：由 systemd 以 `Type=notify` 启动时向其报告，否则不报告
+pub(crate) fn from_env() -> Option<Box<dyn Notifier>> {
+    #[cfg(unix)]
+    {
+        Systemd::from_env().map(|systemd| Box::new(systemd) as Box<dyn Notifier>)
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// 定期取得所有设备的状态并报告，直到关闭
+///
+/// `started` 变为 `true` 之前查询 `handles`，之后经设备管理器查询，热更新增删的设备也计入；
+/// 热更新重启设备期间持有设备管理器的锁，看门狗周期(`WatchdogSec`)须长于设备的停止期限
+pub(crate) async fn supervise(
+    manager: Arc<Mutex<DevManager>>,
+    handles: Vec<DeviceHandle>,
+    started: watch::Receiver<bool>,
+    mut notifier: Box<dyn Notifier>,
+    shutdown: ShutdownManager,
+) {
+    let start = Instant::now();
+    let mut ready = false;
+    let mut last = None;
+    let mut ticker = tokio::time::interval(notifier.interval());
+    loop {
+        tokio::select! {
+            _ = shutdown.wait_for_shutdown() => break,
+            _ = ticker.tick() => {}
+        }
+        let statuses = if *started.borrow() {
+            manager.lock().await.status().await
+        } else {
+            let mut statuses = Vec::with_capacity(handles.len());
+            for handle in &handles {
+                statuses.push(handle.status().await);
+            }
+            statuses
+        };
+        let counts = Counts::new(&statuses);
+        let status = counts.to_string();
+        if !ready && ready_to_report(&counts, start.elapsed()) {
+            if !counts.initialized() {
+                warn!(
+                    "{}s内仍有设备未完成初始化, 报告就绪: {}",
+                    READY_TIMEOUT.as_secs(),
+                    status
+                );
+            }
+            notifier.ready(&status);
+            ready = true;
+        } else if last.as_ref() != Some(&status) {
+            notifier.status(&status);
+        }
+        last = Some(status);
+        notifier.watchdog();
+    }
+    notifier.stopping();
+}
+
+/// systemd 的 `NOTIFY_SOCKET`
+#[cfg(unix)]
+struct Systemd {
+    /// `WatchdogSec`，未启用看门狗时为 `None`
+    watchdog: Option<Duration>,
+}
+
+#[cfg(unix)]
+impl Systemd {
+    fn from_env() -> Option<Self> {
+        std::env::var_os("NOTIFY_SOCKET")?;
+        let mut usec = 0;
+        let watchdog =
+            sd_notify::watchdog_enabled(false, &mut usec).then(|| Duration::from_micros(usec));
+        Some(Self { watchdog })
+    }
+
+    fn notify(&self, states: &[sd_notify::NotifyState]) {
+        if let Err(err) = sd_notify::notify(false, states) {
+            warn!("通知systemd失败: {}", err);
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Notifier for Systemd {
+    /// 心跳间隔取看门狗周期的一半
+    fn interval(&self) -> Duration {
+        self.watchdog
+            .map_or(STATUS_INTERVAL, |watchdog| {
+                (watchdog / 2).min(STATUS_INTERVAL)
+            })
+            .max(Duration::from_millis(100))
+    }
+
+    fn ready(&mut self, status: &str) {
+        self.notify(&[
+            sd_notify::NotifyState::Ready,
+            sd_notify::NotifyState::Status(status),
+        ]);
+    }
+
+    fn status(&mut self, status: &str) {
+        self.notify(&[sd_notify::NotifyState::Status(status)]);
+    }
+
+    fn watchdog(&mut self) {
+        if self.watchdog.is_some() {
+            self.notify(&[sd_notify::NotifyState::Watchdog]);
+        }
+    }
+
+    fn stopping(&mut self) {
+        self.notify(&[
+            sd_notify::NotifyState::Stopping,
+            sd_notify::NotifyState::Status("正在停止"),
+        ]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use collector_core::dev::LifecycleState;
+    use collector_core::dev::manager::DeviceStatus;
+
+    use super::{Counts, READY_TIMEOUT, ready_to_report};
+
+    fn status(state: LifecycleState) -> DeviceStatus {
+        DeviceStatus {
+            id: String::from("pcs"),
+            com_type: None,
+            state,
+            last_poll_ms: None,
+            timeouts: 0,
+            exceptions: 0,
+            reconnects: 0,
+            backup_active: false,
+            restarts: 0,
+            uptime_percent: None,
+            consecutive_failures: 0,
+            failures: 0,
+            mtbf_secs: None,
+            last_failure: None,
+        }
+    }
+
+    #[test]
+    fn counts_group_lifecycle_states() {
+        let statuses: Vec<_> = [
+            LifecycleState::Connected,
+            LifecycleState::Running,
+            LifecycleState::Connecting,
+            LifecycleState::New,
+            LifecycleState::Paused,
+            LifecycleState::Failed,
+            LifecycleState::Stopping,
+            LifecycleState::Stopped,
+        ]
+        .into_iter()
+        .map(status)
+        .collect();
+        let counts = Counts::new(&statuses);
+        assert_eq!(
+            counts,
+            Counts {
+                running: 2,
+                starting: 2,
+                paused: 1,
+                failed: 1,
+                stopped: 2,
+            }
+        );
+        assert!(!counts.initialized());
+        assert_eq!(
+            counts.to_string(),
+            "设备8台: 运行2, 启动中2, 暂停1, 故障1, 停止2"
+        );
+    }
+
+    #[test]
+    fn failed_and_stopped_devices_count_as_initialized() {
+        let statuses: Vec<_> = [LifecycleState::Running, LifecycleState::Failed]
+            .into_iter()
+            .map(status)
+            .collect();
+        assert!(Counts::new(&statuses).initialized());
+        assert!(Counts::new(&[]).initialized());
+    }
+
+    #[test]
+    fn ready_after_initialization_or_timeout() {
+        let starting = Counts::new(&[status(LifecycleState::Connecting)]);
+        let running = Counts::new(&[status(LifecycleState::Running)]);
+        assert!(ready_to_report(&running, Duration::ZERO));
+        assert!(!ready_to_report(
+            &starting,
+            READY_TIMEOUT - Duration::from_millis(1)
+        ));
+        assert!(ready_to_report(&starting, READY_TIMEOUT));
+    }
+}
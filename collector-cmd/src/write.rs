@@ -0,0 +1,89 @@
+//! `collector write`：按设备配置连接设备，下发一次点位并回读校验
+//!
+//! 编码与检查与运行时下发相同；未加 `--confirm` 时只输出将要下发的内容，不写入设备。
+
+use std::process::ExitCode;
+
+use clap::Args;
+use collector_core::config::ConfigFormat;
+use collector_core::core::point::Val;
+
+use crate::link;
+use crate::read::print_reading;
+
+#[derive(Args, Debug)]
+pub(crate) struct WriteArgs {
+    /// 设备的键或 ID
+    #[arg(long)]
+    device: String,
+    /// 点位的键名或名称
+    #[arg(long)]
+    point: String,
+    /// 下发的工程值，如 `1`、`-2.5`、`true`
+    #[arg(long, value_parser = value, allow_hyphen_values = true)]
+    value: Val,
+    /// 确认下发，未指定时只检查能否编码
+    #[arg(long)]
+    confirm: bool,
+}
+
+fn value(value: &str) -> Result<Val, String> {
+    Ok(match value {
+        "true" | "on" => Val::U8(1),
+        "false" | "off" => Val::U8(0),
+        _ => match (
+            value.parse::<i32>(),
+            value.parse::<u32>(),
+            value.parse::<f64>(),
+        ) {
+            (Ok(v), _, _) => Val::I32(v),
+            (_, Ok(v), _) => Val::U32(v),
+            (_, _, Ok(v)) => Val::F64(v),
+            _ => Val::Str(value.to_owned()),
+        },
+    })
+}
+
+pub(crate) async fn write(path: &str, format: Option<ConfigFormat>, args: WriteArgs) -> ExitCode {
+    let mut session = match link::connect(path, format, &args.device).await {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(cfg) = session.point(&args.point) else {
+        eprintln!("设备{}没有点位{}", args.device, args.point);
+        return ExitCode::FAILURE;
+    };
+    let plan = match session.plan_write(&cfg, args.value.clone()) {
+        Ok(plan) => plan,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "{} {:?} {}..+{} ({:?}) ← {}",
+        cfg.name, cfg.register_type, cfg.register_address, cfg.quantity, cfg.data_type, args.value
+    );
+    if !args.confirm {
+        eprintln!("未指定 --confirm, 未下发");
+        return ExitCode::FAILURE;
+    }
+    if let Err(err) = session.write(plan).await {
+        eprintln!("{err}");
+        return ExitCode::FAILURE;
+    }
+    println!("下发成功, 回读:");
+    match session.read(&cfg).await {
+        Ok(reading) => {
+            print_reading(&reading);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
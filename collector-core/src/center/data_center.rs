@@ -8,6 +8,7 @@
 //! 2. **数据查询（Read）**：支持单点查询、批量查询和全量查询
 //! 3. **数据下发（Dispatch）**：将控制指令下发到设备
 //! 4. **数据订阅（Subscribe）**：实时推送数据变化通知
+//! 5. **读改写（Update/CAS）**：在缓存锁内原子地修改数据点，避免与轮询写入竞争
 //!
 //! ## 架构设计
 //!
@@ -30,18 +31,19 @@
 //! - **快照缓存**：避免重复排序和克隆
 //! - **零拷贝**：使用 Arc 共享数据
 //! - **变化检测**：只在数据实际变化时更新版本号和推送通知
+//! - **浮点容差**：浮点值在容差（全局或单点配置）内的抖动不视为变化
 
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use ahash::AHashMap;
 
 use dashmap::DashMap;
-use tokio::sync::watch;
+use tokio::sync::{oneshot, watch};
 use tracing::warn;
 
 use crate::{
-    center::{DataCenterError, DownlinkSender, PointCenter},
-    core::point::{DataPoint, DownDataPoint, PointId},
+    center::{DataCenterError, DownlinkCommand, DownlinkSender, PointCenter},
+    core::point::{DataPoint, DownDataPoint, PointId, PointRef, Quality, Val},
 };
 
 /// 数据中心主结构
@@ -55,6 +57,10 @@ pub struct DataCenter {
     /// 设备缓存映射：设备ID -> 设备缓存
     /// 使用 Arc<RwLock> 实现多线程安全的读写访问
     devices: DashMap<String, Arc<RwLock<DeviceCache>>>,
+
+    /// 全局浮点比较容差
+    /// 浮点值变化不超过该值时不视为变化，默认 0 表示精确比较
+    float_epsilon: f64,
 }
 
 impl DataCenter {
@@ -66,9 +72,19 @@ impl DataCenter {
         Self {
             downlinks: DashMap::with_capacity(dev_len),
             devices: DashMap::with_capacity(dev_len),
+            float_epsilon: 0.0,
         }
     }
 
+    /// 设置全局浮点比较容差
+    ///
+    /// # 参数
+    /// * `epsilon` - 浮点值变化的容差，负数或非有限值按 0 处理
+    pub fn with_float_epsilon(mut self, epsilon: f64) -> Self {
+        self.float_epsilon = sanitize_epsilon(epsilon);
+        self
+    }
+
     /// 获取或创建设备缓存
     ///
     /// 如果设备不存在，会自动创建一个新的缓存
@@ -148,6 +164,10 @@ struct DeviceCache {
     /// 数据更新通知发送器
     /// 用于向订阅者推送数据变化通知
     update_tx: Option<watch::Sender<Arc<[DataPoint]>>>,
+
+    /// 单点浮点比较容差：PointId -> epsilon
+    /// 未配置的点使用全局容差
+    epsilons: AHashMap<PointId, f64>,
 }
 
 impl Default for DeviceCache {
@@ -160,10 +180,82 @@ impl Default for DeviceCache {
             version: 0,
             snapshot_version: 0,
             update_tx: None,
+            epsilons: AHashMap::new(),
+        }
+    }
+}
+
+impl DeviceCache {
+    /// 递增版本号并向订阅者推送最新快照
+    ///
+    /// 只在有订阅者时才构建快照，无订阅者时顺带清理发送器
+    fn publish(&mut self) {
+        // 递增版本号
+        self.version = self.version.wrapping_add(1);
+        // 通过借用快速拿到 tx 并克隆，随后立即释放对 cache 的不可变借用
+        let active_tx = self.update_tx.as_ref().and_then(|tx| {
+            if tx.receiver_count() == 0 {
+                None // 没订阅者了
+            } else {
+                Some(tx.clone()) // 还有订阅者，克隆一个通道发送端
+            }
+        });
+        // 根据 active_tx 的状态来分流
+        match active_tx {
+            None => {
+                // 进到这里有两种可能：
+                // a) 本来 update_tx 就是 None
+                // b) receiver_count 为 0
+                // 如果原本有 tx 但没订阅者了，顺手把它抹掉清理掉
+                if self.update_tx.is_some() {
+                    self.update_tx = None;
+                }
+            }
+            Some(tx) => {
+                // 此时 tx 是一个独立的变量，与 cache 没有任何借用瓜葛了！
+                // 我们可以安全地以可变借用访问 cache 里的所有字段
+                let mut points: Vec<DataPoint> = self.latest_by_id.values().cloned().collect();
+                points.sort_by_key(|point| point.id);
+                let snapshot: Arc<[DataPoint]> = Arc::from(points.into_boxed_slice());
+
+                // 更新缓存快照（尽情修改，不会报错）
+                self.snapshot = snapshot.clone();
+                self.snapshot_version = self.version;
+
+                // 发送更新
+                let _ = tx.send(snapshot);
+            }
         }
     }
 }
 
+/// 规范化容差：负数、NaN、无穷大均按 0（精确比较）处理
+fn sanitize_epsilon(epsilon: f64) -> f64 {
+    if epsilon.is_finite() && epsilon > 0.0 {
+        epsilon
+    } else {
+        0.0
+    }
+}
+
+/// 判断新旧值是否视为相同
+///
+/// 任意一侧为浮点数时按容差比较数值，列表逐项比较，其余类型精确比较
+fn same_value(old: &Val, new: &Val, epsilon: f64) -> bool {
+    match (old, new) {
+        (Val::List(a), Val::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_value(a, b, epsilon))
+        }
+        (Val::F64(_), _) | (_, Val::F64(_)) if epsilon > 0.0 => {
+            match (old.as_f64(), new.as_f64()) {
+                (Ok(a), Ok(b)) => (a - b).abs() <= epsilon,
+                _ => false,
+            }
+        }
+        _ => old == new,
+    }
+}
+
 #[async_trait::async_trait]
 impl PointCenter for DataCenter {
     /// 摄入数据点
@@ -173,7 +265,7 @@ impl PointCenter for DataCenter {
     /// # 性能优化
     /// - 只在数据实际变化时更新版本号
     /// - 只在有订阅者时才构建快照
-    /// - 使用值比较避免无效更新
+    /// - 使用值比较避免无效更新，浮点值按容差比较
     fn ingest(&self, dev_id: &str, points: Vec<DataPoint>) {
         let device = self.get_or_create_device(dev_id);
         let mut cache = Self::write_cache(&device, dev_id);
@@ -183,11 +275,17 @@ impl PointCenter for DataCenter {
         // 遍历所有数据点，只更新值发生变化的点
         for point in points {
             let point_id = point.id;
-            let new_value = point.value.clone();
+            let epsilon = cache
+                .epsilons
+                .get(&point_id)
+                .copied()
+                .unwrap_or(self.float_epsilon);
 
             match cache.latest_by_id.get(&point_id) {
-                // 如果值相同，跳过更新
-                Some(old) if old.value == new_value => {}
+                // 如果值相同（或在容差内）且质量未变，跳过更新
+                Some(old)
+                    if old.quality == point.quality
+                        && same_value(&old.value, &point.value, epsilon) => {}
                 // 如果值不同或点不存在，更新缓存
                 _ => {
                     // 更新索引
@@ -200,43 +298,148 @@ impl PointCenter for DataCenter {
         }
 
         if changed {
-            // 递增版本号
-            cache.version = cache.version.wrapping_add(1);
-            // 通过借用快速拿到 tx 并克隆，随后立即释放对 cache 的不可变借用
-            let active_tx = cache.update_tx.as_ref().and_then(|tx| {
-                if tx.receiver_count() == 0 {
-                    None // 没订阅者了
-                } else {
-                    Some(tx.clone()) // 还有订阅者，克隆一个通道发送端
-                }
-            });
-            // 根据 active_tx 的状态来分流
-            match active_tx {
-                None => {
-                    // 进到这里有两种可能：
-                    // a) 本来 update_tx 就是 None
-                    // b) receiver_count 为 0
-                    // 如果原本有 tx 但没订阅者了，顺手把它抹掉清理掉
-                    if cache.update_tx.is_some() {
-                        cache.update_tx = None;
-                    }
-                }
-                Some(tx) => {
-                    // 此时 tx 是一个独立的变量，与 cache 没有任何借用瓜葛了！
-                    // 我们可以安全地以可变借用访问 cache 里的所有字段
-                    let mut points: Vec<DataPoint> = cache.latest_by_id.values().cloned().collect();
-                    points.sort_by_key(|point| point.id);
-                    let snapshot: Arc<[DataPoint]> = Arc::from(points.into_boxed_slice());
-
-                    // 更新缓存快照（尽情修改，不会报错）
-                    cache.snapshot = snapshot.clone();
-                    cache.snapshot_version = cache.version;
-
-                    // 发送更新
-                    let _ = tx.send(snapshot);
-                }
+            cache.publish();
+        }
+    }
+
+    /// 设置单个数据点的浮点比较容差，覆盖全局容差
+    ///
+    /// # 参数
+    /// * `dev_id` - 设备ID
+    /// * `point_id` - 数据点ID
+    /// * `epsilon` - 浮点值变化的容差，负数或非有限值按 0 处理；`None` 时恢复按全局容差比较
+    fn set_point_epsilon(&self, dev_id: &str, point_id: PointId, epsilon: Option<f64>) {
+        let device = self.get_or_create_device(dev_id);
+        let mut cache = Self::write_cache(&device, dev_id);
+        match epsilon {
+            Some(epsilon) => {
+                cache.epsilons.insert(point_id, sanitize_epsilon(epsilon));
+            }
+            None => {
+                cache.epsilons.remove(&point_id);
+            }
+        }
+    }
+
+    /// 将缓存中的数据点标记为坏质量，保留原值
+    ///
+    /// 不存在或已是坏质量的点位忽略，有点位变化时通知订阅者
+    fn mark_bad(&self, dev_id: &str, point_ids: &[PointId]) {
+        let Some(device) = self.devices.get(dev_id).map(|it| it.clone()) else {
+            return;
+        };
+        let mut cache = Self::write_cache(&device, dev_id);
+        let mut changed = false;
+        for id in point_ids {
+            if let Some(point) = cache.latest_by_id.get_mut(id)
+                && point.quality != Quality::Bad
+            {
+                point.quality = Quality::Bad;
+                changed = true;
+            }
+        }
+        if changed {
+            cache.publish();
+        }
+    }
+
+    /// 将缓存中的数据点标记为通讯故障，有替代值时同时覆盖原值
+    ///
+    /// 不存在的点位忽略，有点位变化时通知订阅者
+    fn mark_comm_fail(&self, dev_id: &str, point_ids: &[PointId], substitute: Option<&Val>) {
+        let Some(device) = self.devices.get(dev_id).map(|it| it.clone()) else {
+            return;
+        };
+        let mut cache = Self::write_cache(&device, dev_id);
+        let mut changed = false;
+        for id in point_ids {
+            let Some(point) = cache.latest_by_id.get_mut(id) else {
+                continue;
+            };
+            if point.quality != Quality::CommFail {
+                point.quality = Quality::CommFail;
+                changed = true;
+            }
+            if let Some(value) = substitute
+                && point.value != *value
+            {
+                point.value = value.clone();
+                changed = true;
             }
         }
+        if changed {
+            cache.publish();
+        }
+    }
+
+    /// 读改写数据点
+    ///
+    /// 在设备写锁内以旧值计算新值并写回，期间轮询线程的 ingest 会被阻塞，
+    /// 因此不会丢失并发更新。新值与旧值相同(浮点值在容差内)时保留旧值、不递增版本号，
+    /// 返回缓存中的值。
+    ///
+    /// # 错误
+    /// - `NotFoundPoint` - 如果设备或数据点不存在
+    fn update(
+        &self,
+        dev_id: &str,
+        key: &str,
+        f: &mut dyn FnMut(&Val) -> Val,
+    ) -> Result<Val, DataCenterError> {
+        let device = self
+            .devices
+            .get(dev_id)
+            .map(|it| it.clone())
+            .ok_or_else(|| DataCenterError::NotFoundPoint(dev_id.to_owned(), key.to_owned()))?;
+        let mut cache = Self::write_cache(&device, dev_id);
+        let point_id = cache
+            .by_key
+            .get(key)
+            .copied()
+            .ok_or_else(|| DataCenterError::NotFoundPoint(dev_id.to_owned(), key.to_owned()))?;
+        let epsilon = cache
+            .epsilons
+            .get(&point_id)
+            .copied()
+            .unwrap_or(self.float_epsilon);
+        let Some(point) = cache.latest_by_id.get_mut(&point_id) else {
+            return Err(DataCenterError::NotFoundPoint(
+                dev_id.to_owned(),
+                key.to_owned(),
+            ));
+        };
+        let new = f(&point.value);
+        if same_value(&point.value, &new, epsilon) {
+            return Ok(point.value.clone());
+        }
+        point.value = new.clone();
+        cache.publish();
+        Ok(new)
+    }
+
+    /// 比较并交换数据点
+    ///
+    /// 当前值等于 `expected` 时写入 `new` 并返回 `true`，否则不修改并返回 `false`
+    ///
+    /// # 错误
+    /// - `NotFoundPoint` - 如果设备或数据点不存在
+    fn cas(
+        &self,
+        dev_id: &str,
+        key: &str,
+        expected: &Val,
+        new: Val,
+    ) -> Result<bool, DataCenterError> {
+        let mut swapped = false;
+        self.update(dev_id, key, &mut |old| {
+            if old == expected {
+                swapped = true;
+                new.clone()
+            } else {
+                old.clone()
+            }
+        })?;
+        Ok(swapped)
     }
 
     /// 下发数据点到设备
@@ -252,7 +455,48 @@ impl PointCenter for DataCenter {
             .get(dev_id)
             .ok_or_else(|| DataCenterError::NotFoundDevError(dev_id.to_owned()))?
             .clone();
-        sender.send(points).await.map_err(Into::into)
+        sender
+            .send(DownlinkCommand::new(points))
+            .await
+            .map_err(Into::into)
+    }
+
+    /// 原子下发数据点到设备
+    ///
+    /// 附带应答通道发送给设备驱动，等待驱动回报整批下发的结果
+    async fn dispatch_atomic(
+        &self,
+        dev_id: &str,
+        points: Vec<DownDataPoint>,
+    ) -> Result<(), DataCenterError> {
+        let sender = self
+            .downlinks
+            .get(dev_id)
+            .ok_or_else(|| DataCenterError::NotFoundDevError(dev_id.to_owned()))?
+            .clone();
+        let (ack_tx, ack_rx) = oneshot::channel();
+        sender.send(DownlinkCommand::atomic(points, ack_tx)).await?;
+        ack_rx.await.map_err(|_| DataCenterError::AckDropped)?
+    }
+
+    /// 按需读取数据点
+    ///
+    /// 附带应答通道发送给设备驱动，等待驱动回报读取结果
+    async fn refresh(
+        &self,
+        dev_id: &str,
+        points: Vec<PointRef>,
+    ) -> Result<Vec<DataPoint>, DataCenterError> {
+        let sender = self
+            .downlinks
+            .get(dev_id)
+            .ok_or_else(|| DataCenterError::NotFoundDevError(dev_id.to_owned()))?
+            .clone();
+        let (ack_tx, ack_rx) = oneshot::channel();
+        sender.send(DownlinkCommand::read(points, ack_tx)).await?;
+        ack_rx
+            .await
+            .map_err(|_| DataCenterError::ReadFailed("设备未回报读取结果".into()))?
     }
 
     /// 读取单个数据点
@@ -360,6 +604,14 @@ impl PointCenter for DataCenter {
         self.downlinks.remove(dev_id);
     }
 
+    /// 注销设备
+    ///
+    /// 移除设备的缓存与下行数据发送器，之后的读取如同设备不存在
+    fn remove_device(&self, dev_id: &str) {
+        self.downlinks.remove(dev_id);
+        self.devices.remove(dev_id);
+    }
+
     /// 订阅指定设备的数据更新
     ///
     /// 返回一个 watch::Receiver，当设备数据有更新时会收到新的快照。
@@ -410,8 +662,8 @@ mod tests {
 
     use super::DataCenter;
     use crate::{
-        center::PointCenter,
-        core::point::{DataPoint, Val},
+        center::{DataCenterError, DownlinkCommand, PointCenter},
+        core::point::{DataPoint, DownDataPoint, PointRef, Quality, Val},
     };
 
     fn point(id: u32, value: u8) -> DataPoint {
@@ -424,6 +676,7 @@ mod tests {
             bits: None,
             words: None,
             unit: None,
+            quality: Quality::Good,
         }
     }
 
@@ -438,6 +691,56 @@ mod tests {
         assert_eq!(ids, vec![1, 2, 3]);
     }
 
+    #[test]
+    fn bad_quality_is_cleared_by_next_ingest() {
+        let center = DataCenter::new(1);
+        center.ingest("dev-1", vec![point(1, 1), point(2, 2)]);
+
+        center.mark_bad("dev-1", &[2, 9]);
+
+        assert_eq!(center.read("dev-1", 1).unwrap().quality, Quality::Good);
+        let bad = center.read("dev-1", 2).unwrap();
+        assert_eq!((bad.quality, bad.value), (Quality::Bad, Val::U8(2)));
+
+        center.ingest("dev-1", vec![point(2, 2)]);
+
+        assert_eq!(center.read("dev-1", 2).unwrap().quality, Quality::Good);
+    }
+
+    #[test]
+    fn comm_fail_applies_substitute_value() {
+        let center = DataCenter::new(1);
+        center.ingest("dev-1", vec![point(1, 1), point(2, 2)]);
+
+        center.mark_comm_fail("dev-1", &[1], None);
+        center.mark_comm_fail("dev-1", &[2], Some(&Val::F64(-1.0)));
+
+        let kept = center.read("dev-1", 1).unwrap();
+        assert_eq!((kept.quality, kept.value), (Quality::CommFail, Val::U8(1)));
+        let substituted = center.read("dev-1", 2).unwrap();
+        assert_eq!(
+            (substituted.quality, substituted.value),
+            (Quality::CommFail, Val::F64(-1.0))
+        );
+
+        center.ingest("dev-1", vec![point(2, 2)]);
+
+        assert_eq!(center.read("dev-1", 2).unwrap().quality, Quality::Good);
+    }
+
+    #[test]
+    fn removed_device_is_forgotten() {
+        let center = DataCenter::new(1);
+        center.ingest("dev-1", vec![point(1, 1)]);
+        let rx = center.subscribe("dev-1").unwrap();
+
+        center.remove_device("dev-1");
+
+        assert!(center.read("dev-1", 1).is_none());
+        assert!(center.dev_ids().is_empty());
+        assert!(rx.has_changed().is_err());
+    }
+
     #[test]
     fn read_all_reuses_snapshot_when_cache_unchanged() {
         let center = DataCenter::new(1);
@@ -477,4 +780,174 @@ mod tests {
 
         assert!(Arc::ptr_eq(&first, &second));
     }
+
+    fn float_point(id: u32, value: f64) -> DataPoint {
+        DataPoint {
+            value: Val::F64(value),
+            ..point(id, 0)
+        }
+    }
+
+    #[test]
+    fn ingest_float_within_global_epsilon_is_ignored() {
+        let center = DataCenter::new(1).with_float_epsilon(0.01);
+        center.ingest("dev-1", vec![float_point(1, 1.000)]);
+
+        let first = center.read_all("dev-1");
+
+        center.ingest("dev-1", vec![float_point(1, 1.004)]);
+
+        let second = center.read_all("dev-1");
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(second[0].value, Val::F64(1.000));
+    }
+
+    #[test]
+    fn ingest_float_beyond_epsilon_updates() {
+        let center = DataCenter::new(1).with_float_epsilon(0.01);
+        center.ingest("dev-1", vec![float_point(1, 1.0)]);
+        center.ingest("dev-1", vec![float_point(1, 1.02)]);
+
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::F64(1.02));
+    }
+
+    #[test]
+    fn point_epsilon_overrides_global_epsilon() {
+        let center = DataCenter::new(1).with_float_epsilon(0.01);
+        center.set_point_epsilon("dev-1", 1, Some(0.5));
+        center.ingest("dev-1", vec![float_point(1, 1.0), float_point(2, 1.0)]);
+        center.ingest("dev-1", vec![float_point(1, 1.2), float_point(2, 1.2)]);
+
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::F64(1.0));
+        assert_eq!(center.read("dev-1", 2).unwrap().value, Val::F64(1.2));
+    }
+
+    #[test]
+    fn cleared_point_epsilon_falls_back_to_global() {
+        let center = DataCenter::new(1).with_float_epsilon(0.01);
+        center.set_point_epsilon("dev-1", 1, Some(0.5));
+        center.set_point_epsilon("dev-1", 1, None);
+        center.ingest("dev-1", vec![float_point(1, 1.0)]);
+        center.ingest("dev-1", vec![float_point(1, 1.2)]);
+
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::F64(1.2));
+    }
+
+    #[test]
+    fn update_within_epsilon_keeps_old_value() {
+        let center = DataCenter::new(1).with_float_epsilon(0.01);
+        center.set_point_epsilon("dev-1", 1, Some(0.5));
+        center.ingest("dev-1", vec![float_point(1, 1.0)]);
+        let rx = center.subscribe("dev-1").unwrap();
+
+        let kept = center.update("dev-1", "p", &mut |_| Val::F64(1.2)).unwrap();
+        assert_eq!(kept, Val::F64(1.0));
+        assert!(!rx.has_changed().unwrap());
+
+        let new = center.update("dev-1", "p", &mut |_| Val::F64(2.0)).unwrap();
+        assert_eq!(new, Val::F64(2.0));
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::F64(2.0));
+    }
+
+    #[test]
+    fn zero_epsilon_keeps_exact_comparison() {
+        let center = DataCenter::new(1);
+        center.ingest("dev-1", vec![float_point(1, 1.0)]);
+        center.ingest("dev-1", vec![float_point(1, 1.0000001)]);
+
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::F64(1.0000001));
+    }
+
+    #[tokio::test]
+    async fn dispatch_atomic_returns_device_reply() {
+        let center = DataCenter::new(1);
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<DownlinkCommand>(1);
+        center.attach_downlink("dev-1", tx).unwrap();
+        tokio::spawn(async move {
+            let cmd = rx.recv().await.unwrap();
+            assert!(cmd.atomic);
+            let (_, ack) = cmd.into_parts();
+            crate::center::reply(ack, Err(DataCenterError::Rejected("bad".into())));
+        });
+
+        let result = center
+            .dispatch_atomic("dev-1", vec![DownDataPoint::by_key("p".into(), Val::U8(1))])
+            .await;
+
+        assert!(matches!(result, Err(DataCenterError::Rejected(reason)) if reason == "bad"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_atomic_reports_dropped_ack() {
+        let center = DataCenter::new(1);
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<DownlinkCommand>(1);
+        center.attach_downlink("dev-1", tx).unwrap();
+        tokio::spawn(async move {
+            let _ = rx.recv().await;
+        });
+
+        let result = center
+            .dispatch_atomic("dev-1", vec![DownDataPoint::by_key("p".into(), Val::U8(1))])
+            .await;
+
+        assert!(matches!(result, Err(DataCenterError::AckDropped)));
+    }
+
+    #[tokio::test]
+    async fn refresh_returns_points_read_by_device() {
+        let center = DataCenter::new(1);
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<DownlinkCommand>(1);
+        center.attach_downlink("dev-1", tx).unwrap();
+        tokio::spawn(async move {
+            let mut cmd = rx.recv().await.unwrap();
+            let read = cmd.read.take().unwrap();
+            assert_eq!(read.points, [PointRef::Id(1)]);
+            read.reply(Ok(vec![point(1, 7)]));
+        });
+
+        let points = center
+            .refresh("dev-1", vec![PointRef::Id(1)])
+            .await
+            .unwrap();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].value, Val::U8(7));
+        assert!(matches!(
+            center.refresh("missing", vec![PointRef::Id(1)]).await,
+            Err(DataCenterError::NotFoundDevError(_))
+        ));
+    }
+
+    #[test]
+    fn update_applies_closure_and_notifies() {
+        let center = DataCenter::new(1);
+        center.ingest("dev-1", vec![point(1, 1)]);
+        let rx = center.subscribe("dev-1").unwrap();
+
+        let new = center
+            .update("dev-1", "p", &mut |old| {
+                Val::U8(old.as_f64().unwrap() as u8 + 1)
+            })
+            .unwrap();
+
+        assert_eq!(new, Val::U8(2));
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::U8(2));
+        assert!(rx.has_changed().unwrap());
+    }
+
+    #[test]
+    fn cas_swaps_only_when_expected_matches() {
+        let center = DataCenter::new(1);
+        center.ingest("dev-1", vec![point(1, 1)]);
+
+        assert!(!center.cas("dev-1", "p", &Val::U8(9), Val::U8(5)).unwrap());
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::U8(1));
+        assert!(center.cas("dev-1", "p", &Val::U8(1), Val::U8(5)).unwrap());
+        assert_eq!(center.read("dev-1", 1).unwrap().value, Val::U8(5));
+        assert!(matches!(
+            center.cas("dev-1", "missing", &Val::U8(1), Val::U8(5)),
+            Err(DataCenterError::NotFoundPoint(_, _))
+        ));
+    }
 }
@@ -1,25 +1,150 @@
 use std::sync::Arc;
 
-use crate::core::point::{DataPoint, DownDataPoint, PointId};
+use crate::core::point::{DataPoint, DownDataPoint, PointId, PointRef, Val};
 
 pub mod data_center;
 
 pub use data_center::DataCenter;
-use tokio::sync::watch;
+use tokio::sync::{oneshot, watch};
 
-pub type DownlinkSender = tokio::sync::mpsc::Sender<Vec<DownDataPoint>>;
+pub type DownlinkSender = tokio::sync::mpsc::Sender<DownlinkCommand>;
+pub type DownlinkReceiver = tokio::sync::mpsc::Receiver<DownlinkCommand>;
+pub type DownlinkAck = oneshot::Sender<Result<(), DataCenterError>>;
+pub type ReadAck = oneshot::Sender<Result<Vec<DataPoint>, DataCenterError>>;
 pub type SharedPointCenter = Arc<dyn PointCenter>;
 
+/// 下行命令
+///
+/// 一组下发点位，以及可选的应答通道，设备驱动处理完成后通过应答通道回报结果
+#[derive(Debug)]
+pub struct DownlinkCommand {
+    /// 下发的数据点
+    pub points: Vec<DownDataPoint>,
+    /// 原子下发：整批校验通过且能在一次写入内完成才会写入，否则整批拒绝
+    pub atomic: bool,
+    /// 应答通道
+    pub ack: Option<DownlinkAck>,
+    /// 按需读取请求，存在时 `points` 为空
+    pub read: Option<ReadRequest>,
+}
+
+/// 按需读取：不等待轮询周期，立即读取指定点位
+#[derive(Debug)]
+pub struct ReadRequest {
+    /// 读取的点位
+    pub points: Vec<PointRef>,
+    /// 应答通道，回报读取到的最新值
+    pub ack: ReadAck,
+}
+
+impl ReadRequest {
+    /// 回报读取结果，调用方已放弃等待时忽略
+    pub fn reply(self, result: Result<Vec<DataPoint>, DataCenterError>) {
+        let _ = self.ack.send(result);
+    }
+}
+
+impl DownlinkCommand {
+    /// 创建普通下行命令（无应答）
+    pub fn new(points: Vec<DownDataPoint>) -> Self {
+        Self {
+            points,
+            atomic: false,
+            ack: None,
+            read: None,
+        }
+    }
+
+    /// 创建原子下行命令，处理结果通过 `ack` 回报
+    pub fn atomic(points: Vec<DownDataPoint>, ack: DownlinkAck) -> Self {
+        Self {
+            points,
+            atomic: true,
+            ack: Some(ack),
+            read: None,
+        }
+    }
+
+    /// 创建按需读取命令，读取结果通过 `ack` 回报
+    pub fn read(points: Vec<PointRef>, ack: ReadAck) -> Self {
+        Self {
+            points: Vec::new(),
+            atomic: false,
+            ack: None,
+            read: Some(ReadRequest { points, ack }),
+        }
+    }
+
+    /// 取出下发点位和应答通道
+    pub fn into_parts(self) -> (Vec<DownDataPoint>, Option<DownlinkAck>) {
+        (self.points, self.ack)
+    }
+}
+
+/// 回报下行命令处理结果，没有应答通道或调用方已放弃等待时忽略
+pub fn reply(ack: Option<DownlinkAck>, result: Result<(), DataCenterError>) {
+    if let Some(ack) = ack {
+        let _ = ack.send(result);
+    }
+}
+
 #[async_trait::async_trait]
 pub trait PointCenter: Send + Sync {
     fn ingest(&self, dev_id: &str, points: Vec<DataPoint>);
 
+    /// 将设备缓存中的点位标记为坏质量，保留原值，下次摄入新值时恢复
+    fn mark_bad(&self, dev_id: &str, point_ids: &[PointId]);
+
+    /// 设备通讯中断时将缓存中的点位标记为通讯故障，`substitute` 非空时以其覆盖原值
+    fn mark_comm_fail(&self, dev_id: &str, point_ids: &[PointId], substitute: Option<&Val>);
+
+    /// 设置单点的浮点比较容差，`None` 时恢复按全局容差比较
+    fn set_point_epsilon(&self, dev_id: &str, point_id: PointId, epsilon: Option<f64>);
+
     async fn dispatch(
         &self,
         dev_id: &str,
         points: Vec<DownDataPoint>,
     ) -> Result<(), DataCenterError>;
 
+    /// 原子下发：整批点位由设备驱动作为一个写入计划执行，要么全部下发，要么全部拒绝。
+    ///
+    /// 驱动只在写入计划能以一次写入完成时下发(如连续寄存器合并为一个写块)，
+    /// 需要多次写入的整批拒绝，避免中途失败时只有部分点位生效。
+    ///
+    /// 等待设备驱动回报结果后返回；设备断线期间命令会排队，调用方可自行包裹超时。
+    async fn dispatch_atomic(
+        &self,
+        dev_id: &str,
+        points: Vec<DownDataPoint>,
+    ) -> Result<(), DataCenterError>;
+
+    /// 读改写：在设备缓存锁内以旧值计算新值并写回，返回写入后的值
+    fn update(
+        &self,
+        dev_id: &str,
+        key: &str,
+        f: &mut dyn FnMut(&Val) -> Val,
+    ) -> Result<Val, DataCenterError>;
+
+    /// 比较并交换：当前值等于 `expected` 时写入 `new`，返回是否写入
+    fn cas(
+        &self,
+        dev_id: &str,
+        key: &str,
+        expected: &Val,
+        new: Val,
+    ) -> Result<bool, DataCenterError>;
+
+    /// 按需读取：由设备驱动立即读取指定点位并返回最新值，同时更新缓存
+    ///
+    /// 等待设备驱动回报结果后返回；设备断线期间命令会排队，调用方可自行包裹超时。
+    async fn refresh(
+        &self,
+        dev_id: &str,
+        points: Vec<PointRef>,
+    ) -> Result<Vec<DataPoint>, DataCenterError>;
+
     fn read(&self, dev_id: &str, point_id: PointId) -> Option<DataPoint>;
 
     fn read_by_key(&self, dev_id: &str, key: &str) -> Option<DataPoint>;
@@ -36,6 +161,9 @@ pub trait PointCenter: Send + Sync {
 
     fn detach_downlink(&self, dev_id: &str);
 
+    /// 注销设备：移除其缓存与下行通道，订阅者随之收到关闭
+    fn remove_device(&self, dev_id: &str);
+
     fn subscribe(&self, dev_id: &str) -> Option<watch::Receiver<Arc<[DataPoint]>>>;
 }
 
@@ -47,10 +175,20 @@ pub enum DataCenterError {
     NotFoundDevError(String),
     #[error("{0}设备已经注册")]
     DevHasRegister(String),
+    #[error("设备{0}中找不到数据点{1}")]
+    NotFoundPoint(String, String),
+    #[error("下发被拒绝: {0}")]
+    Rejected(String),
+    #[error("下发失败: {0}")]
+    WriteFailed(String),
+    #[error("设备未回报下发结果")]
+    AckDropped,
+    #[error("读取失败: {0}")]
+    ReadFailed(String),
 }
 
-impl From<tokio::sync::mpsc::error::SendError<Vec<DownDataPoint>>> for DataCenterError {
-    fn from(value: tokio::sync::mpsc::error::SendError<Vec<DownDataPoint>>) -> Self {
+impl From<tokio::sync::mpsc::error::SendError<DownlinkCommand>> for DataCenterError {
+    fn from(value: tokio::sync::mpsc::error::SendError<DownlinkCommand>) -> Self {
         DataCenterError::SendError(value.to_string())
     }
 }
@@ -0,0 +1,481 @@
+//! 点表格式转换
+//!
+//! 在 Excel、CSV 与 JSON 点表之间互相转换，保留全部列。各格式的行先读成与 JSON 点表字段一一对应的点位，
+//! 再按 JSON 点表的规则校验：解析或校验失败的行、重复的点位 ID 均跳过并带位置报告，其余行写出。
+//!
+//! Excel 点表与采集时一样按表头识别各列并读取缺省的四遥工作表，写出 Excel 时保留原工作表；
+//! CSV 首行为表头，同样按表头识别各列，写出时表头为 JSON 点表的字段名。
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use calamine::{Data, DataType, HeaderRow, Reader, open_workbook_auto};
+use rust_xlsxwriter::Workbook;
+
+use crate::config::modbus_conf::{
+    COLUMNS, ColumnLayout, HEADER_ALIASES, JsonPoint, ModbusConfig, ModbusConfigsError, xlsx_sheets,
+};
+
+/// 来源没有工作表时写出 Excel 使用的工作表名
+const DEFAULT_SHEET: &str = "点表";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConvertError {
+    #[error("无法识别的点表格式: {0}")]
+    UnsupportedFormat(String),
+    #[error(transparent)]
+    Read(#[from] ModbusConfigsError),
+    #[error("读写CSV点表失败: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("写入Excel点表失败: {0}")]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+    #[error("写入JSON点表失败: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("写入点表失败: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// 点表格式，按扩展名判断
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// `.xlsx`/`.xlsm`/`.xlsb`/`.xls`/`.ods`，只能写出 `.xlsx`
+    Xlsx,
+    Csv,
+    /// `.json`/`.json5`
+    Json,
+}
+
+impl TableFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "xlsx" | "xlsm" | "xlsb" | "xls" | "ods" => Some(Self::Xlsx),
+            "csv" => Some(Self::Csv),
+            "json" | "json5" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// 转换结果
+#[derive(Debug, Default)]
+pub struct Conversion {
+    /// 写出的点位数
+    pub points: usize,
+    /// 跳过的行及原因，带工作表与行号
+    pub errors: Vec<String>,
+    /// 缺少必需列而跳过的工作表（如说明页）
+    pub skipped_sheets: Vec<String>,
+}
+
+/// 读出的一行
+struct Row {
+    /// 行的位置，用于报告错误
+    location: String,
+    sheet: Option<String>,
+    point: JsonPoint,
+}
+
+/// 单元格的值，写出时数值列写作数字
+enum Cell {
+    Empty,
+    Number(f64),
+    Text(String),
+}
+
+impl Cell {
+    fn text(value: Option<&String>) -> Self {
+        value.map_or(Cell::Empty, |value| Cell::Text(value.clone()))
+    }
+
+    fn number(value: Option<f64>) -> Self {
+        value.map_or(Cell::Empty, Cell::Number)
+    }
+
+    fn to_csv(&self) -> String {
+        match self {
+            Cell::Empty => String::new(),
+            Cell::Number(value) => value.to_string(),
+            Cell::Text(value) => value.clone(),
+        }
+    }
+}
+
+/// 将 `input` 点表转换为 `output` 的格式，格式均按扩展名判断
+pub fn convert(input: &Path, output: &Path) -> Result<Conversion, ConvertError> {
+    let unsupported = |path: &Path| ConvertError::UnsupportedFormat(path.display().to_string());
+    let from = TableFormat::from_path(input).ok_or_else(|| unsupported(input))?;
+    let to = TableFormat::from_path(output).ok_or_else(|| unsupported(output))?;
+    let xlsx = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("xlsx"));
+    if to == TableFormat::Xlsx && !xlsx {
+        return Err(unsupported(output));
+    }
+    let mut conversion = Conversion::default();
+    let rows = match from {
+        TableFormat::Xlsx => read_xlsx(input, &mut conversion)?,
+        TableFormat::Csv => read_csv(input, &mut conversion)?,
+        TableFormat::Json => read_json(input, &mut conversion)?,
+    };
+    let mut ids = HashSet::with_capacity(rows.len());
+    let mut valid = Vec::with_capacity(rows.len());
+    for row in rows {
+        // 按采集时的规则试建点位；转换只运行一次，试建中驻留的字符串不回收
+        if let Err(err) = ModbusConfig::try_from(row.point.clone()) {
+            conversion.errors.push(format!("{}: {}", row.location, err));
+        } else if !ids.insert(row.point.id) {
+            let err = ModbusConfigsError::DuplicatePointId(row.point.id);
+            conversion.errors.push(format!("{}: {}", row.location, err));
+        } else {
+            valid.push(row);
+        }
+    }
+    conversion.points = valid.len();
+    match to {
+        TableFormat::Xlsx => write_xlsx(output, &valid)?,
+        TableFormat::Csv => write_csv(output, &valid)?,
+        TableFormat::Json => write_json(output, &valid)?,
+    }
+    Ok(conversion)
+}
+
+fn read_xlsx(path: &Path, conversion: &mut Conversion) -> Result<Vec<Row>, ConvertError> {
+    let mut workbook = open_workbook_auto(path).map_err(ModbusConfigsError::from)?;
+    let mut rows = Vec::new();
+    for sheet in xlsx_sheets(&workbook.sheet_names(), None) {
+        let Ok(range) = workbook
+            .with_header_row(HeaderRow::Row(0))
+            .worksheet_range(&sheet)
+        else {
+            continue;
+        };
+        let headers = range.headers().unwrap_or_default();
+        let layout = match ColumnLayout::resolve(&sheet, &headers, None) {
+            Ok(layout) => layout,
+            Err(err) => {
+                conversion.skipped_sheets.push(err.to_string());
+                continue;
+            }
+        };
+        let header_row = range.start().map_or(1, |(row, _)| row as usize + 1);
+        for (offset, row) in range.rows().enumerate().skip(1) {
+            if row.iter().all(|cell| cell.is_empty()) {
+                continue;
+            }
+            let location = format!("工作表{}第{}行", sheet, header_row + offset);
+            match JsonPoint::from_row(&layout.project(row)) {
+                Ok(point) => rows.push(Row {
+                    location,
+                    sheet: Some(sheet.clone()),
+                    point,
+                }),
+                Err(err) => conversion.errors.push(format!("{location}: {err}")),
+            }
+        }
+    }
+    Ok(rows)
+}
+
+fn read_csv(path: &Path, conversion: &mut Conversion) -> Result<Vec<Row>, ConvertError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_owned).collect();
+    let layout = ColumnLayout::resolve(&path.display().to_string(), &headers, None)?;
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                conversion.errors.push(err.to_string());
+                continue;
+            }
+        };
+        let cells: Vec<Data> = record
+            .iter()
+            .map(|cell| match cell.trim() {
+                "" => Data::Empty,
+                cell => Data::String(cell.to_owned()),
+            })
+            .collect();
+        if cells.iter().all(Data::is_empty) {
+            continue;
+        }
+        let line = record.position().map_or(0, |position| position.line());
+        let location = format!("第{line}行");
+        match JsonPoint::from_row(&layout.project(&cells)) {
+            Ok(point) => rows.push(Row {
+                location,
+                sheet: None,
+                point,
+            }),
+            Err(err) => conversion.errors.push(format!("{location}: {err}")),
+        }
+    }
+    Ok(rows)
+}
+
+fn read_json(path: &Path, conversion: &mut Conversion) -> Result<Vec<Row>, ConvertError> {
+    let text = std::fs::read_to_string(path).map_err(ModbusConfigsError::from)?;
+    let values: Vec<serde_json::Value> =
+        json5::from_str(&text).map_err(ModbusConfigsError::from)?;
+    let mut rows = Vec::new();
+    for (index, value) in values.into_iter().enumerate() {
+        let location = format!("第{index}个点位");
+        match serde_json::from_value::<JsonPoint>(value) {
+            Ok(point) => rows.push(Row {
+                location,
+                sheet: None,
+                point,
+            }),
+            Err(err) => conversion.errors.push(format!("{location}: {err}")),
+        }
+    }
+    Ok(rows)
+}
+
+fn write_xlsx(path: &Path, rows: &[Row]) -> Result<(), ConvertError> {
+    // 按工作表首次出现的顺序分组
+    let mut sheets: Vec<(&str, Vec<&JsonPoint>)> = Vec::new();
+    for row in rows {
+        let sheet = row.sheet.as_deref().unwrap_or(DEFAULT_SHEET);
+        match sheets.iter_mut().find(|(name, _)| *name == sheet) {
+            Some((_, points)) => points.push(&row.point),
+            None => sheets.push((sheet, vec![&row.point])),
+        }
+    }
+    if sheets.is_empty() {
+        sheets.push((DEFAULT_SHEET, Vec::new()));
+    }
+    let mut workbook = Workbook::new();
+    for (sheet, points) in sheets {
+        let worksheet = workbook.add_worksheet().set_name(sheet)?;
+        for (column, aliases) in HEADER_ALIASES.iter().enumerate() {
+            worksheet.write_string(0, column as u16, aliases[0])?;
+        }
+        for (index, point) in points.iter().enumerate() {
+            let row = index as u32 + 1;
+            for (column, cell) in point.cells().iter().enumerate() {
+                match cell {
+                    Cell::Empty => {}
+                    Cell::Number(value) => {
+                        worksheet.write_number(row, column as u16, *value)?;
+                    }
+                    Cell::Text(value) => {
+                        worksheet.write_string(row, column as u16, value)?;
+                    }
+                }
+            }
+        }
+    }
+    workbook.save(path)?;
+    Ok(())
+}
+
+fn write_csv(path: &Path, rows: &[Row]) -> Result<(), ConvertError> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(COLUMNS)?;
+    for row in rows {
+        writer.write_record(row.point.cells().iter().map(Cell::to_csv))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_json(path: &Path, rows: &[Row]) -> Result<(), ConvertError> {
+    let points: Vec<&JsonPoint> = rows.iter().map(|row| &row.point).collect();
+    let mut text = serde_json::to_string_pretty(&points)?;
+    text.push('\n');
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+/// 列的显示名，用于报告错误
+fn label(slot: usize) -> &'static str {
+    HEADER_ALIASES[slot][0]
+}
+
+fn text(row: &[Data], slot: usize) -> Option<String> {
+    match &row[slot] {
+        Data::String(value) if !value.trim().is_empty() => Some(value.trim().to_owned()),
+        Data::Float(value) => Some(value.to_string()),
+        Data::Int(value) => Some(value.to_string()),
+        Data::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn number(row: &[Data], slot: usize) -> Result<Option<f64>, anyhow::Error> {
+    match &row[slot] {
+        Data::Empty => Ok(None),
+        Data::Float(value) => Ok(Some(*value)),
+        Data::Int(value) => Ok(Some(*value as f64)),
+        Data::String(value) if value.trim().is_empty() => Ok(None),
+        Data::String(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("{}不是数字: {}", label(slot), value)),
+        other => Err(anyhow::anyhow!("{}无法识别: {}", label(slot), other)),
+    }
+}
+
+fn integer<T: TryFrom<i64>>(row: &[Data], slot: usize) -> Result<Option<T>, anyhow::Error> {
+    let Some(value) = number(row, slot)? else {
+        return Ok(None);
+    };
+    if !value.is_finite() || value.fract() != 0.0 {
+        return Err(anyhow::anyhow!("{}必须是整数", label(slot)));
+    }
+    T::try_from(value as i64)
+        .map(Some)
+        .map_err(|_| anyhow::anyhow!("{}超出范围", label(slot)))
+}
+
+fn required<T>(value: Option<T>, slot: usize) -> Result<T, anyhow::Error> {
+    value.ok_or_else(|| anyhow::anyhow!("{}不能为空", label(slot)))
+}
+
+impl JsonPoint {
+    /// 由按 [`COLUMNS`] 顺序排列的一行读出点位，数值列可写作数字或数字文本
+    fn from_row(row: &[Data]) -> Result<Self, anyhow::Error> {
+        let enable = match &row[11] {
+            Data::Bool(value) => *value,
+            Data::String(value) if value.trim().eq_ignore_ascii_case("true") => true,
+            Data::String(value) if value.trim().eq_ignore_ascii_case("false") => false,
+            _ => number(row, 11)?.is_none_or(|value| value != 0.0),
+        };
+        Ok(Self {
+            id: required(integer(row, 0)?, 0)?,
+            name: required(text(row, 1), 1)?,
+            data_type: required(text(row, 2), 2)?,
+            unit: text(row, 3),
+            remarks: text(row, 4),
+            register_address: required(integer(row, 5)?, 5)?,
+            register_type: required(text(row, 6), 6)?,
+            quantity: required(integer(row, 7)?, 7)?,
+            byte_order: text(row, 8),
+            scale: number(row, 9)?.unwrap_or(1.0),
+            offset: number(row, 10)?.unwrap_or(0.0),
+            enable,
+            key: required(text(row, 12), 12)?,
+            trans: text(row, 13),
+            status_words: text(row, 14),
+            warn_bits: text(row, 15),
+            bit: integer(row, 16)?,
+            scan_class: text(row, 17),
+            select_address: integer(row, 18)?,
+            handshake_address: integer(row, 19)?,
+            pulse_ms: integer(row, 20)?,
+            epsilon: number(row, 21)?,
+        })
+    }
+
+    /// 按 [`COLUMNS`] 顺序排列的单元格，启用写作 1/0
+    fn cells(&self) -> [Cell; COLUMNS.len()] {
+        [
+            Cell::Number(self.id.into()),
+            Cell::Text(self.name.clone()),
+            Cell::Text(self.data_type.clone()),
+            Cell::text(self.unit.as_ref()),
+            Cell::text(self.remarks.as_ref()),
+            Cell::Number(self.register_address.into()),
+            Cell::Text(self.register_type.clone()),
+            Cell::Number(self.quantity.into()),
+            Cell::text(self.byte_order.as_ref()),
+            Cell::Number(self.scale),
+            Cell::Number(self.offset),
+            Cell::Number(if self.enable { 1.0 } else { 0.0 }),
+            Cell::Text(self.key.clone()),
+            Cell::text(self.trans.as_ref()),
+            Cell::text(self.status_words.as_ref()),
+            Cell::text(self.warn_bits.as_ref()),
+            Cell::number(self.bit.map(f64::from)),
+            Cell::text(self.scan_class.as_ref()),
+            Cell::number(self.select_address.map(f64::from)),
+            Cell::number(self.handshake_address.map(f64::from)),
+            Cell::number(self.pulse_ms.map(f64::from)),
+            Cell::number(self.epsilon),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TableFormat, convert};
+
+    const POINTS: &str = r#"[
+        { id: 1, name: "电压", data_type: "U16", unit: "V", register_address: 100,
+          register_type: "InputRegisters", quantity: 1, scale: 0.1, key: "voltage" },
+        { id: 2, name: "运行", data_type: "Bool", register_address: 5, register_type: "Coils",
+          quantity: 1, enable: false, key: "run", remarks: "主接触器", select_address: 6 },
+        { id: 3, name: "未知", data_type: "U99", register_address: 7,
+          register_type: "InputRegisters", quantity: 1, key: "bad" },
+        { id: 1, name: "重复", data_type: "U16", register_address: 8,
+          register_type: "InputRegisters", quantity: 1, key: "dup" },
+    ]"#;
+
+    #[test]
+    fn point_tables_round_trip_through_all_formats() {
+        let dir = std::env::temp_dir().join(format!("collector-convert-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("points.json5");
+        std::fs::write(&source, POINTS).unwrap();
+
+        let first = convert(&source, &dir.join("points.csv")).unwrap();
+        assert_eq!(first.points, 2, "{:?}", first.errors);
+        assert_eq!(first.errors.len(), 2, "{:?}", first.errors);
+        assert!(first.errors[0].starts_with("第2个点位"));
+        assert!(first.errors[1].contains("重复点位ID: 1"));
+
+        let csv = std::fs::read_to_string(dir.join("points.csv")).unwrap();
+        assert!(csv.starts_with("id,name,data_type,unit,"));
+        assert!(csv.contains("2,运行,Bool,,主接触器,5,Coils,1,,1,0,0,run,,,,,,6,,,\n"));
+
+        let second = convert(&dir.join("points.csv"), &dir.join("points.xlsx")).unwrap();
+        let third = convert(&dir.join("points.xlsx"), &dir.join("back.json")).unwrap();
+        assert_eq!((second.points, third.points), (2, 2));
+        assert!(second.errors.is_empty() && third.errors.is_empty());
+
+        let json = |path: &str| -> serde_json::Value {
+            json5::from_str(&std::fs::read_to_string(dir.join(path)).unwrap()).unwrap()
+        };
+        convert(&source, &dir.join("direct.json")).unwrap();
+        let back = json("back.json");
+        assert_eq!(back, json("direct.json"));
+        assert_eq!(back[0]["unit"], "V");
+        assert_eq!(back[1]["enable"], false);
+        assert_eq!(back[1]["remarks"], "主接触器");
+        assert_eq!(back[1]["select_address"], 6);
+
+        assert!(matches!(
+            convert(&source, &dir.join("points.xls")),
+            Err(super::ConvertError::UnsupportedFormat(_))
+        ));
+        assert_eq!(TableFormat::from_path(dir.join("a.txt").as_path()), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csv_rows_are_read_by_header_and_reported_by_line() {
+        let dir =
+            std::env::temp_dir().join(format!("collector-convert-csv-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("points.csv");
+        std::fs::write(
+            &source,
+            "键,点位名称,序号,数据类型,地址,寄存器类型,数量,系数\n\
+             soc,SOC,1,U16,10,InputRegisters,1,0.1\n\
+             ,,,,,,,\n\
+             soh,SOH,x,U16,11,InputRegisters,1,0.1\n",
+        )
+        .unwrap();
+        let conversion = convert(&source, &dir.join("points.json")).unwrap();
+        assert_eq!(conversion.points, 1);
+        assert_eq!(conversion.errors, ["第4行: 序号不是数字: x"]);
+        let text = std::fs::read_to_string(dir.join("points.json")).unwrap();
+        let points: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(points[0]["key"], "soc");
+        assert_eq!(points[0]["scale"], 0.1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+//! 配置文件中的环境变量替换
+//!
+//! 字符串字段中的 `${VAR}` 在加载时替换为环境变量的值，`${VAR:-默认值}` 在变量未设置时使用默认值
+
+use crate::config::{ConfigurationError, Device, DeviceConfig, MqttRoute, Project};
+
+/// 替换字符串中的 `${VAR}` 占位符
+///
+/// `lookup` 用于查询变量，便于测试时替换环境
+pub(crate) fn expand(
+    input: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<String, ConfigurationError> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            return Err(ConfigurationError::InvalidPlaceholder(input.to_owned()));
+        };
+        let expr = &after[..end];
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        if name.is_empty() {
+            return Err(ConfigurationError::InvalidPlaceholder(input.to_owned()));
+        }
+        match lookup(name).or_else(|| default.map(str::to_owned)) {
+            Some(value) => out.push_str(&value),
+            None => return Err(ConfigurationError::MissingEnvVar(name.to_owned())),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn expand_opt(
+    field: &mut Option<String>,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigurationError> {
+    if let Some(value) = field.as_mut() {
+        expand_str(value, lookup)?;
+    }
+    Ok(())
+}
+
+fn expand_str(
+    field: &mut String,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigurationError> {
+    if field.contains("${") {
+        *field = expand(field, lookup)?;
+    }
+    Ok(())
+}
+
+/// 替换项目配置中所有字符串字段的占位符
+pub(crate) fn expand_project(
+    project: &mut Project,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigurationError> {
+    for field in [
+        &mut project.product_type,
+        &mut project.project,
+        &mut project.http_ip,
+        &mut project.mqtt_host,
+        &mut project.mqtt_username,
+        &mut project.mqtt_password,
+        &mut project.mqtt_yt,
+        &mut project.mqtt_yk,
+        &mut project.north_modbus_host,
+        &mut project.north_modbus_conf,
+        &mut project.base_dir,
+    ] {
+        expand_opt(field, lookup)?;
+    }
+    for include in project.includes.iter_mut().flatten() {
+        expand_str(include, lookup)?;
+    }
+    for dev in project.devices.values_mut() {
+        expand_device(dev, lookup)?;
+    }
+    if let Some(defaults) = project.device_defaults.as_mut() {
+        expand_opt(&mut defaults.byte_order, lookup)?;
+    }
+    for config in project
+        .protocol_defaults
+        .iter_mut()
+        .flat_map(|it| it.values_mut())
+    {
+        expand_config(config, lookup)?;
+    }
+    for route in project.mqtt_routes.iter_mut().flatten() {
+        expand_route(route, lookup)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn expand_device(
+    dev: &mut Device,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigurationError> {
+    expand_opt(&mut dev.id, lookup)?;
+    expand_opt(&mut dev.desc, lookup)?;
+    expand_config(&mut dev.config, lookup)
+}
+
+fn expand_config(
+    config: &mut DeviceConfig,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigurationError> {
+    for field in [
+        &mut config.device_type,
+        &mut config.register_file,
+        &mut config.ip,
+        &mut config.serial_tty,
+        &mut config.parity,
+        &mut config.interface,
+        &mut config.desc,
+        &mut config.byte_order,
+        &mut config.version_cell,
+    ] {
+        expand_opt(field, lookup)?;
+    }
+    for sheet in config.sheets.iter_mut().flatten() {
+        expand_str(sheet, lookup)?;
+    }
+    Ok(())
+}
+
+fn expand_route(
+    route: &mut MqttRoute,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigurationError> {
+    expand_str(&mut route.device_id, lookup)?;
+    for rule in route.rules.iter_mut() {
+        expand_str(&mut rule.topic, lookup)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand;
+    use crate::config::ConfigurationError;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOST" => Some("10.0.0.1".to_owned()),
+            "PORT" => Some("502".to_owned()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        assert_eq!(expand("${HOST}:${PORT}", &lookup).unwrap(), "10.0.0.1:502");
+        assert_eq!(expand("plain", &lookup).unwrap(), "plain");
+        assert_eq!(
+            expand("conf/${SITE:-default}.xlsx", &lookup).unwrap(),
+            "conf/default.xlsx"
+        );
+    }
+
+    #[test]
+    fn missing_variable_is_an_error() {
+        assert!(matches!(
+            expand("${SITE}", &lookup),
+            Err(ConfigurationError::MissingEnvVar(name)) if name == "SITE"
+        ));
+        assert!(matches!(
+            expand("${HOST", &lookup),
+            Err(ConfigurationError::InvalidPlaceholder(_))
+        ));
+    }
+}
@@ -3,13 +3,15 @@ use serde::Serialize;
 
 use crate::{
     config::{optional_static_str, required_f64, required_static_str},
-    core::point::{DataPoint, Translator, Val},
+    core::point::{DataPoint, Quality, Translator, Val},
 };
 
 #[derive(Debug, thiserror::Error)]
 pub enum GpioConfigsError {
     #[error("Failed to open workbook: {0}")]
     OpenWorkbookError(#[from] calamine::XlsxError),
+    #[error("点表存在{}处错误: {}", .0.len(), .0.join("; "))]
+    InvalidRows(Vec<String>),
 }
 
 pub type GpioConfigs = Vec<GpioConfig>;
@@ -58,24 +60,23 @@ impl GpioConfig {
             bits: None,
             words: None,
             unit: None,
+            quality: Quality::Good,
         }
     }
 }
 
+/// `strict` 为真时任一行解析失败即返回错误，否则跳过出错的行
 #[cfg(target_os = "linux")]
-pub(crate) fn build_configs(path: String) -> Result<GpioConfigs, GpioConfigsError> {
+pub(crate) fn build_configs(path: String, strict: bool) -> Result<GpioConfigs, GpioConfigsError> {
     let mut workbook: Xlsx<_> = open_workbook(path)?;
     let mut configs = Vec::new();
-    let parse = |range: Range<Data>, configs: &mut Vec<GpioConfig>| {
-        for row in range.rows() {
-            let config = GpioConfig::build(row);
-            match config {
-                Ok(config) => {
-                    configs.push(config);
-                }
-                Err(err) => {
-                    tracing::error!("构建Linux GPIO配置失败: {}", err);
-                }
+    let mut errors = Vec::new();
+    let mut parse = |range: Range<Data>, configs: &mut Vec<GpioConfig>| {
+        // 第一行表头不在 range 中，数据从第 2 行开始
+        for (index, row) in range.rows().enumerate() {
+            match GpioConfig::build(row) {
+                Ok(config) => configs.push(config),
+                Err(err) => errors.push(format!("第{}行: {}", index + 2, err)),
             }
         }
     };
@@ -85,6 +86,12 @@ pub(crate) fn build_configs(path: String) -> Result<GpioConfigs, GpioConfigsErro
     {
         parse(range, &mut configs);
     }
+    if strict && !errors.is_empty() {
+        return Err(GpioConfigsError::InvalidRows(errors));
+    }
+    for err in errors {
+        tracing::error!("构建Linux GPIO配置失败: {}", err);
+    }
     Ok(configs)
 }
 
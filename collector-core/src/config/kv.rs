@@ -0,0 +1,384 @@
+//! etcd/Consul KV 配置后端
+//!
+//! 以 `consul://host:port/key` 或 `etcd://host:port/key` 指定项目配置所在的键。
+//! 项目配置及其引用的点位表、变量文件会同步到本地镜像目录，`register_file`、
+//! `variables_file` 改写为相对镜像目录的路径，之后与本地配置文件一样加载；[`KvSource::watch`] 定期检查键的版本并重新同步，
+//! 镜像文件的变化由配置热更新路径接管。
+//!
+//! 点位表的键以项目配置键所在的目录为基准，例如项目键 `sites/42/project.json`
+//! 中的 `register_file: "pcs.xlsx"` 对应键 `sites/42/pcs.xlsx`。
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::config::{ConfigFormat, ConfigurationError, parse, template};
+use crate::shutdown::ShutdownManager;
+
+/// 请求 KV 服务的超时时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// 镜像中的项目配置文件名
+const PROJECT_FILE: &str = "project.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KvKind {
+    Consul,
+    Etcd,
+}
+
+/// KV 配置源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvSource {
+    pub kind: KvKind,
+    /// HTTP 接口地址，如 `http://127.0.0.1:8500`
+    pub endpoint: String,
+    /// 项目配置所在的键
+    pub key: String,
+}
+
+impl KvSource {
+    /// 解析 `consul://` 或 `etcd://` 地址，其他地址返回 `None`
+    pub fn parse(url: &str) -> Option<Self> {
+        let (kind, rest) = if let Some(rest) = url.strip_prefix("consul://") {
+            (KvKind::Consul, rest)
+        } else if let Some(rest) = url.strip_prefix("etcd://") {
+            (KvKind::Etcd, rest)
+        } else {
+            return None;
+        };
+        let (host, key) = rest.split_once('/')?;
+        if host.is_empty() || key.is_empty() {
+            return None;
+        }
+        Some(Self {
+            kind,
+            endpoint: format!("http://{host}"),
+            key: key.to_owned(),
+        })
+    }
+
+    /// 点位表等键所在的目录前缀
+    fn prefix(&self) -> &str {
+        match self.key.rfind('/') {
+            Some(idx) => &self.key[..=idx],
+            None => "",
+        }
+    }
+
+    /// 将项目配置及点位表同步到本地镜像目录，返回镜像中项目配置的路径
+    ///
+    /// 只在内容变化时写入文件，避免触发无意义的热更新
+    pub async fn sync(
+        &self,
+        dir: &Path,
+        format: Option<ConfigFormat>,
+    ) -> Result<PathBuf, ConfigurationError> {
+        let client = client()?;
+        let bytes = self
+            .get(&client, &self.key)
+            .await?
+            .ok_or_else(|| ConfigurationError::FetchError(format!("键{}不存在", self.key)))?;
+        let format = format.unwrap_or_else(|| ConfigFormat::from_path(&self.key));
+        let mut doc: serde_json::Value = parse(&bytes, format)?;
+
+        // 点位表路径可能引用模板变量，先同步变量文件并展开
+        let mut vars = template::variables(&doc)?;
+        if let Some((key, local, file)) = variables_file(&mut doc, self.prefix(), dir) {
+            *file = serde_json::Value::String(key.clone());
+            self.mirror(&client, &key, &local).await?;
+            if let Ok(bytes) = fs::read(&local).await {
+                vars.extend(parse::<template::Variables>(
+                    &bytes,
+                    ConfigFormat::from_path(&key),
+                )?);
+            }
+        }
+        template::render(&mut doc, &vars)?;
+        for (key, local, file) in register_files(&mut doc, self.prefix(), dir) {
+            self.mirror(&client, &key, &local).await?;
+            *file = serde_json::Value::String(key);
+        }
+        // 镜像中点位表相对镜像目录存放，不能再按原配置的基准目录解析
+        if let Some(doc) = doc.as_object_mut() {
+            doc.remove("base_dir");
+            doc.remove("baseDir");
+        }
+
+        let project = dir.join(PROJECT_FILE);
+        let body = serde_json::to_vec_pretty(&doc)?;
+        write_if_changed(&project, &body).await?;
+        Ok(project)
+    }
+
+    /// 定期检查项目配置前缀下键的版本，变化时重新同步镜像，直到收到关闭信号
+    pub async fn watch(
+        self,
+        dir: PathBuf,
+        format: Option<ConfigFormat>,
+        interval: Duration,
+        shutdown: ShutdownManager,
+    ) {
+        let mut last = None;
+        loop {
+            match client() {
+                Ok(client) => match self.version(&client).await {
+                    Ok(version) if last.as_ref().is_some_and(|last| *last != version) => {
+                        info!("KV配置已变化, 重新同步: {}", self.key);
+                        match self.sync(&dir, format).await {
+                            Ok(_) => last = Some(version),
+                            Err(err) => warn!("同步KV配置失败: {}", err),
+                        }
+                    }
+                    Ok(version) => last = Some(version),
+                    Err(err) => warn!("检查KV配置版本失败: {}", err),
+                },
+                Err(err) => warn!("{}", err),
+            }
+            tokio::select! {
+                _ = shutdown.wait_for_shutdown() => break,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+
+    /// 将键同步到镜像文件，键不存在时只记录警告
+    async fn mirror(
+        &self,
+        client: &reqwest::Client,
+        key: &str,
+        local: &Path,
+    ) -> Result<(), ConfigurationError> {
+        match self.get(client, key).await? {
+            Some(content) => write_if_changed(local, &content).await,
+            None => {
+                warn!("KV中不存在{}", key);
+                Ok(())
+            }
+        }
+    }
+
+    async fn get(
+        &self,
+        client: &reqwest::Client,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, ConfigurationError> {
+        match self.kind {
+            KvKind::Consul => {
+                let url = format!("{}/v1/kv/{}?raw", self.endpoint, key);
+                let resp = client.get(url).send().await.map_err(fetch_error)?;
+                if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                let resp = resp.error_for_status().map_err(fetch_error)?;
+                Ok(Some(resp.bytes().await.map_err(fetch_error)?.to_vec()))
+            }
+            KvKind::Etcd => {
+                let body = serde_json::json!({ "key": BASE64.encode(key) });
+                let resp = self.etcd_range(client, &body).await?;
+                let Some(value) = resp["kvs"][0]["value"].as_str() else {
+                    return Ok(None);
+                };
+                BASE64
+                    .decode(value)
+                    .map(Some)
+                    .map_err(|err| ConfigurationError::FetchError(err.to_string()))
+            }
+        }
+    }
+
+    /// 项目配置前缀下所有键的版本标识，任一键变化时随之变化
+    async fn version(&self, client: &reqwest::Client) -> Result<String, ConfigurationError> {
+        let prefix = self.prefix();
+        match self.kind {
+            KvKind::Consul => {
+                let url = format!("{}/v1/kv/{}?keys", self.endpoint, prefix);
+                let resp = client.get(url).send().await.map_err(fetch_error)?;
+                resp.headers()
+                    .get("X-Consul-Index")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_owned)
+                    .ok_or_else(|| ConfigurationError::FetchError("缺少X-Consul-Index".into()))
+            }
+            KvKind::Etcd => {
+                let body = serde_json::json!({
+                    "key": BASE64.encode(prefix),
+                    "range_end": BASE64.encode(prefix_end(prefix)),
+                    "keys_only": true,
+                });
+                let resp = self.etcd_range(client, &body).await?;
+                let kvs = resp["kvs"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+                let max = kvs
+                    .iter()
+                    .filter_map(|kv| kv["mod_revision"].as_str()?.parse::<u64>().ok())
+                    .max()
+                    .unwrap_or(0);
+                // 键的数量参与比较，删除键时版本同样变化
+                Ok(format!("{}:{}", kvs.len(), max))
+            }
+        }
+    }
+
+    async fn etcd_range(
+        &self,
+        client: &reqwest::Client,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, ConfigurationError> {
+        let url = format!("{}/v3/kv/range", self.endpoint);
+        let resp = client
+            .post(url)
+            .body(body.to_string())
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .map_err(fetch_error)?;
+        let bytes = resp.bytes().await.map_err(fetch_error)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+fn client() -> Result<reqwest::Client, ConfigurationError> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(fetch_error)
+}
+
+fn fetch_error(err: reqwest::Error) -> ConfigurationError {
+    ConfigurationError::FetchError(err.to_string())
+}
+
+/// etcd 前缀查询的 range_end：前缀最后一个字节加一
+fn prefix_end(prefix: &str) -> Vec<u8> {
+    let mut end = prefix.as_bytes().to_vec();
+    while let Some(last) = end.pop() {
+        if last < 0xFF {
+            end.push(last + 1);
+            return end;
+        }
+    }
+    // 空前缀表示查询全部键
+    vec![0]
+}
+
+/// 找出项目配置中各设备的点位表，返回 (KV 键, 镜像路径, 配置字段)
+fn register_files<'a>(
+    doc: &'a mut serde_json::Value,
+    prefix: &str,
+    dir: &Path,
+) -> Vec<(String, PathBuf, &'a mut serde_json::Value)> {
+    let Some(devices) = doc["devices"].as_object_mut() else {
+        return Vec::new();
+    };
+    devices
+        .values_mut()
+        .filter_map(|dev| {
+            let config = dev.get_mut("config")?;
+            let field = if config.get("register_file").is_some() {
+                "register_file"
+            } else {
+                "registerFile"
+            };
+            let file = config.get_mut(field)?;
+            let key = kv_key(file.as_str()?, prefix);
+            let local = dir.join(&key);
+            Some((key, local, file))
+        })
+        .collect()
+}
+
+/// 项目配置中的变量文件，返回值同 [`register_files`]
+fn variables_file<'a>(
+    doc: &'a mut serde_json::Value,
+    prefix: &str,
+    dir: &Path,
+) -> Option<(String, PathBuf, &'a mut serde_json::Value)> {
+    let doc = doc.as_object_mut()?;
+    let field = if doc.contains_key("variables_file") {
+        "variables_file"
+    } else {
+        "variablesFile"
+    };
+    let file = doc.get_mut(field)?;
+    let key = kv_key(file.as_str()?, prefix);
+    Some((key.clone(), dir.join(&key), file))
+}
+
+/// 配置中的文件路径对应的 KV 键，`/` 开头的路径不加前缀
+fn kv_key(file: &str, prefix: &str) -> String {
+    let name = file.trim_start_matches("./");
+    match name.strip_prefix('/') {
+        Some(absolute) => absolute.to_owned(),
+        None => format!("{prefix}{name}"),
+    }
+}
+
+async fn write_if_changed(path: &Path, content: &[u8]) -> Result<(), ConfigurationError> {
+    if fs::read(path).await.is_ok_and(|old| old == content) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(path, content).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{KvKind, KvSource, prefix_end, register_files};
+
+    #[test]
+    fn kv_urls_are_parsed() {
+        let source = KvSource::parse("consul://10.0.0.1:8500/sites/42/project.json").unwrap();
+        assert_eq!(source.kind, KvKind::Consul);
+        assert_eq!(source.endpoint, "http://10.0.0.1:8500");
+        assert_eq!(source.key, "sites/42/project.json");
+        assert_eq!(source.prefix(), "sites/42/");
+
+        let source = KvSource::parse("etcd://etcd:2379/project.yaml").unwrap();
+        assert_eq!(source.kind, KvKind::Etcd);
+        assert_eq!(source.prefix(), "");
+
+        assert!(KvSource::parse("config/config.json").is_none());
+        assert!(KvSource::parse("consul://host:8500/").is_none());
+    }
+
+    #[test]
+    fn etcd_prefix_end_increments_last_byte() {
+        assert_eq!(prefix_end("sites/"), b"sites0");
+        assert_eq!(prefix_end(""), vec![0]);
+    }
+
+    #[test]
+    fn register_files_are_rebased_on_prefix() {
+        let mut doc = serde_json::json!({"devices": {
+            "pcs": {"config": {"register_file": "./pcs.xlsx"}},
+            "bms": {"config": {"register_file": "/shared/bms.xlsx"}},
+            "emu": {"config": {}}
+        }});
+
+        let mut files: Vec<(String, String)> =
+            register_files(&mut doc, "sites/42/", Path::new("m"))
+                .into_iter()
+                .map(|(key, local, _)| (key, local.display().to_string()))
+                .collect();
+        files.sort();
+
+        assert_eq!(
+            files,
+            vec![
+                ("shared/bms.xlsx".to_owned(), "m/shared/bms.xlsx".to_owned()),
+                (
+                    "sites/42/pcs.xlsx".to_owned(),
+                    "m/sites/42/pcs.xlsx".to_owned()
+                ),
+            ]
+        );
+    }
+}
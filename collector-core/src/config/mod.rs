@@ -1,15 +1,29 @@
 use calamine::{Data, DataType};
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing::error;
 
 use crate::core::point::PointId;
 
 pub mod can_conf;
+pub mod convert;
+mod env;
 pub mod gpio_conf;
+pub mod kv;
 pub mod modbus_conf;
 pub mod north_modbus_conf;
+pub mod reload;
+pub mod remote;
+pub mod revision;
+pub mod scaffold;
+pub mod secret;
+pub mod template;
+pub mod validate;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigurationError {
@@ -17,49 +31,392 @@ pub enum ConfigurationError {
     ReadFileError(#[from] std::io::Error),
     #[error("Failed to parse config: {0}")]
     ParseJsonError(#[from] serde_json::Error),
+    #[error("Failed to parse yaml config: {0}")]
+    ParseYamlError(#[from] serde_yaml::Error),
+    #[error("Failed to parse toml config: {0}")]
+    ParseTomlError(#[from] toml::de::Error),
+    #[error("环境变量{0}未设置")]
+    MissingEnvVar(String),
+    #[error("模板变量{0}未定义")]
+    MissingVariable(String),
+    #[error("无效的占位符: {0}")]
+    InvalidPlaceholder(String),
+    #[error("无效的包含路径{0}: {1}")]
+    InvalidInclude(String, String),
+    #[error("加载包含文件{0}失败: {1}")]
+    IncludeError(String, Box<ConfigurationError>),
+    #[error("设备{0}重复定义")]
+    DuplicateDevice(String),
+    #[error("Failed to fetch config: {0}")]
+    FetchError(String),
+    #[error("加载配置密钥失败: {0}")]
+    SecretKey(String),
+    #[error("解密配置字段{0}失败")]
+    Decrypt(String),
+    #[error("设备{0}点位表加载失败: {1}")]
+    PointTable(String, String),
+    #[error("设备{0}的从站列表无效: {1}")]
+    InvalidSlaves(String, String),
+}
+
+/// 项目配置文件格式，按扩展名识别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// `.yaml`/`.yml` 识别为 YAML，`.toml` 识别为 TOML，其余按 JSON 处理
+    pub fn from_path(path: &str) -> Self {
+        let path = if remote::is_remote(path) {
+            remote::strip_query(path)
+        } else {
+            path
+        };
+        let ext = std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+impl std::str::FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            "toml" => Ok(ConfigFormat::Toml),
+            other => Err(format!("不支持的配置格式: {other}")),
+        }
+    }
+}
+
+/// 生成项目配置的 JSON Schema，供编辑器校验补全及上传前预校验
+pub fn project_schema() -> serde_json::Value {
+    schemars::schema_for!(Project).to_value()
 }
 
 #[derive(Debug)]
 pub struct Configuration {
     pub project: Project,
+    /// `includes` 匹配到并已合并的设备文件及 `variables_file`，热更新时一并监听
+    pub included_files: Vec<PathBuf>,
 }
 
 impl Configuration {
     pub async fn new(path: String) -> Result<Self, ConfigurationError> {
-        let mut bytes = fs::read(path.as_str()).await?;
-        // strip UTF-8 BOM (EF BB BF)
-        if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
-            bytes.drain(..3);
+        let format = ConfigFormat::from_path(&path);
+        Self::with_format(path, format).await
+    }
+
+    /// 以指定格式读取项目配置，忽略文件扩展名
+    ///
+    /// `path` 可以是 HTTP(S) 地址，见 [`remote`]。
+    /// `includes` 中的设备文件会一并合并，其格式按各自扩展名识别；
+    /// 远程配置的 `includes`、`variables_file` 以当前工作目录为基准。
+    /// 点位表的相对路径按 [`Project::base_dir`] 解析
+    pub async fn with_format(
+        path: String,
+        format: ConfigFormat,
+    ) -> Result<Self, ConfigurationError> {
+        let (bytes, base) = if remote::is_remote(&path) {
+            (remote::fetch(&path).await?, Path::new(""))
+        } else {
+            let base = Path::new(&path).parent().unwrap_or(Path::new(""));
+            (fs::read(path.as_str()).await?, base)
+        };
+        let doc = parse::<serde_json::Value>(&bytes, format)?;
+        let (overrides, variables_file) = match doc
+            .get("variables_file")
+            .or_else(|| doc.get("variablesFile"))
+            .and_then(serde_json::Value::as_str)
+        {
+            Some(file) => {
+                let path = base.join(file);
+                let vars = load_variables(&path).await.map_err(|err| {
+                    ConfigurationError::IncludeError(path.display().to_string(), Box::new(err))
+                })?;
+                (vars, Some(path))
+            }
+            None => (template::Variables::new(), None),
+        };
+        let mut conf = Self::from_document(doc, overrides)?;
+        conf.included_files.extend(variables_file);
+        conf.merge_includes(base).await?;
+        conf.resolve_register_files(base);
+        Ok(conf)
+    }
+
+    /// 按指定格式解析项目配置，`{{var}}` 替换为模板变量（见 [`template`]），
+    /// 字符串字段中的 `${VAR}` 替换为环境变量，`ENC(...)` 凭据字段解密，见 [`secret`]
+    ///
+    /// 不处理 `includes` 及 `variables_file`
+    pub fn from_slice(bytes: &[u8], format: ConfigFormat) -> Result<Self, ConfigurationError> {
+        let doc = parse::<serde_json::Value>(bytes, format)?;
+        Self::from_document(doc, template::Variables::new())
+    }
+
+    /// `overrides` 覆盖配置中同名的 `variables`
+    fn from_document(
+        mut doc: serde_json::Value,
+        overrides: template::Variables,
+    ) -> Result<Self, ConfigurationError> {
+        let mut vars = template::variables(&doc)?;
+        vars.extend(overrides);
+        template::render(&mut doc, &vars)?;
+        let mut project: Project = serde_json::from_value(doc)?;
+        project.variables = Some(vars);
+        env::expand_project(&mut project, &env_lookup)?;
+        secret::decrypt_project(&mut project, secret::load_key)?;
+        project.apply_device_defaults();
+        project.expand_slaves()?;
+        Ok(Self {
+            project,
+            included_files: Vec::new(),
+        })
+    }
+
+    /// 合并 `includes` 匹配到的设备文件，相对路径以项目文件所在目录为基准
+    ///
+    /// 每个文件是设备键到设备配置的映射，键与已有设备重复时报错
+    async fn merge_includes(&mut self, base: &Path) -> Result<(), ConfigurationError> {
+        let Some(patterns) = self.project.includes.clone() else {
+            return Ok(());
+        };
+        for pattern in patterns {
+            let full = base.join(&pattern);
+            let paths = glob::glob(&full.to_string_lossy()).map_err(|err| {
+                ConfigurationError::InvalidInclude(pattern.clone(), err.to_string())
+            })?;
+            let mut files: Vec<PathBuf> = paths.filter_map(Result::ok).collect();
+            files.sort();
+            for file in files {
+                let name = file.display().to_string();
+                let devices = load_include(&file, self.project.variables.as_ref())
+                    .await
+                    .map_err(|err| ConfigurationError::IncludeError(name, Box::new(err)))?;
+                for (key, dev) in devices {
+                    if self.project.devices.contains_key(&key) {
+                        return Err(ConfigurationError::DuplicateDevice(key));
+                    }
+                    self.project.devices.insert(key, dev);
+                }
+                self.included_files.push(file);
+            }
+        }
+        self.project.apply_device_defaults();
+        self.project.expand_slaves()
+    }
+
+    /// 将点位表的相对路径转为以 `base_dir` 为基准的路径
+    fn resolve_register_files(&mut self, project_dir: &Path) {
+        let base = match self.project.base_dir.as_deref() {
+            Some(dir) => project_dir.join(dir),
+            None => project_dir.to_path_buf(),
+        };
+        for dev in self.project.devices.values_mut() {
+            if let Some(file) = dev.config.register_file.as_mut()
+                && Path::new(file.as_str()).is_relative()
+            {
+                *file = base.join(file.as_str()).display().to_string();
+            }
+        }
+    }
+
+    /// 比较新旧配置，得到需要重建的设备，见 [`reload::ConfigDiff`]
+    pub fn diff(old: &Configuration, new: &Configuration) -> reload::ConfigDiff {
+        reload::diff_devices(&old.project.devices, &new.project.devices)
+    }
+
+    /// 读取所有启用设备的点位表，返回严格模式下加载失败的设备
+    pub async fn load_device_configs(&mut self) -> Result<(), Vec<ConfigurationError>> {
+        let mut errors = Vec::new();
+        for dev in self
+            .project
+            .devices
+            .values_mut()
+            .filter(|dev| dev.is_enabled())
+        {
+            if let Err(err) = dev.load_protocol_configs().await {
+                errors.push(err);
+            }
         }
-        while matches!(bytes.first(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
-            bytes.drain(..1);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
-        let project = serde_json::from_slice::<Project>(&bytes)?;
-        Ok(Self { project })
     }
+}
+
+/// 指定本地缓存目录的环境变量
+const CACHE_DIR_ENV: &str = "COLLECTOR_CACHE_DIR";
+/// 本地缓存目录名
+const CACHE_DIR: &str = "config_cache";
+
+/// 本地缓存目录：远程配置副本、KV 配置镜像与点位表摘要都保存在此，按以下顺序确定：
+/// 1. 环境变量 `COLLECTOR_CACHE_DIR`
+/// 2. systemd 的状态目录(`StateDirectory=`)下的 `config_cache`
+/// 3. 可执行文件所在目录下的 `config_cache`
+///
+/// 不随工作目录变化，以服务方式运行时工作目录通常为根目录
+pub fn cache_dir() -> PathBuf {
+    resolve_cache_dir(
+        std::env::var_os(CACHE_DIR_ENV),
+        std::env::var_os("STATE_DIRECTORY"),
+        std::env::current_exe().ok(),
+    )
+}
+
+fn resolve_cache_dir(
+    dir: Option<OsString>,
+    state_directory: Option<OsString>,
+    exe: Option<PathBuf>,
+) -> PathBuf {
+    if let Some(dir) = dir.filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    // 配置了多个状态目录时以冒号分隔，取第一个
+    let state = state_directory.and_then(|dirs| {
+        let dirs = dirs.to_string_lossy().into_owned();
+        dirs.split(':')
+            .find(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    });
+    let base = state.or_else(|| exe?.parent().map(Path::to_path_buf));
+    base.map_or_else(|| PathBuf::from(CACHE_DIR), |base| base.join(CACHE_DIR))
+}
+
+fn env_lookup(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+fn parse<T: DeserializeOwned>(bytes: &[u8], format: ConfigFormat) -> Result<T, ConfigurationError> {
+    // strip UTF-8 BOM (EF BB BF)
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    let bytes = bytes.trim_ascii_start();
+    Ok(match format {
+        ConfigFormat::Json => serde_json::from_slice::<T>(bytes)?,
+        ConfigFormat::Yaml => serde_yaml::from_slice::<T>(bytes)?,
+        ConfigFormat::Toml => toml::from_slice::<T>(bytes)?,
+    })
+}
+
+async fn load_include(
+    file: &Path,
+    vars: Option<&template::Variables>,
+) -> Result<HashMap<String, Device>, ConfigurationError> {
+    let bytes = fs::read(file).await?;
+    let mut doc: serde_json::Value =
+        parse(&bytes, ConfigFormat::from_path(&file.to_string_lossy()))?;
+    if let Some(vars) = vars {
+        template::render(&mut doc, vars)?;
+    }
+    let mut devices: HashMap<String, Device> = serde_json::from_value(doc)?;
+    for dev in devices.values_mut() {
+        env::expand_device(dev, &env_lookup)?;
+    }
+    Ok(devices)
+}
 
-    pub async fn load_device_configs(&mut self) {
-        for (_, dev) in self.project.devices.iter_mut() {
-            dev.protocol_configs = Some(load_protocol_configs(dev).await);
+async fn load_variables(file: &Path) -> Result<template::Variables, ConfigurationError> {
+    let bytes = fs::read(file).await?;
+    parse(&bytes, ConfigFormat::from_path(&file.to_string_lossy()))
+}
+
+/// 点位表路径含通配符时返回匹配到的文件（按路径排序），否则原样返回
+pub fn expand_register_file(file: &str) -> Vec<PathBuf> {
+    if !is_glob(file) {
+        return vec![PathBuf::from(file)];
+    }
+    let mut files: Vec<PathBuf> = glob::glob(file)
+        .map(|paths| paths.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+pub(crate) fn is_glob(file: &str) -> bool {
+    file.contains(['*', '?', '['])
+}
+
+impl Device {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// 读取设备的点位表，并记录点位表版本，见 [`revision`]
+    ///
+    /// 点位表读取失败时设备没有点位；`strict_point_tables` 开启时返回错误
+    pub async fn load_protocol_configs(&mut self) -> Result<(), ConfigurationError> {
+        match load_protocol_configs(self).await {
+            Ok(configs) => self.protocol_configs = Some(configs),
+            Err(err) if self.config.strict_point_tables == Some(true) => {
+                let id = self.id.clone().unwrap_or_default();
+                return Err(ConfigurationError::PointTable(id, err));
+            }
+            Err(err) => {
+                error!("Failed to build {:?} configs: {}", self.id, err);
+                self.protocol_configs = Some(ProtocolConfigs::None);
+            }
         }
+        record_revisions(self).await;
+        Ok(())
+    }
+}
+
+async fn record_revisions(dev: &Device) {
+    let (Some(id), Some(file)) = (dev.id.clone(), dev.config.register_file.clone()) else {
+        return;
+    };
+    let cell = dev.config.version_cell.clone();
+    let task = move || {
+        let files = expand_register_file(&file)
+            .into_iter()
+            .filter_map(|path| {
+                let path = path.display().to_string();
+                revision::inspect(&id, &path, cell.as_deref())
+                    .inspect_err(|err| error!("Failed to hash register file {}: {}", path, err))
+                    .ok()
+            })
+            .collect();
+        revision::record(&id, files);
+    };
+    if let Err(err) = tokio::task::spawn_blocking(task).await {
+        error!("Failed to join revision recorder for {:?}: {}", dev.id, err);
     }
 }
 
-async fn load_protocol_configs(dev: &Device) -> ProtocolConfigs {
+async fn load_protocol_configs(dev: &Device) -> Result<ProtocolConfigs, String> {
     let Some(com) = dev.config.com_type else {
-        return ProtocolConfigs::None;
+        return Ok(ProtocolConfigs::None);
     };
     let Some(file) = dev.config.register_file.clone() else {
-        return ProtocolConfigs::None;
+        return Ok(ProtocolConfigs::None);
     };
+    let strict = dev.config.strict_point_tables == Some(true);
     let dev_id = dev.id.clone();
 
     match com {
         ComType::ModbusTCP | ComType::ModbusRTU => {
+            let options = modbus_conf::XlsxOptions {
+                sheets: dev.config.sheets.clone(),
+                columns: dev.config.columns.clone(),
+                strict,
+            };
             load_configs(
                 file,
                 dev_id,
-                modbus_conf::build_configs,
+                move |path| modbus_conf::build_configs(path, &options),
                 ProtocolConfigs::Modbus,
             )
             .await
@@ -74,16 +431,16 @@ async fn load_protocol_configs(dev: &Device) -> ProtocolConfigs {
                 "Failed to build {:?} configs: CAN is only supported on Linux",
                 dev_id
             );
-            ProtocolConfigs::None
+            Ok(ProtocolConfigs::None)
         }
-        ComType::IEC104 => ProtocolConfigs::None,
-        ComType::IEC61850 => ProtocolConfigs::None,
+        ComType::IEC104 => Ok(ProtocolConfigs::None),
+        ComType::IEC61850 => Ok(ProtocolConfigs::None),
         #[cfg(target_os = "linux")]
         ComType::GPIO => {
             load_configs(
                 file,
                 dev_id,
-                gpio_conf::build_configs,
+                move |path| gpio_conf::build_configs(path, strict),
                 ProtocolConfigs::GPIO,
             )
             .await
@@ -94,37 +451,46 @@ async fn load_protocol_configs(dev: &Device) -> ProtocolConfigs {
                 "Failed to build {:?} configs: GPIO is only supported on Linux",
                 dev_id
             );
-            ProtocolConfigs::None
+            Ok(ProtocolConfigs::None)
         }
     }
 }
 
-async fn load_configs<T, E, B, W>(
+/// 读取点位表，路径含通配符时依次读取匹配到的文件并合并
+pub(crate) async fn load_configs<T, E, B, W, R>(
     file: String,
     dev_id: Option<String>,
     build: B,
     wrap: W,
-) -> ProtocolConfigs
+) -> Result<R, String>
 where
     T: Send + 'static,
     E: std::fmt::Display + Send + 'static,
-    B: FnOnce(String) -> Result<T, E> + Send + 'static,
-    W: FnOnce(T) -> ProtocolConfigs,
+    B: Fn(String) -> Result<Vec<T>, E> + Send + 'static,
+    W: FnOnce(Vec<T>) -> R,
 {
-    match tokio::task::spawn_blocking(move || build(file)).await {
-        Ok(Ok(configs)) => wrap(configs),
-        Ok(Err(err)) => {
-            error!("Failed to build {:?} configs: {}", dev_id, err);
-            ProtocolConfigs::None
+    let task = move || {
+        let files = expand_register_file(&file);
+        if files.is_empty() {
+            return Err(format!("{file}: 没有匹配的点位表"));
         }
-        Err(err) => {
-            error!("Failed to join config loader for {:?}: {}", dev_id, err);
-            ProtocolConfigs::None
+        let mut configs = Vec::new();
+        for path in files {
+            let path = path.display().to_string();
+            let part = build(path.clone()).map_err(|err| format!("{path}: {err}"))?;
+            configs.extend(part);
         }
+        Ok(configs)
+    };
+    match tokio::task::spawn_blocking(task).await {
+        Ok(configs) => configs.map(wrap),
+        Err(err) => Err(format!(
+            "Failed to join config loader for {dev_id:?}: {err}"
+        )),
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, JsonSchema)]
 pub struct Project {
     pub product_type: Option<String>,
     pub project: Option<String>,
@@ -141,21 +507,154 @@ pub struct Project {
     pub north_modbus_host: Option<String>,
     pub north_modbus_port: Option<u16>,
     pub north_modbus_conf: Option<String>,
+    /// 数据中心浮点变化检测的全局容差
+    pub float_epsilon: Option<f64>,
+    /// 设备配置的缺省值，设备自身未配置的字段继承此处的值
+    #[serde(alias = "deviceDefaults")]
+    pub device_defaults: Option<DeviceDefaults>,
+    /// 按通信类型划分的设备配置缺省值，如所有 ModbusRTU 设备共用的波特率、校验位
+    #[serde(alias = "protocolDefaults")]
+    pub protocol_defaults: Option<HashMap<ComType, DeviceConfig>>,
+    /// 设备模板，成批相同的设备只需声明一次点位表、采集间隔等配置，
+    /// 设备以 `template` 引用模板，只配置 ID、IP、从站地址等不同的字段
+    pub templates: Option<HashMap<String, DeviceConfig>>,
+    /// 点位表相对路径的基准目录，自身为相对路径时以项目文件所在目录为基准；
+    /// 缺省为项目文件所在目录
+    #[serde(alias = "baseDir")]
+    pub base_dir: Option<String>,
+    /// 所有设备点位表的严格模式，设备自身的 `strict_point_tables` 优先
+    #[serde(alias = "strictPointTables")]
+    pub strict_point_tables: Option<bool>,
+    /// 启动时相邻两台设备的间隔(ms)，避免大量设备同时连接，缺省同时启动
+    #[serde(alias = "startSpacingMs")]
+    pub start_spacing: Option<u64>,
+    /// 同时尝试连接的最大设备数，其余设备排队等待，缺省不限制
+    #[serde(alias = "maxConnecting")]
+    pub max_connecting: Option<usize>,
+    /// 关闭时等待所有设备停止的期限(ms)，缺省 10000
+    #[serde(alias = "stopDeadlineMs")]
+    pub stop_deadline: Option<u64>,
+    /// 控制套接字的路径，`collector status` 经此查询设备状态；缺省为工作目录下的 `collector.sock`
+    #[serde(alias = "controlSocket")]
+    pub control_socket: Option<String>,
+    /// 需要合并的设备文件，支持通配符，如 `devices/*.json`
+    pub includes: Option<Vec<String>>,
+    /// 模板变量，配置中的 `{{name}}` 替换为变量的值，见 [`template`]
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    /// 覆盖 `variables` 的变量文件，相对路径以项目文件所在目录为基准
+    #[serde(alias = "variablesFile")]
+    pub variables_file: Option<String>,
+    #[serde(default)]
     pub devices: HashMap<String, Device>,
     pub mqtt_routes: Option<Vec<MqttRoute>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl Project {
+    /// 填充各设备未配置的字段
+    ///
+    /// 优先级：设备自身配置 > 引用的模板 > `protocol_defaults` 中对应通信类型的配置 > `device_defaults`；
+    /// 引用不存在的模板时由 [`Configuration::validate`] 报告
+    pub fn apply_device_defaults(&mut self) {
+        for dev in self.devices.values_mut() {
+            let config = &mut dev.config;
+            if let Some(template) = dev
+                .template
+                .as_ref()
+                .and_then(|name| self.templates.as_ref()?.get(name))
+            {
+                config.inherit(template);
+            }
+            if let Some(defaults) = config
+                .com_type
+                .and_then(|com| self.protocol_defaults.as_ref()?.get(&com))
+            {
+                config.inherit(defaults);
+            }
+            if let Some(defaults) = self.device_defaults.as_ref() {
+                config.interval = config.interval.or(defaults.interval);
+                config.timeout = config.timeout.or(defaults.timeout);
+                config.slave = config.slave.or(defaults.slave);
+                if config.byte_order.is_none() {
+                    config.byte_order = defaults.byte_order.clone();
+                }
+            }
+            config.strict_point_tables = config.strict_point_tables.or(self.strict_point_tables);
+        }
+    }
+
+    /// 将配置了 `slaves` 的设备展开为每个从站一个设备，见 [`DeviceConfig::slaves`]
+    pub fn expand_slaves(&mut self) -> Result<(), ConfigurationError> {
+        let mut keys: Vec<String> = self
+            .devices
+            .iter()
+            .filter(|(_, dev)| dev.config.slaves.is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+        for key in keys {
+            let Some(mut dev) = self.devices.remove(&key) else {
+                continue;
+            };
+            let slaves = dev.config.slaves.take().unwrap_or_default();
+            let invalid =
+                |reason: &str| ConfigurationError::InvalidSlaves(key.clone(), reason.into());
+            if slaves.is_empty() {
+                return Err(invalid("列表为空"));
+            }
+            if let Some(slave) = slaves.iter().find(|slave| !(1..=247).contains(*slave)) {
+                return Err(invalid(&format!("从站地址{slave}超出 1~247")));
+            }
+            if dev.config.com_type == Some(ComType::ModbusTCP) {
+                dev.config.shared_connection.get_or_insert(true);
+            }
+            for slave in slaves {
+                let mut unit = dev.clone();
+                unit.id = dev.id.as_ref().map(|id| format!("{id}_{slave}"));
+                unit.config.slave = Some(slave);
+                unit.config.key_prefix = Some(format!(
+                    "{}{slave}_",
+                    dev.config.key_prefix.as_deref().unwrap_or_default()
+                ));
+                let unit_key = format!("{key}_{slave}");
+                if self.devices.contains_key(&unit_key) {
+                    return Err(ConfigurationError::DuplicateDevice(unit_key));
+                }
+                self.devices.insert(unit_key, unit);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 设备配置缺省值
+#[derive(Deserialize, Clone, Debug, Default, JsonSchema)]
+pub struct DeviceDefaults {
+    pub interval: Option<u64>,
+    pub timeout: Option<u64>,
+    pub slave: Option<u8>,
+    pub byte_order: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, JsonSchema)]
 pub struct Device {
     pub id: Option<String>,
     pub desc: Option<String>,
+    /// 为 `false` 时保留设备配置但不启动设备，缺省启用
+    pub enabled: Option<bool>,
+    /// 启动分组，缺省设备自成一组，组名为设备 ID
+    pub group: Option<String>,
+    /// 须先启动的分组（或未分组设备的 ID），这些分组的设备运行后才启动本设备
+    #[serde(default, alias = "dependsOn")]
+    pub depends_on: Vec<String>,
+    /// 引用的设备模板，见 [`Project::templates`]
+    pub template: Option<String>,
     pub config: DeviceConfig,
 
     #[serde(skip)]
     pub protocol_configs: Option<ProtocolConfigs>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, JsonSchema)]
 pub enum ComType {
     #[serde(rename = "ModbusTCP")]
     ModbusTCP,
@@ -171,26 +670,430 @@ pub enum ComType {
     GPIO,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// 点表中各扫描等级、各寄存器类型的轮询周期(ms)
+///
+/// 点位所在扫描等级配置了周期时按等级的周期轮询，否则按其寄存器类型的周期，
+/// 如开关量 500ms、模拟量 5s；都未配置时不限制周期，与不分等级时一样按 `request_interval` 连续轮询
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct ScanIntervals {
+    pub fast: Option<u64>,
+    pub normal: Option<u64>,
+    pub slow: Option<u64>,
+    pub coils: Option<u64>,
+    #[serde(alias = "discreteInputs")]
+    pub discrete_inputs: Option<u64>,
+    #[serde(alias = "holdingRegisters")]
+    pub holding_registers: Option<u64>,
+    #[serde(alias = "inputRegisters")]
+    pub input_registers: Option<u64>,
+}
+
+impl ScanIntervals {
+    pub fn get(&self, class: modbus_conf::ScanClass) -> Option<u64> {
+        match class {
+            modbus_conf::ScanClass::Fast => self.fast,
+            modbus_conf::ScanClass::Normal => self.normal,
+            modbus_conf::ScanClass::Slow => self.slow,
+        }
+    }
+
+    /// 点位的轮询周期：扫描等级的周期优先，其次是寄存器类型的周期
+    pub fn period(
+        &self,
+        class: modbus_conf::ScanClass,
+        register_type: modbus_conf::RegisterType,
+    ) -> Option<u64> {
+        self.get(class).or(match register_type {
+            modbus_conf::RegisterType::Coils => self.coils,
+            modbus_conf::RegisterType::DiscreteInputs => self.discrete_inputs,
+            modbus_conf::RegisterType::HoldingRegisters => self.holding_registers,
+            modbus_conf::RegisterType::InputRegisters => self.input_registers,
+        })
+    }
+}
+
+/// Modbus/TCP Security 的 TLS 参数，证书与私钥为 PEM 文件
+///
+/// Modbus Security 要求双向认证，设备一般只接受由其信任的 CA 签发的客户端证书
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct TlsOptions {
+    /// 校验设备证书的 CA
+    #[serde(alias = "caFile")]
+    pub ca_file: String,
+    /// 客户端证书链
+    #[serde(alias = "certFile")]
+    pub cert_file: String,
+    /// 客户端私钥
+    #[serde(alias = "keyFile")]
+    pub key_file: String,
+    /// 校验设备证书使用的名称，缺省为设备 IP
+    #[serde(alias = "serverName")]
+    pub server_name: Option<String>,
+}
+
+/// 设备后台任务意外结束时的重启方式
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RestartMode {
+    /// 不重启
+    Never,
+    /// 任务结束即重启
+    Always,
+    /// 任务 panic 或以失败状态结束时重启
+    #[default]
+    #[serde(alias = "on-failure")]
+    OnFailure,
+}
+
+/// 设备的重启策略，未配置时设备任务结束后不重启
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct RestartPolicy {
+    #[serde(default)]
+    pub mode: RestartMode,
+    /// 最多重启次数，缺省不限
+    #[serde(alias = "maxRetries")]
+    pub max_retries: Option<u32>,
+    /// 第一次重启前的等待(ms)，之后每次加倍，缺省 1000
+    #[serde(alias = "backoffMs")]
+    pub backoff: Option<u64>,
+    /// 重启等待的上限(ms)，缺省 60000
+    #[serde(alias = "maxBackoffMs")]
+    pub max_backoff: Option<u64>,
+}
+
+impl RestartPolicy {
+    /// 设备任务以 `exit` 结束、已重启 `restarts` 次时是否再次重启
+    pub fn should_restart(&self, exit: crate::dev::DeviceExit, restarts: u32) -> bool {
+        let restart = match self.mode {
+            RestartMode::Never => false,
+            RestartMode::Always => true,
+            RestartMode::OnFailure => exit == crate::dev::DeviceExit::Failed,
+        };
+        restart && self.max_retries.is_none_or(|max| restarts < max)
+    }
+}
+
+/// 设备看门狗，设备停留在连接中/失败状态过久或数据长时间未更新时强制重启设备
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct Watchdog {
+    /// 连接中/失败状态持续超过该时间(ms)时重启
+    #[serde(alias = "stuckTimeoutMs")]
+    pub stuck_timeout: Option<u64>,
+    /// 数据超过该数量的采集周期(`interval`)未更新时重启
+    #[serde(alias = "staleIntervals")]
+    pub stale_intervals: Option<u32>,
+}
+
+/// Modbus 从站返回异常码（如非法数据地址）时的处理方式
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExceptionPolicy {
+    /// 跳过该块，其点位保留上次的值，继续读取其他块
+    Skip,
+    /// 跳过该块，并将其点位标记为坏质量
+    Bad,
+    /// 与通讯失败一样计入连续失败，达到阈值后重连
+    #[default]
+    Reconnect,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, JsonSchema)]
 pub struct DeviceConfig {
     #[serde(rename = "type")]
     pub device_type: Option<String>,
     pub com_type: Option<ComType>,
+    /// 点位表路径，支持通配符，匹配到的多个文件合并为一张点位表
+    #[serde(alias = "registerFile")]
     pub register_file: Option<String>,
     pub interval: Option<u64>,
     pub timeout: Option<u64>,
     pub request_interval: Option<u64>,
+    /// Modbus 相邻两次请求之间的最小间隔(ms)，轮询、重试、回读与下发的每个请求都受限制，缺省不限制
+    #[serde(alias = "minRequestDelayMs")]
+    pub min_request_delay: Option<u64>,
+    /// Modbus 第一次连接后随机等待 0 到该时间(ms)再开始轮询，分散各设备的轮询相位，缺省不等待
+    #[serde(alias = "startJitterMs")]
+    pub start_jitter: Option<u64>,
+    /// 相邻点位之间不超过该数量的空洞寄存器时合并为一次读取，空洞的数据解析时忽略，缺省为 0
+    #[serde(alias = "maxGap")]
     pub max_gap: Option<u16>,
+    /// Modbus 单次读写的最大寄存器数，缺省 120
+    #[serde(alias = "maxReadRegisters")]
+    pub max_read_registers: Option<u16>,
+    /// Modbus 单次读写的最大线圈/离散输入数，缺省 2000
+    #[serde(alias = "maxReadBits")]
+    pub max_read_bits: Option<u16>,
+    /// Modbus 读取失败（含超时）后的重试次数，重试都失败才计入连续失败，缺省 0
+    #[serde(alias = "requestRetries")]
+    pub request_retries: Option<u32>,
+    /// Modbus 从站返回异常码时的处理方式：skip/bad/reconnect，缺省 reconnect
+    #[serde(alias = "exceptionPolicy")]
+    pub exception_policy: Option<ExceptionPolicy>,
+    /// Modbus 块读取失败时继续读取其余块，失败块的点位标记为坏质量，连续该圈数所有块都失败才重连；
+    /// 缺省任一块连续失败 3 次即重连
+    #[serde(alias = "maxFailedCycles")]
+    pub max_failed_cycles: Option<u32>,
+    /// Modbus 通讯中断时点位的替代值，缺省保留中断前的值；点位质量均标记为通讯故障
+    #[serde(alias = "substituteValue")]
+    pub substitute_value: Option<f64>,
+    /// Modbus 调试：每次块读取后输出原始寄存器/线圈数据与解析结果，调试时核对字节序与缩放
+    #[serde(alias = "debugDump")]
+    pub debug_dump: Option<bool>,
+    /// Modbus 下发后回读写入的线圈/寄存器，与下发值不一致时下发失败
+    #[serde(alias = "verifyWrites")]
+    pub verify_writes: Option<bool>,
+    /// Modbus 选择-执行控制中等待选择确认的超时(ms)，缺省 3000
+    #[serde(alias = "selectTimeout")]
+    pub select_timeout: Option<u64>,
+    /// Modbus TCP 与同一 IP、端口的其他设备共用一条连接，按请求切换从站地址
+    #[serde(alias = "sharedConnection")]
+    pub shared_connection: Option<bool>,
+    /// Modbus TCP 连接空闲该秒数后开始发送 TCP 保活探测，之后按同样的间隔探测，
+    /// 轮询周期较长时可以在下次读取前发现被 NAT/防火墙丢弃的连接；缺省不开启
+    #[serde(alias = "tcpKeepAlive")]
+    pub tcp_keepalive: Option<u64>,
+    /// Modbus TCP 连接后读取设备标识（功能码 0x2B/0x0E），发布厂商、产品代码与版本
+    #[serde(alias = "deviceIdentification")]
+    pub device_identification: Option<bool>,
+    /// Modbus RTU 帧间隔(ms)，缺省按波特率取 3.5 个字符时间，慢速转换器可以调大
+    #[serde(alias = "interFrameDelayMs")]
+    pub inter_frame_delay: Option<u64>,
+    /// Modbus RTU 的 RS-485 方向控制线：`RTS` 或 `/dev/gpiochip0:17` 形式的 GPIO，缺省由硬件自动切换
+    #[serde(alias = "rs485Direction")]
+    pub rs485_direction: Option<String>,
+    /// 停止设备时等待后台任务结束的时间(ms)，超时后强制中止，缺省 3000
+    #[serde(alias = "stopTimeout")]
+    pub stop_timeout: Option<u64>,
+    /// Modbus 允许轮询的时段(本地时间)，如 `06:00-22:00`、`Mon-Fri 07:30-18:00`，缺省全天；
+    /// 时段外断开连接，见 [`crate::dev::schedule`]
+    #[serde(alias = "pollWindows")]
+    pub poll_windows: Option<Vec<String>>,
+    /// Modbus 不轮询的维护时段，如 `2026-10-20 08:00-12:00`，优先于 `poll_windows`
+    #[serde(alias = "blackoutWindows")]
+    pub blackout_windows: Option<Vec<String>>,
+    /// Modbus 各扫描等级、各寄存器类型的轮询周期(ms)
+    #[serde(alias = "scanIntervals")]
+    pub scan_intervals: Option<ScanIntervals>,
     pub ip: Option<String>,
     pub port: Option<u16>,
+    /// Modbus TCP 备用链路的 IP，主链路连续连接失败后切换到备用链路，并定期回试主链路
+    #[serde(alias = "backupIp")]
+    pub backup_ip: Option<String>,
+    /// 备用链路的端口，缺省与主链路相同
+    #[serde(alias = "backupPort")]
+    pub backup_port: Option<u16>,
+    /// Modbus TCP 使用 TLS 连接（Modbus/TCP Security，端口一般为 802）
+    pub tls: Option<TlsOptions>,
     pub slave: Option<u8>,
+    /// Modbus 同一连接上的多个从站，如电池簇的各个电池组；加载时展开为每个从站一个设备，
+    /// 设备键与 ID 追加 `_从站地址`，共用点位表，点位键加上 `从站地址_` 前缀，TCP 设备共用连接
+    pub slaves: Option<Vec<u8>>,
+    /// Modbus 点位键的前缀，同一点位表用于多个设备时区分各设备的点位
+    #[serde(alias = "keyPrefix")]
+    pub key_prefix: Option<String>,
     pub serial_tty: Option<String>,
+    /// Modbus RTU 备用链路的串口设备，串口参数与主链路相同
+    #[serde(alias = "backupSerialTty")]
+    pub backup_serial_tty: Option<String>,
     pub baud_rate: Option<u32>,
     pub data_bits: Option<u8>,
     pub parity: Option<String>,
     pub stop_bits: Option<u8>,
     pub interface: Option<String>,
     pub desc: Option<String>,
+    /// 点表未指定字节序的点位使用的字节序(AB/BA/ABCD/CDAB/DCBA/BADC)
+    pub byte_order: Option<String>,
+    /// 点位表寄存器地址的起始编号，缺省 0；为 1 时同时识别 40001 形式的 Modicon 编号
+    #[serde(alias = "addressBase")]
+    pub address_base: Option<u16>,
+    /// Excel 点表读取的工作表，缺省读取遥信/遥控/遥测/遥调，都不存在时读取全部工作表
+    pub sheets: Option<Vec<String>>,
+    /// Excel 点表的列映射，如 `{"key": "Tag", "scale": 9}`，值为表头文字或从 0 开始的列序号
+    pub columns: Option<HashMap<String, modbus_conf::ColumnRef>>,
+    /// 点位表中记录版本号的单元格，如 `说明!B2`
+    pub version_cell: Option<String>,
+    /// 严格模式：点位表中任一行解析失败即设备加载失败，而不是跳过该行
+    #[serde(alias = "strictPointTables")]
+    pub strict_point_tables: Option<bool>,
+    /// 设备后台任务意外结束时的重启策略
+    pub restart: Option<RestartPolicy>,
+    /// 看门狗，缺省不检查
+    pub watchdog: Option<Watchdog>,
+}
+
+impl DeviceConfig {
+    /// 用 `defaults` 填充未配置的字段
+    pub fn inherit(&mut self, defaults: &DeviceConfig) {
+        fn fill<T: Clone>(field: &mut Option<T>, default: &Option<T>) {
+            if field.is_none() {
+                field.clone_from(default);
+            }
+        }
+        fill(&mut self.device_type, &defaults.device_type);
+        fill(&mut self.com_type, &defaults.com_type);
+        fill(&mut self.register_file, &defaults.register_file);
+        fill(&mut self.interval, &defaults.interval);
+        fill(&mut self.timeout, &defaults.timeout);
+        fill(&mut self.request_interval, &defaults.request_interval);
+        fill(&mut self.min_request_delay, &defaults.min_request_delay);
+        fill(&mut self.start_jitter, &defaults.start_jitter);
+        fill(&mut self.max_gap, &defaults.max_gap);
+        fill(&mut self.max_read_registers, &defaults.max_read_registers);
+        fill(&mut self.max_read_bits, &defaults.max_read_bits);
+        fill(&mut self.request_retries, &defaults.request_retries);
+        fill(&mut self.exception_policy, &defaults.exception_policy);
+        fill(&mut self.max_failed_cycles, &defaults.max_failed_cycles);
+        fill(&mut self.substitute_value, &defaults.substitute_value);
+        fill(&mut self.debug_dump, &defaults.debug_dump);
+        fill(&mut self.verify_writes, &defaults.verify_writes);
+        fill(&mut self.select_timeout, &defaults.select_timeout);
+        fill(&mut self.shared_connection, &defaults.shared_connection);
+        fill(&mut self.tcp_keepalive, &defaults.tcp_keepalive);
+        fill(
+            &mut self.device_identification,
+            &defaults.device_identification,
+        );
+        fill(&mut self.inter_frame_delay, &defaults.inter_frame_delay);
+        fill(&mut self.rs485_direction, &defaults.rs485_direction);
+        fill(&mut self.stop_timeout, &defaults.stop_timeout);
+        fill(&mut self.poll_windows, &defaults.poll_windows);
+        fill(&mut self.blackout_windows, &defaults.blackout_windows);
+        fill(&mut self.scan_intervals, &defaults.scan_intervals);
+        fill(&mut self.ip, &defaults.ip);
+        fill(&mut self.port, &defaults.port);
+        fill(&mut self.backup_ip, &defaults.backup_ip);
+        fill(&mut self.backup_port, &defaults.backup_port);
+        fill(&mut self.tls, &defaults.tls);
+        fill(&mut self.slave, &defaults.slave);
+        fill(&mut self.slaves, &defaults.slaves);
+        fill(&mut self.key_prefix, &defaults.key_prefix);
+        fill(&mut self.serial_tty, &defaults.serial_tty);
+        fill(&mut self.backup_serial_tty, &defaults.backup_serial_tty);
+        fill(&mut self.baud_rate, &defaults.baud_rate);
+        fill(&mut self.data_bits, &defaults.data_bits);
+        fill(&mut self.parity, &defaults.parity);
+        fill(&mut self.stop_bits, &defaults.stop_bits);
+        fill(&mut self.interface, &defaults.interface);
+        fill(&mut self.desc, &defaults.desc);
+        fill(&mut self.byte_order, &defaults.byte_order);
+        fill(&mut self.address_base, &defaults.address_base);
+        fill(&mut self.sheets, &defaults.sheets);
+        fill(&mut self.columns, &defaults.columns);
+        fill(&mut self.version_cell, &defaults.version_cell);
+        fill(&mut self.strict_point_tables, &defaults.strict_point_tables);
+        fill(&mut self.restart, &defaults.restart);
+        fill(&mut self.watchdog, &defaults.watchdog);
+    }
+
+    /// 与 `other` 取值不同的字段名
+    pub fn changed_fields(&self, other: &DeviceConfig) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        let mut compare = |name, changed: bool| {
+            if changed {
+                fields.push(name);
+            }
+        };
+        compare("type", self.device_type != other.device_type);
+        compare("com_type", self.com_type != other.com_type);
+        compare("register_file", self.register_file != other.register_file);
+        compare("interval", self.interval != other.interval);
+        compare("timeout", self.timeout != other.timeout);
+        compare(
+            "request_interval",
+            self.request_interval != other.request_interval,
+        );
+        compare(
+            "min_request_delay",
+            self.min_request_delay != other.min_request_delay,
+        );
+        compare("start_jitter", self.start_jitter != other.start_jitter);
+        compare("max_gap", self.max_gap != other.max_gap);
+        compare(
+            "max_read_registers",
+            self.max_read_registers != other.max_read_registers,
+        );
+        compare("max_read_bits", self.max_read_bits != other.max_read_bits);
+        compare(
+            "request_retries",
+            self.request_retries != other.request_retries,
+        );
+        compare(
+            "exception_policy",
+            self.exception_policy != other.exception_policy,
+        );
+        compare(
+            "max_failed_cycles",
+            self.max_failed_cycles != other.max_failed_cycles,
+        );
+        compare("verify_writes", self.verify_writes != other.verify_writes);
+        compare(
+            "select_timeout",
+            self.select_timeout != other.select_timeout,
+        );
+        compare(
+            "shared_connection",
+            self.shared_connection != other.shared_connection,
+        );
+        compare("tcp_keepalive", self.tcp_keepalive != other.tcp_keepalive);
+        compare("debug_dump", self.debug_dump != other.debug_dump);
+        compare(
+            "substitute_value",
+            self.substitute_value != other.substitute_value,
+        );
+        compare(
+            "device_identification",
+            self.device_identification != other.device_identification,
+        );
+        compare(
+            "inter_frame_delay",
+            self.inter_frame_delay != other.inter_frame_delay,
+        );
+        compare(
+            "rs485_direction",
+            self.rs485_direction != other.rs485_direction,
+        );
+        compare("stop_timeout", self.stop_timeout != other.stop_timeout);
+        compare("poll_windows", self.poll_windows != other.poll_windows);
+        compare(
+            "blackout_windows",
+            self.blackout_windows != other.blackout_windows,
+        );
+        compare(
+            "scan_intervals",
+            self.scan_intervals != other.scan_intervals,
+        );
+        compare("ip", self.ip != other.ip);
+        compare("port", self.port != other.port);
+        compare("backup_ip", self.backup_ip != other.backup_ip);
+        compare("backup_port", self.backup_port != other.backup_port);
+        compare("tls", self.tls != other.tls);
+        compare("slave", self.slave != other.slave);
+        compare("slaves", self.slaves != other.slaves);
+        compare("key_prefix", self.key_prefix != other.key_prefix);
+        compare("serial_tty", self.serial_tty != other.serial_tty);
+        compare(
+            "backup_serial_tty",
+            self.backup_serial_tty != other.backup_serial_tty,
+        );
+        compare("baud_rate", self.baud_rate != other.baud_rate);
+        compare("data_bits", self.data_bits != other.data_bits);
+        compare("parity", self.parity != other.parity);
+        compare("stop_bits", self.stop_bits != other.stop_bits);
+        compare("interface", self.interface != other.interface);
+        compare("desc", self.desc != other.desc);
+        compare("byte_order", self.byte_order != other.byte_order);
+        compare("address_base", self.address_base != other.address_base);
+        compare("sheets", self.sheets != other.sheets);
+        compare("columns", self.columns != other.columns);
+        compare("version_cell", self.version_cell != other.version_cell);
+        compare(
+            "strict_point_tables",
+            self.strict_point_tables != other.strict_point_tables,
+        );
+        compare("restart", self.restart != other.restart);
+        compare("watchdog", self.watchdog != other.watchdog);
+        fields
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -203,13 +1106,13 @@ pub enum ProtocolConfigs {
     None,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
 pub struct MqttRoute {
     pub device_id: String,
     pub rules: Vec<MqttRule>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
 pub struct MqttRule {
     pub topic: String,
     pub point_ids: Vec<PointId>,
@@ -272,3 +1175,324 @@ pub(crate) fn required_hex(row: &[Data], idx: usize, field: &str) -> Result<u32,
     let str = str.strip_prefix("0x").unwrap_or(str);
     u32::from_str_radix(str, 16).map_err(|_| anyhow::Error::msg(format!("{field}格式错误")))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{ComType, ConfigFormat, Configuration, ConfigurationError, resolve_cache_dir};
+
+    #[test]
+    fn cache_dir_prefers_env_then_state_directory_then_exe_dir() {
+        let exe = Some(PathBuf::from("/opt/collector/collector-cmd"));
+        assert_eq!(
+            resolve_cache_dir(
+                Some("/data/cache".into()),
+                Some("/var/lib/a".into()),
+                exe.clone()
+            ),
+            PathBuf::from("/data/cache")
+        );
+        assert_eq!(
+            resolve_cache_dir(None, Some("/var/lib/a:/var/lib/b".into()), exe.clone()),
+            PathBuf::from("/var/lib/a/config_cache")
+        );
+        assert_eq!(
+            resolve_cache_dir(Some("".into()), None, exe),
+            PathBuf::from("/opt/collector/config_cache")
+        );
+    }
+
+    #[test]
+    fn format_is_detected_by_extension() {
+        assert_eq!(ConfigFormat::from_path("project.yaml"), ConfigFormat::Yaml);
+        assert_eq!(
+            ConfigFormat::from_path("conf/project.YML"),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(ConfigFormat::from_path("project.toml"), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path("project.json"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("project"), ConfigFormat::Json);
+    }
+
+    #[test]
+    fn yaml_project_uses_json_schema() {
+        let yaml = "\u{feff}
+http_port: 8080
+devices:
+  pcs:
+    id: pcs
+    config:
+      type: pcs
+      com_type: ModbusTCP
+      ip: 127.0.0.1
+      port: 502
+";
+        let conf = Configuration::from_slice(yaml.as_bytes(), ConfigFormat::Yaml).unwrap();
+
+        assert_eq!(conf.project.http_port, Some(8080));
+        let dev = &conf.project.devices["pcs"];
+        assert_eq!(dev.config.com_type, Some(ComType::ModbusTCP));
+        assert_eq!(dev.config.port, Some(502));
+    }
+
+    #[test]
+    fn project_schema_describes_devices() {
+        let schema = super::project_schema();
+
+        assert_eq!(schema["title"], "Project");
+        assert!(schema["properties"]["devices"].is_object());
+        assert!(schema["properties"]["includes"].is_object());
+    }
+
+    #[test]
+    fn toml_project_uses_json_schema() {
+        let toml = r#"
+http_port = 8080
+
+[devices.pcs]
+id = "pcs"
+
+[devices.pcs.config]
+type = "pcs"
+com_type = "ModbusRTU"
+serial_tty = "/dev/ttyS1"
+baud_rate = 9600
+"#;
+        let conf = Configuration::from_slice(toml.as_bytes(), ConfigFormat::Toml).unwrap();
+
+        assert_eq!(conf.project.http_port, Some(8080));
+        let dev = &conf.project.devices["pcs"];
+        assert_eq!(dev.config.com_type, Some(ComType::ModbusRTU));
+        assert_eq!(dev.config.baud_rate, Some(9600));
+    }
+
+    #[tokio::test]
+    async fn includes_are_merged_and_duplicates_rejected() {
+        let dir = std::env::temp_dir().join(format!("collector-includes-{}", std::process::id()));
+        let devices = dir.join("devices");
+        std::fs::create_dir_all(&devices).unwrap();
+        std::fs::write(
+            dir.join("project.json"),
+            r#"{"includes": ["devices/*.json"], "devices": {"pcs": {"id": "pcs", "config": {}}}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            devices.join("cabinet1.json"),
+            r#"{"bms1": {"id": "bms1", "config": {}}}"#,
+        )
+        .unwrap();
+        let project = dir.join("project.json").display().to_string();
+
+        let conf = Configuration::new(project.clone()).await.unwrap();
+        let mut keys: Vec<&String> = conf.project.devices.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["bms1", "pcs"]);
+        assert_eq!(conf.included_files.len(), 1);
+
+        std::fs::write(
+            devices.join("cabinet2.json"),
+            r#"{"pcs": {"id": "pcs2", "config": {}}}"#,
+        )
+        .unwrap();
+        let err = Configuration::new(project).await.unwrap_err();
+        assert!(matches!(err, super::ConfigurationError::DuplicateDevice(key) if key == "pcs"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn variables_file_overrides_template_variables() {
+        let dir = std::env::temp_dir().join(format!("collector-variables-{}", std::process::id()));
+        let devices = dir.join("devices");
+        std::fs::create_dir_all(&devices).unwrap();
+        std::fs::write(
+            dir.join("project.json"),
+            r#"{"variables": {"prefix": "10.0.0", "poll": 1000},
+                "variables_file": "site.yaml",
+                "includes": ["devices/*.json"],
+                "devices": {"pcs": {"id": "pcs", "config": {"ip": "{{prefix}}.20", "interval": "{{poll}}"}}}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("site.yaml"), "prefix: 10.7.1\n").unwrap();
+        std::fs::write(
+            devices.join("bms.json"),
+            r#"{"bms": {"id": "bms", "config": {"ip": "{{prefix}}.30"}}}"#,
+        )
+        .unwrap();
+
+        let conf = Configuration::new(dir.join("project.json").display().to_string())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let pcs = &conf.project.devices["pcs"].config;
+        assert_eq!(pcs.ip.as_deref(), Some("10.7.1.20"));
+        assert_eq!(pcs.interval, Some(1000));
+        let bms = &conf.project.devices["bms"].config;
+        assert_eq!(bms.ip.as_deref(), Some("10.7.1.30"));
+        assert_eq!(conf.included_files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn register_files_resolve_against_base_dir_and_merge_globs() {
+        let dir = std::env::temp_dir().join(format!("collector-base-dir-{}", std::process::id()));
+        let tables = dir.join("tables").join("pcs");
+        std::fs::create_dir_all(&tables).unwrap();
+        std::fs::write(
+            dir.join("project.json"),
+            r#"{"base_dir": "tables", "devices": {"pcs": {"id": "pcs",
+                "config": {"com_type": "ModbusTCP", "registerFile": "pcs/*.json"}}}}"#,
+        )
+        .unwrap();
+        for (file, key, addr) in [("a.json", "voltage", 0), ("b.json", "current", 1)] {
+            std::fs::write(
+                tables.join(file),
+                format!(
+                    r#"[{{"id": 1, "name": "{key}", "data_type": "U16", "register_address": {addr},
+                         "register_type": "InputRegisters", "quantity": 1, "key": "{key}"}}]"#
+                ),
+            )
+            .unwrap();
+        }
+
+        let mut conf = Configuration::new(dir.join("project.json").display().to_string())
+            .await
+            .unwrap();
+        let file = conf.project.devices["pcs"].config.register_file.clone();
+        assert_eq!(
+            file.as_deref(),
+            Some(tables.join("*.json").display().to_string().as_str())
+        );
+
+        conf.load_device_configs().await.unwrap();
+        let configs = &conf.project.devices["pcs"].protocol_configs;
+        assert!(
+            matches!(configs, Some(super::ProtocolConfigs::Modbus(points)) if points.len() == 2)
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn strict_point_tables_fail_device_loading() {
+        let json = r#"{"strictPointTables": true, "devices": {
+            "pcs": {"id": "pcs", "config": {"com_type": "ModbusTCP", "register_file": "missing.xlsx"}},
+            "bms": {"id": "bms", "config": {"com_type": "ModbusTCP", "register_file": "missing.xlsx",
+                    "strict_point_tables": false}}
+        }}"#;
+        let mut conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        let errors = conf.load_device_configs().await.unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], super::ConfigurationError::PointTable(id, _) if id == "pcs"));
+        assert!(matches!(
+            conf.project.devices["bms"].protocol_configs,
+            Some(super::ProtocolConfigs::None)
+        ));
+    }
+
+    #[test]
+    fn device_defaults_fill_missing_fields() {
+        let json = r#"{
+            "deviceDefaults": {"interval": 1000, "timeout": 3000, "slave": 1, "byte_order": "CDAB"},
+            "devices": {
+                "a": {"id": "a", "config": {}},
+                "b": {"id": "b", "config": {"timeout": 500, "slave": 2, "byte_order": "ABCD"}}
+            }
+        }"#;
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        let a = &conf.project.devices["a"].config;
+        assert_eq!(
+            (a.interval, a.timeout, a.slave),
+            (Some(1000), Some(3000), Some(1))
+        );
+        assert_eq!(a.byte_order.as_deref(), Some("CDAB"));
+        let b = &conf.project.devices["b"].config;
+        assert_eq!(
+            (b.interval, b.timeout, b.slave),
+            (Some(1000), Some(500), Some(2))
+        );
+        assert_eq!(b.byte_order.as_deref(), Some("ABCD"));
+    }
+
+    #[test]
+    fn protocol_defaults_sit_between_device_and_global_defaults() {
+        let json = r#"{
+            "device_defaults": {"timeout": 3000, "interval": 1000},
+            "protocol_defaults": {
+                "ModbusRTU": {"baud_rate": 9600, "parity": "N", "stop_bits": 1, "timeout": 800}
+            },
+            "devices": {
+                "a": {"id": "a", "config": {"com_type": "ModbusRTU"}},
+                "b": {"id": "b", "config": {"com_type": "ModbusRTU", "baud_rate": 19200}},
+                "c": {"id": "c", "config": {"com_type": "ModbusTCP"}}
+            }
+        }"#;
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        let a = &conf.project.devices["a"].config;
+        assert_eq!(
+            (a.baud_rate, a.timeout, a.interval),
+            (Some(9600), Some(800), Some(1000))
+        );
+        assert_eq!(a.parity.as_deref(), Some("N"));
+        assert_eq!(conf.project.devices["b"].config.baud_rate, Some(19200));
+        let c = &conf.project.devices["c"].config;
+        assert_eq!((c.baud_rate, c.timeout), (None, Some(3000)));
+    }
+
+    #[test]
+    fn devices_are_expanded_from_templates() {
+        let json = r#"{
+            "protocol_defaults": {"ModbusTCP": {"port": 502, "timeout": 800}},
+            "templates": {
+                "cluster": {"com_type": "ModbusTCP", "register_file": "bms.xlsx",
+                            "interval": 1000, "byte_order": "CDAB"}
+            },
+            "devices": {
+                "bms1": {"id": "bms1", "template": "cluster", "config": {"ip": "10.0.0.1", "slave": 1}},
+                "bms2": {"id": "bms2", "template": "cluster",
+                         "config": {"ip": "10.0.0.2", "slave": 2, "interval": 500}}
+            }
+        }"#;
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        let bms1 = &conf.project.devices["bms1"].config;
+        assert_eq!(bms1.com_type, Some(ComType::ModbusTCP));
+        assert_eq!(bms1.register_file.as_deref(), Some("bms.xlsx"));
+        assert_eq!(
+            (bms1.interval, bms1.port, bms1.timeout, bms1.slave),
+            (Some(1000), Some(502), Some(800), Some(1))
+        );
+        assert_eq!(bms1.byte_order.as_deref(), Some("CDAB"));
+        assert_eq!(conf.project.devices["bms2"].config.interval, Some(500));
+    }
+
+    #[test]
+    fn slaves_expand_into_one_device_per_slave() {
+        let json = r#"{"devices": {
+            "rack": {"id": "rack", "config": {"com_type": "ModbusTCP", "ip": "10.0.0.1",
+                     "port": 502, "slaves": [1, 2, 3]}},
+            "bad": {"id": "bad", "enabled": false, "config": {"slaves": [0]}}
+        }}"#;
+        let err = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap_err();
+        assert!(matches!(err, ConfigurationError::InvalidSlaves(key, _) if key == "bad"));
+
+        let json = json.replace(r#""slaves": [0]"#, r#""slave": 9"#);
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+        let mut keys: Vec<&String> = conf.project.devices.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["bad", "rack_1", "rack_2", "rack_3"]);
+        let rack = &conf.project.devices["rack_2"];
+        assert_eq!(rack.id.as_deref(), Some("rack_2"));
+        assert_eq!(
+            (rack.config.slave, rack.config.slaves.as_ref()),
+            (Some(2), None)
+        );
+        assert_eq!(rack.config.key_prefix.as_deref(), Some("2_"));
+        assert_eq!(rack.config.shared_connection, Some(true));
+    }
+}
@@ -1,6 +1,8 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use calamine::{Data, DataType, HeaderRow, Range, Reader, Xlsx, open_workbook};
+use calamine::{Data, DataType, HeaderRow, Range, Reader, open_workbook_auto};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tracing::error;
 
 use crate::{
@@ -18,12 +20,31 @@ pub enum ModbusDataType {
     I16,
     U32,
     I32,
+    /// IEEE-754 单精度浮点，占两个寄存器
+    F32,
+    /// IEEE-754 双精度浮点，占四个寄存器
+    F64,
+    /// 64 位无符号整数，占四个寄存器，用于超出 32 位的累计电量等计数
+    U64,
+    /// 64 位有符号整数，占四个寄存器
+    I64,
+    /// 占指定数量寄存器的 ASCII/UTF-8 字符串，每个寄存器两个字节，如序列号、固件版本
+    String(u16),
+    /// 每 4 位表示一个十进制数字的 BCD 码，占一个寄存器，0~9999，常见于国产电表
+    Bcd16,
+    /// 占两个寄存器的 BCD 码，0~99999999
+    Bcd32,
 }
 
 impl ModbusDataType {
     pub fn register_width(&self) -> u16 {
         match self {
-            ModbusDataType::I32 | ModbusDataType::U32 => 2,
+            ModbusDataType::I32
+            | ModbusDataType::U32
+            | ModbusDataType::F32
+            | ModbusDataType::Bcd32 => 2,
+            ModbusDataType::F64 | ModbusDataType::U64 | ModbusDataType::I64 => 4,
+            ModbusDataType::String(len) => *len,
             _ => 1,
         }
     }
@@ -46,17 +67,62 @@ impl TryFrom<&str> for ModbusDataType {
             "I16" => Ok(ModbusDataType::I16),
             "U32" => Ok(ModbusDataType::U32),
             "I32" => Ok(ModbusDataType::I32),
-            _ => Err(ModbusDataTypeError::InvalidDataType),
+            "F32" => Ok(ModbusDataType::F32),
+            "F64" => Ok(ModbusDataType::F64),
+            "U64" => Ok(ModbusDataType::U64),
+            "I64" => Ok(ModbusDataType::I64),
+            "BCD16" | "Bcd16" => Ok(ModbusDataType::Bcd16),
+            "BCD32" | "Bcd32" => Ok(ModbusDataType::Bcd32),
+            _ => value
+                .strip_prefix("String(")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(|len| len.trim().parse::<u16>().ok())
+                .filter(|len| *len > 0)
+                .map(ModbusDataType::String)
+                .ok_or(ModbusDataTypeError::InvalidDataType),
         }
     }
 }
 
+/// BCD 码转十进制，任一半字节大于 9 时返回 `None`
+pub(crate) fn bcd_to_decimal(raw: u32) -> Option<u32> {
+    (0..8).rev().try_fold(0u32, |acc, nibble| {
+        let digit = (raw >> (nibble * 4)) & 0xF;
+        (digit <= 9).then_some(acc * 10 + digit)
+    })
+}
+
+/// 十进制转 BCD 码，超过 8 位十进制数时返回 `None`
+pub(crate) fn decimal_to_bcd(mut value: u32) -> Option<u32> {
+    if value > 99_999_999 {
+        return None;
+    }
+    let mut raw = 0u32;
+    for nibble in 0..8 {
+        raw |= (value % 10) << (nibble * 4);
+        value /= 10;
+    }
+    Some(raw)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ByteOrder {
     AB,
     BA,
     ABCD,
     CDAB,
+    /// 32 位值：完全逆序
+    DCBA,
+    /// 32 位值：高位字在前，字内字节交换
+    BADC,
+    /// 64 位值：高位字在前
+    ABCDEFGH,
+    /// 64 位值：低位字在前，与 64 位值上的 `CDAB` 相同，`DCBA`、`BADC` 同理
+    GHEFCDAB,
+    /// 64 位值：高位字在前，字内字节交换
+    BADCFEHG,
+    /// 64 位值：完全逆序
+    HGFEDCBA,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -75,6 +141,12 @@ impl TryFrom<Option<&str>> for ByteOrder {
             Some("BA") => Ok(ByteOrder::BA),
             Some("ABCD") => Ok(ByteOrder::ABCD),
             Some("CDAB") => Ok(ByteOrder::CDAB),
+            Some("DCBA") => Ok(ByteOrder::DCBA),
+            Some("BADC") => Ok(ByteOrder::BADC),
+            Some("ABCDEFGH") => Ok(ByteOrder::ABCDEFGH),
+            Some("GHEFCDAB") => Ok(ByteOrder::GHEFCDAB),
+            Some("BADCFEHG") => Ok(ByteOrder::BADCFEHG),
+            Some("HGFEDCBA") => Ok(ByteOrder::HGFEDCBA),
             _ => Err(ByteOrderError::InvalidByteOrder),
         }
     }
@@ -89,16 +161,77 @@ impl ByteOrder {
     }
 
     pub fn assemble_u32(&self, v: u32) -> [u16; 2] {
-        let w0 = (v >> 16) as u16;
-        let w1 = (v & 0xFFFF) as u16;
-        match self {
-            ByteOrder::CDAB => [w1, w0],
-            _ => [w0, w1],
+        self.reorder_u32([(v >> 16) as u16, v as u16])
+    }
+
+    /// 在高位字在前的顺序与本字节序之间转换 32 位值的两个字，与 [`Self::reorder_u64`] 一样是对合的
+    pub fn reorder_u32(&self, mut words: [u16; 2]) -> [u16; 2] {
+        if matches!(self, ByteOrder::DCBA | ByteOrder::BADC) {
+            words = words.map(u16::swap_bytes);
+        }
+        if matches!(self, ByteOrder::CDAB | ByteOrder::DCBA) {
+            words.reverse();
+        }
+        words
+    }
+
+    pub fn assemble_u64(&self, v: u64) -> [u16; 4] {
+        let words = [
+            (v >> 48) as u16,
+            (v >> 32) as u16,
+            (v >> 16) as u16,
+            v as u16,
+        ];
+        self.reorder_u64(words)
+    }
+
+    /// 在高位字在前的顺序与本字节序之间转换 64 位值的四个字，变换是对合的，编解码共用
+    pub fn reorder_u64(&self, mut words: [u16; 4]) -> [u16; 4] {
+        if matches!(
+            self,
+            ByteOrder::BADC | ByteOrder::DCBA | ByteOrder::BADCFEHG | ByteOrder::HGFEDCBA
+        ) {
+            words = words.map(u16::swap_bytes);
+        }
+        if matches!(
+            self,
+            ByteOrder::CDAB | ByteOrder::DCBA | ByteOrder::GHEFCDAB | ByteOrder::HGFEDCBA
+        ) {
+            words.reverse();
         }
+        words
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// 点位的扫描等级，同一设备连接内各等级按各自的周期轮询，周期见设备配置的 `scan_intervals`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ScanClass {
+    Fast,
+    #[default]
+    Normal,
+    Slow,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScanClassError {
+    #[error("Invalid scan class: {0}")]
+    InvalidScanClass(String),
+}
+
+impl TryFrom<&str> for ScanClass {
+    type Error = ScanClassError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "fast" | "Fast" | "快" => Ok(ScanClass::Fast),
+            "normal" | "Normal" | "常规" | "中" => Ok(ScanClass::Normal),
+            "slow" | "Slow" | "慢" => Ok(ScanClass::Slow),
+            other => Err(ScanClassError::InvalidScanClass(other.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum RegisterType {
     Coils = 1,
     DiscreteInputs = 2,
@@ -126,49 +259,572 @@ impl TryFrom<&str> for RegisterType {
     }
 }
 
+impl RegisterType {
+    /// Modicon 5 位编号中该寄存器类型的首位：0xxxx 线圈、1xxxx 离散输入、3xxxx 输入寄存器、4xxxx 保持寄存器
+    fn modicon_prefix(self) -> u16 {
+        match self {
+            RegisterType::Coils => 0,
+            RegisterType::DiscreteInputs => 1,
+            RegisterType::InputRegisters => 3,
+            RegisterType::HoldingRegisters => 4,
+        }
+    }
+
+    /// 将点位表中的地址换算为协议地址，地址小于 `base` 时为 `None`
+    ///
+    /// `base` 为点位表地址的起始编号；从 1 开始编号时同时识别 Modicon 编号：
+    /// 地址的万位与寄存器类型相符（如保持寄存器的 40001~49999）时减去 x0001
+    pub fn protocol_address(self, address: u16, base: u16) -> Option<u16> {
+        let prefix = self.modicon_prefix() * 10_000;
+        if base == 1 && prefix > 0 && (prefix + 1..=prefix + 9_999).contains(&address) {
+            return Some(address - prefix - 1);
+        }
+        address.checked_sub(base)
+    }
+}
+
 pub type ModbusConfigs = Vec<ModbusConfig>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ModbusConfigsError {
     #[error("Failed to open workbook: {0}")]
-    OpenWorkbookError(#[from] calamine::XlsxError),
+    OpenWorkbookError(#[from] calamine::Error),
     #[error("存在重复点位ID: {0}")]
     DuplicatePointId(u16),
+    #[error("Failed to read point table: {0}")]
+    ReadFileError(#[from] std::io::Error),
+    #[error("Failed to parse point table: {0}")]
+    ParseJsonError(#[from] json5::Error),
+    #[error("第{index}个点位配置错误: {msg}")]
+    InvalidPoint { index: usize, msg: String },
+    #[error("未知的点表列: {0}")]
+    UnknownColumn(String),
+    #[error("工作表{sheet}中找不到列: {header}")]
+    MissingColumn { sheet: String, header: String },
+    #[error("点表存在{}处错误: {}", .0.len(), .0.join("; "))]
+    InvalidRows(Vec<String>),
 }
 
-pub(crate) fn build_configs(path: String) -> Result<ModbusConfigs, ModbusConfigsError> {
-    let mut workbook: Xlsx<_> = open_workbook(path)?;
-    let mut configs = Vec::new();
-    let parse = |range: Range<Data>, configs: &mut Vec<ModbusConfig>| {
-        for row in range.rows() {
-            let config = ModbusConfig::build(row);
-            match config {
-                Ok(config) => {
-                    configs.push(config);
-                }
-                Err(err) => {
-                    error!("构建Modbus配置失败: {}", err);
-                }
-            }
-        }
+/// 缺省读取的 Excel 工作表
+const DEFAULT_SHEETS: [&str; 4] = ["遥信", "遥控", "遥测", "遥调"];
+
+/// Excel 点表的列，顺序即缺省的列顺序
+pub(super) const COLUMNS: [&str; 22] = [
+    "id",
+    "name",
+    "data_type",
+    "unit",
+    "remarks",
+    "register_address",
+    "register_type",
+    "quantity",
+    "byte_order",
+    "scale",
+    "offset",
+    "enable",
+    "key",
+    "trans",
+    "status_words",
+    "warn_bits",
+    "bit",
+    "scan_class",
+    "select_address",
+    "handshake_address",
+    "pulse_ms",
+    "epsilon",
+];
+
+/// 各列可识别的表头文字，比较时忽略大小写、空格和下划线
+pub(super) const HEADER_ALIASES: [&[&str]; COLUMNS.len()] = [
+    &["序号", "id", "no", "no.", "index"],
+    &["点位名称", "名称", "name", "pointname"],
+    &["数据类型", "类型", "datatype", "type"],
+    &["单位", "unit"],
+    &["备注", "remarks", "remark", "comment", "description"],
+    &["寄存器地址", "地址", "registeraddress", "address", "addr"],
+    &["寄存器类型", "registertype", "function"],
+    &["数量", "quantity", "qty", "count", "length"],
+    &["字节序/字序", "字节序", "byteorder", "endian"],
+    &["系数", "缩放", "scale", "factor", "ratio"],
+    &["偏移量", "偏移", "offset"],
+    &["启用", "使能", "enable", "enabled"],
+    &["键", "key", "tag"],
+    &["点位名称翻译", "名称翻译", "翻译", "trans", "translation"],
+    &["状态字", "statuswords"],
+    &["告警位", "warnbits", "alarmbits"],
+    &["位", "位号", "bit", "bitindex"],
+    &["扫描等级", "扫描周期", "scanclass", "class"],
+    &["选择地址", "预置地址", "selectaddress", "sbo"],
+    &["握手地址", "handshakeaddress", "handshake"],
+    &["脉冲宽度", "脉冲", "pulsems", "pulse", "pulsewidth"],
+    &["变化容差", "容差", "死区", "epsilon", "deadband"],
+];
+
+/// 必须存在的列，其余列缺失时按空值处理
+const REQUIRED_COLUMNS: [&str; 7] = [
+    "id",
+    "name",
+    "data_type",
+    "register_address",
+    "register_type",
+    "quantity",
+    "key",
+];
+
+fn normalize_header(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace() && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 列映射中的列：从 0 开始的列序号，或表头文字
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    Index(usize),
+    Header(String),
+}
+
+/// Excel 点表的读取选项
+#[derive(Debug, Clone, Default)]
+pub(crate) struct XlsxOptions {
+    /// 读取的工作表，见 [`xlsx_sheets`]
+    pub sheets: Option<Vec<String>>,
+    /// 列映射，键为 [`COLUMNS`] 中的列名，未映射的列保持缺省位置
+    pub columns: Option<HashMap<String, ColumnRef>>,
+    /// 严格模式：任一点位行解析失败即整张点表失败，而不是跳过该行
+    pub strict: bool,
+}
+
+/// 按扩展名选择点表格式：`.json`/`.json5` 为 JSON 点表，其余为表格点表，
+/// 支持 `.xlsx`/`.xlsm`/`.xlsb`/`.xls`/`.ods`
+pub(crate) fn build_configs(
+    path: String,
+    options: &XlsxOptions,
+) -> Result<ModbusConfigs, ModbusConfigsError> {
+    let lower = path.to_ascii_lowercase();
+    let configs = if lower.ends_with(".json") || lower.ends_with(".json5") {
+        parse_json_configs(&std::fs::read_to_string(&path)?)?
+    } else {
+        build_xlsx_configs(path, options)?
     };
-    for sheet in ["遥信", "遥控", "遥测", "遥调"] {
-        if let Ok(range) = workbook
-            .with_header_row(HeaderRow::Row(1))
-            .worksheet_range(sheet)
-        {
-            parse(range, &mut configs);
-        }
-    }
+    check_duplicate(&configs)?;
+    Ok(configs)
+}
+
+fn check_duplicate(configs: &[ModbusConfig]) -> Result<(), ModbusConfigsError> {
     let mut seen = HashSet::with_capacity(configs.len());
-    for cfg in &configs {
+    for cfg in configs {
         if !seen.insert(cfg.id) {
             return Err(ModbusConfigsError::DuplicatePointId(cfg.id));
         }
     }
+    Ok(())
+}
+
+/// 解析 JSON/JSON5 点表（点位对象数组），任一点位出错即返回其下标
+pub(crate) fn parse_json_configs(text: &str) -> Result<ModbusConfigs, ModbusConfigsError> {
+    let values: Vec<serde_json::Value> = json5::from_str(text)?;
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            serde_json::from_value::<JsonPoint>(value)
+                .map_err(anyhow::Error::from)
+                .and_then(ModbusConfig::try_from)
+                .map_err(|err| ModbusConfigsError::InvalidPoint {
+                    index,
+                    msg: err.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// 需要读取的工作表：指定了 `sheets` 时按指定顺序读取；
+/// 否则读取缺省的四遥工作表，工作簿中一个都没有时读取全部工作表
+pub(super) fn xlsx_sheets(names: &[String], sheets: Option<&[String]>) -> Vec<String> {
+    if let Some(sheets) = sheets {
+        return sheets.to_vec();
+    }
+    let defaults: Vec<String> = DEFAULT_SHEETS
+        .iter()
+        .filter(|sheet| names.iter().any(|name| name == *sheet))
+        .map(|sheet| sheet.to_string())
+        .collect();
+    if defaults.is_empty() {
+        names.to_vec()
+    } else {
+        defaults
+    }
+}
+
+/// 点表各列在工作表中的位置，下标与 [`COLUMNS`] 对应
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ColumnLayout([Option<usize>; COLUMNS.len()]);
+
+impl ColumnLayout {
+    /// 确定各列位置
+    ///
+    /// 先按表头文字识别各列，一列都识别不出时按缺省列顺序；
+    /// 再应用列映射，映射中的表头文字在 `headers` 中查找
+    pub(super) fn resolve(
+        sheet: &str,
+        headers: &[String],
+        columns: Option<&HashMap<String, ColumnRef>>,
+    ) -> Result<Self, ModbusConfigsError> {
+        let mut layout = Self::detect(headers).unwrap_or(std::array::from_fn(Some));
+        for (column, target) in columns.into_iter().flatten() {
+            let slot = COLUMNS
+                .iter()
+                .position(|name| name == column)
+                .ok_or_else(|| ModbusConfigsError::UnknownColumn(column.clone()))?;
+            layout[slot] = Some(match target {
+                ColumnRef::Index(index) => *index,
+                ColumnRef::Header(header) => headers
+                    .iter()
+                    .position(|text| text.trim() == header.trim())
+                    .ok_or_else(|| ModbusConfigsError::MissingColumn {
+                        sheet: sheet.to_owned(),
+                        header: header.clone(),
+                    })?,
+            });
+        }
+        if let Some(column) = REQUIRED_COLUMNS
+            .iter()
+            .find(|column| layout[column_slot(column)].is_none())
+        {
+            return Err(ModbusConfigsError::MissingColumn {
+                sheet: sheet.to_owned(),
+                header: column.to_string(),
+            });
+        }
+        Ok(Self(layout))
+    }
+
+    /// 按表头文字识别各列，一列都没有识别出时返回 `None`
+    fn detect(headers: &[String]) -> Option<[Option<usize>; COLUMNS.len()]> {
+        let headers: Vec<String> = headers.iter().map(|text| normalize_header(text)).collect();
+        let layout: [Option<usize>; COLUMNS.len()] = std::array::from_fn(|slot| {
+            headers.iter().position(|header| {
+                HEADER_ALIASES[slot]
+                    .iter()
+                    .any(|alias| normalize_header(alias) == *header)
+            })
+        });
+        layout.iter().any(Option::is_some).then_some(layout)
+    }
+
+    /// 按缺省列顺序重排一行，缺少的单元格视为空；
+    /// 缺少系数、偏移量列时分别按 1、0 处理
+    pub(super) fn project(&self, row: &[Data]) -> Vec<Data> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(slot, index)| match index {
+                Some(index) => row.get(*index).cloned().unwrap_or(Data::Empty),
+                None if COLUMNS[slot] == "scale" => Data::Float(1.0),
+                None if COLUMNS[slot] == "offset" => Data::Float(0.0),
+                None => Data::Empty,
+            })
+            .collect()
+    }
+}
+
+fn column_slot(column: &str) -> usize {
+    COLUMNS
+        .iter()
+        .position(|name| *name == column)
+        .expect("column listed in COLUMNS")
+}
+
+fn build_xlsx_configs(
+    path: String,
+    options: &XlsxOptions,
+) -> Result<ModbusConfigs, ModbusConfigsError> {
+    let mut workbook = open_workbook_auto(path)?;
+    let mut configs = Vec::new();
+    let mut errors = Vec::new();
+    let sheets = options.sheets.as_deref();
+    for sheet in xlsx_sheets(&workbook.sheet_names(), sheets) {
+        let range = match workbook
+            .with_header_row(HeaderRow::Row(0))
+            .worksheet_range(&sheet)
+        {
+            Ok(range) => range,
+            Err(err) if sheets.is_some() => {
+                error!("读取工作表{}失败: {}", sheet, err);
+                continue;
+            }
+            Err(_) => continue,
+        };
+        match parse_sheet(&sheet, &range, options, &mut configs) {
+            Ok(rows) => errors.extend(rows),
+            // 缺少必需列的多半不是点表（如说明页），跳过该工作表
+            Err(err @ ModbusConfigsError::MissingColumn { .. }) => error!("跳过工作表: {}", err),
+            Err(err) => return Err(err),
+        }
+    }
+    if options.strict && !errors.is_empty() {
+        return Err(ModbusConfigsError::InvalidRows(errors));
+    }
+    for err in errors {
+        error!("构建Modbus配置失败: {}", err);
+    }
     Ok(configs)
 }
 
+/// 解析一张工作表，首行为表头；空行跳过，返回出错的点位行（含工作表与行号）
+fn parse_sheet(
+    sheet: &str,
+    range: &Range<Data>,
+    options: &XlsxOptions,
+    configs: &mut Vec<ModbusConfig>,
+) -> Result<Vec<String>, ModbusConfigsError> {
+    let headers = range.headers().unwrap_or_default();
+    let layout = ColumnLayout::resolve(sheet, &headers, options.columns.as_ref())?;
+    // 表头在 Excel 中的行号（从 1 开始）
+    let header_row = range.start().map_or(1, |(row, _)| row as usize + 1);
+    let mut errors = Vec::new();
+    for (offset, row) in range.rows().enumerate().skip(1) {
+        if row.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        match ModbusConfig::build(&layout.project(row)) {
+            Ok(config) => configs.push(config),
+            Err(err) => errors.push(format!(
+                "工作表{}第{}行: {}",
+                sheet,
+                header_row + offset,
+                err
+            )),
+        }
+    }
+    Ok(errors)
+}
+
+/// JSON 点表中的单个点位，字段与 Excel 点表列一一对应；点表格式转换时也作为各格式共用的行
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct JsonPoint {
+    pub(super) id: u16,
+    pub(super) name: String,
+    pub(super) data_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) remarks: Option<String>,
+    pub(super) register_address: u16,
+    pub(super) register_type: String,
+    pub(super) quantity: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) byte_order: Option<String>,
+    #[serde(default = "default_scale")]
+    pub(super) scale: f64,
+    #[serde(default)]
+    pub(super) offset: f64,
+    #[serde(default = "default_enable")]
+    pub(super) enable: bool,
+    pub(super) key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) trans: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) status_words: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) warn_bits: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) bit: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) scan_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) select_address: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) handshake_address: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) pulse_ms: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) epsilon: Option<f64>,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+fn check_quantity(data_type: ModbusDataType, quantity: u16) -> Result<(), anyhow::Error> {
+    if quantity == 0 {
+        return Err(anyhow::Error::msg("数量必须大于0"));
+    }
+    if !quantity.is_multiple_of(data_type.register_width()) {
+        return Err(anyhow::Error::msg("数量与数据类型不匹配"));
+    }
+    Ok(())
+}
+
+/// 位点位只能取单个 16 位寄存器中的一位
+fn check_bit(
+    data_type: ModbusDataType,
+    register_type: RegisterType,
+    quantity: u16,
+    bit: Option<u8>,
+) -> Result<(), anyhow::Error> {
+    let Some(bit) = bit else {
+        return Ok(());
+    };
+    if !matches!(
+        register_type,
+        RegisterType::HoldingRegisters | RegisterType::InputRegisters
+    ) {
+        return Err(anyhow::Error::msg("只有寄存器点位可以指定位"));
+    }
+    if !matches!(data_type, ModbusDataType::U16 | ModbusDataType::I16) || quantity != 1 {
+        return Err(anyhow::Error::msg("位点位的数据类型须为U16/I16且数量为1"));
+    }
+    if bit > 15 {
+        return Err(anyhow::Error::msg("位须在0~15之间"));
+    }
+    Ok(())
+}
+
+/// 选择-执行只用于可写的线圈、保持寄存器点位
+fn check_select(
+    register_type: RegisterType,
+    bit: Option<u8>,
+    select_address: Option<u16>,
+) -> Result<(), anyhow::Error> {
+    if select_address.is_none() {
+        return Ok(());
+    }
+    if !matches!(
+        register_type,
+        RegisterType::Coils | RegisterType::HoldingRegisters
+    ) || bit.is_some()
+    {
+        return Err(anyhow::Error::msg(
+            "只有线圈和保持寄存器点位可以指定选择地址",
+        ));
+    }
+    Ok(())
+}
+
+/// 握手读取只用于整寄存器写入的保持寄存器点位，与选择-执行互斥
+fn check_handshake(
+    register_type: RegisterType,
+    bit: Option<u8>,
+    select_address: Option<u16>,
+    handshake_address: Option<u16>,
+) -> Result<(), anyhow::Error> {
+    if handshake_address.is_none() {
+        return Ok(());
+    }
+    if register_type != RegisterType::HoldingRegisters || bit.is_some() {
+        return Err(anyhow::Error::msg(
+            "只有非位的保持寄存器点位可以指定握手地址",
+        ));
+    }
+    if select_address.is_some() {
+        return Err(anyhow::Error::msg("握手地址与选择地址不能同时指定"));
+    }
+    Ok(())
+}
+
+/// 脉冲输出只用于线圈点位，与选择-执行互斥
+fn check_pulse(
+    register_type: RegisterType,
+    select_address: Option<u16>,
+    pulse_ms: Option<u32>,
+) -> Result<(), anyhow::Error> {
+    let Some(pulse_ms) = pulse_ms else {
+        return Ok(());
+    };
+    if register_type != RegisterType::Coils {
+        return Err(anyhow::Error::msg("只有线圈点位可以指定脉冲宽度"));
+    }
+    if pulse_ms == 0 {
+        return Err(anyhow::Error::msg("脉冲宽度须大于0"));
+    }
+    if select_address.is_some() {
+        return Err(anyhow::Error::msg("脉冲宽度与选择地址不能同时指定"));
+    }
+    Ok(())
+}
+
+/// 变化容差须为非负的有限值
+fn check_epsilon(epsilon: Option<f64>) -> Result<(), anyhow::Error> {
+    match epsilon {
+        Some(epsilon) if !epsilon.is_finite() || epsilon < 0.0 => {
+            Err(anyhow::Error::msg("变化容差须为非负数"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn leak_str(s: String) -> &'static str {
+    s.leak()
+}
+
+impl TryFrom<JsonPoint> for ModbusConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(p: JsonPoint) -> Result<Self, Self::Error> {
+        let data_type = ModbusDataType::try_from(p.data_type.as_str())?;
+        let register_type = RegisterType::try_from(p.register_type.as_str())?;
+        check_quantity(data_type, p.quantity)?;
+        check_bit(data_type, register_type, p.quantity, p.bit)?;
+        check_select(register_type, p.bit, p.select_address)?;
+        check_handshake(register_type, p.bit, p.select_address, p.handshake_address)?;
+        check_pulse(register_type, p.select_address, p.pulse_ms)?;
+        check_epsilon(p.epsilon)?;
+        let scan_class = match p.scan_class.as_deref() {
+            Some(class) => ScanClass::try_from(class)?,
+            None => ScanClass::default(),
+        };
+        let byte_order = match p.byte_order.as_deref() {
+            Some(order) => Some(ByteOrder::try_from(Some(order))?),
+            None => None,
+        };
+        let trans: Option<&'static Translator> = match p.trans.as_deref() {
+            Some(str) => Some(Box::leak(Box::new(Translator::try_from(str)?))),
+            None => None,
+        };
+        let status_words: Option<&'static Words> = match p.status_words.as_deref() {
+            Some(str) => Some(Box::leak(Box::new(Words::try_from(str)?))),
+            None => None,
+        };
+        let warn_bits: Option<&'static Bits> = match p.warn_bits.as_deref() {
+            Some(str) => Some(Box::leak(Box::new(Bits::try_from(str)?))),
+            None => None,
+        };
+        Ok(ModbusConfig {
+            id: p.id,
+            name: leak_str(p.name),
+            data_type,
+            unit: p.unit.map(leak_str),
+            remarks: p.remarks.map(leak_str),
+            register_address: p.register_address,
+            register_type,
+            quantity: p.quantity,
+            byte_order,
+            scale: p.scale,
+            offset: p.offset,
+            enable: p.enable,
+            key: leak_str(p.key),
+            trans,
+            status_words,
+            warn_bits,
+            bit: p.bit,
+            scan_class,
+            select_address: p.select_address,
+            handshake_address: p.handshake_address,
+            pulse_ms: p.pulse_ms,
+            epsilon: p.epsilon,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ModbusConfig {
     pub id: u16,
@@ -187,6 +843,20 @@ pub struct ModbusConfig {
     pub trans: Option<&'static Translator>,
     pub status_words: Option<&'static Words>,
     pub warn_bits: Option<&'static Bits>,
+    /// 取寄存器中的第几位（0~15），解析为布尔点位；同一寄存器的多个位点位只读取一次
+    pub bit: Option<u8>,
+    /// 扫描等级，决定点位的轮询周期
+    pub scan_class: ScanClass,
+    /// 选择-执行（SBO）控制的选择地址，与点位同为线圈或保持寄存器；
+    /// 下发时先写选择地址并回读确认，再写入点位
+    pub select_address: Option<u16>,
+    /// 握手寄存器地址：下发时以读写多个寄存器（0x17）在同一事务中写入点位并读取该地址，
+    /// 读到的值按该地址上的保持寄存器点位发布
+    pub handshake_address: Option<u16>,
+    /// 脉冲宽度(ms)：下发 ON 时先合上线圈，保持该时长后自动断开，用于启停机等点动触点
+    pub pulse_ms: Option<u32>,
+    /// 变化容差：浮点值变化不超过该值时不视为变化，覆盖数据中心的全局容差
+    pub epsilon: Option<f64>,
 }
 
 impl ModbusConfig {
@@ -203,13 +873,7 @@ impl ModbusConfig {
         let register_address = required_f64(row, 5, "寄存器地址")? as u16;
         let register_type = RegisterType::try_from(required_str(row, 6, "寄存器类型")?)?;
         let quantity = required_usize_integerish(row, 7, "数量")? as u16;
-        let item_width = data_type.register_width();
-        if quantity == 0 {
-            return Err(anyhow::Error::msg("数量必须大于0"));
-        }
-        if !quantity.is_multiple_of(item_width) {
-            return Err(anyhow::Error::msg("数量与数据类型不匹配"));
-        }
+        check_quantity(data_type, quantity)?;
 
         let byte_order = ByteOrder::try_from(row[8].get_string()).ok();
         let scale = required_f64(row, 9, "缩放")?;
@@ -237,6 +901,57 @@ impl ModbusConfig {
             Some(t) => Some(Box::leak(Box::new(t))),
             None => None,
         };
+        let bit = match row.get(16) {
+            Some(cell) if !cell.is_empty() => {
+                let bit = required_usize_integerish(row, 16, "位")?;
+                Some(u8::try_from(bit).map_err(|_| anyhow::Error::msg("位须在0~15之间"))?)
+            }
+            _ => None,
+        };
+        check_bit(data_type, register_type, quantity, bit)?;
+        let scan_class = match row.get(17).and_then(|cell| cell.get_string()) {
+            Some(class) if !class.trim().is_empty() => ScanClass::try_from(class)?,
+            _ => ScanClass::default(),
+        };
+        let select_address = match row.get(18) {
+            Some(cell) if !cell.is_empty() => {
+                let address = required_usize_integerish(row, 18, "选择地址")?;
+                Some(
+                    u16::try_from(address)
+                        .map_err(|_| anyhow::Error::msg("选择地址超出允许范围"))?,
+                )
+            }
+            _ => None,
+        };
+        check_select(register_type, bit, select_address)?;
+        let handshake_address = match row.get(19) {
+            Some(cell) if !cell.is_empty() => {
+                let address = required_usize_integerish(row, 19, "握手地址")?;
+                Some(
+                    u16::try_from(address)
+                        .map_err(|_| anyhow::Error::msg("握手地址超出允许范围"))?,
+                )
+            }
+            _ => None,
+        };
+        check_handshake(register_type, bit, select_address, handshake_address)?;
+        let pulse_ms = match row.get(20) {
+            Some(cell) if !cell.is_empty() => {
+                let pulse = required_usize_integerish(row, 20, "脉冲宽度")?;
+                Some(u32::try_from(pulse).map_err(|_| anyhow::Error::msg("脉冲宽度超出允许范围"))?)
+            }
+            _ => None,
+        };
+        check_pulse(register_type, select_address, pulse_ms)?;
+        let epsilon = match row.get(21) {
+            Some(cell) if !cell.is_empty() => Some(
+                cell.get_float()
+                    .or_else(|| cell.get_int().map(|v| v as f64))
+                    .ok_or_else(|| anyhow::Error::msg("变化容差必须是数值"))?,
+            ),
+            _ => None,
+        };
+        check_epsilon(epsilon)?;
         Ok(ModbusConfig {
             id,
             name,
@@ -254,6 +969,341 @@ impl ModbusConfig {
             trans,
             status_words,
             warn_bits,
+            bit,
+            scan_class,
+            select_address,
+            handshake_address,
+            pulse_ms,
+            epsilon,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use calamine::{Data, Range};
+
+    use super::{
+        ColumnLayout, ColumnRef, ModbusConfigsError, ModbusDataType, RegisterType, XlsxOptions,
+        bcd_to_decimal, build_configs, decimal_to_bcd, parse_json_configs, parse_sheet,
+        xlsx_sheets,
+    };
+
+    #[test]
+    fn json5_point_table_is_parsed() {
+        let text = r#"[
+            // 电池电压
+            { id: 1, name: "电压", data_type: "U16", register_address: 100,
+              register_type: "InputRegisters", quantity: 1, scale: 0.1, key: "voltage" },
+            { id: 2, name: "功率", data_type: "I32", register_address: 102,
+              register_type: "HoldingRegisters", quantity: 2, byte_order: "CDAB", key: "power", },
+        ]"#;
+
+        let configs = parse_json_configs(text).unwrap();
+
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].scale, 0.1);
+        assert!(configs[0].enable);
+        assert_eq!(configs[1].data_type, ModbusDataType::I32);
+        assert_eq!(configs[1].register_type, RegisterType::HoldingRegisters);
+    }
+
+    #[test]
+    fn json_point_error_reports_index() {
+        let text = r#"[
+            {"id": 1, "name": "a", "data_type": "U16", "register_address": 0,
+             "register_type": "InputRegisters", "quantity": 1, "key": "a"},
+            {"id": 2, "name": "b", "data_type": "U32", "register_address": 1,
+             "register_type": "InputRegisters", "quantity": 1, "key": "b"}
+        ]"#;
+
+        let err = parse_json_configs(text).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ModbusConfigsError::InvalidPoint { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn json_bit_points_are_validated() {
+        let text = r#"[
+            {"id": 1, "name": "a", "data_type": "U16", "register_address": 0,
+             "register_type": "HoldingRegisters", "quantity": 1, "key": "a", "bit": 3},
+        ]"#;
+        assert_eq!(parse_json_configs(text).unwrap()[0].bit, Some(3));
+
+        for point in [
+            r#"{"data_type": "U16", "register_type": "HoldingRegisters", "bit": 16}"#,
+            r#"{"data_type": "U32", "register_type": "HoldingRegisters", "bit": 1}"#,
+            r#"{"data_type": "Bool", "register_type": "Coils", "bit": 1}"#,
+        ] {
+            let mut point: serde_json::Value = serde_json::from_str(point).unwrap();
+            let quantity = if point["data_type"] == "U32" { 2 } else { 1 };
+            point["id"] = 1.into();
+            point["name"] = "a".into();
+            point["key"] = "a".into();
+            point["register_address"] = 0.into();
+            point["quantity"] = quantity.into();
+            let text = format!("[{point}]");
+            assert!(parse_json_configs(&text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn json_point_epsilon_is_validated() {
+        let text = r#"[
+            {"id": 1, "name": "a", "data_type": "F32", "register_address": 0,
+             "register_type": "InputRegisters", "quantity": 2, "key": "a", "epsilon": 0.5},
+        ]"#;
+        assert_eq!(parse_json_configs(text).unwrap()[0].epsilon, Some(0.5));
+
+        let text = text.replace("0.5", "-0.5");
+        assert!(matches!(
+            parse_json_configs(&text),
+            Err(ModbusConfigsError::InvalidPoint { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn xlsx_sheets_default_to_telemetry_sheets_or_all() {
+        let names = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            xlsx_sheets(&names(&["说明", "遥测", "遥信"]), None),
+            names(&["遥信", "遥测"])
+        );
+        assert_eq!(
+            xlsx_sheets(&names(&["Status", "Analog"]), None),
+            names(&["Status", "Analog"])
+        );
+        let custom = names(&["Analog"]);
+        assert_eq!(
+            xlsx_sheets(&names(&["Status", "Analog"]), Some(&custom)),
+            custom
+        );
+    }
+
+    #[test]
+    fn column_mapping_reorders_customer_sheet() {
+        let rows = [
+            "Tag,Extra,Desc,Type,Addr,Kind,Qty,Scale,Offset",
+            "pv_power,x,光伏功率,I32,100,HoldingRegisters,2,0.1,0",
+        ];
+        let mut range = Range::new((0, 0), (1, 8));
+        for (r, row) in rows.iter().enumerate() {
+            for (c, cell) in row.split(',').enumerate() {
+                let value = match cell.parse::<f64>() {
+                    Ok(v) => Data::Float(v),
+                    Err(_) => Data::String(cell.to_owned()),
+                };
+                range.set_value((r as u32, c as u32), value);
+            }
+        }
+        let header = |text: &str| ColumnRef::Header(text.to_owned());
+        let options = XlsxOptions {
+            strict: true,
+            sheets: None,
+            columns: Some(HashMap::from([
+                ("id".to_owned(), header("Addr")),
+                ("name".to_owned(), header("Desc")),
+                ("data_type".to_owned(), header("Type")),
+                ("register_address".to_owned(), header("Addr")),
+                ("register_type".to_owned(), header("Kind")),
+                ("quantity".to_owned(), header("Qty")),
+                ("scale".to_owned(), header("Scale")),
+                ("offset".to_owned(), ColumnRef::Index(8)),
+                ("key".to_owned(), header("Tag")),
+            ])),
+        };
+
+        let mut configs = Vec::new();
+        parse_sheet("Points", &range, &options, &mut configs).unwrap();
+
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].key, "pv_power");
+        assert_eq!(configs[0].register_address, 100);
+        assert_eq!(configs[0].data_type, ModbusDataType::I32);
+        assert_eq!(configs[0].scale, 0.1);
+    }
+
+    #[test]
+    fn column_mapping_rejects_unknown_columns() {
+        let headers = vec!["Tag".to_owned()];
+        let unknown = HashMap::from([("color".to_owned(), ColumnRef::Index(0))]);
+        let missing = HashMap::from([("key".to_owned(), ColumnRef::Header("Key".to_owned()))]);
+
+        assert!(matches!(
+            ColumnLayout::resolve("s", &headers, Some(&unknown)),
+            Err(ModbusConfigsError::UnknownColumn(column)) if column == "color"
+        ));
+        assert!(matches!(
+            ColumnLayout::resolve("s", &headers, Some(&missing)),
+            Err(ModbusConfigsError::MissingColumn { .. })
+        ));
+    }
+
+    #[test]
+    fn columns_are_detected_by_header_text() {
+        let rows = [
+            "Key,Name,Address,Register Type,Data Type,Qty,Scale,",
+            "soc,SOC,10,InputRegisters,U16,1,0.1,",
+            ",,,,,,,",
+            "soh,SOH,11,InputRegisters,U16,1,,",
+        ];
+        let mut range = Range::new((1, 0), (4, 7));
+        for (r, row) in rows.iter().enumerate() {
+            for (c, cell) in row.split(',').enumerate() {
+                let value = match cell.parse::<f64>() {
+                    Ok(v) => Data::Float(v),
+                    Err(_) if cell.is_empty() => Data::Empty,
+                    Err(_) => Data::String(cell.to_owned()),
+                };
+                range.set_value((r as u32 + 1, c as u32), value);
+            }
+        }
+        let headers = range.headers().unwrap();
+
+        let layout = ColumnLayout::resolve("s", &headers, None);
+        assert!(matches!(
+            layout,
+            Err(ModbusConfigsError::MissingColumn { header, .. }) if header == "id"
+        ));
+
+        let options = XlsxOptions {
+            columns: Some(HashMap::from([("id".to_owned(), ColumnRef::Index(2))])),
+            ..Default::default()
+        };
+        let mut configs = Vec::new();
+        let errors = parse_sheet("s", &range, &options, &mut configs).unwrap();
+
+        // 空行跳过，第二个点位缺少系数而报错
+        assert_eq!(errors, vec!["工作表s第5行: 缩放不能为空".to_owned()]);
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].key, "soc");
+        assert_eq!(configs[0].id, 10);
+        assert_eq!(configs[0].offset, 0.0);
+        assert_eq!(configs[0].register_type, RegisterType::InputRegisters);
+    }
+
+    #[test]
+    fn epsilon_cells_must_be_numeric() {
+        let headers = [
+            "Key",
+            "Name",
+            "Address",
+            "Register Type",
+            "Data Type",
+            "Qty",
+            "Scale",
+        ];
+        let points = [
+            ("a", Data::Float(0.5)),
+            ("b", Data::Int(2)),
+            ("c", Data::Empty),
+            ("d", Data::String("0,01".to_owned())),
+        ];
+        let mut range = Range::new((0, 0), (points.len() as u32, 7));
+        for (c, header) in headers.iter().chain(["Epsilon"].iter()).enumerate() {
+            range.set_value((0, c as u32), Data::String(header.to_string()));
+        }
+        for (r, (key, epsilon)) in points.into_iter().enumerate() {
+            let row = [
+                Data::String(key.to_owned()),
+                Data::String(key.to_owned()),
+                Data::Float(r as f64),
+                Data::String("InputRegisters".to_owned()),
+                Data::String("U16".to_owned()),
+                Data::Float(1.0),
+                Data::Float(1.0),
+                epsilon,
+            ];
+            for (c, value) in row.into_iter().enumerate() {
+                range.set_value((r as u32 + 1, c as u32), value);
+            }
+        }
+        let options = XlsxOptions {
+            columns: Some(HashMap::from([("id".to_owned(), ColumnRef::Index(2))])),
+            ..Default::default()
+        };
+        let mut configs = Vec::new();
+        let errors = parse_sheet("s", &range, &options, &mut configs).unwrap();
+
+        assert_eq!(errors, vec!["工作表s第5行: 变化容差必须是数值".to_owned()]);
+        let epsilons: Vec<_> = configs.iter().map(|config| config.epsilon).collect();
+        assert_eq!(epsilons, [Some(0.5), Some(2.0), None]);
+    }
+
+    #[test]
+    fn unrecognized_headers_fall_back_to_positions() {
+        let headers: Vec<String> = (0..16).map(|i| format!("c{i}")).collect();
+
+        let layout = ColumnLayout::resolve("s", &headers, None).unwrap();
+
+        assert_eq!(layout.0[12], Some(12));
+    }
+
+    #[test]
+    fn workbook_is_opened_by_extension() {
+        let path = "../config/PCS_125_英博.xlsx".to_owned();
+
+        let configs = build_configs(path, &XlsxOptions::default()).unwrap();
+
+        // 表头下的第一行点位同样被读取
+        assert!(configs.iter().any(|config| config.id == 1));
+        assert!(matches!(
+            build_configs("../config/missing.ods".to_owned(), &XlsxOptions::default()),
+            Err(ModbusConfigsError::OpenWorkbookError(_))
+        ));
+    }
+
+    #[test]
+    fn bcd_round_trips_decimal_digits() {
+        assert_eq!(
+            ModbusDataType::try_from("BCD32").unwrap(),
+            ModbusDataType::Bcd32
+        );
+        assert_eq!(decimal_to_bcd(2305), Some(0x2305));
+        assert_eq!(decimal_to_bcd(12_345_678), Some(0x1234_5678));
+        assert_eq!(decimal_to_bcd(100_000_000), None);
+        assert_eq!(bcd_to_decimal(0x1234_5678), Some(12_345_678));
+        assert_eq!(bcd_to_decimal(0x00A1), None);
+    }
+
+    #[test]
+    fn table_addresses_are_converted_by_base() {
+        assert_eq!(
+            RegisterType::HoldingRegisters.protocol_address(100, 0),
+            Some(100)
+        );
+        assert_eq!(
+            RegisterType::HoldingRegisters.protocol_address(100, 1),
+            Some(99)
+        );
+        assert_eq!(
+            RegisterType::HoldingRegisters.protocol_address(40001, 1),
+            Some(0)
+        );
+        assert_eq!(
+            RegisterType::InputRegisters.protocol_address(30010, 1),
+            Some(9)
+        );
+        assert_eq!(
+            RegisterType::DiscreteInputs.protocol_address(10001, 1),
+            Some(0)
+        );
+        assert_eq!(RegisterType::Coils.protocol_address(1, 1), Some(0));
+        // 万位与寄存器类型不符时按普通编号处理
+        assert_eq!(
+            RegisterType::InputRegisters.protocol_address(40001, 1),
+            Some(40000)
+        );
+        assert_eq!(
+            RegisterType::HoldingRegisters.protocol_address(40001, 0),
+            Some(40001)
+        );
+        assert_eq!(RegisterType::Coils.protocol_address(0, 1), None);
+    }
+}
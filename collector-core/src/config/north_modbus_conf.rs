@@ -4,7 +4,7 @@ use calamine::{Data, DataType, HeaderRow, Reader, Xlsx, open_workbook};
 
 use crate::{
     config::{
-        modbus_conf::{ByteOrder, ModbusDataType, RegisterType},
+        modbus_conf::{ByteOrder, ModbusDataType, RegisterType, decimal_to_bcd},
         required_f64, required_str,
     },
     core::point::Val,
@@ -14,6 +14,7 @@ pub(crate) enum RegValue {
     Bool(bool),
     Word(u16),
     DWord([u16; 2]),
+    QWord([u16; 4]),
 }
 
 pub(crate) struct PointSource {
@@ -102,6 +103,47 @@ impl NorthboundConfig {
                 let bo = self.byte_order.unwrap_or(ByteOrder::ABCD);
                 Some(RegValue::DWord(bo.assemble_u32(scaled)))
             }
+            ModbusDataType::F32 => {
+                let raw = f64::try_from(val).ok()?;
+                let scaled = ((raw * self.scale + self.offset) as f32).to_bits();
+                let bo = self.byte_order.unwrap_or(ByteOrder::ABCD);
+                Some(RegValue::DWord(bo.assemble_u32(scaled)))
+            }
+            ModbusDataType::F64 => {
+                let raw = f64::try_from(val).ok()?;
+                let scaled = (raw * self.scale + self.offset).to_bits();
+                let bo = self.byte_order.unwrap_or(ByteOrder::ABCDEFGH);
+                Some(RegValue::QWord(bo.assemble_u64(scaled)))
+            }
+            ModbusDataType::U64 => {
+                let raw = f64::try_from(val).ok()?;
+                let scaled = (raw * self.scale + self.offset) as u64;
+                let bo = self.byte_order.unwrap_or(ByteOrder::ABCDEFGH);
+                Some(RegValue::QWord(bo.assemble_u64(scaled)))
+            }
+            ModbusDataType::I64 => {
+                let raw = f64::try_from(val).ok()?;
+                let scaled = (raw * self.scale + self.offset) as i64 as u64;
+                let bo = self.byte_order.unwrap_or(ByteOrder::ABCDEFGH);
+                Some(RegValue::QWord(bo.assemble_u64(scaled)))
+            }
+            ModbusDataType::Bcd16 => {
+                let raw = f64::try_from(val).ok()?;
+                let scaled = decimal_to_bcd((raw * self.scale + self.offset) as u32)
+                    .filter(|bcd| *bcd <= 0x9999)?;
+                let word = self
+                    .byte_order
+                    .map_or(scaled as u16, |bo| bo.assemble_u16(scaled as u16));
+                Some(RegValue::Word(word))
+            }
+            ModbusDataType::Bcd32 => {
+                let raw = f64::try_from(val).ok()?;
+                let scaled = decimal_to_bcd((raw * self.scale + self.offset) as u32)?;
+                let bo = self.byte_order.unwrap_or(ByteOrder::ABCD);
+                Some(RegValue::DWord(bo.assemble_u32(scaled)))
+            }
+            // 北向表格暂不转发字符串点位
+            ModbusDataType::String(_) => None,
         }
     }
 }
@@ -0,0 +1,262 @@
+//! 配置热更新：比较新旧设备配置，找出需要重建的设备
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use serde::Serialize;
+
+use crate::config::revision::{self, RegisterFileRevision};
+use crate::config::{Device, expand_register_file};
+
+/// 新旧配置的差异，设备均为 `devices` 中的键
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigDiff {
+    /// 新增的设备
+    pub added: Vec<String>,
+    /// 被删除的设备
+    pub removed: Vec<String>,
+    /// 配置字段发生变化的设备
+    pub modified: Vec<DeviceChange>,
+    /// 配置未变、点位表内容发生变化的设备
+    pub point_tables: Vec<String>,
+}
+
+/// 设备配置的变化
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceChange {
+    pub device: String,
+    /// 发生变化的字段，`config` 下的字段以 `config.` 开头
+    pub fields: Vec<String>,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.point_tables.is_empty()
+    }
+
+    /// 需要停止旧实例的设备：删除、修改及点位表变化
+    pub fn stopped(&self) -> impl Iterator<Item = &String> {
+        self.removed.iter().chain(self.changed())
+    }
+
+    /// 需要重新加载点位表并重建的设备：修改、点位表变化及新增
+    pub fn rebuilt(&self) -> impl Iterator<Item = &String> {
+        self.changed().chain(&self.added)
+    }
+
+    fn changed(&self) -> impl Iterator<Item = &String> {
+        self.modified
+            .iter()
+            .map(|change| &change.device)
+            .chain(&self.point_tables)
+    }
+}
+
+/// 最近一次热更新应用的差异
+static LAST_DIFF: LazyLock<RwLock<Option<ConfigDiff>>> = LazyLock::new(Default::default);
+
+/// 最近一次热更新应用的差异，尚未发生热更新时为 `None`
+pub fn last_diff() -> Option<ConfigDiff> {
+    LAST_DIFF.read().expect("diff lock poisoned").clone()
+}
+
+/// 记录热更新应用的差异，供 API 查询
+pub fn record_diff(diff: ConfigDiff) {
+    *LAST_DIFF.write().expect("diff lock poisoned") = Some(diff);
+}
+
+/// 比较新旧设备配置
+///
+/// 配置未变的运行中设备再比较点位表内容与上次加载时记录的摘要（见 [`revision`]），
+/// 只有内容真正变化时才需要重建
+pub fn diff_devices(old: &HashMap<String, Device>, new: &HashMap<String, Device>) -> ConfigDiff {
+    let mut diff = ConfigDiff::default();
+    for (key, dev) in new {
+        let Some(prev) = old.get(key) else {
+            diff.added.push(key.clone());
+            continue;
+        };
+        let fields = changed_fields(prev, dev);
+        if !fields.is_empty() {
+            diff.modified.push(DeviceChange {
+                device: key.clone(),
+                fields,
+            });
+        } else if point_tables_changed(dev) {
+            diff.point_tables.push(key.clone());
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|key| !new.contains_key(*key))
+        .cloned()
+        .collect();
+    diff.added.sort();
+    diff.removed.sort();
+    diff.modified.sort_by(|a, b| a.device.cmp(&b.device));
+    diff.point_tables.sort();
+    diff
+}
+
+fn changed_fields(a: &Device, b: &Device) -> Vec<String> {
+    let mut fields = Vec::new();
+    if a.id != b.id {
+        fields.push("id".to_owned());
+    }
+    if a.desc != b.desc {
+        fields.push("desc".to_owned());
+    }
+    if a.enabled != b.enabled {
+        fields.push("enabled".to_owned());
+    }
+    if a.group != b.group {
+        fields.push("group".to_owned());
+    }
+    if a.depends_on != b.depends_on {
+        fields.push("depends_on".to_owned());
+    }
+    fields.extend(
+        a.config
+            .changed_fields(&b.config)
+            .into_iter()
+            .map(|field| format!("config.{field}")),
+    );
+    fields
+}
+
+/// 运行中设备的点位表是否与上次加载时不同
+fn point_tables_changed(dev: &Device) -> bool {
+    if !dev.is_enabled() || dev.config.com_type.is_none() {
+        return false;
+    }
+    let (Some(id), Some(file)) = (dev.id.as_deref(), dev.config.register_file.as_deref()) else {
+        return false;
+    };
+    tables_changed(&revision::device_revisions(id), file)
+}
+
+/// 比较点位表当前匹配的文件及其摘要与已加载的版本
+fn tables_changed(loaded: &[RegisterFileRevision], file: &str) -> bool {
+    let current = expand_register_file(file);
+    current.len() != loaded.len()
+        || current.iter().any(|path| {
+            let path = path.display().to_string();
+            let sha256 = revision::file_sha256(&path).ok();
+            loaded
+                .iter()
+                .find(|rev| rev.path == path)
+                .is_none_or(|rev| sha256.as_ref() != Some(&rev.sha256))
+        })
+}
+
+/// 设备引用的所有点位表的绝对路径，通配符展开为当前匹配到的文件
+pub fn register_files(devices: &HashMap<String, Device>) -> HashSet<PathBuf> {
+    devices
+        .values()
+        .filter_map(|dev| dev.config.register_file.as_deref())
+        .flat_map(expand_register_file)
+        .map(|file| absolute_path(&file))
+        .collect()
+}
+
+/// 转为绝对路径，便于与文件监听事件中的路径比较
+pub fn absolute_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{diff_devices, tables_changed};
+    use crate::config::revision::inspect;
+    use crate::config::{ConfigFormat, Configuration, Device};
+
+    fn devices(json: &str) -> HashMap<String, Device> {
+        Configuration::from_slice(json.as_bytes(), ConfigFormat::Json)
+            .unwrap()
+            .project
+            .devices
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_modified() {
+        let old = devices(
+            r#"{"devices": {
+                "a": {"id": "a", "config": {"interval": 1000}},
+                "b": {"id": "b", "config": {"interval": 1000}},
+                "c": {"id": "c", "config": {"interval": 1000}}
+            }}"#,
+        );
+        let new = devices(
+            r#"{"devices": {
+                "a": {"id": "a", "config": {"interval": 1000}},
+                "b": {"id": "b", "enabled": false, "config": {"interval": 500}},
+                "d": {"id": "d", "config": {"interval": 1000}}
+            }}"#,
+        );
+
+        let diff = diff_devices(&old, &new);
+
+        assert_eq!(diff.added, vec!["d"]);
+        assert_eq!(diff.removed, vec!["c"]);
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].device, "b");
+        assert_eq!(diff.modified[0].fields, vec!["enabled", "config.interval"]);
+        assert!(diff.point_tables.is_empty());
+        assert_eq!(diff.stopped().collect::<Vec<_>>(), vec!["c", "b"]);
+        assert_eq!(diff.rebuilt().collect::<Vec<_>>(), vec!["b", "d"]);
+    }
+
+    #[test]
+    fn diff_reports_startup_group_changes() {
+        let old = devices(
+            r#"{"devices": {
+                "a": {"id": "a", "group": "bms", "config": {"interval": 1000}},
+                "b": {"id": "b", "depends_on": ["bms"], "config": {"interval": 1000}}
+            }}"#,
+        );
+        let new = devices(
+            r#"{"devices": {
+                "a": {"id": "a", "group": "pcs", "config": {"interval": 1000}},
+                "b": {"id": "b", "depends_on": ["pcs"], "config": {"interval": 1000}}
+            }}"#,
+        );
+
+        let diff = diff_devices(&old, &new);
+
+        assert_eq!(diff.modified.len(), 2);
+        assert_eq!(diff.modified[0].fields, vec!["group"]);
+        assert_eq!(diff.modified[1].fields, vec!["depends_on"]);
+    }
+
+    #[test]
+    fn point_tables_are_compared_by_content() {
+        let dir = std::env::temp_dir().join(format!("collector-diff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("points.csv");
+        let file = file.to_str().unwrap();
+        std::fs::write(file, "a,b\n").unwrap();
+        let loaded = vec![inspect("pcs", file, None).unwrap()];
+
+        std::fs::write(file, "a,b\n").unwrap();
+        let rewritten = tables_changed(&loaded, file);
+        std::fs::write(file, "a,c\n").unwrap();
+        let edited = tables_changed(&loaded, file);
+        std::fs::write(file, "a,b\n").unwrap();
+        let pattern = format!("{}/*.csv", dir.display());
+        let same_glob = tables_changed(&loaded, &pattern);
+        std::fs::write(dir.join("extra.csv"), "").unwrap();
+        let grown_glob = tables_changed(&loaded, &pattern);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!rewritten);
+        assert!(edited);
+        assert!(!same_glob);
+        assert!(grown_glob);
+    }
+}
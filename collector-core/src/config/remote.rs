@@ -0,0 +1,174 @@
+//! 从 HTTP(S) 地址拉取项目配置
+//!
+//! 拉取成功后在本地保存一份副本及其 ETag；再次拉取时携带 `If-None-Match`，
+//! 服务端返回 304 或不可达时使用本地副本，副本保存在 [`cache_dir`](crate::config::cache_dir) 中
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::config::ConfigurationError;
+
+/// 拉取配置的超时时间
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 判断配置路径是否为 HTTP(S) 地址
+pub fn is_remote(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// 去掉地址中的查询参数与锚点，便于按扩展名识别格式
+pub(crate) fn strip_query(url: &str) -> &str {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    &url[..end]
+}
+
+pub(crate) async fn fetch(url: &str) -> Result<Vec<u8>, ConfigurationError> {
+    fetch_with_cache(url, &crate::config::cache_dir()).await
+}
+
+pub(crate) async fn fetch_with_cache(
+    url: &str,
+    cache_dir: &Path,
+) -> Result<Vec<u8>, ConfigurationError> {
+    let body_path = cache_path(cache_dir, url);
+    let etag_path = body_path.with_extension("etag");
+    let cached = fs::read(&body_path).await.ok();
+    let etag = match cached {
+        Some(_) => fs::read_to_string(&etag_path).await.ok(),
+        None => None,
+    };
+
+    match request(url, etag.as_deref()).await {
+        Ok(Response::NotModified) => {
+            if let Some(body) = cached {
+                info!("远程配置未变化, 使用本地副本: {}", url);
+                return Ok(body);
+            }
+            Err(ConfigurationError::FetchError(format!(
+                "{url} 返回304但本地没有副本"
+            )))
+        }
+        Ok(Response::Body { body, etag }) => {
+            if let Err(err) = save(cache_dir, &body_path, &body, &etag_path, etag.as_deref()).await
+            {
+                warn!("保存远程配置副本失败: {}", err);
+            }
+            Ok(body)
+        }
+        Err(err) => match cached {
+            Some(body) => {
+                warn!("拉取远程配置失败, 使用本地副本: {}", err);
+                Ok(body)
+            }
+            None => Err(ConfigurationError::FetchError(err)),
+        },
+    }
+}
+
+enum Response {
+    NotModified,
+    Body { body: Vec<u8>, etag: Option<String> },
+}
+
+async fn request(url: &str, etag: Option<&str>) -> Result<Response, String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let mut req = client.get(url);
+    if let Some(etag) = etag {
+        req = req.header(IF_NONE_MATCH, etag.trim());
+    }
+    let resp = req.send().await.map_err(|err| err.to_string())?;
+    match resp.status() {
+        StatusCode::NOT_MODIFIED => Ok(Response::NotModified),
+        status if status.is_success() => {
+            let etag = resp
+                .headers()
+                .get(ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned);
+            let body = resp.bytes().await.map_err(|err| err.to_string())?;
+            Ok(Response::Body {
+                body: body.to_vec(),
+                etag,
+            })
+        }
+        status => Err(format!("{url} 返回状态码 {status}")),
+    }
+}
+
+async fn save(
+    cache_dir: &Path,
+    body_path: &Path,
+    body: &[u8],
+    etag_path: &Path,
+    etag: Option<&str>,
+) -> std::io::Result<()> {
+    fs::create_dir_all(cache_dir).await?;
+    fs::write(body_path, body).await?;
+    match etag {
+        Some(etag) => fs::write(etag_path, etag).await,
+        None => match fs::remove_file(etag_path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// 由地址生成本地副本文件名，非字母数字字符替换为下划线
+fn cache_path(cache_dir: &Path, url: &str) -> PathBuf {
+    let name: String = url
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    cache_dir.join(format!("{name}.cache"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{cache_path, fetch_with_cache, is_remote, strip_query};
+
+    #[test]
+    fn remote_paths_are_detected() {
+        assert!(is_remote("https://config-server/site42.json"));
+        assert!(is_remote("HTTP://config-server/site42.json"));
+        assert!(!is_remote("config/config.json"));
+        assert_eq!(
+            strip_query("https://s/site.yaml?v=2#x"),
+            "https://s/site.yaml"
+        );
+        assert_eq!(
+            cache_path(Path::new("c"), "https://s:8080/a.json"),
+            Path::new("c/https___s_8080_a.json.cache")
+        );
+    }
+
+    #[tokio::test]
+    async fn unreachable_server_falls_back_to_cached_copy() {
+        let dir = std::env::temp_dir().join(format!("collector-remote-{}", std::process::id()));
+        let url = "http://127.0.0.1:1/site42.json";
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(cache_path(&dir, url), b"{\"devices\": {}}").unwrap();
+
+        let body = fetch_with_cache(url, &dir).await.unwrap();
+        assert_eq!(body, b"{\"devices\": {}}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(fetch_with_cache(url, &dir).await.is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! 点位表版本追踪
+//!
+//! 加载点位表时记录文件的 SHA-256 及可选的版本单元格，供 API 查询现场实际运行的点位表版本。
+//! 上次运行时的摘要保存在 [`cache_dir`](crate::config::cache_dir) 下的 [`STATE_FILE`] 中，
+//! 文件在两次运行之间发生变化时输出日志。
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, RwLock};
+
+use calamine::{Data, Reader, open_workbook_auto};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// 各点位表上次加载时的摘要
+const STATE_FILE: &str = "register_files.json";
+
+/// 已加载点位表的版本信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegisterFileRevision {
+    pub device: String,
+    pub path: String,
+    pub sha256: String,
+    /// `version_cell` 指定的单元格内容
+    pub version: Option<String>,
+}
+
+/// 设备 ID 到其点位表版本的映射
+static REVISIONS: LazyLock<RwLock<HashMap<String, Vec<RegisterFileRevision>>>> =
+    LazyLock::new(Default::default);
+
+/// 所有设备当前加载的点位表版本，按设备、路径排序
+pub fn revisions() -> Vec<RegisterFileRevision> {
+    let mut all: Vec<RegisterFileRevision> = REVISIONS
+        .read()
+        .expect("revisions lock poisoned")
+        .values()
+        .flatten()
+        .cloned()
+        .collect();
+    all.sort_by(|a, b| (&a.device, &a.path).cmp(&(&b.device, &b.path)));
+    all
+}
+
+/// 指定设备当前加载的点位表版本
+pub fn device_revisions(device: &str) -> Vec<RegisterFileRevision> {
+    REVISIONS
+        .read()
+        .expect("revisions lock poisoned")
+        .get(device)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// 计算点位表摘要并读取版本单元格
+///
+/// `version_cell` 形如 `说明!B2`，只对表格点位表生效，读取失败时记录警告
+pub(crate) fn inspect(
+    device: &str,
+    path: &str,
+    version_cell: Option<&str>,
+) -> std::io::Result<RegisterFileRevision> {
+    let sha256 = file_sha256(path)?;
+    let version = version_cell.and_then(|cell| match read_cell(path, cell) {
+        Ok(version) => version,
+        Err(err) => {
+            warn!("读取点位表{}版本单元格{}失败: {}", path, cell, err);
+            None
+        }
+    });
+    Ok(RegisterFileRevision {
+        device: device.to_owned(),
+        path: path.to_owned(),
+        sha256,
+        version,
+    })
+}
+
+/// 记录设备的点位表版本，与上次运行时的摘要比较
+pub(crate) fn record(device: &str, files: Vec<RegisterFileRevision>) {
+    let state_file = crate::config::cache_dir().join(STATE_FILE);
+    let mut state = load_state(&state_file);
+    let mut dirty = false;
+    for file in &files {
+        let key = format!("{}|{}", file.device, file.path);
+        match state.get(&key) {
+            Some(old) if *old == file.sha256 => {}
+            Some(old) => {
+                warn!(
+                    "设备{}点位表{}自上次运行后已变化: {} -> {}, 版本: {}",
+                    file.device,
+                    file.path,
+                    short(old),
+                    short(&file.sha256),
+                    file.version.as_deref().unwrap_or("-")
+                );
+            }
+            None => info!(
+                "设备{}加载点位表{}: {}, 版本: {}",
+                file.device,
+                file.path,
+                short(&file.sha256),
+                file.version.as_deref().unwrap_or("-")
+            ),
+        }
+        if state.get(&key) != Some(&file.sha256) {
+            state.insert(key, file.sha256.clone());
+            dirty = true;
+        }
+    }
+    if dirty && let Err(err) = save_state(&state_file, &state) {
+        warn!("保存点位表摘要失败: {}", err);
+    }
+    REVISIONS
+        .write()
+        .expect("revisions lock poisoned")
+        .insert(device.to_owned(), files);
+}
+
+fn load_state(path: &Path) -> HashMap<String, String> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(path: &Path, state: &HashMap<String, String>) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_vec_pretty(state)?)
+}
+
+/// 文件内容的 SHA-256，十六进制小写
+pub(crate) fn file_sha256(path: &str) -> std::io::Result<String> {
+    Ok(hex(&Sha256::digest(std::fs::read(path)?)))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn short(sha256: &str) -> &str {
+    &sha256[..sha256.len().min(12)]
+}
+
+fn read_cell(path: &str, cell: &str) -> Result<Option<String>, String> {
+    let (sheet, position) = parse_cell(cell).ok_or_else(|| "格式应为 工作表!A1".to_owned())?;
+    let mut workbook = open_workbook_auto(path).map_err(|err| err.to_string())?;
+    let range = workbook
+        .worksheet_range(sheet)
+        .map_err(|err| err.to_string())?;
+    Ok(match range.get_value(position) {
+        None | Some(Data::Empty) => None,
+        Some(value) => Some(value.to_string()),
+    })
+}
+
+/// 解析 `工作表!B2` 形式的单元格，返回工作表名与从 0 开始的 (行, 列)
+fn parse_cell(cell: &str) -> Option<(&str, (u32, u32))> {
+    let (sheet, addr) = cell.rsplit_once('!')?;
+    let split = addr.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = addr.split_at(split);
+    if sheet.is_empty() || letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let col = letters.chars().try_fold(0u32, |acc, c| {
+        acc.checked_mul(26)?
+            .checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)
+    })?;
+    let row: u32 = digits.parse().ok()?;
+    Some((sheet, (row.checked_sub(1)?, col - 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inspect, parse_cell};
+
+    #[test]
+    fn cell_references_are_parsed() {
+        assert_eq!(parse_cell("说明!B2"), Some(("说明", (1, 1))));
+        assert_eq!(parse_cell("Info!aa10"), Some(("Info", (9, 26))));
+        assert_eq!(parse_cell("B2"), None);
+        assert_eq!(parse_cell("Info!B0"), None);
+        assert_eq!(parse_cell("Info!2B"), None);
+    }
+
+    #[test]
+    fn revision_reads_hash_and_version_cell() {
+        let path = "../config/PCS_125_英博.xlsx";
+
+        let revision = inspect("pcs", path, Some("遥测!A1")).unwrap();
+
+        assert_eq!(revision.sha256.len(), 64);
+        assert_eq!(revision.version.as_deref(), Some("序号"));
+    }
+}
@@ -0,0 +1,356 @@
+//! 起步配置生成
+//!
+//! `collector init` 按选定的通信类型生成一份可以直接通过 `collector check` 的项目配置与示例点位表，
+//! 新用户照着示例修改，不必从源码反推配置格式。Modbus 示例点位表为 JSON，
+//! CAN 与 GPIO 的点位表只支持 Excel，按加载时要求的工作表与列生成。
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rust_xlsxwriter::{Workbook, XlsxError};
+use serde_json::{Value, json};
+
+use crate::config::ComType;
+use crate::config::modbus_conf::JsonPoint;
+
+/// 生成的项目配置文件名
+pub const PROJECT_FILE: &str = "collector.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScaffoldError {
+    #[error("暂不支持{0:?}设备")]
+    Unsupported(ComType),
+    #[error("{0}已存在, 加 --force 覆盖")]
+    Exists(String),
+    #[error("写入{0}失败: {1}")]
+    Io(String, io::Error),
+    #[error("生成项目配置失败: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("生成Excel点位表失败: {0}")]
+    Xlsx(#[from] XlsxError),
+}
+
+/// 起步配置的选项，未指定的字段取 [`Scaffold::new`] 的缺省值
+#[derive(Debug, Clone)]
+pub struct Scaffold {
+    pub project: String,
+    pub com_type: ComType,
+    /// 设备键与 ID
+    pub device: String,
+    pub ip: String,
+    pub port: u16,
+    pub slave: u8,
+    pub serial_tty: String,
+    pub baud_rate: u32,
+    /// CAN 接口，如 `can0`
+    pub interface: String,
+}
+
+impl Scaffold {
+    pub fn new(com_type: ComType) -> Self {
+        let device = match com_type {
+            ComType::CAN => "bms",
+            ComType::GPIO => "gpio",
+            _ => "pcs",
+        };
+        Self {
+            project: "collector".to_owned(),
+            com_type,
+            device: device.to_owned(),
+            ip: "127.0.0.1".to_owned(),
+            port: 502,
+            slave: 1,
+            serial_tty: "/dev/ttyS1".to_owned(),
+            baud_rate: match com_type {
+                ComType::CAN => 500_000,
+                _ => 9600,
+            },
+            interface: "can0".to_owned(),
+        }
+    }
+
+    /// 示例点位表的文件名，与项目配置位于同一目录
+    pub fn point_table(&self) -> &'static str {
+        match self.com_type {
+            ComType::ModbusTCP | ComType::ModbusRTU => "points.json",
+            ComType::CAN => "can_points.xlsx",
+            ComType::GPIO => "gpio_points.xlsx",
+            ComType::IEC104 | ComType::IEC61850 => "",
+        }
+    }
+
+    /// 项目配置，点位表以相对于项目文件的路径引用
+    pub fn project_json(&self) -> Result<Value, ScaffoldError> {
+        let link = match self.com_type {
+            ComType::ModbusTCP => json!({
+                "ip": self.ip,
+                "port": self.port,
+                "slave": self.slave,
+                "interval": 1000,
+                "timeout": 1000,
+            }),
+            ComType::ModbusRTU => json!({
+                "serial_tty": self.serial_tty,
+                "baud_rate": self.baud_rate,
+                "data_bits": 8,
+                "parity": "N",
+                "stop_bits": 1,
+                "slave": self.slave,
+                "interval": 1000,
+                "timeout": 1000,
+            }),
+            ComType::CAN => json!({
+                "interface": self.interface,
+                "baud_rate": self.baud_rate,
+                "interval": 1000,
+                "timeout": 5000,
+            }),
+            ComType::GPIO => json!({}),
+            com @ (ComType::IEC104 | ComType::IEC61850) => {
+                return Err(ScaffoldError::Unsupported(com));
+            }
+        };
+        let mut config = json!({
+            "com_type": self.com_type,
+            "register_file": self.point_table(),
+        });
+        if let (Value::Object(config), Value::Object(link)) = (&mut config, link) {
+            config.extend(link);
+        }
+        Ok(json!({
+            "project": self.project,
+            "http_ip": "0.0.0.0",
+            "http_port": 9091,
+            "emu_enable": false,
+            "mqtt_enable": false,
+            "devices": {
+                self.device.as_str(): {
+                    "id": self.device,
+                    "desc": "示例设备，按现场修改连接参数与点位表",
+                    "config": config,
+                },
+            },
+        }))
+    }
+
+    /// 在 `dir` 下写出项目配置与示例点位表，返回写出的文件；文件已存在且 `force` 为假时不写任何文件
+    pub fn write(&self, dir: &Path, force: bool) -> Result<Vec<PathBuf>, ScaffoldError> {
+        let project = self.project_json()?;
+        let files = [dir.join(PROJECT_FILE), dir.join(self.point_table())];
+        if !force && let Some(file) = files.iter().find(|file| file.exists()) {
+            return Err(ScaffoldError::Exists(file.display().to_string()));
+        }
+        let io_err = |path: &Path| {
+            let path = path.display().to_string();
+            move |err| ScaffoldError::Io(path, err)
+        };
+        std::fs::create_dir_all(dir).map_err(io_err(dir))?;
+        let mut text = serde_json::to_string_pretty(&project)?;
+        text.push('\n');
+        std::fs::write(&files[0], text).map_err(io_err(&files[0]))?;
+        match self.com_type {
+            ComType::CAN => write_can_points(&files[1])?,
+            ComType::GPIO => write_gpio_points(&files[1])?,
+            _ => {
+                let mut text = serde_json::to_string_pretty(&modbus_points())?;
+                text.push('\n');
+                std::fs::write(&files[1], text).map_err(io_err(&files[1]))?;
+            }
+        }
+        Ok(files.to_vec())
+    }
+}
+
+/// Modbus 示例点位：各寄存器类型与常用数据类型各一个
+fn modbus_points() -> Vec<JsonPoint> {
+    let point =
+        |id, name: &str, key: &str, data_type: &str, register_type: &str, address| JsonPoint {
+            id,
+            name: name.to_owned(),
+            data_type: data_type.to_owned(),
+            unit: None,
+            remarks: None,
+            register_address: address,
+            register_type: register_type.to_owned(),
+            quantity: if data_type == "F32" { 2 } else { 1 },
+            byte_order: None,
+            scale: 1.0,
+            offset: 0.0,
+            enable: true,
+            key: key.to_owned(),
+            trans: None,
+            status_words: None,
+            warn_bits: None,
+            bit: None,
+            scan_class: None,
+            select_address: None,
+            handshake_address: None,
+            pulse_ms: None,
+            epsilon: None,
+        };
+    vec![
+        JsonPoint {
+            unit: Some("V".to_owned()),
+            scale: 0.1,
+            ..point(1, "电压", "voltage", "U16", "InputRegisters", 0)
+        },
+        JsonPoint {
+            unit: Some("kW".to_owned()),
+            byte_order: Some("ABCD".to_owned()),
+            ..point(2, "有功功率", "active_power", "F32", "InputRegisters", 2)
+        },
+        JsonPoint {
+            unit: Some("kW".to_owned()),
+            remarks: Some("下发的功率设定值".to_owned()),
+            ..point(
+                3,
+                "功率设定",
+                "power_setpoint",
+                "I16",
+                "HoldingRegisters",
+                100,
+            )
+        },
+        point(4, "运行", "running", "Bool", "DiscreteInputs", 0),
+        JsonPoint {
+            remarks: Some("1 启动, 0 停止".to_owned()),
+            ..point(5, "启停", "start_stop", "Bool", "Coils", 0)
+        },
+    ]
+}
+
+/// 按表头与行写出一个工作表，表头与每行都以逗号分隔单元格，能解析为数字的写为数值单元格
+///
+/// 加载时表头行不在读取范围内，列数按数据行计算，示例行的最后一列须有值
+fn write_sheet(
+    workbook: &mut Workbook,
+    name: &str,
+    header: &str,
+    rows: &[&str],
+) -> Result<(), XlsxError> {
+    let worksheet = workbook.add_worksheet().set_name(name)?;
+    for (column, title) in header.split(',').enumerate() {
+        worksheet.write_string(0, column as u16, title)?;
+    }
+    for (index, cells) in rows.iter().enumerate() {
+        let row = index as u32 + 1;
+        for (column, cell) in cells.split(',').enumerate() {
+            match cell.parse::<f64>() {
+                _ if cell.is_empty() => {}
+                Ok(value) => {
+                    worksheet.write_number(row, column as u16, value)?;
+                }
+                Err(_) => {
+                    worksheet.write_string(row, column as u16, cell)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// CAN 点位表：报文、信号与按多帧聚合的扩展信号(如单体电压)各一例
+fn write_can_points(path: &Path) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
+    write_sheet(
+        &mut workbook,
+        "报文",
+        "序号,报文名称,FrameID,ID类型,DLC,周期ms,超时ms,发送方,接收方,规则,使能",
+        &[
+            "1,电池状态,0x18FF50E5,extended,8,1000,5000,BMS,EMS,cycle,1",
+            "2,单体电压,0x18FF60E5,extended,8,1000,5000,BMS,EMS,cycle,1",
+        ],
+    )?;
+    write_sheet(
+        &mut workbook,
+        "信号",
+        "点号,点位名称,FrameID,信号名称,起始位,位长,字节序,数据类型,缩放,偏移,单位,无效值,枚举值,备注,键,转换,位枚举",
+        &[
+            r#"1,SOC,0x18FF50E5,soc,0,16,intel,u16,0.1,0,%,,,荷电状态,soc,{"en":"SOC"}"#,
+            r#"2,总电压,0x18FF50E5,voltage,16,16,intel,u16,0.1,0,V,,,电池组总电压,voltage,{"en":"Voltage"}"#,
+        ],
+    )?;
+    write_sheet(
+        &mut workbook,
+        "信号_扩展",
+        "点号,点位名称,聚合键,FrameID,Frame数量,FrameID步长,每帧元素,总元素数,元素起始Bit,\
+         单元素BitLen,字节序,数据类型,缩放,偏移,单位,无效值,键,转换",
+        &[
+            r#"1,单体电压,cell_voltage,0x18FF60E5,4,1,4,16,0,16,intel,u16,0.001,0,V,,cell_voltage,{"en":"Cell voltage"}"#,
+        ],
+    )?;
+    workbook.save(path)
+}
+
+/// GPIO 点位表：输入、输出各一例
+fn write_gpio_points(path: &Path) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
+    write_sheet(
+        &mut workbook,
+        "gpio",
+        "id,KEY,GPIO,DIRECTION,CHIP,LINE,NAME,ENABLE,TRANS",
+        &[
+            r#"1,door,17,DI,/dev/gpiochip0,17,门禁,1,{"en":"Door"}"#,
+            r#"2,fan,18,DO,/dev/gpiochip0,18,风扇,1,{"en":"Fan"}"#,
+        ],
+    )?;
+    workbook.save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PROJECT_FILE, Scaffold, ScaffoldError};
+    #[cfg(target_os = "linux")]
+    use crate::config::gpio_conf;
+    use crate::config::{ComType, Configuration, ProtocolConfigs, can_conf};
+
+    #[tokio::test]
+    async fn generated_projects_pass_validation_and_load_their_point_tables() {
+        let root = std::env::temp_dir().join(format!("collector-scaffold-{}", std::process::id()));
+        for com_type in [
+            ComType::ModbusTCP,
+            ComType::ModbusRTU,
+            ComType::CAN,
+            ComType::GPIO,
+        ] {
+            let dir = root.join(format!("{com_type:?}"));
+            let scaffold = Scaffold::new(com_type);
+            let files = scaffold.write(&dir, false).unwrap();
+            assert!(matches!(
+                scaffold.write(&dir, false),
+                Err(ScaffoldError::Exists(_))
+            ));
+            scaffold.write(&dir, true).unwrap();
+
+            let mut configuration =
+                Configuration::new(dir.join(PROJECT_FILE).display().to_string())
+                    .await
+                    .unwrap();
+            configuration.validate().unwrap();
+            let points = files[1].display().to_string();
+            match com_type {
+                ComType::CAN => assert_eq!(can_conf::build_configs(points).unwrap().len(), 2),
+                #[cfg(target_os = "linux")]
+                ComType::GPIO => {
+                    assert_eq!(gpio_conf::build_configs(points, true).unwrap().len(), 2)
+                }
+                // GPIO 点位表只在 Linux 上解析
+                #[cfg(not(target_os = "linux"))]
+                ComType::GPIO => {}
+                _ => {
+                    configuration.load_device_configs().await.unwrap();
+                    let dev = &configuration.project.devices[&scaffold.device];
+                    assert!(matches!(
+                        &dev.protocol_configs,
+                        Some(ProtocolConfigs::Modbus(points)) if points.len() == 5
+                    ));
+                }
+            }
+        }
+        assert!(matches!(
+            Scaffold::new(ComType::IEC104).write(&root, false),
+            Err(ScaffoldError::Unsupported(ComType::IEC104))
+        ));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
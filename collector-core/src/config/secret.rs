@@ -0,0 +1,175 @@
+//! 配置中的加密字段
+//!
+//! 凭据类字段（如 `mqtt_username`、`mqtt_password`）可以写成 `ENC(...)`，括号内为
+//! base64 编码的 12 字节随机数与 AES-256-GCM 密文，加载时解密。
+//!
+//! 32 字节密钥按以下顺序查找，只在配置中出现加密字段时读取：
+//! 1. 环境变量 `COLLECTOR_SECRET_KEY`
+//! 2. 环境变量 `COLLECTOR_SECRET_KEY_FILE` 指向的文件
+//! 3. systemd 凭据目录中的 `collector-secret-key`，配合 `LoadCredentialEncrypted=`
+//!    可以使用 TPM2 封装的密钥
+//! 4. `/etc/collector/secret.key`
+//!
+//! 密钥文件可以是 32 字节原始数据，也可以是其 base64 编码。
+
+use std::path::PathBuf;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use crate::config::{ConfigurationError, Project};
+
+const KEY_ENV: &str = "COLLECTOR_SECRET_KEY";
+const KEY_FILE_ENV: &str = "COLLECTOR_SECRET_KEY_FILE";
+/// systemd 凭据名
+const CREDENTIAL: &str = "collector-secret-key";
+const DEFAULT_KEY_FILE: &str = "/etc/collector/secret.key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+pub type SecretKey = [u8; KEY_LEN];
+
+fn is_encrypted(value: &str) -> bool {
+    value.starts_with("ENC(") && value.ends_with(')')
+}
+
+/// 加密明文，返回可直接写入配置的 `ENC(...)`
+pub fn encrypt(plain: &str, key: &SecretKey) -> Result<String, ConfigurationError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::fill(&mut nonce).map_err(|err| ConfigurationError::SecretKey(err.to_string()))?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), plain.as_bytes())
+        .expect("AES-GCM 只在明文超长时加密失败");
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&sealed);
+    Ok(format!("ENC({})", BASE64.encode(payload)))
+}
+
+/// 解密 `ENC(...)`，`field` 用于错误信息
+pub fn decrypt(value: &str, key: &SecretKey, field: &str) -> Result<String, ConfigurationError> {
+    let error = || ConfigurationError::Decrypt(field.to_owned());
+    if !is_encrypted(value) {
+        return Err(error());
+    }
+    let encoded = &value[4..value.len() - 1];
+    let payload = BASE64.decode(encoded.trim()).map_err(|_| error())?;
+    if payload.len() <= NONCE_LEN {
+        return Err(error());
+    }
+    let (nonce, sealed) = payload.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| error())?;
+    String::from_utf8(plain).map_err(|_| error())
+}
+
+/// 解析密钥：32 字节原始数据或其 base64 编码
+pub fn parse_key(bytes: &[u8]) -> Result<SecretKey, ConfigurationError> {
+    if let Ok(key) = SecretKey::try_from(bytes) {
+        return Ok(key);
+    }
+    let decoded = BASE64
+        .decode(bytes.trim_ascii())
+        .map_err(|err| ConfigurationError::SecretKey(err.to_string()))?;
+    SecretKey::try_from(decoded.as_slice())
+        .map_err(|_| ConfigurationError::SecretKey(format!("密钥长度应为{}字节", KEY_LEN)))
+}
+
+/// 按模块文档中的顺序查找密钥
+pub fn load_key() -> Result<SecretKey, ConfigurationError> {
+    if let Ok(value) = std::env::var(KEY_ENV) {
+        return parse_key(value.as_bytes());
+    }
+    let file = std::env::var_os(KEY_FILE_ENV)
+        .map(PathBuf::from)
+        .or_else(|| {
+            let path = PathBuf::from(std::env::var_os("CREDENTIALS_DIRECTORY")?).join(CREDENTIAL);
+            path.exists().then_some(path)
+        })
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_KEY_FILE));
+    let bytes = std::fs::read(&file).map_err(|err| {
+        ConfigurationError::SecretKey(format!("读取{}失败: {}", file.display(), err))
+    })?;
+    parse_key(&bytes)
+}
+
+/// 解密项目配置中的凭据字段
+///
+/// `key` 只在存在加密字段时调用
+pub(crate) fn decrypt_project(
+    project: &mut Project,
+    key: impl FnOnce() -> Result<SecretKey, ConfigurationError>,
+) -> Result<(), ConfigurationError> {
+    let fields = [
+        ("mqtt_username", &mut project.mqtt_username),
+        ("mqtt_password", &mut project.mqtt_password),
+    ];
+    if !fields
+        .iter()
+        .any(|(_, field)| field.as_deref().is_some_and(is_encrypted))
+    {
+        return Ok(());
+    }
+    let key = key()?;
+    for (name, field) in fields {
+        if let Some(value) = field.as_mut().filter(|value| is_encrypted(value)) {
+            *value = decrypt(value, &key, name)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, decrypt_project, encrypt, parse_key};
+    use crate::config::{ConfigFormat, ConfigurationError, parse};
+
+    const KEY: [u8; 32] = [7; 32];
+
+    #[test]
+    fn encrypted_fields_are_decrypted() {
+        let password = encrypt("s3cret", &KEY).unwrap();
+        let doc = format!(r#"{{"mqtt_username": "gw", "mqtt_password": "{password}"}}"#);
+        let mut project = parse(doc.as_bytes(), ConfigFormat::Json).unwrap();
+
+        decrypt_project(&mut project, || Ok(KEY)).unwrap();
+
+        assert_eq!(project.mqtt_username.as_deref(), Some("gw"));
+        assert_eq!(project.mqtt_password.as_deref(), Some("s3cret"));
+    }
+
+    #[test]
+    fn plaintext_config_does_not_need_key() {
+        let mut project = parse(br#"{"mqtt_password": "plain"}"#, ConfigFormat::Json).unwrap();
+
+        decrypt_project(&mut project, || {
+            Err(ConfigurationError::SecretKey("unused".into()))
+        })
+        .unwrap();
+
+        assert_eq!(project.mqtt_password.as_deref(), Some("plain"));
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let value = encrypt("s3cret", &KEY).unwrap();
+
+        assert!(matches!(
+            decrypt(&value, &[8; 32], "mqtt_password"),
+            Err(ConfigurationError::Decrypt(field)) if field == "mqtt_password"
+        ));
+        assert!(decrypt("ENC(!!)", &KEY, "mqtt_password").is_err());
+    }
+
+    #[test]
+    fn keys_accept_raw_and_base64() {
+        assert_eq!(parse_key(&KEY).unwrap(), KEY);
+        let encoded = "BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc=\n";
+        assert_eq!(parse_key(encoded.as_bytes()).unwrap(), KEY);
+        assert!(parse_key(b"short").is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! 项目配置模板变量
+//!
+//! 项目配置中的 `variables` 定义变量，`variables_file` 指向的文件可以覆盖同名变量，
+//! 近似的站点因此可以共用一份模板，只维护一个小的变量文件。
+//!
+//! 字符串中的 `{{name}}` 在解析配置前替换为变量的值，替换范围包括 `includes` 合并的设备文件。
+//! 整个字符串只有一个占位符时保留变量的类型，如 `"interval": "{{poll}}"` 可以得到数字；
+//! 变量之间不能相互引用。
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::config::ConfigurationError;
+
+pub type Variables = HashMap<String, Value>;
+
+/// 取出文档中的 `variables`，文档中不存在时为空
+pub(crate) fn variables(doc: &Value) -> Result<Variables, ConfigurationError> {
+    match doc.get("variables") {
+        None | Some(Value::Null) => Ok(Variables::new()),
+        Some(value) => Ok(serde_json::from_value(value.clone())?),
+    }
+}
+
+/// 替换文档中所有字符串值的占位符，跳过 `variables` 本身
+pub(crate) fn render(doc: &mut Value, vars: &Variables) -> Result<(), ConfigurationError> {
+    match doc {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key != "variables" {
+                    render(value, vars)?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                render(item, vars)?;
+            }
+        }
+        Value::String(text) if text.contains("{{") => *doc = render_str(text, vars)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn render_str(input: &str, vars: &Variables) -> Result<Value, ConfigurationError> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            return Err(ConfigurationError::InvalidPlaceholder(input.to_owned()));
+        };
+        let name = after[..end].trim();
+        if name.is_empty() {
+            return Err(ConfigurationError::InvalidPlaceholder(input.to_owned()));
+        }
+        let value = vars
+            .get(name)
+            .ok_or_else(|| ConfigurationError::MissingVariable(name.to_owned()))?;
+        rest = &after[end + 2..];
+        if start == 0 && rest.is_empty() && out.is_empty() {
+            return Ok(value.clone());
+        }
+        match value {
+            Value::String(value) => out.push_str(value),
+            value => out.push_str(&value.to_string()),
+        }
+    }
+    out.push_str(rest);
+    Ok(Value::String(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Variables, render};
+    use crate::config::ConfigurationError;
+
+    fn vars() -> Variables {
+        serde_json::from_value(json!({"prefix": "10.0.1", "poll": 500, "site": "A7"})).unwrap()
+    }
+
+    #[test]
+    fn placeholders_are_rendered() {
+        let mut doc = json!({
+            "variables": {"x": "{{y}}"},
+            "devices": {"pcs": {"config": {
+                "ip": "{{prefix}}.20",
+                "interval": "{{ poll }}",
+                "register_file": "sites/{{site}}/pcs_{{poll}}.xlsx",
+                "sheets": ["遥测"]
+            }}}
+        });
+
+        render(&mut doc, &vars()).unwrap();
+
+        let config = &doc["devices"]["pcs"]["config"];
+        assert_eq!(config["ip"], "10.0.1.20");
+        assert_eq!(config["interval"], 500);
+        assert_eq!(config["register_file"], "sites/A7/pcs_500.xlsx");
+        assert_eq!(doc["variables"]["x"], "{{y}}");
+    }
+
+    #[test]
+    fn undefined_variable_is_an_error() {
+        let mut doc = json!({"ip": "{{gateway}}"});
+        assert!(matches!(
+            render(&mut doc, &vars()),
+            Err(ConfigurationError::MissingVariable(name)) if name == "gateway"
+        ));
+
+        let mut doc = json!({"ip": "{{prefix"});
+        assert!(matches!(
+            render(&mut doc, &vars()),
+            Err(ConfigurationError::InvalidPlaceholder(_))
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+//! 启动前的配置校验，一次性汇总所有设备的配置错误
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::config::modbus_conf::ByteOrder;
+use crate::config::{ComType, Configuration, Device, expand_register_file};
+use crate::dev::schedule::TimeWindow;
+
+/// 单条配置错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("[{device}] {message}")]
+pub struct ValidationError {
+    /// 设备在 `devices` 中的键
+    pub device: String,
+    pub message: String,
+}
+
+impl Configuration {
+    /// 校验所有设备配置，返回全部错误而不是遇到第一个就停止
+    ///
+    /// 检查项：设备ID缺失或重复、引用的模板不存在、各通信类型的必填字段、IP/端口、采集间隔与超时、点位表是否存在
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let mut keys: Vec<&String> = self.project.devices.keys().collect();
+        keys.sort();
+
+        let mut ids: HashMap<&str, &str> = HashMap::new();
+        for key in keys {
+            let dev = &self.project.devices[key];
+            let mut push = |message: String| {
+                errors.push(ValidationError {
+                    device: key.clone(),
+                    message,
+                })
+            };
+            match dev.id.as_deref() {
+                None => push("设备ID不能为空".to_owned()),
+                Some(id) => {
+                    if let Some(prev) = ids.insert(id, key) {
+                        push(format!("设备ID {id} 与 {prev} 重复"));
+                    }
+                }
+            }
+            // 禁用的设备允许配置不完整
+            if !dev.is_enabled() {
+                continue;
+            }
+            if let Some(name) = dev.template.as_deref()
+                && !self
+                    .project
+                    .templates
+                    .as_ref()
+                    .is_some_and(|templates| templates.contains_key(name))
+            {
+                push(format!("模板{name}未定义"));
+            }
+            for message in validate_device(dev) {
+                push(message);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn validate_device(dev: &Device) -> Vec<String> {
+    let config = &dev.config;
+    let Some(com_type) = config.com_type else {
+        return Vec::new();
+    };
+    let mut errors = Vec::new();
+    let mut required = |present: bool, field: &str| {
+        if !present {
+            errors.push(format!("{field}不能为空"));
+        }
+    };
+    match com_type {
+        ComType::ModbusTCP => {
+            required(config.slave.is_some(), "从站地址");
+            required(config.ip.is_some(), "IP");
+            required(config.port.is_some(), "端口");
+            required(config.interval.is_some(), "间隔时间");
+            required(config.timeout.is_some(), "超时时间");
+            required(config.register_file.is_some(), "点位表");
+        }
+        ComType::ModbusRTU => {
+            required(config.slave.is_some(), "从站地址");
+            required(config.serial_tty.is_some(), "串口设备");
+            required(config.baud_rate.is_some(), "波特率");
+            required(config.data_bits.is_some(), "数据位");
+            required(config.parity.is_some(), "校验位");
+            required(config.stop_bits.is_some(), "停止位");
+            required(config.interval.is_some(), "间隔时间");
+            required(config.timeout.is_some(), "超时时间");
+            required(config.register_file.is_some(), "点位表");
+        }
+        ComType::CAN => {
+            required(config.interface.is_some(), "CAN接口");
+            required(config.interval.is_some(), "间隔时间");
+            required(config.timeout.is_some(), "超时时间");
+            required(config.register_file.is_some(), "点位表");
+        }
+        ComType::GPIO => {
+            required(config.register_file.is_some(), "点位表");
+        }
+        ComType::IEC104 | ComType::IEC61850 => {}
+    }
+
+    if let Some(ip) = config.ip.as_deref()
+        && ip.parse::<IpAddr>().is_err()
+    {
+        errors.push(format!("无效的IP地址: {ip}"));
+    }
+    if let Some(order) = config.byte_order.as_deref()
+        && ByteOrder::try_from(Some(order)).is_err()
+    {
+        errors.push(format!("无效的字节序: {order}"));
+    }
+    for window in config
+        .poll_windows
+        .iter()
+        .chain(&config.blackout_windows)
+        .flatten()
+    {
+        if window.parse::<TimeWindow>().is_err() {
+            errors.push(format!("无效的时段: {window}"));
+        }
+    }
+    if config.port == Some(0) {
+        errors.push("端口不能为0".to_owned());
+    }
+    if config.interval == Some(0) {
+        errors.push("间隔时间必须大于0".to_owned());
+    }
+    if config.timeout == Some(0) {
+        errors.push("超时时间必须大于0".to_owned());
+    }
+    if let Some(file) = config.register_file.as_deref() {
+        let files = expand_register_file(file);
+        if files.is_empty() || !files.iter().all(|path| path.is_file()) {
+            errors.push(format!("点位表不存在: {file}"));
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{ConfigFormat, Configuration};
+
+    #[test]
+    fn validate_collects_errors_from_all_devices() {
+        let json = r#"{"devices": {
+            "a": {"id": "pcs", "config": {"com_type": "ModbusTCP", "slave": 1, "ip": "10.0.0.256",
+                  "port": 502, "interval": 0, "timeout": 1000, "register_file": "missing.xlsx"}},
+            "b": {"id": "pcs", "config": {"com_type": "ModbusRTU", "slave": 1}},
+            "c": {"config": {}}
+        }}"#;
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        let errors = conf.validate().unwrap_err();
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+
+        assert!(messages.contains(&"[a] 无效的IP地址: 10.0.0.256".to_owned()));
+        assert!(messages.contains(&"[a] 间隔时间必须大于0".to_owned()));
+        assert!(messages.contains(&"[a] 点位表不存在: missing.xlsx".to_owned()));
+        assert!(messages.contains(&"[b] 设备ID pcs 与 a 重复".to_owned()));
+        assert!(messages.contains(&"[b] 串口设备不能为空".to_owned()));
+        assert!(messages.contains(&"[c] 设备ID不能为空".to_owned()));
+    }
+
+    #[test]
+    fn validate_rejects_unknown_template() {
+        let json = r#"{"templates": {"bms": {}},
+            "devices": {"a": {"id": "a", "template": "pcs", "config": {}}}}"#;
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        let errors = conf.validate().unwrap_err();
+        assert_eq!(errors[0].to_string(), "[a] 模板pcs未定义");
+    }
+
+    #[test]
+    fn validate_accepts_device_without_com_type() {
+        let json = r#"{"devices": {"a": {"id": "a", "config": {}}}}"#;
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        assert!(conf.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_skips_disabled_device() {
+        let json = r#"{"devices": {"a": {"id": "a", "enabled": false,
+            "config": {"com_type": "ModbusRTU", "slave": 1}}}}"#;
+        let conf = Configuration::from_slice(json.as_bytes(), ConfigFormat::Json).unwrap();
+
+        assert!(!conf.project.devices["a"].is_enabled());
+        assert!(conf.validate().is_ok());
+    }
+}
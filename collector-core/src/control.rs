@@ -0,0 +1,221 @@
+//! 控制套接字
+//!
+//! 运行中的采集程序在 Unix 域套接字上接受本机的查询，`collector status` 经此列出设备状态，运维不必翻日志；
+//! `collector maintain` 经此对单个设备执行维护操作，`collector restart` 经此重启单个设备。
+//! 每个连接发送一行 JSON 请求，收到一行 JSON 应答后连接关闭。
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::dev::DeviceError;
+use crate::dev::maintenance::{Maintenance, MaintenanceOutput};
+use crate::dev::manager::{DevManager, DeviceStatus};
+
+/// 未配置 `control_socket` 时的套接字路径，相对于工作目录
+pub const DEFAULT_SOCKET: &str = "collector.sock";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ControlError {
+    #[error("连接控制套接字{0}失败, 采集程序是否在运行: {1}")]
+    Connect(String, io::Error),
+    #[error("控制套接字{0}已被另一个采集程序使用")]
+    InUse(String),
+    #[error("控制套接字读写失败: {0}")]
+    Io(#[from] io::Error),
+    #[error("无效的控制消息: {0}")]
+    Message(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /// 所有设备的运行状态
+    Status,
+    /// 对设备执行维护操作，见 [`DevManager::maintain`]
+    Maintain { id: String, op: Maintenance },
+    /// 重启设备，见 [`DevManager::restart_device`]
+    Restart { id: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+    Status(Vec<DeviceStatus>),
+    Maintenance(MaintenanceOutput),
+    /// 请求已完成，无返回内容
+    Done,
+    Error(String),
+}
+
+/// 控制套接字服务，退出时删除套接字文件
+pub struct ControlServer {
+    path: PathBuf,
+    listener: UnixListener,
+    manager: Arc<Mutex<DevManager>>,
+}
+
+impl ControlServer {
+    /// 在 `path` 上监听；套接字文件已存在但无人监听时视为上次异常退出的残留，删除后重新监听
+    pub async fn bind(
+        path: impl Into<PathBuf>,
+        manager: Arc<Mutex<DevManager>>,
+    ) -> Result<Self, ControlError> {
+        let path = path.into();
+        if path.exists() {
+            if UnixStream::connect(&path).await.is_ok() {
+                return Err(ControlError::InUse(path.display().to_string()));
+            }
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        Ok(Self {
+            path,
+            listener,
+            manager,
+        })
+    }
+
+    pub async fn run(self, token: CancellationToken) {
+        info!("控制套接字监听 {}", self.path.display());
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let manager = self.manager.clone();
+                        tokio::spawn(async move {
+                            if let Err(err) = handle(stream, manager).await {
+                                warn!("控制连接处理失败: {}", err);
+                            }
+                        });
+                    }
+                    Err(err) => warn!("控制套接字接受连接失败: {}", err),
+                },
+            }
+        }
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            warn!("删除控制套接字{}失败: {}", self.path.display(), err);
+        }
+    }
+}
+
+async fn handle(stream: UnixStream, manager: Arc<Mutex<DevManager>>) -> Result<(), ControlError> {
+    let (read, mut write) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(read).read_line(&mut line).await?;
+    let reply = match serde_json::from_str::<Request>(&line) {
+        Ok(Request::Status) => Reply::Status(manager.lock().await.status().await),
+        Ok(Request::Maintain { id, op }) => {
+            // 只在取得句柄时持有设备管理器的锁，等待设备应答期间不阻塞其他请求
+            let handle = manager.lock().await.get(&id);
+            let result = match handle {
+                Some(handle) => handle.maintain(op).await,
+                None => Err(DeviceError::NotFound(id)),
+            };
+            match result {
+                Ok(output) => Reply::Maintenance(output),
+                Err(err) => Reply::Error(err.to_string()),
+            }
+        }
+        Ok(Request::Restart { id }) => match manager.lock().await.restart_device(&id).await {
+            Ok(()) => Reply::Done,
+            Err(err) => Reply::Error(err.to_string()),
+        },
+        Err(err) => Reply::Error(format!("无效的请求: {err}")),
+    };
+    let mut text = serde_json::to_string(&reply)?;
+    text.push('\n');
+    write.write_all(text.as_bytes()).await?;
+    Ok(())
+}
+
+/// 向运行中的采集程序发送请求并等待应答
+pub async fn request(path: &Path, request: &Request) -> Result<Reply, ControlError> {
+    let stream = UnixStream::connect(path)
+        .await
+        .map_err(|err| ControlError::Connect(path.display().to_string(), err))?;
+    let (read, mut write) = stream.into_split();
+    let mut text = serde_json::to_string(request)?;
+    text.push('\n');
+    write.write_all(text.as_bytes()).await?;
+    let mut line = String::new();
+    BufReader::new(read).read_line(&mut line).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+    use tokio_util::sync::CancellationToken;
+
+    use super::{ControlError, ControlServer, Reply, Request, request};
+    use crate::center::DataCenter;
+    use crate::dev::can_bus::SharedCanBus;
+    use crate::dev::maintenance::Maintenance;
+    use crate::dev::manager::DevManager;
+
+    #[tokio::test]
+    async fn status_is_served_over_the_socket() {
+        let path =
+            std::env::temp_dir().join(format!("collector-control-{}.sock", std::process::id()));
+        let manager = DevManager::new(
+            HashMap::new(),
+            Arc::new(DataCenter::new(1)),
+            SharedCanBus::default(),
+        );
+        let manager = Arc::new(Mutex::new(manager));
+        let server = ControlServer::bind(&path, manager.clone()).await.unwrap();
+        assert!(matches!(
+            ControlServer::bind(&path, manager.clone()).await,
+            Err(ControlError::InUse(_))
+        ));
+        let token = CancellationToken::new();
+        let task = tokio::spawn(server.run(token.clone()));
+
+        let reply = request(&path, &Request::Status).await.unwrap();
+        assert_eq!(reply, Reply::Status(Vec::new()));
+        let maintain = Request::Maintain {
+            id: "pcs".into(),
+            op: Maintenance::PollNow,
+        };
+        assert!(matches!(
+            request(&path, &maintain).await.unwrap(),
+            Reply::Error(err) if err.contains("pcs")
+        ));
+        assert!(matches!(
+            request(&path, &Request::Restart { id: "pcs".into() }).await.unwrap(),
+            Reply::Error(err) if err.contains("pcs")
+        ));
+        let dump: Request = serde_json::from_str(
+            r#"{"command": "maintain", "id": "pcs", "op": {"dump_registers":
+                {"register_type": "HoldingRegisters", "address": 0, "count": 4}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            dump,
+            Request::Maintain {
+                op: Maintenance::DumpRegisters { count: 4, .. },
+                ..
+            }
+        ));
+
+        token.cancel();
+        task.await.unwrap();
+        assert!(!path.exists());
+        assert!(matches!(
+            request(&path, &Request::Status).await,
+            Err(ControlError::Connect(..))
+        ));
+    }
+}
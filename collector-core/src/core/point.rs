@@ -95,6 +95,8 @@ pub enum Val {
     U32(u32),
     F64(f64),
     List(Vec<Val>),
+    /// 字符串，如寄存器中的序列号、固件版本
+    Str(String),
 }
 
 impl Val {
@@ -107,7 +109,7 @@ impl Val {
             Val::U16(v) => Ok(*v != 0),
             Val::U32(v) => Ok(*v != 0),
             Val::F64(v) => Ok(v.abs() > f64::EPSILON),
-            Val::List(_) => Err(ValError::InvalidValue),
+            Val::List(_) | Val::Str(_) => Err(ValError::InvalidValue),
         }
     }
 
@@ -120,7 +122,7 @@ impl Val {
             Val::U16(v) => Ok(*v as f64),
             Val::U32(v) => Ok(*v as f64),
             Val::F64(v) => Ok(*v),
-            Val::List(_) => Err(ValError::InvalidValue),
+            Val::List(_) | Val::Str(_) => Err(ValError::InvalidValue),
         }
     }
 
@@ -133,7 +135,7 @@ impl Val {
             Val::U16(v) => Ok(*v as u32),
             Val::U32(v) => Ok(*v),
             Val::F64(v) => Ok(*v as u32),
-            Val::List(_) => Err(ValError::InvalidValue),
+            Val::List(_) | Val::Str(_) => Err(ValError::InvalidValue),
         }
     }
 }
@@ -199,6 +201,7 @@ impl Serialize for Val {
             Val::U16(v) => serializer.serialize_u16(*v),
             Val::U32(v) => serializer.serialize_u32(*v),
             Val::F64(v) => serializer.serialize_f64(*v),
+            Val::Str(v) => serializer.serialize_str(v),
             Val::List(items) => {
                 let mut seq = serializer.serialize_seq(Some(items.len()))?;
                 for item in items {
@@ -221,7 +224,7 @@ impl<'de> Deserialize<'de> for Val {
             type Value = Val;
 
             fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-                f.write_str("a number or array of numbers")
+                f.write_str("a number, string or array of numbers")
             }
 
             fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Val, E> {
@@ -255,6 +258,10 @@ impl<'de> Deserialize<'de> for Val {
                 Ok(Val::F64(v))
             }
 
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Val, E> {
+                Ok(Val::Str(v.to_owned()))
+            }
+
             fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Val, A::Error> {
                 let mut items = Vec::new();
                 while let Some(item) = seq.next_element::<Val>()? {
@@ -278,6 +285,7 @@ impl Display for Val {
             Val::U16(v) => write!(f, "{}", *v),
             Val::U32(v) => write!(f, "{}", *v),
             Val::F64(v) => write!(f, "{}", *v),
+            Val::Str(v) => f.write_str(v),
             Val::List(vals) => {
                 write!(f, "[")?;
                 for (i, val) in vals.iter().enumerate() {
@@ -292,6 +300,26 @@ impl Display for Val {
     }
 }
 
+/// 数据点的质量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+    /// 最近一次读取成功
+    #[default]
+    Good,
+    /// 设备未能提供该点位的值，缓存中保留的是之前的值
+    Bad,
+    /// 设备通讯中断，缓存中是之前的值或配置的替代值
+    #[serde(rename = "commFail")]
+    CommFail,
+}
+
+impl Quality {
+    pub fn is_good(&self) -> bool {
+        *self == Quality::Good
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DataPoint {
     pub id: PointId,
@@ -302,6 +330,7 @@ pub struct DataPoint {
     pub bits: Option<&'static Bits>,
     pub words: Option<&'static Words>,
     pub unit: Option<&'static str>,
+    pub quality: Quality,
 }
 
 impl DataPoint {
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+pub(crate) struct Backoff {
+    current: Duration,
+    base: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            current: base,
+            base,
+            max,
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.current = self.base;
+    }
+
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+}
+
+/// 0 到 `max` 之间（不含 `max`）的随机等待，精确到毫秒
+pub(crate) fn jitter(max: Duration) -> Duration {
+    let max = u64::try_from(max.as_millis()).unwrap_or(u64::MAX);
+    if max == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(getrandom::u64().unwrap_or(0) % max)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::jitter;
+
+    #[test]
+    fn jitter_stays_below_the_limit() {
+        assert_eq!(jitter(Duration::ZERO), Duration::ZERO);
+        let max = Duration::from_millis(50);
+        assert!((0..100).all(|_| jitter(max) < max));
+    }
+}
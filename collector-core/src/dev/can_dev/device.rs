@@ -2,16 +2,14 @@ use std::time::Duration;
 
 use tokio::sync::{Mutex, watch};
 use tokio::task::JoinHandle;
-use tokio::time;
 use tracing::{info, warn};
 
-use crate::center::SharedPointCenter;
+use crate::center::{DownlinkCommand, SharedPointCenter};
 use crate::{
     center::DataCenterError,
     config::{self, Device, can_conf::CanConfigs},
-    core::point::DownDataPoint,
     dev::{
-        DeviceError, Executable, Identifiable, Lifecycle, LifecycleState,
+        self, DeviceError, DeviceExit, Executable, Identifiable, Lifecycle, LifecycleState,
         dev_config::CanDeviceConfig, state::SharedState,
     },
 };
@@ -29,6 +27,8 @@ pub struct CanDev {
     task: Mutex<Option<JoinHandle<()>>>,
     center: SharedPointCenter,
     can_bus: SharedCanBus,
+    /// 停止时等待后台任务结束的时间
+    stop_timeout: Duration,
 }
 
 impl CanDev {
@@ -52,6 +52,7 @@ impl CanDev {
                 return Err(DeviceError::NotFoundConfigs(id));
             }
         };
+        let stop_timeout = dev::stop_timeout(dev.config.stop_timeout);
         let config = CanDeviceConfig::try_from(dev.config)?;
         let state = SharedState::new(LifecycleState::New);
         let (stop_tx, stop_rx) = watch::channel(false);
@@ -66,6 +67,7 @@ impl CanDev {
             task: Mutex::new(None),
             center,
             can_bus,
+            stop_timeout,
         })
     }
 
@@ -74,7 +76,7 @@ impl CanDev {
     }
 
     fn cas_state(&self, from: LifecycleState, to: LifecycleState) -> bool {
-        self.state.cas(from, to)
+        self.state.cas(&self.id, from, to)
     }
 
     fn store_state(&self, to: LifecycleState) {
@@ -100,12 +102,15 @@ impl Lifecycle for CanDev {
 
     async fn start(&mut self) -> Result<(), DeviceError> {
         let ok = self.cas_state(LifecycleState::Ready, LifecycleState::Starting)
-            || self.cas_state(LifecycleState::Stopped, LifecycleState::Starting);
+            || self.cas_state(LifecycleState::Stopped, LifecycleState::Starting)
+            // 意外结束的任务由监督重新启动
+            || (self.exit().is_some()
+                && self.cas_state(LifecycleState::Failed, LifecycleState::Starting));
         if !ok {
             return Ok(());
         }
 
-        let (tx, rx) = tokio::sync::mpsc::channel::<Vec<DownDataPoint>>(16);
+        let (tx, rx) = tokio::sync::mpsc::channel::<DownlinkCommand>(16);
         match self.center.attach_downlink(&self.id, tx.clone()) {
             Ok(()) => {}
             Err(DataCenterError::DevHasRegister(_)) => {
@@ -140,9 +145,11 @@ impl Lifecycle for CanDev {
             raw_rx,
             center: self.center.clone(),
         };
-        let handle = tokio::spawn(async move {
-            runner.run().await;
-        });
+        let handle = tokio::spawn(dev::catch_panic(
+            self.id.clone(),
+            self.state.clone(),
+            runner.run(),
+        ));
         *task_guard = Some(handle);
         Ok(())
     }
@@ -166,13 +173,12 @@ impl Lifecycle for CanDev {
 
         self.center.detach_downlink(&self.id);
         let mut task_guard = self.task.lock().await;
-        if let Some(mut handle) = task_guard.take() {
-            tokio::select! {
-                _ = time::sleep(Duration::from_secs(3)) => {
-                    handle.abort();
-                }
-                _ = &mut handle => {}
-            }
+        if let Some(mut handle) = task_guard.take()
+            && dev::join_or_abort(&mut handle, self.stop_timeout).await
+        {
+            // 中止的任务来不及更新状态，否则无法再次启动
+            self.store_state(LifecycleState::Stopped);
+            return Err(DeviceError::ForceAborted(self.id.clone()));
         }
         Ok(())
     }
@@ -180,6 +186,22 @@ impl Lifecycle for CanDev {
     fn state(&self) -> LifecycleState {
         self.load_state()
     }
+
+    fn exit(&self) -> Option<DeviceExit> {
+        if *self.stop_rx.borrow() {
+            return None;
+        }
+        let task = self.task.try_lock().ok()?;
+        if !task.as_ref()?.is_finished() {
+            return None;
+        }
+        if self.load_state() == LifecycleState::Stopped {
+            return Some(DeviceExit::Completed);
+        }
+        // panic 的任务已由 catch_panic 标记为失败，其余未停止就结束的任务同样标记为失败
+        self.store_state(LifecycleState::Failed);
+        Some(DeviceExit::Failed)
+    }
 }
 
 impl Executable for CanDev {}
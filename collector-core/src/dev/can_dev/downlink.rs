@@ -12,6 +12,8 @@ use crate::dev::can_dev::CanDevError;
 
 pub(super) struct WritePlan {
     frames: Vec<CanFrame>,
+    /// 构建时被忽略的点位及原因，原子下发时据此整批拒绝
+    rejected: Vec<String>,
 }
 
 impl WritePlan {
@@ -25,6 +27,7 @@ impl WritePlan {
     ) -> Self {
         let mut payloads: BTreeMap<u32, FramePayload> = BTreeMap::new();
         let mut initialized_bindings: HashSet<u32> = HashSet::new();
+        let mut rejected = Vec::new();
 
         // 先预加载所有涉及帧的当前值
         for entry in &entries {
@@ -33,6 +36,7 @@ impl WritePlan {
                     "[{}] 未找到点位配置, 忽略CAN下发: {:?}",
                     dev_id, entry.point
                 );
+                rejected.push(format!("未找到点位配置: {:?}", entry.point));
                 continue;
             };
             let Some(binding) = point_map
@@ -40,6 +44,7 @@ impl WritePlan {
                 .and_then(|cfg| frame_map.get(&cfg.binding_frame_id))
             else {
                 warn!("[{}] 未找到报文配置, 忽略CAN下发: {}", dev_id, id);
+                rejected.push(format!("未找到报文配置: {}", id));
                 continue;
             };
             if initialized_bindings.insert(binding.frame.frame_id) {
@@ -54,7 +59,9 @@ impl WritePlan {
             let Some(point_cfg) = point_map.get(&id) else {
                 continue;
             };
-            encode_entry(&mut payloads, point_cfg, &entry.value, dev_id);
+            if !encode_entry(&mut payloads, point_cfg, &entry.value, dev_id) {
+                rejected.push(format!("点位值无法编码: {}", id));
+            }
         }
 
         WritePlan {
@@ -62,9 +69,15 @@ impl WritePlan {
                 .into_values()
                 .filter_map(|payload| payload.build_frame(dev_id))
                 .collect(),
+            rejected,
         }
     }
 
+    /// 构建时被忽略的点位及原因
+    pub(super) fn rejected(&self) -> &[String] {
+        &self.rejected
+    }
+
     pub(super) async fn apply(
         &self,
         socket: &socketcan::tokio::CanSocket,
@@ -81,6 +94,11 @@ impl WritePlan {
     pub(super) fn is_empty(&self) -> bool {
         self.frames.is_empty()
     }
+
+    /// 下发的报文数，每帧单独发送
+    pub(super) fn frames(&self) -> usize {
+        self.frames.len()
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -214,7 +232,7 @@ fn encode_entry(
     point_cfg: &CanPointConfig,
     value: &Val,
     dev_id: &str,
-) {
+) -> bool {
     match point_cfg.signal {
         CanSignal::Normal(signal) => {
             let Some(raw) = encode_value(
@@ -226,7 +244,7 @@ fn encode_entry(
                 signal.name,
                 dev_id,
             ) else {
-                return;
+                return false;
             };
             let payload = payloads
                 .entry(point_cfg.frame.frame_id)
@@ -238,12 +256,14 @@ fn encode_entry(
                 signal.byte_order,
                 raw,
             );
+            true
         }
         CanSignal::Ext(signal) => {
             warn!(
                 "[{}] 扩展信号暂不支持点位下发, 忽略: {}",
                 dev_id, signal.name
             );
+            false
         }
     }
 }
@@ -4,15 +4,15 @@ use std::time::{Duration, Instant};
 
 use futures::StreamExt;
 use socketcan::{CanFrame, EmbeddedFrame, ExtendedId, Frame, Id, StandardId, tokio::CanSocket};
-use tokio::sync::{mpsc, watch};
+use tokio::sync::watch;
 use tokio::time;
 use tracing::{debug, info, warn};
 
-use crate::center::SharedPointCenter;
+use crate::center::{self, DataCenterError, DownlinkReceiver, SharedPointCenter};
 use crate::config::can_conf::{
     ByteOrder, CanConfig, CanDataType, CanSignal, CanSignalConfig, CanSignalExtConfig, IdType,
 };
-use crate::core::point::{DataPoint, DownDataPoint, PointId, PointRef, Val};
+use crate::core::point::{DataPoint, PointId, PointRef, Quality, Val};
 use crate::dev::can_dev::CanDevError;
 use crate::dev::{LifecycleState, dev_config::CanDeviceConfig, state::SharedState};
 
@@ -26,7 +26,7 @@ pub(super) struct CanRunner {
     pub(super) configs: Vec<CanConfig>,
     pub(super) state: SharedState,
     pub(super) stop_rx: watch::Receiver<bool>,
-    pub(super) rx: mpsc::Receiver<Vec<DownDataPoint>>,
+    pub(super) rx: DownlinkReceiver,
     pub(super) raw_rx: RawFrameRx,
     pub(super) center: SharedPointCenter,
 }
@@ -55,6 +55,7 @@ impl CanRunner {
                 bits: None,
                 words: None,
                 unit: None,
+                quality: Quality::Good,
             }],
         );
     }
@@ -218,19 +219,45 @@ impl CanRunner {
                     }
                 }
                 msg = self.rx.recv() => {
-                    let Some(entries) = msg else {
+                    let Some(mut cmd) = msg else {
                         self.state.store(&self.id, LifecycleState::Stopped);
                         self.set_comm_fault(true);
                         return Ok(());
                     };
+                    if let Some(read) = cmd.read.take() {
+                        read.reply(Err(DataCenterError::ReadFailed("CAN设备不支持按需读取".into())));
+                        continue;
+                    }
+                    let atomic = cmd.atomic;
+                    let (entries, ack) = cmd.into_parts();
                     let items: Vec<String> = entries.iter().map(|e| format!("{}: {}", resolve_signal_name(&e.point, point_map), e.value)).collect();
                     info!("[{}] ↓: {}", self.id, items.join(", "));
                     let plan =
                         WritePlan::build(entries, point_map, name_map, frame_map, self.center.as_ref(), &self.id);
+                    // 原子下发：任一信号无法编码或需要多帧报文则整批拒绝，不发送任何报文
+                    if atomic {
+                        let reason = if !plan.rejected().is_empty() {
+                            Some(plan.rejected().join("; "))
+                        } else if plan.frames() > 1 {
+                            Some(format!("原子下发须在一帧报文内完成, 当前需{}帧", plan.frames()))
+                        } else {
+                            None
+                        };
+                        if let Some(reason) = reason {
+                            warn!("[{}] 原子下发被拒绝: {}", self.id, reason);
+                            center::reply(ack, Err(DataCenterError::Rejected(reason)));
+                            continue;
+                        }
+                    }
                     if plan.is_empty() {
+                        center::reply(ack, Ok(()));
                         continue;
                     }
-                    plan.apply(socket).await?;
+                    if let Err(err) = plan.apply(socket).await {
+                        center::reply(ack, Err(DataCenterError::WriteFailed(err.to_string())));
+                        return Err(err);
+                    }
+                    center::reply(ack, Ok(()));
                 }
                 result = socket.next() => {
                     let frame = result
@@ -320,13 +347,18 @@ impl CanRunner {
                         )
                         .await
                     {
-                        self.state.store(&self.id, LifecycleState::Failed);
+                        self.state.store_with_reason(
+                            &self.id,
+                            LifecycleState::Failed,
+                            err.to_string(),
+                        );
                         warn!("[{}] CAN连接中断，准备重连: {}", self.id, err);
                         self.set_comm_fault(true);
                     }
                 }
                 Err(err) => {
-                    self.state.store(&self.id, LifecycleState::Failed);
+                    self.state
+                        .store_with_reason(&self.id, LifecycleState::Failed, err.to_string());
                     warn!("[{}] 打开CAN接口失败，准备重连: {}", self.id, err);
                     self.set_comm_fault(true);
                 }
@@ -398,6 +430,7 @@ fn decode_signal(cfg: &CanSignalConfig, data: &[u8]) -> Option<DataPoint> {
         bits: cfg.enum_bits,
         words: cfg.enum_values,
         unit: cfg.unit,
+        quality: Quality::Good,
     })
 }
 
@@ -451,6 +484,7 @@ fn decode_ext_signal(
         bits: None,
         words: None,
         unit: None,
+        quality: Quality::Good,
     })
 }
 
@@ -0,0 +1,54 @@
+//! 同时连接数限制
+//!
+//! 限制同时尝试建立连接的设备数量。局域网或网关中断时大量设备同时连接、同时超时，
+//! 资源有限的网关上会拖慢整个运行时；限制后其余设备排队等待连接许可。
+
+use std::sync::{Arc, RwLock};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 设备共享的连接许可，缺省不限制
+#[derive(Debug, Clone, Default)]
+pub struct ConnectLimit(Arc<RwLock<Option<Arc<Semaphore>>>>);
+
+impl ConnectLimit {
+    /// 设置同时连接的最大设备数，`None` 为不限制；正在等待的设备仍按原来的限制获取许可
+    pub fn set(&self, max: Option<usize>) {
+        *self.0.write().expect("connect limit lock poisoned") =
+            max.map(|max| Arc::new(Semaphore::new(max.max(1))));
+    }
+
+    /// 等待连接许可，连接结束后释放；不限制时立即返回 `None`
+    pub(crate) async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self
+            .0
+            .read()
+            .expect("connect limit lock poisoned")
+            .clone()?;
+        semaphore.acquire_owned().await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time;
+
+    use super::ConnectLimit;
+
+    #[tokio::test]
+    async fn connections_wait_for_a_permit() {
+        let limit = ConnectLimit::default();
+        assert!(limit.acquire().await.is_none());
+
+        limit.set(Some(1));
+        let first = limit.acquire().await;
+        assert!(first.is_some());
+        let waiting = time::timeout(Duration::from_millis(20), limit.acquire()).await;
+        assert!(waiting.is_err());
+
+        drop(first);
+        assert!(limit.acquire().await.is_some());
+    }
+}
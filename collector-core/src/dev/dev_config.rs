@@ -1,7 +1,38 @@
 use std::net::IpAddr;
 use std::time::Duration;
 
-use crate::config::DeviceConfig;
+use crate::config::{DeviceConfig, ExceptionPolicy, ScanIntervals, TlsOptions};
+use crate::dev::schedule::{InvalidWindow, Schedule};
+
+/// Modbus 单次请求的最大长度
+///
+/// 缺省与协议上限接近（寄存器 120、线圈/离散输入 2000），部分网关需要调小；超出协议上限的配置按上限处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLimits {
+    /// 单次读取的最大寄存器数，1~125
+    pub registers: u16,
+    /// 单次读取的最大线圈/离散输入数，1~2000
+    pub bits: u16,
+}
+
+impl Default for FrameLimits {
+    fn default() -> Self {
+        Self {
+            registers: 120,
+            bits: 2000,
+        }
+    }
+}
+
+impl FrameLimits {
+    fn new(registers: Option<u16>, bits: Option<u16>) -> Self {
+        let defaults = Self::default();
+        Self {
+            registers: registers.map_or(defaults.registers, |n| n.clamp(1, 125)),
+            bits: bits.map_or(defaults.bits, |n| n.clamp(1, 2000)),
+        }
+    }
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum ModbusTcpConfError {
@@ -9,6 +40,8 @@ pub enum ModbusTcpConfError {
     ValueNotNone(String),
     #[error("无效的IP:{0}地址")]
     InvalidIp(String),
+    #[error(transparent)]
+    InvalidWindow(#[from] InvalidWindow),
 }
 
 #[derive(Clone)]
@@ -16,11 +49,42 @@ pub struct ModbusTcpConfig {
     pub slave: u8,
     pub ip: String,
     pub port: u16,
+    /// 备用链路的 IP 与端口
+    pub backup: Option<(String, u16)>,
     #[allow(dead_code)]
     pub interval: u64,
     pub timeout: u64,
     pub request_interval: u64,
+    /// 相邻两次请求之间的最小间隔
+    pub min_request_delay: Duration,
+    /// 第一次轮询前随机等待的上限
+    pub start_jitter: Duration,
     pub max_gap: u16,
+    pub scan_intervals: ScanIntervals,
+    pub limits: FrameLimits,
+    /// 单次读取失败后的重试次数
+    pub retries: u32,
+    /// 从站返回异常码时的处理方式
+    pub exception_policy: ExceptionPolicy,
+    /// 块读取失败时继续读取其余块，连续该圈数全部失败才重连
+    pub max_failed_cycles: Option<u32>,
+    /// 通讯中断时点位的替代值
+    pub substitute: Option<f64>,
+    /// 输出块读取的原始数据
+    pub debug_dump: bool,
+    /// 下发后回读校验
+    pub verify_writes: bool,
+    /// 选择-执行控制等待选择确认的超时(ms)
+    pub select_timeout: u64,
+    /// 与同一地址的其他设备共用 TCP 连接
+    pub shared_connection: bool,
+    pub tls: Option<TlsOptions>,
+    /// TCP 保活探测的空闲时间与间隔
+    pub keepalive: Option<Duration>,
+    /// 连接后读取设备标识
+    pub identify: bool,
+    /// 轮询时段
+    pub schedule: Schedule,
 }
 
 impl TryFrom<DeviceConfig> for ModbusTcpConfig {
@@ -45,30 +109,139 @@ impl TryFrom<DeviceConfig> for ModbusTcpConfig {
         if ip.parse::<IpAddr>().is_err() {
             return Err(ModbusTcpConfError::InvalidIp(ip));
         }
+        let backup = match value.backup_ip {
+            Some(backup_ip) if backup_ip.parse::<IpAddr>().is_err() => {
+                return Err(ModbusTcpConfError::InvalidIp(backup_ip));
+            }
+            Some(backup_ip) => Some((backup_ip, value.backup_port.unwrap_or(port))),
+            None => None,
+        };
         let request_interval = value.request_interval.unwrap_or(0);
+        let min_request_delay = Duration::from_millis(value.min_request_delay.unwrap_or(0));
+        let start_jitter = Duration::from_millis(value.start_jitter.unwrap_or(0));
         let max_gap = value.max_gap.unwrap_or(0);
+        let scan_intervals = value.scan_intervals.unwrap_or_default();
+        let limits = FrameLimits::new(value.max_read_registers, value.max_read_bits);
+        let retries = value.request_retries.unwrap_or(0);
+        let exception_policy = value.exception_policy.unwrap_or_default();
+        let max_failed_cycles = value.max_failed_cycles.map(|cycles| cycles.max(1));
+        let substitute = value.substitute_value;
+        let debug_dump = value.debug_dump.unwrap_or(false);
+        let verify_writes = value.verify_writes.unwrap_or(false);
+        let select_timeout = value.select_timeout.unwrap_or(3000);
+        let shared_connection = value.shared_connection.unwrap_or(false);
+        let tls = value.tls;
+        let keepalive = value
+            .tcp_keepalive
+            .map(|secs| Duration::from_secs(secs.max(1)));
+        let identify = value.device_identification.unwrap_or(false);
+        let schedule = schedule(value.poll_windows, value.blackout_windows)?;
         Ok(ModbusTcpConfig {
             slave,
             ip,
             port,
+            backup,
             interval,
             timeout,
             request_interval,
+            min_request_delay,
+            start_jitter,
             max_gap,
+            scan_intervals,
+            limits,
+            retries,
+            exception_policy,
+            max_failed_cycles,
+            substitute,
+            debug_dump,
+            verify_writes,
+            select_timeout,
+            shared_connection,
+            tls,
+            keepalive,
+            identify,
+            schedule,
         })
     }
 }
 
+fn schedule(
+    windows: Option<Vec<String>>,
+    blackouts: Option<Vec<String>>,
+) -> Result<Schedule, InvalidWindow> {
+    Schedule::new(
+        windows.as_deref().unwrap_or_default(),
+        blackouts.as_deref().unwrap_or_default(),
+    )
+}
+
+/// 波特率对应的 3.5 个字符时间，一个字符按 11 位计；波特率高于 19200 时取协议规定的 1.75ms
+fn t35(baudrate: u32) -> Duration {
+    if baudrate > 19200 {
+        Duration::from_micros(1750)
+    } else {
+        Duration::from_micros(38_500_000_u64.div_ceil(u64::from(baudrate.max(1))))
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ModbusRtuConfError {
     #[error("{0}不能为空")]
     ValueNotNone(String),
+    #[error("无效的RS-485方向控制:{0}")]
+    InvalidDirection(String),
+    #[error("Modbus RTU 不支持读取设备标识")]
+    UnsupportedIdentification,
+    #[error("广播地址(从站 0)没有响应，不能回读校验下发")]
+    BroadcastVerify,
+    #[error(transparent)]
+    InvalidWindow(#[from] InvalidWindow),
+}
+
+/// RS-485 收发方向控制：发送期间置位 DE/RE 的控制线
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectionControl {
+    /// 串口的 RTS 线
+    Rts,
+    /// GPIO 线，`chip` 如 `/dev/gpiochip0`
+    Gpio { chip: String, line: u32 },
+}
+
+impl TryFrom<&str> for DirectionControl {
+    type Error = ModbusRtuConfError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.eq_ignore_ascii_case("rts") {
+            return Ok(DirectionControl::Rts);
+        }
+        value
+            .rsplit_once(':')
+            .and_then(|(chip, line)| {
+                let line = line.trim().parse().ok()?;
+                (!chip.trim().is_empty()).then(|| DirectionControl::Gpio {
+                    chip: chip.trim().to_owned(),
+                    line,
+                })
+            })
+            .ok_or_else(|| ModbusRtuConfError::InvalidDirection(value.to_owned()))
+    }
+}
+
+impl std::fmt::Display for DirectionControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DirectionControl::Rts => f.write_str("RTS"),
+            DirectionControl::Gpio { chip, line } => write!(f, "{chip}:{line}"),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct ModbusRtuConfig {
     pub slave: u8,
     pub serial_tty: String,
+    /// 备用链路的串口设备
+    pub backup_tty: Option<String>,
     pub baudrate: u32,
     pub data_bits: u8,
     pub parity: String,
@@ -77,7 +250,33 @@ pub struct ModbusRtuConfig {
     pub interval: u64,
     pub timeout: u64,
     pub request_interval: u64,
+    /// 相邻两次请求之间的最小间隔
+    pub min_request_delay: Duration,
+    /// 第一次轮询前随机等待的上限
+    pub start_jitter: Duration,
     pub max_gap: u16,
+    pub scan_intervals: ScanIntervals,
+    pub limits: FrameLimits,
+    /// 单次读取失败后的重试次数
+    pub retries: u32,
+    /// 从站返回异常码时的处理方式
+    pub exception_policy: ExceptionPolicy,
+    /// 块读取失败时继续读取其余块，连续该圈数全部失败才重连
+    pub max_failed_cycles: Option<u32>,
+    /// 通讯中断时点位的替代值
+    pub substitute: Option<f64>,
+    /// 输出块读取的原始数据
+    pub debug_dump: bool,
+    /// 下发后回读校验
+    pub verify_writes: bool,
+    /// 选择-执行控制等待选择确认的超时(ms)
+    pub select_timeout: u64,
+    /// 总线上前一帧结束到本设备下一帧开始的最小间隔
+    pub inter_frame_delay: Duration,
+    /// RS-485 方向控制，缺省由硬件自动切换
+    pub direction: Option<DirectionControl>,
+    /// 轮询时段
+    pub schedule: Schedule,
 }
 
 impl TryFrom<DeviceConfig> for ModbusRtuConfig {
@@ -108,11 +307,40 @@ impl TryFrom<DeviceConfig> for ModbusRtuConfig {
         let Some(timeout) = value.timeout else {
             return Err(ModbusRtuConfError::ValueNotNone(String::from("超时时间")));
         };
+        // RTU 编解码器无法确定 0x2B 响应的帧长
+        if value.device_identification == Some(true) {
+            return Err(ModbusRtuConfError::UnsupportedIdentification);
+        }
+        let backup_tty = value.backup_serial_tty;
         let request_interval = value.request_interval.unwrap_or(0);
+        let min_request_delay = Duration::from_millis(value.min_request_delay.unwrap_or(0));
+        let start_jitter = Duration::from_millis(value.start_jitter.unwrap_or(0));
         let max_gap = value.max_gap.unwrap_or(0);
+        let scan_intervals = value.scan_intervals.unwrap_or_default();
+        let limits = FrameLimits::new(value.max_read_registers, value.max_read_bits);
+        let retries = value.request_retries.unwrap_or(0);
+        let exception_policy = value.exception_policy.unwrap_or_default();
+        let max_failed_cycles = value.max_failed_cycles.map(|cycles| cycles.max(1));
+        let substitute = value.substitute_value;
+        let debug_dump = value.debug_dump.unwrap_or(false);
+        let verify_writes = value.verify_writes.unwrap_or(false);
+        if slave == 0 && verify_writes {
+            return Err(ModbusRtuConfError::BroadcastVerify);
+        }
+        let select_timeout = value.select_timeout.unwrap_or(3000);
+        let inter_frame_delay = value
+            .inter_frame_delay
+            .map_or_else(|| t35(baudrate), Duration::from_millis);
+        let direction = value
+            .rs485_direction
+            .as_deref()
+            .map(DirectionControl::try_from)
+            .transpose()?;
+        let schedule = schedule(value.poll_windows, value.blackout_windows)?;
         Ok(ModbusRtuConfig {
             slave,
             serial_tty,
+            backup_tty,
             baudrate,
             data_bits,
             parity,
@@ -120,7 +348,21 @@ impl TryFrom<DeviceConfig> for ModbusRtuConfig {
             interval,
             timeout,
             request_interval,
+            min_request_delay,
+            start_jitter,
             max_gap,
+            scan_intervals,
+            limits,
+            retries,
+            exception_policy,
+            max_failed_cycles,
+            substitute,
+            debug_dump,
+            verify_writes,
+            select_timeout,
+            inter_frame_delay,
+            direction,
+            schedule,
         })
     }
 }
@@ -160,3 +402,34 @@ impl TryFrom<DeviceConfig> for CanDeviceConfig {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{DirectionControl, t35};
+
+    #[test]
+    fn t35_follows_baud_rate() {
+        assert_eq!(t35(1200), Duration::from_micros(32_084));
+        assert_eq!(t35(9600), Duration::from_micros(4_011));
+        assert_eq!(t35(115_200), Duration::from_micros(1_750));
+    }
+
+    #[test]
+    fn direction_control_is_parsed() {
+        assert_eq!(
+            DirectionControl::try_from("rts").unwrap(),
+            DirectionControl::Rts
+        );
+        assert_eq!(
+            DirectionControl::try_from("/dev/gpiochip0:17").unwrap(),
+            DirectionControl::Gpio {
+                chip: "/dev/gpiochip0".into(),
+                line: 17
+            }
+        );
+        assert!(DirectionControl::try_from("gpiochip0").is_err());
+        assert!(DirectionControl::try_from(":3").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! 设备通讯诊断计数
+//!
+//! 按设备统计发出的请求、收到的响应、超时、异常响应、重连次数、平均往返时间与最近一次成功读取的时间，
+//! 现场排查不稳定的链路时不必抓包；配置了备用链路的设备同时记录当前使用的链路。
+//! 计数在设备重建时清零，设备移除后不再列出。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+/// 设备 ID 到其计数的映射，计数由设备持有
+static COUNTERS: LazyLock<Mutex<HashMap<String, Weak<Counters>>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    responses: AtomicU64,
+    timeouts: AtomicU64,
+    exceptions: AtomicU64,
+    reconnects: AtomicU64,
+    /// 收到响应的请求的往返时间之和(μs)
+    rtt_total: AtomicU64,
+    /// 最近一次成功读取的时间(Unix ms)，0 表示尚未读到
+    last_poll: AtomicU64,
+    /// 当前使用备用链路
+    backup_active: AtomicBool,
+}
+
+/// 一个设备的诊断计数，克隆后共享同一组计数
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    device: Arc<str>,
+    counters: Arc<Counters>,
+}
+
+/// 诊断计数的快照
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DiagnosticsSnapshot {
+    pub device: String,
+    pub requests: u64,
+    pub responses: u64,
+    pub timeouts: u64,
+    /// 从站返回异常码的响应数，已计入 `responses`
+    pub exceptions: u64,
+    pub reconnects: u64,
+    /// 平均往返时间(ms)，尚无响应时为 `None`
+    pub avg_rtt_ms: Option<f64>,
+    /// 最近一次成功读取的时间(Unix ms)，尚未读到时为 `None`
+    pub last_poll_ms: Option<u64>,
+    /// 当前使用备用链路
+    pub backup_active: bool,
+}
+
+impl Diagnostics {
+    /// 为设备新建一组计数并登记，替换同一设备之前的计数
+    pub(crate) fn register(device: &str) -> Self {
+        let counters = Arc::new(Counters::default());
+        let mut all = COUNTERS.lock().expect("diagnostics lock poisoned");
+        all.retain(|_, counters| counters.strong_count() > 0);
+        all.insert(device.to_owned(), Arc::downgrade(&counters));
+        Self {
+            device: device.into(),
+            counters,
+        }
+    }
+
+    pub(crate) fn request(&self) {
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 收到响应，`exception` 表示从站返回了异常码
+    pub(crate) fn response(&self, rtt: Duration, exception: bool) {
+        let counters = &self.counters;
+        counters.responses.fetch_add(1, Ordering::Relaxed);
+        if exception {
+            counters.exceptions.fetch_add(1, Ordering::Relaxed);
+        }
+        let micros = u64::try_from(rtt.as_micros()).unwrap_or(u64::MAX);
+        counters.rtt_total.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn timeout(&self) {
+        self.counters.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reconnect(&self) {
+        self.counters.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 清零计数，最近一次成功读取的时间保留
+    pub(crate) fn reset(&self) {
+        let counters = &self.counters;
+        for counter in [
+            &counters.requests,
+            &counters.responses,
+            &counters.timeouts,
+            &counters.exceptions,
+            &counters.reconnects,
+            &counters.rtt_total,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// 切换到备用链路或切回主链路
+    pub(crate) fn set_backup_active(&self, active: bool) {
+        self.counters.backup_active.store(active, Ordering::Relaxed);
+    }
+
+    /// 成功读取了一次数据
+    pub(crate) fn poll(&self) {
+        self.counters
+            .last_poll
+            .store(unix_millis(), Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> DiagnosticsSnapshot {
+        snapshot(&self.device, &self.counters)
+    }
+}
+
+/// 当前时间(Unix ms)
+pub(crate) fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| u64::try_from(now.as_millis()).unwrap_or(u64::MAX))
+}
+
+fn snapshot(device: &str, counters: &Counters) -> DiagnosticsSnapshot {
+    let responses = counters.responses.load(Ordering::Relaxed);
+    let rtt_total = counters.rtt_total.load(Ordering::Relaxed);
+    DiagnosticsSnapshot {
+        device: device.to_owned(),
+        requests: counters.requests.load(Ordering::Relaxed),
+        responses,
+        timeouts: counters.timeouts.load(Ordering::Relaxed),
+        exceptions: counters.exceptions.load(Ordering::Relaxed),
+        reconnects: counters.reconnects.load(Ordering::Relaxed),
+        avg_rtt_ms: (responses > 0).then(|| rtt_total as f64 / responses as f64 / 1000.0),
+        last_poll_ms: Some(counters.last_poll.load(Ordering::Relaxed)).filter(|ms| *ms > 0),
+        backup_active: counters.backup_active.load(Ordering::Relaxed),
+    }
+}
+
+/// 所有设备的诊断计数，按设备排序
+pub fn diagnostics() -> Vec<DiagnosticsSnapshot> {
+    let mut all: Vec<DiagnosticsSnapshot> = COUNTERS
+        .lock()
+        .expect("diagnostics lock poisoned")
+        .iter()
+        .filter_map(|(device, counters)| {
+            counters
+                .upgrade()
+                .map(|counters| snapshot(device, &counters))
+        })
+        .collect();
+    all.sort_by(|a, b| a.device.cmp(&b.device));
+    all
+}
+
+/// 指定设备的诊断计数
+pub fn device_diagnostics(device: &str) -> Option<DiagnosticsSnapshot> {
+    let all = COUNTERS.lock().expect("diagnostics lock poisoned");
+    let counters = all.get(device)?.upgrade()?;
+    Some(snapshot(device, &counters))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Diagnostics, device_diagnostics};
+
+    #[test]
+    fn counters_are_listed_while_the_device_lives() {
+        let diag = Diagnostics::register("diag-test");
+        diag.request();
+        diag.response(Duration::from_millis(4), false);
+        diag.request();
+        diag.response(Duration::from_millis(8), true);
+        diag.request();
+        diag.timeout();
+
+        let snapshot = device_diagnostics("diag-test").unwrap();
+        assert_eq!(
+            (snapshot.requests, snapshot.responses, snapshot.exceptions),
+            (3, 2, 1)
+        );
+        assert_eq!(snapshot.timeouts, 1);
+        assert_eq!(snapshot.avg_rtt_ms, Some(6.0));
+        assert_eq!(snapshot.last_poll_ms, None);
+        diag.poll();
+        assert!(
+            device_diagnostics("diag-test")
+                .unwrap()
+                .last_poll_ms
+                .is_some()
+        );
+
+        diag.reset();
+        let snapshot = device_diagnostics("diag-test").unwrap();
+        assert_eq!((snapshot.requests, snapshot.timeouts), (0, 0));
+        assert!(snapshot.last_poll_ms.is_some());
+        assert!(!snapshot.backup_active);
+        diag.set_backup_active(true);
+        diag.reset();
+        assert!(device_diagnostics("diag-test").unwrap().backup_active);
+
+        drop(diag);
+        assert!(device_diagnostics("diag-test").is_none());
+    }
+}
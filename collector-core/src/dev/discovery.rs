@@ -0,0 +1,385 @@
+//! Modbus 从站发现
+//!
+//! 调试现场时逐个地址探测串口总线或 IP 段上的从站：读取探测寄存器收到响应（含异常响应）即视为从站存在，
+//! 返回找到的从站及往返时间，并可为其生成设备配置骨架，补上点位表即可使用。
+//! 串口总线上的从站依次探测；IP 段的各主机并发探测，连接失败的主机跳过。
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::{Map, Value, json};
+use tokio::net::TcpStream;
+use tokio::time::{self, Instant};
+use tokio_modbus::client::{Context, Reader, rtu, tcp};
+use tokio_modbus::prelude::SlaveContext;
+use tokio_modbus::{Slave, SlaveId};
+use tracing::debug;
+
+use crate::config::modbus_conf::RegisterType;
+use crate::dev::modbus_dev::serial_port;
+
+/// 同时探测的主机数
+const PARALLEL_HOSTS: usize = 32;
+/// 一次最多探测的主机数，避免误填的网段扫描过久
+const MAX_HOSTS: usize = 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DiscoveryError {
+    #[error("无效的主机范围: {0}")]
+    InvalidHosts(String),
+    #[error("主机范围包含{0}个地址, 超过上限{MAX_HOSTS}")]
+    TooManyHosts(usize),
+    #[error("打开串口失败: {0}")]
+    Serial(#[from] tokio_serial::Error),
+}
+
+/// 探测的总线或网段
+#[derive(Debug, Clone)]
+pub enum ScanTarget {
+    /// 串口总线
+    Serial {
+        tty: String,
+        baud_rate: u32,
+        data_bits: u8,
+        parity: String,
+        stop_bits: u8,
+    },
+    /// IP 段内的主机
+    Tcp { hosts: Vec<IpAddr>, port: u16 },
+}
+
+/// 探测方式
+#[derive(Debug, Clone)]
+pub struct Probe {
+    /// 探测寄存器的类型
+    pub register_type: RegisterType,
+    /// 探测寄存器的协议地址
+    pub address: u16,
+    /// 探测的从站地址
+    pub slaves: RangeInclusive<SlaveId>,
+    /// 单个从站的响应超时，也是连接主机的超时
+    pub timeout: Duration,
+}
+
+impl Default for Probe {
+    fn default() -> Self {
+        Self {
+            register_type: RegisterType::HoldingRegisters,
+            address: 0,
+            slaves: 1..=247,
+            timeout: Duration::from_millis(200),
+        }
+    }
+}
+
+/// 从站所在的链路
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Endpoint {
+    Serial(String),
+    Tcp(SocketAddr),
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Serial(tty) => write!(f, "{}", tty),
+            Endpoint::Tcp(addr) => write!(f, "{}", addr),
+        }
+    }
+}
+
+/// 响应了探测的从站
+#[derive(Debug, Clone, PartialEq)]
+pub struct Responder {
+    pub endpoint: Endpoint,
+    pub slave: SlaveId,
+    pub rtt: Duration,
+    /// 从站以异常码响应时的异常，地址存在但探测寄存器不可读
+    pub exception: Option<String>,
+}
+
+/// 解析主机范围：单个地址、`192.168.0.10-20`、`192.168.0.10-192.168.0.20` 或 `192.168.0.0/24`
+pub fn parse_hosts(range: &str) -> Result<Vec<IpAddr>, DiscoveryError> {
+    let invalid = || DiscoveryError::InvalidHosts(range.to_owned());
+    let range = range.trim();
+    let (first, last) = if let Some((net, bits)) = range.split_once('/') {
+        let net: Ipv4Addr = net.parse().map_err(|_| invalid())?;
+        let bits: u32 = bits
+            .parse()
+            .ok()
+            .filter(|bits| *bits <= 32)
+            .ok_or_else(invalid)?;
+        let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+        let first = u32::from(net) & mask;
+        let last = first | !mask;
+        // 去掉网络地址与广播地址
+        if bits < 31 {
+            (first + 1, last - 1)
+        } else {
+            (first, last)
+        }
+    } else if let Some((start, end)) = range.split_once('-') {
+        let start: Ipv4Addr = start.trim().parse().map_err(|_| invalid())?;
+        let end: Ipv4Addr = match end.trim().parse::<u8>() {
+            Ok(octet) => {
+                let [a, b, c, _] = start.octets();
+                Ipv4Addr::new(a, b, c, octet)
+            }
+            Err(_) => end.trim().parse().map_err(|_| invalid())?,
+        };
+        (u32::from(start), u32::from(end))
+    } else {
+        return range.parse().map(|ip| vec![ip]).map_err(|_| invalid());
+    };
+    if first > last {
+        return Err(invalid());
+    }
+    let count = (last - first) as usize + 1;
+    if count > MAX_HOSTS {
+        return Err(DiscoveryError::TooManyHosts(count));
+    }
+    Ok((first..=last)
+        .map(|ip| IpAddr::V4(Ipv4Addr::from(ip)))
+        .collect())
+}
+
+/// 探测目标上的从站，按链路与从站地址排序返回响应的从站
+pub async fn scan(target: &ScanTarget, probe: &Probe) -> Result<Vec<Responder>, DiscoveryError> {
+    match target {
+        ScanTarget::Serial {
+            tty,
+            baud_rate,
+            data_bits,
+            parity,
+            stop_bits,
+        } => {
+            let builder =
+                serial_port(tty, *baud_rate, *data_bits, parity, *stop_bits).timeout(probe.timeout);
+            let port = tokio_serial::SerialStream::open(&builder)?;
+            let mut ctx = rtu::attach(port);
+            Ok(probe_slaves(&mut ctx, Endpoint::Serial(tty.clone()), probe).await)
+        }
+        ScanTarget::Tcp { hosts, port } => {
+            let mut found: Vec<Responder> = futures::stream::iter(hosts)
+                .map(|host| probe_host(SocketAddr::new(*host, *port), probe))
+                .buffer_unordered(PARALLEL_HOSTS)
+                .concat()
+                .await;
+            found.sort_by(|a, b| (&a.endpoint, a.slave).cmp(&(&b.endpoint, b.slave)));
+            Ok(found)
+        }
+    }
+}
+
+async fn probe_host(addr: SocketAddr, probe: &Probe) -> Vec<Responder> {
+    match time::timeout(probe.timeout, TcpStream::connect(addr)).await {
+        Ok(Ok(stream)) => {
+            let mut ctx = tcp::attach(stream);
+            probe_slaves(&mut ctx, Endpoint::Tcp(addr), probe).await
+        }
+        Ok(Err(err)) => {
+            debug!("探测 {} 连接失败: {}", addr, err);
+            Vec::new()
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 在同一连接上依次探测各从站地址，连接断开后停止
+async fn probe_slaves(ctx: &mut Context, endpoint: Endpoint, probe: &Probe) -> Vec<Responder> {
+    let mut found = Vec::new();
+    for slave in probe.slaves.clone() {
+        ctx.set_slave(Slave(slave));
+        let started = Instant::now();
+        let (address, register_type) = (probe.address, probe.register_type);
+        let read = async {
+            match register_type {
+                RegisterType::Coils => ctx.read_coils(address, 1).await.map(|r| r.map(drop)),
+                RegisterType::DiscreteInputs => ctx
+                    .read_discrete_inputs(address, 1)
+                    .await
+                    .map(|r| r.map(drop)),
+                RegisterType::HoldingRegisters => ctx
+                    .read_holding_registers(address, 1)
+                    .await
+                    .map(|r| r.map(drop)),
+                RegisterType::InputRegisters => ctx
+                    .read_input_registers(address, 1)
+                    .await
+                    .map(|r| r.map(drop)),
+            }
+        };
+        let exception = match time::timeout(probe.timeout, read).await {
+            Ok(Ok(Ok(()))) => None,
+            Ok(Ok(Err(code))) => Some(format!("{:?}", code)),
+            Ok(Err(tokio_modbus::Error::Transport(err)))
+                if err.kind() != std::io::ErrorKind::TimedOut =>
+            {
+                debug!("探测 {} 中断: {}", endpoint, err);
+                break;
+            }
+            Ok(Err(_)) | Err(_) => continue,
+        };
+        found.push(Responder {
+            endpoint: endpoint.clone(),
+            slave,
+            rtt: started.elapsed(),
+            exception,
+        });
+    }
+    found
+}
+
+/// 为响应的从站生成设备配置骨架，键为设备 ID，点位表路径留空待填写
+pub fn skeleton(target: &ScanTarget, found: &[Responder]) -> Map<String, Value> {
+    let mut devices = Map::new();
+    for responder in found {
+        let (name, link) = match (&responder.endpoint, target) {
+            (Endpoint::Tcp(addr), _) => (
+                addr.ip().to_string().replace(['.', ':'], "_"),
+                json!({
+                    "com_type": "ModbusTCP",
+                    "ip": addr.ip().to_string(),
+                    "port": addr.port(),
+                }),
+            ),
+            (
+                Endpoint::Serial(tty),
+                ScanTarget::Serial {
+                    baud_rate,
+                    data_bits,
+                    parity,
+                    stop_bits,
+                    ..
+                },
+            ) => (
+                Path::new(tty)
+                    .file_name()
+                    .map_or_else(|| tty.clone(), |name| name.to_string_lossy().into_owned()),
+                json!({
+                    "com_type": "ModbusRTU",
+                    "serial_tty": tty,
+                    "baud_rate": baud_rate,
+                    "data_bits": data_bits,
+                    "parity": parity,
+                    "stop_bits": stop_bits,
+                }),
+            ),
+            (Endpoint::Serial(_), ScanTarget::Tcp { .. }) => continue,
+        };
+        let id = format!("{}_{}", name, responder.slave);
+        let mut config = json!({
+            "register_file": null,
+            "interval": 2000,
+            "timeout": probe_timeout_ms(responder.rtt),
+            "slave": responder.slave,
+        });
+        if let (Value::Object(config), Value::Object(link)) = (&mut config, link) {
+            config.extend(link);
+        }
+        devices.insert(
+            id.clone(),
+            json!({
+                "id": id,
+                "desc": format!("发现于 {}", responder.endpoint),
+                "config": config,
+            }),
+        );
+    }
+    devices
+}
+
+/// 按探测的往返时间建议的超时(ms)，不少于 1000ms
+fn probe_timeout_ms(rtt: Duration) -> u64 {
+    u64::try_from(rtt.as_millis() * 10)
+        .unwrap_or(u64::MAX)
+        .max(1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio_modbus::client::{Client, Context};
+    use tokio_modbus::prelude::SlaveContext;
+    use tokio_modbus::{ExceptionCode, Request, Response, Slave};
+
+    use super::{Endpoint, Probe, ScanTarget, parse_hosts, probe_slaves, skeleton};
+
+    #[test]
+    fn host_ranges_are_expanded() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(parse_hosts("10.0.0.5").unwrap(), [ip("10.0.0.5")]);
+        assert_eq!(
+            parse_hosts("10.0.0.5-7").unwrap(),
+            [ip("10.0.0.5"), ip("10.0.0.6"), ip("10.0.0.7")]
+        );
+        assert_eq!(parse_hosts("10.0.0.254-10.0.1.1").unwrap().len(), 4);
+        let net = parse_hosts("192.168.1.0/30").unwrap();
+        assert_eq!(net, [ip("192.168.1.1"), ip("192.168.1.2")]);
+        assert!(parse_hosts("10.0.0.7-5").is_err());
+        assert!(parse_hosts("10.0.0.0/8").is_err());
+    }
+
+    /// 从站 1 正常响应，从站 2 以异常码响应，其余不响应
+    #[derive(Debug, Default)]
+    struct Bus(u8);
+
+    impl SlaveContext for Bus {
+        fn set_slave(&mut self, slave: Slave) {
+            self.0 = slave.0;
+        }
+    }
+
+    #[async_trait]
+    impl Client for Bus {
+        async fn call(&mut self, _: Request<'_>) -> tokio_modbus::Result<Response> {
+            match self.0 {
+                1 => Ok(Ok(Response::ReadHoldingRegisters(vec![0]))),
+                2 => Ok(Err(ExceptionCode::IllegalDataAddress)),
+                _ => std::future::pending().await,
+            }
+        }
+
+        async fn disconnect(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn responding_slaves_are_found() {
+        let mut ctx = Context::from(Box::new(Bus::default()) as Box<dyn Client>);
+        let probe = Probe {
+            slaves: 1..=3,
+            timeout: Duration::from_millis(20),
+            ..Probe::default()
+        };
+        let endpoint = Endpoint::Serial("/dev/ttyS1".into());
+        let found = probe_slaves(&mut ctx, endpoint, &probe).await;
+        let slaves: Vec<_> = found
+            .iter()
+            .map(|r| (r.slave, r.exception.is_some()))
+            .collect();
+        assert_eq!(slaves, [(1, false), (2, true)]);
+
+        let target = ScanTarget::Serial {
+            tty: "/dev/ttyS1".into(),
+            baud_rate: 9600,
+            data_bits: 8,
+            parity: "N".into(),
+            stop_bits: 1,
+        };
+        let devices = skeleton(&target, &found);
+        let config = &devices["ttyS1_2"]["config"];
+        assert_eq!(config["com_type"], "ModbusRTU");
+        assert_eq!(
+            (config["slave"].as_u64(), config["baud_rate"].as_u64()),
+            (Some(2), Some(9600))
+        );
+    }
+}
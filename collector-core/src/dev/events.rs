@@ -0,0 +1,99 @@
+//! 设备生命周期事件
+//!
+//! 设备状态变化时广播结构化事件，北向通道与告警引擎订阅后可发布设备离线等告警。
+//! 订阅者处理不及时时会丢失最早的事件，收到 `Lagged` 后可按 [`DevManager::status`] 重新同步。
+//!
+//! [`DevManager::status`]: crate::dev::manager::DevManager::status
+
+use std::sync::LazyLock;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::dev::LifecycleState;
+
+/// 未被订阅者取走的事件的最大数量
+const CAPACITY: usize = 256;
+
+static EVENTS: LazyLock<broadcast::Sender<LifecycleEvent>> =
+    LazyLock::new(|| broadcast::channel(CAPACITY).0);
+
+/// 设备的一次状态变化
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LifecycleEvent {
+    pub device: String,
+    pub from: LifecycleState,
+    pub to: LifecycleState,
+    /// 变化的时间(Unix ms)
+    pub timestamp_ms: u64,
+    /// 变化的原因，如连接失败的错误
+    pub reason: Option<String>,
+}
+
+/// 订阅之后发生的状态变化
+pub fn subscribe() -> broadcast::Receiver<LifecycleEvent> {
+    EVENTS.subscribe()
+}
+
+/// 广播事件，没有订阅者时丢弃
+pub(crate) fn emit(event: LifecycleEvent) {
+    let _ = EVENTS.send(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::subscribe;
+    use crate::dev::LifecycleState;
+    use crate::dev::state::SharedState;
+
+    #[tokio::test]
+    async fn state_changes_are_broadcast() {
+        let mut events = subscribe();
+        let state = SharedState::new(LifecycleState::Connecting);
+        state.store("events-test", LifecycleState::Connecting);
+        state.store_with_reason("events-test", LifecycleState::Failed, "连接超时");
+        state.store("events-test", LifecycleState::Connecting);
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            let event = events.recv().await.unwrap();
+            if event.device == "events-test" {
+                received.push((event.from, event.to, event.reason));
+            }
+        }
+        assert_eq!(
+            received,
+            [
+                (
+                    LifecycleState::Connecting,
+                    LifecycleState::Failed,
+                    Some("连接超时".to_owned())
+                ),
+                (LifecycleState::Failed, LifecycleState::Connecting, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn compare_and_swap_transitions_are_broadcast() {
+        let mut events = subscribe();
+        let state = SharedState::new(LifecycleState::Ready);
+        assert!(!state.cas(
+            "cas-test",
+            LifecycleState::Stopped,
+            LifecycleState::Starting
+        ));
+        assert!(state.cas("cas-test", LifecycleState::Ready, LifecycleState::Starting));
+
+        let event = loop {
+            let event = events.recv().await.unwrap();
+            if event.device == "cas-test" {
+                break event;
+            }
+        };
+        assert_eq!(
+            (event.from, event.to),
+            (LifecycleState::Ready, LifecycleState::Starting)
+        );
+    }
+}
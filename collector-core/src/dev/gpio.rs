@@ -5,16 +5,17 @@ use futures::StreamExt;
 use gpio_cdev::{AsyncLineEventHandle, Chip, EventRequestFlags, Line, LineRequestFlags};
 use tokio::sync::{Mutex, watch};
 use tokio::task::{self, JoinHandle};
-use tokio::time;
 
 use crate::{
-    center::{DataCenterError, SharedPointCenter},
+    center::{self, DataCenterError, DownlinkCommand, DownlinkReceiver, SharedPointCenter},
     config::{
         self, Device,
         gpio_conf::{Direction, GpioConfig, GpioConfigs},
     },
     core::point::{DownDataPoint, PointRef, Val},
-    dev::{DeviceError, Executable, Identifiable, Lifecycle, LifecycleState, state::SharedState},
+    dev::{
+        self, DeviceError, Executable, Identifiable, Lifecycle, LifecycleState, state::SharedState,
+    },
 };
 
 pub struct GpioDev {
@@ -26,6 +27,8 @@ pub struct GpioDev {
     stop_rx: watch::Receiver<bool>,
     di_task: Mutex<Option<JoinHandle<()>>>,
     do_task: Mutex<Option<JoinHandle<()>>>,
+    /// 停止时等待 DI、DO 任务结束的时间
+    stop_timeout: Duration,
 }
 
 impl GpioDev {
@@ -36,6 +39,7 @@ impl GpioDev {
         let Some(configs) = dev.protocol_configs else {
             return Err(DeviceError::NotFoundConfigs(id));
         };
+        let stop_timeout = dev::stop_timeout(dev.config.stop_timeout);
         let configs = match configs {
             config::ProtocolConfigs::Modbus(_) => {
                 return Err(DeviceError::UnSupportedComType);
@@ -63,6 +67,7 @@ impl GpioDev {
             stop_rx,
             di_task: Mutex::new(None),
             do_task: Mutex::new(None),
+            stop_timeout,
         })
     }
 
@@ -80,7 +85,7 @@ impl GpioDev {
     /// # 返回值
     /// - `bool`: 是否成功改变状态
     fn cas_state(&self, from: LifecycleState, to: LifecycleState) -> bool {
-        self.state.cas(from, to)
+        self.state.cas(&self.id, from, to)
     }
 
     /// 存储设备的生命周期状态
@@ -115,7 +120,7 @@ impl Lifecycle for GpioDev {
         if !ok {
             return Ok(());
         }
-        let (tx, rx) = tokio::sync::mpsc::channel::<Vec<DownDataPoint>>(8);
+        let (tx, rx) = tokio::sync::mpsc::channel::<DownlinkCommand>(8);
         //将设备注册到消息中心
         match self.center.attach_downlink(&self.id, tx.clone()) {
             Ok(()) => {}
@@ -200,36 +205,26 @@ impl Lifecycle for GpioDev {
         // 从数据中心注销
         self.center.detach_downlink(&self.id);
 
-        // 停止 DI 任务
-        let mut di_task_guard = self.di_task.lock().await;
-        if let Some(mut handle) = di_task_guard.take() {
-            tokio::select! {
-                _ = time::sleep(Duration::from_secs(3)) => {
-                    tracing::warn!("[{}] DI任务停止超时，强制中止", self.id);
-                    handle.abort();
-                }
-                _ = &mut handle => {
-                    tracing::info!("[{}] DI任务已停止", self.id);
-                }
-            }
-        }
-
-        // 停止 DO 任务
-        let mut do_task_guard = self.do_task.lock().await;
-        if let Some(mut handle) = do_task_guard.take() {
-            tokio::select! {
-                _ = time::sleep(Duration::from_secs(3)) => {
-                    tracing::warn!("[{}] DO任务停止超时，强制中止", self.id);
-                    handle.abort();
-                }
-                _ = &mut handle => {
-                    tracing::info!("[{}] DO任务已停止", self.id);
-                }
+        // 停止 DI、DO 任务
+        let mut aborted = false;
+        for (name, task) in [("DI", &self.di_task), ("DO", &self.do_task)] {
+            let mut task_guard = task.lock().await;
+            let Some(mut handle) = task_guard.take() else {
+                continue;
+            };
+            if dev::join_or_abort(&mut handle, self.stop_timeout).await {
+                tracing::warn!("[{}] {}任务停止超时，强制中止", self.id, name);
+                aborted = true;
+            } else {
+                tracing::info!("[{}] {}任务已停止", self.id, name);
             }
         }
 
         self.store_state(LifecycleState::Stopped);
         tracing::info!("[{}] GPIO设备已停止", self.id);
+        if aborted {
+            return Err(DeviceError::ForceAborted(self.id.clone()));
+        }
         Ok(())
     }
 
@@ -340,7 +335,7 @@ async fn handle_di(
 async fn handle_do(
     id: String,
     vec: Vec<GpioConfDev>,
-    mut rx: tokio::sync::mpsc::Receiver<Vec<DownDataPoint>>,
+    mut rx: DownlinkReceiver,
     mut stop_rx: watch::Receiver<bool>,
 ) -> Result<(), gpio_cdev::Error> {
     use std::collections::HashMap;
@@ -390,9 +385,33 @@ async fn handle_do(
                     break;
                 }
             }
-            points = rx.recv() => {
-                match points {
-                    Some(points) => {
+            cmd = rx.recv() => {
+                match cmd {
+                    Some(mut cmd) => {
+                        if let Some(read) = cmd.read.take() {
+                            read.reply(Err(DataCenterError::ReadFailed("GPIO不支持按需读取".into())));
+                            continue;
+                        }
+                        let atomic = cmd.atomic;
+                        let (points, ack) = cmd.into_parts();
+                        // 原子下发：先校验所有点位，任一不可写则整批拒绝；
+                        // 各输出逐个写入，原子下发只能包含一个点位
+                        if atomic {
+                            let mut rejected = reject_reasons(&points, &output_handles);
+                            if points.len() > 1 {
+                                rejected.push(format!(
+                                    "原子下发只能包含一个GPIO输出, 当前{}个",
+                                    points.len()
+                                ));
+                            }
+                            if !rejected.is_empty() {
+                                let reason = rejected.join("; ");
+                                tracing::warn!("[{}] 原子下发被拒绝: {}", id, reason);
+                                center::reply(ack, Err(DataCenterError::Rejected(reason)));
+                                continue;
+                            }
+                        }
+                        let mut failed: Option<String> = None;
                         for dp in points {
                             let key = match &dp.point {
                                 PointRef::Key(key) | PointRef::Name(key) => key,
@@ -450,11 +469,16 @@ async fn handle_do(
                                         tracing::warn!("[{}] GPIO[{}] 不支持List类型", id, key);
                                         continue;
                                     }
+                                    Val::Str(_) => {
+                                        tracing::warn!("[{}] GPIO[{}] 不支持字符串类型", id, key);
+                                        continue;
+                                    }
                                 };
 
                                 // 设置 GPIO 输出
                                 if let Err(e) = handle.set_value(value) {
                                     tracing::error!("[{}] 设置GPIO[{}]输出失败: {}", id, key, e);
+                                    failed.get_or_insert_with(|| format!("{}: {}", key, e));
                                 } else {
                                     tracing::debug!("[{}] 设置GPIO[{}]输出: {}", id, key, value);
                                 }
@@ -462,6 +486,11 @@ async fn handle_do(
                                 tracing::warn!("[{}] 未找到GPIO配置: {}", id, key);
                             }
                         }
+                        let result = match failed {
+                            Some(err) => Err(DataCenterError::WriteFailed(err)),
+                            None => Ok(()),
+                        };
+                        center::reply(ack, result);
                     }
                     None => {
                         tracing::info!("[{}] DO控制通道已关闭", id);
@@ -475,3 +504,25 @@ async fn handle_do(
     tracing::info!("[{}] DO控制任务退出", id);
     Ok(())
 }
+
+/// 校验下发点位是否均可写入 DO，返回不可写的点位及原因
+fn reject_reasons(
+    points: &[DownDataPoint],
+    output_handles: &std::collections::HashMap<&'static str, gpio_cdev::LineHandle>,
+) -> Vec<String> {
+    points
+        .iter()
+        .filter_map(|dp| match &dp.point {
+            PointRef::Id(id) => Some(format!("GPIO不支持ID方式下发: {}", id)),
+            PointRef::Key(key) | PointRef::Name(key) => {
+                if !output_handles.contains_key(key.as_str()) {
+                    Some(format!("未找到GPIO配置: {}", key))
+                } else if matches!(dp.value, Val::List(_)) {
+                    Some(format!("GPIO[{}] 不支持List类型", key))
+                } else {
+                    None
+                }
+            }
+        })
+        .collect()
+}
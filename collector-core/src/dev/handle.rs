@@ -0,0 +1,251 @@
+//! 设备句柄
+//!
+//! 每台设备由一个 actor 任务独占，句柄通过命令通道请求启动、停止、查询状态与下发，
+//! 可廉价克隆，HTTP API、规则引擎等子系统据此控制指定设备。停止设备期间仍响应状态查询，
+//! 其他请求排在停止之后处理。设备被移除后句柄的请求返回 [`DeviceError::NotFound`]。
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Instant;
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::center::{DataCenterError, SharedPointCenter};
+use crate::config::ComType;
+use crate::core::point::DownDataPoint;
+use crate::dev::health::SharedHealth;
+use crate::dev::maintenance::{self, Maintenance, MaintenanceOutput};
+use crate::dev::manager::DeviceStatus;
+use crate::dev::{DeviceError, DeviceExit, Executable, LifecycleState, diagnostics};
+
+/// 命令通道的容量
+const CAPACITY: usize = 16;
+
+type Reply<T> = oneshot::Sender<T>;
+
+enum Command {
+    Start(Reply<Result<(), DeviceError>>),
+    Stop(Reply<Result<(), DeviceError>>),
+    State(Reply<LifecycleState>),
+    Exit(Reply<Option<DeviceExit>>),
+    SetPaused(bool, Reply<Result<(), DeviceError>>),
+    Write(Vec<DownDataPoint>, Reply<Result<(), DeviceError>>),
+    ReloadPoints(String, Reply<Result<(), DeviceError>>),
+    Maintain(Maintenance, maintenance::Reply),
+}
+
+/// 单个设备的句柄
+#[derive(Clone)]
+pub struct DeviceHandle {
+    id: String,
+    com_type: Option<ComType>,
+    tx: mpsc::Sender<Command>,
+    restarts: Arc<AtomicU32>,
+    health: SharedHealth,
+}
+
+impl DeviceHandle {
+    /// 启动设备的 actor，设备需已初始化
+    pub(crate) fn spawn(
+        device: Box<dyn Executable>,
+        com_type: Option<ComType>,
+        center: SharedPointCenter,
+        health: SharedHealth,
+    ) -> Self {
+        let id = device.id().to_owned();
+        let (tx, rx) = mpsc::channel(CAPACITY);
+        tokio::spawn(run(device, center, rx));
+        Self {
+            id,
+            com_type,
+            tx,
+            restarts: Arc::default(),
+            health,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(Reply<T>) -> Command,
+    ) -> Result<T, DeviceError> {
+        let (reply, rx) = oneshot::channel();
+        let gone = || DeviceError::NotFound(self.id.clone());
+        self.tx.send(command(reply)).await.map_err(|_| gone())?;
+        rx.await.map_err(|_| gone())
+    }
+
+    pub async fn start(&self) -> Result<(), DeviceError> {
+        self.request(Command::Start).await?
+    }
+
+    pub async fn stop(&self) -> Result<(), DeviceError> {
+        self.request(Command::Stop).await?
+    }
+
+    /// 设备的生命周期状态，设备已移除时为 [`LifecycleState::Stopped`]
+    pub async fn state(&self) -> LifecycleState {
+        self.request(Command::State)
+            .await
+            .unwrap_or(LifecycleState::Stopped)
+    }
+
+    /// 后台任务未经停止请求就已结束时返回其结束方式，见 [`Lifecycle::exit`](crate::dev::Lifecycle::exit)
+    pub(crate) async fn exit(&self) -> Option<DeviceExit> {
+        self.request(Command::Exit).await.ok().flatten()
+    }
+
+    /// 设备因重启策略被重启的次数
+    pub fn restarts(&self) -> u32 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn restart_counter(&self) -> Arc<AtomicU32> {
+        self.restarts.clone()
+    }
+
+    /// 设备的运行状态
+    pub async fn status(&self) -> DeviceStatus {
+        let state = self.state().await;
+        let diag = diagnostics::device_diagnostics(&self.id).unwrap_or_default();
+        let health = self
+            .health
+            .lock()
+            .expect("health lock poisoned")
+            .report(&self.id, Instant::now());
+        DeviceStatus {
+            id: self.id.clone(),
+            com_type: self.com_type,
+            state,
+            last_poll_ms: diag.last_poll_ms,
+            timeouts: diag.timeouts,
+            exceptions: diag.exceptions,
+            reconnects: diag.reconnects,
+            backup_active: diag.backup_active,
+            restarts: self.restarts(),
+            uptime_percent: health.uptime_percent,
+            consecutive_failures: health.consecutive_failures,
+            failures: health.failures,
+            mtbf_secs: health.mtbf_secs,
+            last_failure: health.last_failure,
+        }
+    }
+
+    /// 暂停轮询，保留连接与下发；区别于停止，不会产生重连告警
+    pub async fn pause(&self) -> Result<(), DeviceError> {
+        self.request(|reply| Command::SetPaused(true, reply))
+            .await?
+    }
+
+    /// 恢复暂停的轮询
+    pub async fn resume(&self) -> Result<(), DeviceError> {
+        self.request(|reply| Command::SetPaused(false, reply))
+            .await?
+    }
+
+    /// 按 `path` 重新读取点位表，运行中的设备在下一轮询周期换用，不中断连接
+    pub async fn reload_points(&self, path: &str) -> Result<(), DeviceError> {
+        let path = path.to_owned();
+        self.request(|reply| Command::ReloadPoints(path, reply))
+            .await?
+    }
+
+    /// 交给设备的后台任务执行维护操作，设备未运行时返回 [`DeviceError::NotRunning`]
+    ///
+    /// 后台任务在期限内未应答时返回错误，不无限等待断开连接的设备
+    pub async fn maintain(&self, op: Maintenance) -> Result<MaintenanceOutput, DeviceError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(Command::Maintain(op, reply))
+            .await
+            .map_err(|_| DeviceError::NotFound(self.id.clone()))?;
+        match tokio::time::timeout(maintenance::REPLY_TIMEOUT, rx).await {
+            // 后台任务未处理就结束时请求随之丢弃
+            Ok(result) => result.unwrap_or(Err(DeviceError::NotRunning)),
+            Err(_) => Err(DeviceError::Maintenance(format!(
+                "设备{}秒内未应答",
+                maintenance::REPLY_TIMEOUT.as_secs()
+            ))),
+        }
+    }
+
+    /// 作为一个整体下发点位，等待设备回报结果；设备未运行时直接拒绝而不排队
+    pub async fn write(&self, points: Vec<DownDataPoint>) -> Result<(), DeviceError> {
+        self.request(|reply| Command::Write(points, reply)).await?
+    }
+}
+
+/// 设备的 actor，所有句柄都被丢弃后结束
+async fn run(
+    mut device: Box<dyn Executable>,
+    center: SharedPointCenter,
+    mut rx: mpsc::Receiver<Command>,
+) {
+    let mut deferred = VecDeque::new();
+    loop {
+        let command = match deferred.pop_front() {
+            Some(command) => command,
+            None => match rx.recv().await {
+                Some(command) => command,
+                None => return,
+            },
+        };
+        match command {
+            Command::Start(reply) => {
+                let _ = reply.send(device.start().await);
+            }
+            Command::Stop(reply) => {
+                let device = &*device;
+                let stop = device.stop();
+                tokio::pin!(stop);
+                // 停止可能要等待后台任务结束，期间仍回答状态查询
+                let result = loop {
+                    tokio::select! {
+                        result = &mut stop => break result,
+                        Some(command) = rx.recv() => match command {
+                            Command::State(reply) => {
+                                let _ = reply.send(device.state());
+                            }
+                            command => deferred.push_back(command),
+                        },
+                    }
+                };
+                let _ = reply.send(result);
+            }
+            Command::State(reply) => {
+                let _ = reply.send(device.state());
+            }
+            Command::Exit(reply) => {
+                let _ = reply.send(device.exit());
+            }
+            Command::SetPaused(paused, reply) => {
+                let _ = reply.send(device.set_paused(paused));
+            }
+            Command::ReloadPoints(path, reply) => {
+                let _ = reply.send(device.reload_points(&path).await);
+            }
+            Command::Maintain(op, reply) => device.maintain(op, reply),
+            Command::Write(points, reply) => {
+                let state = device.state();
+                if !matches!(
+                    state,
+                    LifecycleState::Connected | LifecycleState::Running | LifecycleState::Paused
+                ) {
+                    let reason = format!("设备{}", state);
+                    let _ = reply.send(Err(DataCenterError::WriteFailed(reason).into()));
+                    continue;
+                }
+                // 下发由设备后台任务执行，不阻塞其他请求
+                let (center, id) = (center.clone(), device.id().to_owned());
+                tokio::spawn(async move {
+                    let result = center.dispatch_atomic(&id, points).await;
+                    let _ = reply.send(result.map_err(DeviceError::from));
+                });
+            }
+        }
+    }
+}
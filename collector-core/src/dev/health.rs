@@ -0,0 +1,254 @@
+//! 设备健康统计
+//!
+//! 按生命周期事件统计每台设备在滑动窗口内的可用率、连续失败次数与重连的平均故障间隔(MTBF)，
+//! 经设备状态对外提供，便于按设备排查不稳定的链路。已连接、运行中与暂停计为可用，
+//! 连接中与失败计为不可用，其余状态（未启动、已停止等）不计入可用率。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::broadcast;
+
+use crate::dev::LifecycleState;
+use crate::dev::events::LifecycleEvent;
+
+/// 统计的滑动窗口
+const WINDOW: Duration = Duration::from_secs(3600);
+
+/// 设备管理器与设备句柄共享的健康统计
+pub(crate) type SharedHealth = Arc<Mutex<Health>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Availability {
+    Up,
+    Down,
+    /// 不计入可用率
+    Idle,
+}
+
+impl From<LifecycleState> for Availability {
+    fn from(state: LifecycleState) -> Self {
+        match state {
+            LifecycleState::Connected | LifecycleState::Running | LifecycleState::Paused => {
+                Availability::Up
+            }
+            LifecycleState::Connecting | LifecycleState::Failed => Availability::Down,
+            _ => Availability::Idle,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Span {
+    start: Instant,
+    end: Instant,
+    availability: Availability,
+}
+
+/// 一台设备的状态时间线
+#[derive(Debug, Default)]
+struct Timeline {
+    current: Option<(Availability, Instant)>,
+    spans: VecDeque<Span>,
+    /// 由可用变为不可用的时刻
+    failures: VecDeque<Instant>,
+    consecutive_failures: u32,
+    last_failure: Option<String>,
+}
+
+/// 一台设备的健康指标
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct HealthReport {
+    /// 窗口内可用时间的占比(%)，尚无可统计的时间时为 `None`
+    pub(crate) uptime_percent: Option<f64>,
+    /// 自上次进入运行状态以来的失败次数
+    pub(crate) consecutive_failures: u32,
+    /// 窗口内由可用变为不可用的次数
+    pub(crate) failures: u32,
+    /// 窗口内的平均故障间隔(s)，窗口内未发生故障时为 `None`
+    pub(crate) mtbf_secs: Option<f64>,
+    /// 最近一次失败的原因，进入运行状态后清除
+    pub(crate) last_failure: Option<String>,
+}
+
+/// 所有设备的健康统计
+#[derive(Debug)]
+pub(crate) struct Health {
+    window: Duration,
+    devices: HashMap<String, Timeline>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            window: WINDOW,
+            devices: HashMap::new(),
+        }
+    }
+}
+
+impl Health {
+    fn record(&mut self, event: &LifecycleEvent, now: Instant) {
+        let window = self.window;
+        let timeline = self.devices.entry(event.device.clone()).or_default();
+        let availability = Availability::from(event.to);
+        match event.to {
+            LifecycleState::Failed => {
+                timeline.consecutive_failures += 1;
+                timeline.last_failure = event.reason.clone();
+            }
+            LifecycleState::Running => {
+                timeline.consecutive_failures = 0;
+                timeline.last_failure = None;
+            }
+            _ => {}
+        }
+        if let Some((previous, start)) = timeline.current {
+            if previous == availability {
+                return;
+            }
+            if previous == Availability::Up && availability == Availability::Down {
+                timeline.failures.push_back(now);
+            }
+            timeline.spans.push_back(Span {
+                start,
+                end: now,
+                availability: previous,
+            });
+        }
+        timeline.current = Some((availability, now));
+        let since = now.checked_sub(window);
+        while let Some(span) = timeline.spans.front()
+            && since.is_some_and(|since| span.end <= since)
+        {
+            timeline.spans.pop_front();
+        }
+        while let Some(failure) = timeline.failures.front()
+            && since.is_some_and(|since| *failure <= since)
+        {
+            timeline.failures.pop_front();
+        }
+    }
+
+    /// 设备在 `now` 时的健康指标
+    pub(crate) fn report(&self, device: &str, now: Instant) -> HealthReport {
+        let Some(timeline) = self.devices.get(device) else {
+            return HealthReport::default();
+        };
+        let since = now.checked_sub(self.window);
+        let overlap = |start: Instant, end: Instant| {
+            let start = since.map_or(start, |since| start.max(since));
+            end.saturating_duration_since(start)
+        };
+        let (mut up, mut down) = (Duration::ZERO, Duration::ZERO);
+        let current = timeline.current.map(|(availability, start)| Span {
+            start,
+            end: now,
+            availability,
+        });
+        for span in timeline.spans.iter().chain(current.as_ref()) {
+            match span.availability {
+                Availability::Up => up += overlap(span.start, span.end),
+                Availability::Down => down += overlap(span.start, span.end),
+                Availability::Idle => {}
+            }
+        }
+        let failures = timeline
+            .failures
+            .iter()
+            .filter(|failure| since.is_none_or(|since| **failure > since))
+            .count() as u32;
+        let total = (up + down).as_secs_f64();
+        HealthReport {
+            uptime_percent: (total > 0.0).then(|| up.as_secs_f64() / total * 100.0),
+            consecutive_failures: timeline.consecutive_failures,
+            failures,
+            mtbf_secs: (failures > 0).then(|| up.as_secs_f64() / f64::from(failures)),
+            last_failure: timeline.last_failure.clone(),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, device: &str) {
+        self.devices.remove(device);
+    }
+}
+
+/// 按生命周期事件更新健康统计，错过的事件不再补记
+pub(crate) async fn track(mut events: broadcast::Receiver<LifecycleEvent>, health: SharedHealth) {
+    loop {
+        match events.recv().await {
+            Ok(event) => health
+                .lock()
+                .expect("health lock poisoned")
+                .record(&event, Instant::now()),
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Health;
+    use crate::dev::LifecycleState;
+    use crate::dev::events::LifecycleEvent;
+
+    #[test]
+    fn availability_is_measured_over_the_window() {
+        let mut health = Health::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut from = LifecycleState::Ready;
+        let mut record = |to, secs| {
+            let event = LifecycleEvent {
+                device: "pcs".into(),
+                from,
+                to,
+                timestamp_ms: 0,
+                reason: None,
+            };
+            from = to;
+            health.record(&event, at(secs));
+        };
+        record(LifecycleState::Connecting, 0);
+        record(LifecycleState::Running, 10);
+        record(LifecycleState::Failed, 310);
+        record(LifecycleState::Connecting, 320);
+        record(LifecycleState::Failed, 330);
+        record(LifecycleState::Running, 340);
+
+        let report = health.report("pcs", at(640));
+        // 可用 600s，不可用 40s
+        assert_eq!(report.uptime_percent, Some(600.0 / 640.0 * 100.0));
+        assert_eq!((report.failures, report.consecutive_failures), (1, 0));
+        assert_eq!(report.mtbf_secs, Some(600.0));
+
+        // 窗口外的故障不再计入
+        let later = health.report("pcs", at(4000));
+        assert_eq!((later.failures, later.mtbf_secs), (0, None));
+        assert_eq!(later.uptime_percent, Some(100.0));
+        assert_eq!(health.report("bms", at(10)).uptime_percent, None);
+
+        let failed = LifecycleEvent {
+            device: "meter".into(),
+            from: LifecycleState::Running,
+            to: LifecycleState::Failed,
+            timestamp_ms: 0,
+            reason: Some("后台任务 panic".into()),
+        };
+        health.record(&failed, at(700));
+        let report = health.report("meter", at(700));
+        assert_eq!(report.last_failure.as_deref(), Some("后台任务 panic"));
+        let running = LifecycleEvent {
+            from: LifecycleState::Failed,
+            to: LifecycleState::Running,
+            reason: None,
+            ..failed
+        };
+        health.record(&running, at(710));
+        assert_eq!(health.report("meter", at(710)).last_failure, None);
+    }
+}
@@ -0,0 +1,50 @@
+//! 设备标识
+//!
+//! 设备连接后读到的厂商、产品代码与版本，供 API 查询，用于资产盘点以及核对点位表与现场设备是否匹配。
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use serde::Serialize;
+
+/// 设备自身上报的标识
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeviceIdentity {
+    pub device: String,
+    pub vendor: Option<String>,
+    pub product_code: Option<String>,
+    pub revision: Option<String>,
+}
+
+/// 设备 ID 到其最近一次读到的标识的映射
+static IDENTITIES: LazyLock<RwLock<HashMap<String, DeviceIdentity>>> =
+    LazyLock::new(Default::default);
+
+/// 所有已读到标识的设备，按设备排序
+pub fn identities() -> Vec<DeviceIdentity> {
+    let mut all: Vec<DeviceIdentity> = IDENTITIES
+        .read()
+        .expect("identities lock poisoned")
+        .values()
+        .cloned()
+        .collect();
+    all.sort_by(|a, b| a.device.cmp(&b.device));
+    all
+}
+
+/// 指定设备最近一次读到的标识
+pub fn device_identity(device: &str) -> Option<DeviceIdentity> {
+    IDENTITIES
+        .read()
+        .expect("identities lock poisoned")
+        .get(device)
+        .cloned()
+}
+
+/// 记录设备的标识，覆盖上一次的结果
+pub(crate) fn record(identity: DeviceIdentity) {
+    IDENTITIES
+        .write()
+        .expect("identities lock poisoned")
+        .insert(identity.device.clone(), identity);
+}
@@ -0,0 +1,56 @@
+//! 设备维护操作
+//!
+//! 现场排查时对单个设备执行的操作：立即重连、清零诊断计数、立即轮询与读取原始寄存器。
+//! 请求经通道交给设备的后台任务在两次读取之间执行，不打断正在进行的请求；设备未运行时直接拒绝，
+//! 只有重连请求在连接中或连接失败等待退避时也被接受，此时立即发起连接。
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::modbus_conf::RegisterType;
+use crate::dev::DeviceError;
+
+/// 维护请求通道的容量
+pub(crate) const CAPACITY: usize = 8;
+/// 等待设备后台任务应答维护请求的期限
+pub(crate) const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 维护操作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Maintenance {
+    /// 断开当前连接并立即重连，不等待退避；共享链路上只断开本设备，链路保留
+    Reconnect,
+    /// 清零通讯诊断计数，最近一次读取的时间保留
+    ResetDiagnostics,
+    /// 所有扫描等级立即开始新一轮读取，不等待轮询周期
+    PollNow,
+    /// 读取原始寄存器或线圈，`address` 为协议地址
+    DumpRegisters {
+        register_type: RegisterType,
+        address: u16,
+        count: u16,
+    },
+}
+
+/// 维护操作的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceOutput {
+    Done,
+    Registers(Vec<u16>),
+    Bits(Vec<bool>),
+}
+
+pub(crate) type Reply = oneshot::Sender<Result<MaintenanceOutput, DeviceError>>;
+
+/// 交给设备后台任务的维护请求
+pub(crate) struct Request {
+    pub(crate) op: Maintenance,
+    pub(crate) reply: Reply,
+}
+
+pub(crate) type RequestSender = mpsc::Sender<Request>;
+pub(crate) type RequestReceiver = mpsc::Receiver<Request>;
@@ -1,27 +1,165 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
 
-use tokio::sync::Mutex;
-use tokio::task::JoinSet;
+use futures::StreamExt;
+use futures::future::join_all;
+use futures::stream::FuturesUnordered;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::{AbortHandle, JoinSet};
+use tokio::time::{self, Instant};
 use tokio_util::sync::CancellationToken;
-use tracing::error;
+use tracing::{error, info, warn};
 
-use crate::center::SharedPointCenter;
-use crate::config::{ComType, Device};
+use crate::center::{DataCenter, SharedPointCenter};
+use crate::config::reload::{self, ConfigDiff};
+use crate::config::{ComType, Device, RestartPolicy};
 
 use crate::dev::can_bus::SharedCanBus;
 #[cfg(target_os = "linux")]
 use crate::dev::can_dev::CanDev;
+use crate::dev::connect_limit::ConnectLimit;
 #[cfg(target_os = "linux")]
 use crate::dev::gpio::GpioDev;
 use crate::{
     config,
-    dev::{DeviceError, Executable, modbus_dev::ModbusDev},
+    dev::{
+        DeviceError, DevicePlan, Executable, LifecycleState, events,
+        handle::DeviceHandle,
+        health,
+        health::SharedHealth,
+        maintenance::{Maintenance, MaintenanceOutput},
+        modbus_dev::ModbusDev,
+        startup, supervisor, watchdog,
+    },
 };
 
+/// 等待一个启动阶段的设备运行的最长时间，超时后继续启动下一阶段
+const PHASE_TIMEOUT: Duration = Duration::from_secs(30);
+/// 检查启动阶段的设备状态的间隔
+const PHASE_POLL: Duration = Duration::from_millis(200);
+/// [`DevManager::apply`] 中同时重启的设备数的缺省值
+const RESTART_PARALLELISM: usize = 4;
+
+/// 设备的运行状态，供命令行与 HTTP API 查询
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceStatus {
+    pub id: String,
+    /// 虚拟设备等非配置创建的设备为 `None`
+    pub com_type: Option<ComType>,
+    pub state: LifecycleState,
+    /// 最近一次成功读取的时间(Unix ms)，尚未读到或设备不统计时为 `None`
+    pub last_poll_ms: Option<u64>,
+    pub timeouts: u64,
+    pub exceptions: u64,
+    pub reconnects: u64,
+    /// 当前使用备用链路，未配置备用链路时总为 `false`
+    pub backup_active: bool,
+    /// 因重启策略被重启的次数
+    pub restarts: u32,
+    /// 最近一小时内可用时间的占比(%)，尚无统计时为 `None`
+    pub uptime_percent: Option<f64>,
+    /// 自上次进入运行状态以来的失败次数
+    pub consecutive_failures: u32,
+    /// 最近一小时内由可用变为不可用的次数
+    pub failures: u32,
+    /// 最近一小时内的平均故障间隔(s)，未发生故障时为 `None`
+    pub mtbf_secs: Option<f64>,
+    /// 最近一次失败的原因，如后台任务 panic 的信息；进入运行状态后清除
+    pub last_failure: Option<String>,
+}
+
+/// 试运行中一个设备的检查结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DryRunDevice {
+    pub id: String,
+    pub com_type: Option<ComType>,
+    /// 启动分组
+    pub group: String,
+    /// 读取计划，设备不支持或新建失败时为 `None`
+    pub plan: Option<DevicePlan>,
+    /// 新建、构建读取块或在数据中心登记失败的原因
+    pub error: Option<String>,
+}
+
+/// 试运行的结果，见 [`DevManager::dry_run`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DryRunReport {
+    /// 启用的设备，按设备 ID 排序
+    pub devices: Vec<DryRunDevice>,
+    /// 已禁用、不会启动的设备
+    pub disabled: Vec<String>,
+    /// 启动阶段，每个阶段为同时启动的分组
+    pub phases: Vec<Vec<String>>,
+}
+
+impl DryRunReport {
+    /// 所有启用的设备都能正常新建
+    pub fn is_ok(&self) -> bool {
+        self.devices.iter().all(|dev| dev.error.is_none())
+    }
+}
+
+/// [`DevManager::apply`] 的结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ApplyReport {
+    /// 与运行中设备的配置差异，设备均为设备 ID
+    pub diff: ConfigDiff,
+    /// 加载点位表或新建失败、保持原状的设备及原因
+    pub failed: Vec<(String, String)>,
+}
+
+/// 受管理的设备
+struct Managed {
+    handle: DeviceHandle,
+    /// 重启策略，未配置时设备任务结束后不再重启
+    restart: Option<RestartPolicy>,
+    /// 启动分组，未配置时为设备 ID
+    group: String,
+    depends_on: Vec<String>,
+    watchdog: Option<watchdog::Limits>,
+    /// 启动、监督设备的任务与看门狗
+    tasks: Vec<AbortHandle>,
+    /// 创建设备的配置，重启时据此重建；非配置创建的设备为 `None`
+    config: Option<Device>,
+}
+
+impl Managed {
+    fn new(handle: DeviceHandle) -> Self {
+        Self {
+            group: handle.id().to_owned(),
+            handle,
+            restart: None,
+            depends_on: Vec::new(),
+            watchdog: None,
+            tasks: Vec::new(),
+            config: None,
+        }
+    }
+
+    fn id(&self) -> &str {
+        self.handle.id()
+    }
+}
+
 pub struct DevManager {
-    devices: Vec<Arc<Mutex<Box<dyn Executable>>>>,
+    devices: Vec<Managed>,
+    /// 相邻两台设备启动的间隔
+    start_spacing: Duration,
+    /// [`stop_all`](Self::stop_all) 等待所有设备停止的期限，未设置时一直等待
+    stop_deadline: Option<Duration>,
+    /// [`apply`](Self::apply) 中同时重启的设备数
+    restart_parallelism: usize,
     tasks: JoinSet<()>,
     cancel_token: Option<CancellationToken>,
+    center: SharedPointCenter,
+    can_bus: SharedCanBus,
+    connect_limit: ConnectLimit,
+    health: SharedHealth,
+    /// 按生命周期事件更新健康统计的任务
+    health_task: Option<AbortHandle>,
 }
 
 impl DevManager {
@@ -30,88 +168,573 @@ impl DevManager {
         center: SharedPointCenter,
         can_bus: SharedCanBus,
     ) -> Self {
-        let mut devices: Vec<Arc<Mutex<Box<dyn Executable>>>> = Vec::new();
-        for (_, dev) in map.into_iter() {
+        let mut manager = DevManager {
+            devices: Vec::new(),
+            start_spacing: Duration::ZERO,
+            stop_deadline: None,
+            restart_parallelism: RESTART_PARALLELISM,
+            tasks: JoinSet::new(),
+            cancel_token: None,
+            center,
+            can_bus,
+            connect_limit: ConnectLimit::default(),
+            health: SharedHealth::default(),
+            health_task: None,
+        };
+        for (key, mut dev) in map.into_iter() {
+            if !dev.is_enabled() {
+                info!("设备 {} 已禁用, 不启动", key);
+                continue;
+            }
             let Some(com_type) = dev.config.com_type else {
                 continue;
             };
-            match init_device(dev, com_type, center.clone(), can_bus.clone()) {
+            dev.id.get_or_insert(key);
+            match manager.manage(dev, com_type) {
                 Ok(dev) => {
-                    devices.push(dev);
+                    manager.devices.push(dev);
                 }
                 Err(err) => {
                     error!("{}", err)
                 }
             }
         }
-        DevManager {
-            devices,
-            tasks: JoinSet::new(),
-            cancel_token: None,
+        manager
+    }
+
+    /// 试运行：按配置新建所有设备，加载点位表、构建读取块并在数据中心登记，但不启动设备、不建立连接
+    ///
+    /// 设备的点位表需已加载（见 [`Device::load_protocol_configs`]）；使用独立的数据中心，不影响运行中的设备
+    pub fn dry_run(map: HashMap<String, Device>) -> DryRunReport {
+        let center: SharedPointCenter = Arc::new(DataCenter::new(map.len()));
+        let mut report = DryRunReport::default();
+        let mut groups: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (key, mut dev) in map {
+            if !dev.is_enabled() {
+                report.disabled.push(key);
+                continue;
+            }
+            let id = dev.id.get_or_insert(key).clone();
+            let group = dev.group.clone().unwrap_or_else(|| id.clone());
+            groups
+                .entry(group.clone())
+                .or_default()
+                .extend(dev.depends_on.iter().cloned());
+            let com_type = dev.config.com_type;
+            let checked = com_type
+                .ok_or(DeviceError::InvalidComType)
+                .and_then(|com_type| {
+                    let device = init_device(
+                        dev,
+                        com_type,
+                        center.clone(),
+                        SharedCanBus::default(),
+                        ConnectLimit::default(),
+                    )?;
+                    let plan = device.plan()?;
+                    // 与启动时一样登记下发通道，ID 重复时失败
+                    let (tx, _rx) = mpsc::channel(1);
+                    center.attach_downlink(&id, tx)?;
+                    Ok(plan)
+                });
+            let (plan, error) = match checked {
+                Ok(plan) => (plan, None),
+                Err(err) => (None, Some(err.to_string())),
+            };
+            report.devices.push(DryRunDevice {
+                id,
+                com_type,
+                group,
+                plan,
+                error,
+            });
         }
+        report.devices.sort_by(|a, b| a.id.cmp(&b.id));
+        report.disabled.sort();
+        report.phases = startup::phases(&groups);
+        report
     }
 
     pub fn set_cancel_token(&mut self, token: CancellationToken) {
         self.cancel_token = Some(token);
     }
 
-    pub async fn add_device(&mut self, device: Arc<Mutex<Box<dyn Executable>>>) {
-        {
-            let dev = device.lock().await;
-            if let Err(err) = dev.init() {
-                error!("设备 {} 初始化失败: {}", dev.id(), err);
+    /// 设置同时尝试连接的最大设备数，缺省不限制
+    pub fn set_max_connecting(&mut self, max: Option<usize>) {
+        self.connect_limit.set(max);
+    }
+
+    /// 设置 [`start_all`](Self::start_all) 中相邻两台设备启动的间隔，缺省同时启动
+    pub fn set_start_spacing(&mut self, spacing: Duration) {
+        self.start_spacing = spacing;
+    }
+
+    /// 设置 [`stop_all`](Self::stop_all) 等待所有设备停止的期限，缺省一直等待
+    pub fn set_stop_deadline(&mut self, deadline: Option<Duration>) {
+        self.stop_deadline = deadline;
+    }
+
+    /// 设置 [`apply`](Self::apply) 中同时重启的设备数，缺省 4
+    pub fn set_restart_parallelism(&mut self, parallelism: usize) {
+        self.restart_parallelism = parallelism.max(1);
+    }
+
+    pub fn add_device(&mut self, device: Box<dyn Executable>) {
+        if let Err(err) = device.init() {
+            error!("设备 {} 初始化失败: {}", device.id(), err);
+            return;
+        }
+        let handle = DeviceHandle::spawn(device, None, self.center.clone(), self.health.clone());
+        self.devices.push(Managed::new(handle));
+    }
+
+    /// 按启动分组的依赖分阶段启动所有设备
+    ///
+    /// 前一阶段的设备都已连接、失败或等待超过 [`PHASE_TIMEOUT`] 后再启动下一阶段，
+    /// 未配置分组与依赖时所有设备同一阶段启动；设置了启动间隔时逐台间隔启动
+    pub async fn start_all(&mut self) {
+        if self.health_task.is_none() {
+            let events = events::subscribe();
+            let task = self.tasks.spawn(health::track(events, self.health.clone()));
+            self.health_task = Some(task);
+        }
+        let mut groups: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for dev in &self.devices {
+            groups
+                .entry(dev.group.clone())
+                .or_default()
+                .extend(dev.depends_on.iter().cloned());
+        }
+        let phases = startup::phases(&groups);
+        let last = phases.len();
+        let mut first = true;
+        for (n, phase) in phases.into_iter().enumerate() {
+            for dev in self.devices.iter_mut() {
+                if !phase.contains(&dev.group) {
+                    continue;
+                }
+                if !std::mem::take(&mut first) && !self.start_spacing.is_zero() {
+                    time::sleep(self.start_spacing).await;
+                }
+                Self::spawn_start(&mut self.tasks, dev);
+            }
+            if last > 1 {
+                info!("启动阶段 {}/{}: {:?}", n + 1, last, phase);
+            }
+            if n + 1 < last {
+                self.wait_phase(&phase).await;
+            }
+        }
+    }
+
+    /// 等待分组的设备都已连接或失败，输出每个分组的启动结果
+    async fn wait_phase(&self, phase: &[String]) {
+        let deadline = Instant::now() + PHASE_TIMEOUT;
+        loop {
+            let mut progress: BTreeMap<&str, (usize, usize, usize)> = BTreeMap::new();
+            for dev in self.devices.iter().filter(|dev| phase.contains(&dev.group)) {
+                let (running, failed, total) = progress.entry(&dev.group).or_default();
+                *total += 1;
+                match dev.handle.state().await {
+                    LifecycleState::Connected | LifecycleState::Running => *running += 1,
+                    LifecycleState::Failed | LifecycleState::Stopped => *failed += 1,
+                    _ => {}
+                }
+            }
+            let settled = progress
+                .values()
+                .all(|(running, failed, total)| running + failed == *total);
+            if settled || Instant::now() >= deadline {
+                for (group, (running, failed, total)) in progress {
+                    if running == total {
+                        info!("启动分组 {} 已运行: {}/{}", group, running, total);
+                    } else {
+                        warn!(
+                            "启动分组 {} 未全部运行: 运行 {}, 失败 {}, 共 {}",
+                            group, running, failed, total
+                        );
+                    }
+                }
                 return;
             }
+            time::sleep(PHASE_POLL).await;
+        }
+    }
+
+    /// 启动设备，配置了重启策略时由监督任务启动并在任务意外结束后重启，配置了看门狗时一并启动
+    fn spawn_start(tasks: &mut JoinSet<()>, dev: &mut Managed) {
+        let handle = dev.handle.clone();
+        let task = match dev.restart {
+            Some(policy) => tasks.spawn(supervisor::supervise(handle, policy)),
+            None => tasks.spawn(async move {
+                if let Err(err) = handle.start().await {
+                    error!("{}", err);
+                }
+            }),
+        };
+        dev.tasks.push(task);
+        if let Some(limits) = dev.watchdog {
+            let handle = dev.handle.clone();
+            dev.tasks.push(tasks.spawn(watchdog::watch(handle, limits)));
         }
+    }
+
+    /// 所有设备的运行状态，按设备 ID 排序
+    pub async fn status(&self) -> Vec<DeviceStatus> {
+        let mut all = Vec::with_capacity(self.devices.len());
+        for dev in &self.devices {
+            all.push(dev.handle.status().await);
+        }
+        all.sort_by(|a, b| a.id.cmp(&b.id));
+        all
+    }
+
+    /// 所有设备的句柄
+    pub fn handles(&self) -> Vec<DeviceHandle> {
+        self.devices.iter().map(|dev| dev.handle.clone()).collect()
+    }
+
+    /// 按 ID 取得设备的句柄
+    pub fn get(&self, id: &str) -> Option<DeviceHandle> {
+        self.devices
+            .iter()
+            .find(|dev| dev.id() == id)
+            .map(|dev| dev.handle.clone())
+    }
+
+    /// 暂停设备的轮询，保留连接与下发；区别于停止，不会产生重连告警
+    pub async fn pause(&self, id: &str) -> Result<(), DeviceError> {
+        self.handle(id)?.pause().await
+    }
+
+    /// 恢复暂停的设备的轮询
+    pub async fn resume(&self, id: &str) -> Result<(), DeviceError> {
+        self.handle(id)?.resume().await
+    }
+
+    /// 按 `path` 重新读取设备的点位表，在下一轮询周期换用，不中断设备的连接
+    pub async fn reload_points(&self, id: &str, path: &str) -> Result<(), DeviceError> {
+        self.handle(id)?.reload_points(path).await
+    }
+
+    /// 对设备执行维护操作，见 [`Maintenance`]
+    ///
+    /// 等待设备应答期间借用设备管理器；共享的管理器应先经 [`get`](Self::get) 取得句柄、释放锁后再调用
+    /// [`DeviceHandle::maintain`]
+    pub async fn maintain(
+        &self,
+        id: &str,
+        op: Maintenance,
+    ) -> Result<MaintenanceOutput, DeviceError> {
+        self.handle(id)?.maintain(op).await
+    }
+
+    fn handle(&self, id: &str) -> Result<DeviceHandle, DeviceError> {
+        self.get(id)
+            .ok_or_else(|| DeviceError::NotFound(id.to_owned()))
+    }
+
+    /// 设备因重启策略被重启的次数，设备不存在时返回 `None`
+    pub fn restarts(&self, id: &str) -> Option<u32> {
+        self.get(id).map(|handle| handle.restarts())
+    }
+
+    /// 运行时按配置新建并启动设备，ID 已存在时返回错误
+    ///
+    /// 设备的点位表需已加载（见 [`Device::load_protocol_configs`]）
+    pub async fn add_device_from_config(&mut self, dev: Device) -> Result<(), DeviceError> {
+        let (id, com_type) = check_device(&dev)?;
+        if self.get(&id).is_some() {
+            return Err(DeviceError::AlreadyExists(id));
+        }
+        let mut device = self.manage(dev, com_type)?;
+        Self::spawn_start(&mut self.tasks, &mut device);
         self.devices.push(device);
+        info!("设备 {} 已添加", id);
+        Ok(())
     }
 
-    pub async fn start_all(&mut self) {
-        for dev in self.devices.iter() {
-            let dev_clone = Arc::clone(dev);
-            self.tasks.spawn(async move {
-                let mut dev_clone_mutex = dev_clone.lock().await;
-                if let Err(err) = dev_clone_mutex.start().await {
+    /// 按新配置重建并启动设备，同 ID 的旧设备会先被停止并移除
+    ///
+    /// 设备的点位表需已加载（见 [`Device::load_protocol_configs`]）；
+    /// 重建期间保留旧设备的数据，新设备读到数据后覆盖
+    pub async fn replace_device(&mut self, dev: Device) -> Result<(), DeviceError> {
+        let (id, com_type) = check_device(&dev)?;
+        let mut device = self.manage(dev, com_type)?;
+        self.stop_device(&id).await;
+        Self::spawn_start(&mut self.tasks, &mut device);
+        self.devices.push(device);
+        info!("设备 {} 已按新配置重建", id);
+        Ok(())
+    }
+
+    /// 重启单个设备：停止并等待后台任务结束，按原配置重建后启动，其他设备不受影响
+    ///
+    /// 非配置创建的设备（见 [`add_device`](Self::add_device)）不重建，停止后重新启动；
+    /// 重建失败时按原设备重新启动并返回错误
+    pub async fn restart_device(&mut self, id: &str) -> Result<(), DeviceError> {
+        let idx = self
+            .devices
+            .iter()
+            .position(|dev| dev.id() == id)
+            .ok_or_else(|| DeviceError::NotFound(id.to_owned()))?;
+        let dev = &mut self.devices[idx];
+        // 先取消监督任务与看门狗，避免停止期间被重新启动
+        for task in dev.tasks.drain(..) {
+            task.abort();
+        }
+        if let Err(err) = dev.handle.stop().await {
+            error!("{}", err);
+        }
+        let rebuilt = match dev.config.clone() {
+            Some(config) => config
+                .config
+                .com_type
+                .ok_or(DeviceError::InvalidComType)
+                .and_then(|com_type| self.manage(config, com_type))
+                .map(Some),
+            None => Ok(None),
+        };
+        let result = match rebuilt {
+            Ok(Some(device)) => {
+                self.devices[idx] = device;
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(err) => {
+                error!("设备 {} 重建失败, 按原设备重新启动: {}", id, err);
+                Err(err)
+            }
+        };
+        Self::spawn_start(&mut self.tasks, &mut self.devices[idx]);
+        info!("设备 {} 已重启", id);
+        result
+    }
+
+    /// 按新配置更新设备：停止删除的设备，分批重启修改或点位表变化的设备，启动新增的设备，其余设备不中断轮询
+    ///
+    /// 与运行中设备的配置按设备 ID 比较，禁用的设备视为删除；先加载点位表并新建设备，
+    /// 失败的设备保持原状。每批同时停止旧设备后启动新设备，重建期间保留旧设备的数据
+    pub async fn apply(&mut self, devices: HashMap<String, Device>) -> ApplyReport {
+        let old: HashMap<String, Device> = self
+            .devices
+            .iter()
+            .filter_map(|dev| Some((dev.id().to_owned(), dev.config.clone()?)))
+            .collect();
+        let new: HashMap<String, Device> = devices
+            .into_iter()
+            .filter(|(_, dev)| dev.is_enabled() && dev.config.com_type.is_some())
+            .map(|(key, mut dev)| (dev.id.get_or_insert(key).clone(), dev))
+            .collect();
+        let diff = reload::diff_devices(&old, &new);
+        let mut report = ApplyReport::default();
+        if diff.is_empty() {
+            return report;
+        }
+
+        let mut rebuilt = Vec::new();
+        for id in diff.rebuilt() {
+            let Some(mut dev) = new.get(id).cloned() else {
+                continue;
+            };
+            let built = match dev.load_protocol_configs().await {
+                Ok(()) => dev
+                    .config
+                    .com_type
+                    .ok_or(DeviceError::InvalidComType)
+                    .and_then(|com_type| self.manage(dev, com_type))
+                    .map_err(|err| err.to_string()),
+                Err(err) => Err(err.to_string()),
+            };
+            match built {
+                Ok(device) => rebuilt.push(device),
+                Err(err) => {
+                    error!("设备 {} 保持原状: {}", id, err);
+                    report.failed.push((id.clone(), err));
+                }
+            }
+        }
+
+        for id in &diff.removed {
+            self.remove_device(id).await;
+        }
+        let mut rebuilt = rebuilt.into_iter().peekable();
+        while rebuilt.peek().is_some() {
+            let batch: Vec<Managed> = rebuilt.by_ref().take(self.restart_parallelism).collect();
+            let replaced: Vec<Managed> = batch
+                .iter()
+                .filter_map(|dev| self.take_device(dev.id()))
+                .collect();
+            join_all(replaced.iter().map(|dev| async move {
+                if let Err(err) = dev.handle.stop().await {
                     error!("{}", err);
                 }
-            });
+            }))
+            .await;
+            for mut device in batch {
+                Self::spawn_start(&mut self.tasks, &mut device);
+                self.devices.push(device);
+            }
+        }
+        info!(
+            "已应用新配置, 新增: {:?}, 删除: {:?}, 修改: {:?}, 点位表变化: {:?}",
+            diff.added,
+            diff.removed,
+            diff.modified
+                .iter()
+                .map(|change| &change.device)
+                .collect::<Vec<_>>(),
+            diff.point_tables
+        );
+        report.diff = diff;
+        report
+    }
+
+    /// 停止并注销设备，数据中心不再保留其数据，返回设备是否存在
+    pub async fn remove_device(&mut self, id: &str) -> bool {
+        if !self.stop_device(id).await {
+            return false;
+        }
+        self.center.remove_device(id);
+        self.health.lock().expect("health lock poisoned").remove(id);
+        info!("设备 {} 已移除", id);
+        true
+    }
+
+    /// 停止设备并从管理列表中移除，返回设备是否存在
+    async fn stop_device(&mut self, id: &str) -> bool {
+        let Some(dev) = self.take_device(id) else {
+            return false;
+        };
+        if let Err(err) = dev.handle.stop().await {
+            error!("{}", err);
+        }
+        true
+    }
+
+    /// 从管理列表中移除设备并取消其监督任务与看门狗，避免停止后被重新启动
+    fn take_device(&mut self, id: &str) -> Option<Managed> {
+        let idx = self.devices.iter().position(|dev| dev.id() == id)?;
+        let dev = self.devices.remove(idx);
+        for task in &dev.tasks {
+            task.abort();
         }
+        Some(dev)
     }
 
-    pub async fn stop_all(&mut self) {
+    /// 同时停止所有设备，返回停止超时被强制中止、或超过停止期限仍未停止的设备
+    pub async fn stop_all(&mut self) -> Vec<String> {
+        if let Some(task) = self.health_task.take() {
+            task.abort();
+        }
         for dev in self.devices.iter() {
-            let dev_mutex = dev.lock().await;
-            if let Err(err) = dev_mutex.stop().await {
-                error!("{}", err);
+            for task in &dev.tasks {
+                task.abort();
+            }
+        }
+        let mut pending: BTreeSet<&str> = self.devices.iter().map(Managed::id).collect();
+        let mut stops: FuturesUnordered<_> = self
+            .devices
+            .iter()
+            .map(|dev| async move { (dev.id(), dev.handle.stop().await) })
+            .collect();
+        let deadline = self.stop_deadline.map(|deadline| Instant::now() + deadline);
+        let mut aborted = Vec::new();
+        loop {
+            let next = match deadline {
+                Some(deadline) => match time::timeout_at(deadline, stops.next()).await {
+                    Ok(next) => next,
+                    Err(_) => break,
+                },
+                None => stops.next().await,
+            };
+            let Some((id, result)) = next else {
+                break;
+            };
+            pending.remove(id);
+            match result {
+                Ok(()) => {}
+                Err(DeviceError::ForceAborted(id)) => aborted.push(id),
+                Err(err) => error!("{}", err),
             }
         }
+        drop(stops);
+        if !aborted.is_empty() {
+            warn!("停止超时被强制中止的设备: {:?}", aborted);
+        }
+        if !pending.is_empty() {
+            error!("超过停止期限仍未停止的设备: {:?}", pending);
+        }
+        aborted.extend(pending.into_iter().map(str::to_owned));
         while let Some(res) = self.tasks.join_next().await {
-            if let Err(err) = res {
+            if let Err(err) = res
+                && !err.is_cancelled()
+            {
                 error!("{}", err);
             }
         }
+        aborted
     }
 
-    pub async fn find_dev(&self, id: &str) -> Option<Arc<Mutex<Box<dyn Executable>>>> {
-        for dev in self.devices.iter() {
-            let dev_mutex = dev.lock().await;
-            if dev_mutex.id() == id {
-                return Some(dev.clone());
-            }
-        }
-        None
+    /// 新建设备及其 actor，附带配置中的重启策略、启动分组与看门狗
+    fn manage(&self, dev: Device, com_type: ComType) -> Result<Managed, DeviceError> {
+        let config = dev.clone();
+        let group = dev.group.clone().or_else(|| dev.id.clone());
+        let depends_on = dev.depends_on.clone();
+        let restart = dev.config.restart;
+        let watchdog = dev
+            .config
+            .watchdog
+            .and_then(|watchdog| watchdog::Limits::new(watchdog, dev.config.interval));
+        let device = init_device(
+            dev,
+            com_type,
+            self.center.clone(),
+            self.can_bus.clone(),
+            self.connect_limit.clone(),
+        )?;
+        let handle = DeviceHandle::spawn(
+            device,
+            Some(com_type),
+            self.center.clone(),
+            self.health.clone(),
+        );
+        let managed = Managed::new(handle);
+        Ok(Managed {
+            group: group.unwrap_or(managed.group.clone()),
+            depends_on,
+            restart,
+            watchdog,
+            config: Some(config),
+            ..managed
+        })
     }
 }
 
+/// 运行时添加的设备须已启用，并配置了 ID 与通信类型
+fn check_device(dev: &Device) -> Result<(String, ComType), DeviceError> {
+    if !dev.is_enabled() {
+        return Err(DeviceError::Disabled);
+    }
+    let Some(com_type) = dev.config.com_type else {
+        return Err(DeviceError::InvalidComType);
+    };
+    let Some(id) = dev.id.clone() else {
+        return Err(DeviceError::InvalidId);
+    };
+    Ok((id, com_type))
+}
+
 fn init_device(
     dev: Device,
     com_type: ComType,
     center: SharedPointCenter,
     can_bus: SharedCanBus,
-) -> Result<Arc<Mutex<Box<dyn Executable>>>, DeviceError> {
+    connect_limit: ConnectLimit,
+) -> Result<Box<dyn Executable>, DeviceError> {
     let my_dev: Box<dyn Executable> = match com_type {
         config::ComType::ModbusTCP | config::ComType::ModbusRTU => {
-            Box::new(ModbusDev::new(dev, center)?)
+            Box::new(ModbusDev::new(dev, center)?.with_connect_limit(connect_limit))
         }
         #[cfg(target_os = "linux")]
         config::ComType::CAN => Box::new(CanDev::new(dev, center, can_bus)?),
@@ -128,5 +751,225 @@ fn init_device(
         config::ComType::GPIO => return Err(DeviceError::UnSupportedComType),
     };
     my_dev.init()?;
-    Ok(Arc::new(Mutex::new(my_dev)))
+    Ok(my_dev)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{DevManager, DeviceStatus};
+    use crate::center::DataCenter;
+    use crate::config::Device;
+    use crate::dev::maintenance::Maintenance;
+    use crate::dev::{DeviceError, Executable, Identifiable, Lifecycle, LifecycleState};
+
+    struct Stub(&'static str, LifecycleState);
+
+    impl Identifiable for Stub {
+        fn id(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Lifecycle for Stub {
+        fn init(&self) -> Result<(), DeviceError> {
+            Ok(())
+        }
+
+        async fn start(&mut self) -> Result<(), DeviceError> {
+            self.1 = LifecycleState::Running;
+            Ok(())
+        }
+
+        async fn stop(&self) -> Result<(), DeviceError> {
+            Ok(())
+        }
+
+        fn state(&self) -> LifecycleState {
+            self.1
+        }
+    }
+
+    impl Executable for Stub {}
+
+    /// 停止时一直等待的设备
+    struct Hung;
+
+    impl Identifiable for Hung {
+        fn id(&self) -> &str {
+            "hung"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Lifecycle for Hung {
+        fn init(&self) -> Result<(), DeviceError> {
+            Ok(())
+        }
+
+        async fn start(&mut self) -> Result<(), DeviceError> {
+            Ok(())
+        }
+
+        async fn stop(&self) -> Result<(), DeviceError> {
+            std::future::pending().await
+        }
+
+        fn state(&self) -> LifecycleState {
+            LifecycleState::Running
+        }
+    }
+
+    impl Executable for Hung {}
+
+    #[test]
+    fn dry_run_reports_every_device() {
+        let map: HashMap<String, Device> = serde_json::from_str(
+            r#"{
+                "meter": {"enabled": false, "config": {"com_type": "ModbusTCP"}},
+                "pcs": {"group": "power", "config": {"com_type": "ModbusTCP"}},
+                "bms": {"config": {}}
+            }"#,
+        )
+        .unwrap();
+        let report = DevManager::dry_run(map);
+        assert!(!report.is_ok());
+        assert_eq!(report.disabled, ["meter"]);
+        let ids: Vec<&str> = report.devices.iter().map(|dev| dev.id.as_str()).collect();
+        assert_eq!(ids, ["bms", "pcs"]);
+        // 未加载点位表与未配置通信类型的设备都报告原因
+        assert!(report.devices.iter().all(|dev| dev.error.is_some()));
+        assert_eq!(report.devices[1].group, "power");
+        assert_eq!(report.phases, [["bms", "power"]]);
+    }
+
+    #[tokio::test]
+    async fn apply_rolls_out_config_changes() {
+        let mut manager = DevManager::new(
+            HashMap::new(),
+            Arc::new(DataCenter::new(8)),
+            Default::default(),
+        );
+        manager.add_device(Box::new(Stub("bms", LifecycleState::Running)));
+        let dir = std::env::temp_dir().join(format!("collector-apply-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let table = dir.join("points.json");
+        std::fs::write(
+            &table,
+            r#"[{ id: 1, name: "电压", data_type: "U16",
+            register_address: 0, register_type: "HoldingRegisters", quantity: 1, key: "voltage" }]"#,
+        )
+        .unwrap();
+        let config = |port: u16| -> HashMap<String, Device> {
+            let file = table.display();
+            let json = format!(
+                r#"{{
+                    "pcs": {{"config": {{"com_type": "ModbusTCP", "ip": "127.0.0.1", "port": {port}, "slave": 1, "interval": 1000, "timeout": 100, "register_file": "{file}"}}}},
+                    "meter": {{"enabled": false, "config": {{"com_type": "ModbusTCP", "register_file": "{file}"}}}},
+                    "pv": {{"config": {{"com_type": "ModbusTCP", "ip": "localhost", "slave": 1, "register_file": "{file}"}}}}
+                }}"#
+            );
+            serde_json::from_str(&json).unwrap()
+        };
+
+        let report = manager.apply(config(1)).await;
+        assert_eq!(report.diff.added, ["pcs", "pv"]);
+        // 新建失败的设备保持原状，没有配置的设备不受影响
+        assert_eq!(report.failed.len(), 1, "{:?}", report.failed);
+        assert_eq!(report.failed[0].0, "pv");
+        let ids = |status: Vec<DeviceStatus>| -> Vec<String> {
+            status.into_iter().map(|s| s.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(manager.status().await), ["bms", "pcs"]);
+
+        // 新建失败的设备下次仍视为新增，其余设备不变
+        let report = manager.apply(config(1)).await;
+        assert_eq!(report.diff.added, ["pv"]);
+        assert!(report.diff.modified.is_empty() && report.diff.point_tables.is_empty());
+        let report = manager.apply(config(2)).await;
+        assert_eq!(report.diff.modified[0].device, "pcs");
+        assert_eq!(ids(manager.status().await), ["bms", "pcs"]);
+
+        let report = manager.apply(HashMap::new()).await;
+        assert_eq!(report.diff.removed, ["pcs"]);
+        assert_eq!(ids(manager.status().await), ["bms"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stop_all_reports_devices_past_the_deadline() {
+        let mut manager = DevManager::new(
+            HashMap::new(),
+            Arc::new(DataCenter::new(8)),
+            Default::default(),
+        );
+        manager.add_device(Box::new(Stub("pcs", LifecycleState::Running)));
+        manager.add_device(Box::new(Hung));
+        manager.set_stop_deadline(Some(Duration::from_millis(50)));
+        assert_eq!(manager.stop_all().await, ["hung"]);
+    }
+
+    #[tokio::test]
+    async fn status_lists_devices_by_id() {
+        let mut manager = DevManager::new(
+            HashMap::new(),
+            Arc::new(DataCenter::new(8)),
+            Default::default(),
+        );
+        for id in ["pcs", "bms"] {
+            let device: Box<dyn Executable> = Box::new(Stub(id, LifecycleState::Ready));
+            manager.add_device(device);
+        }
+        let status = manager.status().await;
+        let ids: Vec<&str> = status.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["bms", "pcs"]);
+        assert_eq!(status[0].state, LifecycleState::Ready);
+        assert_eq!((status[0].com_type, status[0].last_poll_ms), (None, None));
+        assert_eq!(
+            manager.get("pcs").unwrap().state().await,
+            LifecycleState::Ready
+        );
+        assert!(manager.get("meter").is_none());
+        let reset = manager.maintain("pcs", Maintenance::ResetDiagnostics).await;
+        assert!(matches!(reset, Err(DeviceError::Unsupported(_))));
+        // 未运行的设备直接拒绝下发
+        assert!(manager.get("pcs").unwrap().write(Vec::new()).await.is_err());
+
+        manager.start_all().await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(
+            manager
+                .status()
+                .await
+                .iter()
+                .all(|s| s.state == LifecycleState::Running)
+        );
+    }
+
+    #[tokio::test]
+    async fn restart_device_restarts_a_running_device() {
+        let mut manager = DevManager::new(
+            HashMap::new(),
+            Arc::new(DataCenter::new(8)),
+            Default::default(),
+        );
+        manager.add_device(Box::new(Stub("pcs", LifecycleState::Ready)));
+        manager.start_all().await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(matches!(
+            manager.restart_device("meter").await,
+            Err(DeviceError::NotFound(_))
+        ));
+        manager.restart_device("pcs").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            manager.get("pcs").unwrap().state().await,
+            LifecycleState::Running
+        );
+    }
 }
@@ -1,19 +1,42 @@
+use std::any::Any;
 use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use futures::FutureExt;
+use tokio::task::JoinHandle;
+use tokio::time;
+use tracing::error;
 
 use crate::{
     center::DataCenterError,
     dev::dev_config::{CanConfError, ModbusRtuConfError, ModbusTcpConfError},
 };
 
+pub(crate) mod backoff;
 pub mod can_bus;
 #[cfg(target_os = "linux")]
 pub(crate) mod can_dev;
+pub mod connect_limit;
 pub(crate) mod dev_config;
+pub mod diagnostics;
+pub mod discovery;
+pub mod events;
 #[cfg(target_os = "linux")]
 pub(crate) mod gpio;
+pub mod handle;
+pub(crate) mod health;
+pub mod identity;
+pub mod maintenance;
 pub mod manager;
 pub(crate) mod modbus_dev;
+pub mod schedule;
+pub(crate) mod startup;
 pub mod state;
+pub(crate) mod supervisor;
+pub(crate) mod watchdog;
+
+pub use modbus_dev::{bench, capture, oneshot, simulator};
 
 #[derive(Debug, thiserror::Error)]
 pub enum DeviceError {
@@ -31,10 +54,82 @@ pub enum DeviceError {
     CanConfigError(#[from] CanConfError),
     #[error("{0}找不到点位表")]
     NotFoundConfigs(String),
+    #[error("无效的字节序: {0}")]
+    InvalidByteOrder(String),
+    #[error("点位{name}的地址{address}小于起始编号{base}")]
+    InvalidAddress {
+        name: &'static str,
+        address: u16,
+        base: u16,
+    },
+    #[error("设备已禁用")]
+    Disabled,
+    #[error("设备{0}已存在")]
+    AlreadyExists(String),
+    #[error("设备{0}不存在")]
+    NotFound(String),
+    #[error("设备不支持{0}")]
+    Unsupported(&'static str),
+    #[error("点位表错误: {0}")]
+    PointTable(String),
+    #[error("设备未运行")]
+    NotRunning,
+    #[error("维护操作失败: {0}")]
+    Maintenance(String),
+    #[error("读取失败: {0}")]
+    Read(String),
+    #[error("下发失败: {0}")]
+    Write(String),
+    #[error("设备{0}停止超时, 已强制中止")]
+    ForceAborted(String),
     #[error("数据中心错误: {0}")]
     DCenterError(#[from] DataCenterError),
     #[error("设备发生错误: {0}")]
-    DevRuntimeError(#[from] Box<dyn std::error::Error>),
+    DevRuntimeError(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// 停止设备时等待后台任务结束的缺省时间
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 配置的停止等待时间(ms)，缺省 [`DEFAULT_STOP_TIMEOUT`]
+pub(crate) fn stop_timeout(millis: Option<u64>) -> Duration {
+    millis.map_or(DEFAULT_STOP_TIMEOUT, Duration::from_millis)
+}
+
+/// 等待后台任务结束，超过 `timeout` 仍未结束时强制中止，返回是否被中止
+pub(crate) async fn join_or_abort(handle: &mut JoinHandle<()>, timeout: Duration) -> bool {
+    tokio::select! {
+        _ = time::sleep(timeout) => {
+            handle.abort();
+            true
+        }
+        _ = &mut *handle => false,
+    }
+}
+
+/// 运行设备的后台任务，任务 panic 时把设备标记为失败并附带 panic 信息，供状态查询与监督重启
+pub(crate) async fn catch_panic(
+    id: String,
+    state: state::SharedState,
+    task: impl Future<Output = ()>,
+) {
+    if let Err(panic) = AssertUnwindSafe(task).catch_unwind().await {
+        let message = panic_message(panic.as_ref());
+        error!("[{}] 后台任务 panic: {}", id, message);
+        state.store_with_reason(
+            &id,
+            LifecycleState::Failed,
+            format!("后台任务 panic: {message}"),
+        );
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("未知原因")
 }
 
 pub trait Identifiable: Sync + Send {
@@ -50,7 +145,7 @@ impl Identifiable for DevStr {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum LifecycleState {
     New = 0,
     Initializing = 1,
@@ -62,6 +157,8 @@ pub enum LifecycleState {
     Stopping = 7,
     Stopped = 8,
     Failed = 9,
+    /// 暂停轮询，保留连接与下发
+    Paused = 10,
 }
 
 impl From<u8> for LifecycleState {
@@ -77,6 +174,7 @@ impl From<u8> for LifecycleState {
             7 => LifecycleState::Stopping,
             8 => LifecycleState::Stopped,
             9 => LifecycleState::Failed,
+            10 => LifecycleState::Paused,
             _ => LifecycleState::Failed,
         }
     }
@@ -95,16 +193,76 @@ impl fmt::Display for LifecycleState {
             LifecycleState::Stopping => write!(f, "停止中"),
             LifecycleState::Stopped => write!(f, "停止成功"),
             LifecycleState::Failed => write!(f, "失败"),
+            LifecycleState::Paused => write!(f, "已暂停"),
         }
     }
 }
 
+/// 设备后台任务未经停止请求而结束的方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceExit {
+    /// 任务 panic 或以失败状态结束
+    Failed,
+    /// 任务以停止状态正常返回
+    Completed,
+}
+
+/// 设备的读取计划，见 [`Lifecycle::plan`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct DevicePlan {
+    /// 启用的点位数
+    pub points: usize,
+    /// 每轮读取的请求数
+    pub blocks: usize,
+}
+
 #[async_trait::async_trait]
 pub trait Lifecycle {
     fn init(&self) -> Result<(), DeviceError>;
     async fn start(&mut self) -> Result<(), DeviceError>;
+    /// 停止设备，后台任务未在停止等待时间内结束时强制中止并返回 [`DeviceError::ForceAborted`]
     async fn stop(&self) -> Result<(), DeviceError>;
     fn state(&self) -> LifecycleState;
+
+    /// 后台任务未经停止请求就已结束时返回其结束方式，供监督重启；运行中或已请求停止时为 `None`
+    fn exit(&self) -> Option<DeviceExit> {
+        None
+    }
+
+    /// 暂停或恢复轮询，暂停期间保留连接与下发，不再读取数据；状态跨重启保留
+    fn set_paused(&self, _paused: bool) -> Result<(), DeviceError> {
+        Err(DeviceError::Unsupported("暂停轮询"))
+    }
+
+    /// 按 `path` 重新读取点位表并在运行中换用，不中断连接；点位表有误时保留原点位表
+    async fn reload_points(&self, _path: &str) -> Result<(), DeviceError> {
+        Err(DeviceError::Unsupported("热更新点位表"))
+    }
+
+    /// 不建立连接，按当前点位表构建读取计划，供试运行检查；设备不支持时为 `None`
+    fn plan(&self) -> Result<Option<DevicePlan>, DeviceError> {
+        Ok(None)
+    }
+
+    /// 交给后台任务执行维护操作，结果经 `reply` 返回；不等待执行，避免阻塞其他控制请求
+    fn maintain(&self, _op: maintenance::Maintenance, reply: maintenance::Reply) {
+        let _ = reply.send(Err(DeviceError::Unsupported("维护操作")));
+    }
 }
 
 pub trait Executable: Identifiable + Lifecycle {}
+
+#[cfg(test)]
+mod tests {
+    use super::{LifecycleState, catch_panic, state::SharedState};
+
+    #[tokio::test]
+    async fn panicked_task_marks_the_device_failed() {
+        let state = SharedState::new(LifecycleState::Running);
+        let task = tokio::spawn(catch_panic("panicky".into(), state.clone(), async {
+            panic!("寄存器越界");
+        }));
+        task.await.unwrap();
+        assert_eq!(state.load(), LifecycleState::Failed);
+    }
+}
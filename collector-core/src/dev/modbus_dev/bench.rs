@@ -0,0 +1,161 @@
+//! 轮询性能测试
+//!
+//! 按设备的读取计划不间断地发出请求(不等待 `interval` 与 `request_interval`)，统计可达到的轮询速率与
+//! 每个请求的往返时间分布；再以最后一圈的应答反复解码，得到不含通信开销的解码吞吐，用于部署前估算硬件。
+
+use std::time::{Duration, Instant};
+
+use super::capture::{Recorder, Response, decode};
+
+/// 解码吞吐的测量时长
+const DECODE_DURATION: Duration = Duration::from_millis(500);
+
+/// 请求往返时间的分布
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latency {
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Latency {
+    /// 没有样本时返回 `None`
+    pub fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let total: Duration = samples.iter().sum();
+        // 最近秩法：第 p 百分位取排序后第 ceil(p·n) 个样本
+        let percentile = |p: usize| samples[(p * samples.len()).div_ceil(100).max(1) - 1];
+        Some(Self {
+            min: samples[0],
+            mean: total / samples.len() as u32,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+        })
+    }
+}
+
+/// 测试结果
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    /// 完整轮询的圈数
+    pub cycles: u64,
+    pub requests: u64,
+    /// 超时、异常码或连接错误的请求数
+    pub errors: u64,
+    /// 轮询用时
+    pub elapsed: Duration,
+    /// 成功请求的往返时间，全部失败时为 `None`
+    pub latency: Option<Latency>,
+    /// 每圈解码的点位数
+    pub points_per_cycle: usize,
+    /// 解码吞吐测量中解码的点位数与用时
+    pub decoded: u64,
+    pub decode_elapsed: Duration,
+}
+
+impl BenchReport {
+    /// 每秒完成的轮询圈数
+    pub fn cycles_per_sec(&self) -> f64 {
+        self.cycles as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn requests_per_sec(&self) -> f64 {
+        self.requests as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// 每秒解码的点位数
+    pub fn decode_per_sec(&self) -> f64 {
+        self.decoded as f64 / self.decode_elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// 连续轮询 `duration`，至少完成一圈
+pub async fn run(recorder: &mut Recorder, duration: Duration) -> BenchReport {
+    let count = recorder.requests().len();
+    let mut samples = Vec::new();
+    let mut last: Vec<Option<Response>> = vec![None; count];
+    let (mut cycles, mut errors) = (0, 0);
+    let start = Instant::now();
+    while cycles == 0 || start.elapsed() < duration {
+        for (index, slot) in last.iter_mut().enumerate() {
+            let sent = Instant::now();
+            let response = recorder.request(index).await;
+            if matches!(response, Response::Error(_)) {
+                errors += 1;
+            } else {
+                samples.push(sent.elapsed());
+                *slot = Some(response);
+            }
+        }
+        cycles += 1;
+    }
+    let elapsed = start.elapsed();
+
+    let points = recorder.points();
+    let responses: Vec<_> = recorder
+        .requests()
+        .iter()
+        .zip(&last)
+        .filter_map(|(&(register_type, address, _), response)| {
+            Some((register_type, address, response.as_ref()?))
+        })
+        .collect();
+    let decode_cycle = || {
+        responses
+            .iter()
+            .map(|&(register_type, address, response)| {
+                std::hint::black_box(decode(&points, register_type, address, response)).len()
+            })
+            .sum::<usize>()
+    };
+    let points_per_cycle = decode_cycle();
+    let mut decoded = 0;
+    let decode_start = Instant::now();
+    if points_per_cycle > 0 {
+        while decode_start.elapsed() < DECODE_DURATION {
+            decoded += decode_cycle() as u64;
+        }
+    }
+
+    BenchReport {
+        cycles,
+        requests: cycles * count as u64,
+        errors,
+        elapsed,
+        latency: Latency::from_samples(samples),
+        points_per_cycle,
+        decoded,
+        decode_elapsed: decode_start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Latency;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        assert!(Latency::from_samples(Vec::new()).is_none());
+        let samples = (1..=200).rev().map(Duration::from_millis).collect();
+        let latency = Latency::from_samples(samples).unwrap();
+        assert_eq!(latency.min, Duration::from_millis(1));
+        assert_eq!(latency.p50, Duration::from_millis(100));
+        assert_eq!(latency.p90, Duration::from_millis(180));
+        assert_eq!(latency.p99, Duration::from_millis(198));
+        assert_eq!(latency.max, Duration::from_millis(200));
+        assert_eq!(latency.mean, Duration::from_micros(100_500));
+
+        let single = Latency::from_samples(vec![Duration::from_millis(7)]).unwrap();
+        assert_eq!(single.p99, Duration::from_millis(7));
+    }
+}
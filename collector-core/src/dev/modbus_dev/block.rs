@@ -2,11 +2,12 @@ use std::collections::BTreeMap;
 use std::time::Duration;
 
 use tokio_modbus::client::{Context, Reader};
+use tracing::warn;
 
 use crate::{
-    config::modbus_conf::{ByteOrder, ModbusConfig, ModbusDataType, RegisterType},
-    core::point::{DataPoint, Val},
-    dev::modbus_dev::ModbusDevError,
+    config::modbus_conf::{ByteOrder, ModbusConfig, ModbusDataType, RegisterType, bcd_to_decimal},
+    core::point::{DataPoint, PointId, Quality, Val},
+    dev::{dev_config::FrameLimits, modbus_dev::ModbusDevError},
 };
 
 #[derive(Debug)]
@@ -32,7 +33,7 @@ impl TryFrom<Vec<ModbusConfig>> for Blocks {
     type Error = BuildBlocksError;
 
     fn try_from(value: Vec<ModbusConfig>) -> Result<Self, Self::Error> {
-        Blocks::build(value, 0)
+        Blocks::build(value, 0, FrameLimits::default())
     }
 }
 
@@ -40,6 +41,7 @@ impl Blocks {
     pub(super) fn build(
         configs: Vec<ModbusConfig>,
         max_gap: u16,
+        limits: FrameLimits,
     ) -> Result<Self, BuildBlocksError> {
         // 1) 按 RegisterType 分组
         let mut groups: BTreeMap<RegisterType, Vec<ModbusConfig>> = BTreeMap::new();
@@ -54,14 +56,25 @@ impl Blocks {
         for (rt, mut pts) in groups {
             pts.sort_by_key(|it| it.register_address);
             let max_len: u16 = match rt {
-                RegisterType::Coils | RegisterType::DiscreteInputs => 2000,
-                RegisterType::HoldingRegisters | RegisterType::InputRegisters => 120,
+                RegisterType::Coils | RegisterType::DiscreteInputs => limits.bits,
+                RegisterType::HoldingRegisters | RegisterType::InputRegisters => limits.registers,
             };
 
             let mut active_range: Option<(u16, u16)> = None;
             let mut current_block: Option<Block> = None;
 
             for cfg in pts {
+                // 同一寄存器的位点位共用一次读取
+                if cfg.bit.is_some()
+                    && let Some(last) = logical_regions.last_mut()
+                    && last.cfg.bit.is_some()
+                    && last.cfg.register_type == rt
+                    && last.cfg.register_address == cfg.register_address
+                {
+                    last.shared.push(cfg);
+                    continue;
+                }
+
                 let cfg_start = cfg.register_address;
                 let cfg_end = cfg.register_address.saturating_add(cfg.quantity);
 
@@ -86,7 +99,10 @@ impl Blocks {
                 }
 
                 let region_idx = logical_regions.len();
-                logical_regions.push(LogicalRegion { cfg });
+                logical_regions.push(LogicalRegion {
+                    cfg,
+                    shared: Vec::new(),
+                });
 
                 let mut region_offset = 0u16;
                 let mut next_addr = cfg.register_address;
@@ -170,6 +186,32 @@ impl Blocks {
             .join(", ")
     }
 
+    /// 第 `index` 个块的原始数据及完全落在该块中的点位的解析结果，用于调试时核对字节序与缩放
+    pub(super) fn dump(&self, index: usize, read: &BlockRead) -> String {
+        let block = &self.blocks[index];
+        let raw: Vec<String> = match read {
+            BlockRead::Coils(data) | BlockRead::DiscreteInputs(data) => {
+                data.iter().map(|bit| u8::from(*bit).to_string()).collect()
+            }
+            BlockRead::HoldingRegisters(data) | BlockRead::InputRegisters(data) => {
+                data.iter().map(|word| format!("{word:#06x}")).collect()
+            }
+        };
+        let decoded: Vec<String> = self
+            .parse_reads((0..self.blocks.len()).map(|i| (i == index).then_some(read)))
+            .iter()
+            .map(|point| format!("{}={}", point.key, point.value))
+            .collect();
+        format!(
+            "{:?}[{:#06x}..+{}] 原始: [{}] 解析: {}",
+            block.register_type,
+            block.start,
+            block.len,
+            raw.join(" "),
+            decoded.join(", ")
+        )
+    }
+
     /// 读取单个 block，不含 interval sleep
     pub(super) async fn request_one(
         &self,
@@ -198,6 +240,33 @@ impl Blocks {
         Ok(read)
     }
 
+    /// 将第 `index` 个块从中间的点位处一分为二，后一半作为第 `index + 1` 个块；
+    /// 两半各自去掉首尾的空洞。只含一个点位的块无法拆分，返回 `false`
+    pub(super) fn split(&mut self, index: usize) -> bool {
+        let block = &mut self.blocks[index];
+        if block.segments.len() < 2 {
+            return false;
+        }
+        let tail = block.segments.split_off(block.segments.len() / 2);
+        let offset = tail[0].block_offset;
+        let tail = Block {
+            register_type: block.register_type,
+            start: block.start.saturating_add(offset),
+            len: block.len - offset,
+            segments: tail
+                .into_iter()
+                .map(|segment| RegionSegment {
+                    block_offset: segment.block_offset - offset,
+                    ..segment
+                })
+                .collect(),
+        };
+        let last = block.segments.last().expect("前一半至少有一个点位");
+        block.len = last.block_offset + last.width;
+        self.blocks.insert(index + 1, tail);
+        true
+    }
+
     pub(super) fn block_count(&self) -> usize {
         self.blocks.len()
     }
@@ -221,11 +290,40 @@ impl Blocks {
     }
 
     pub(super) fn parse(&self, reads: &[BlockRead]) -> Vec<DataPoint> {
+        self.parse_reads(reads.iter().map(Some))
+    }
+
+    /// 解析部分读取的结果，`None` 表示对应的块没有数据，完全落在这些块中的点位不输出
+    pub(super) fn parse_partial(&self, reads: &[Option<BlockRead>]) -> Vec<DataPoint> {
+        self.parse_reads(reads.iter().map(Option::as_ref))
+    }
+
+    /// 第 `index` 个块涉及的点位
+    pub(super) fn point_ids(&self, index: usize) -> Vec<PointId> {
+        let mut ids: Vec<PointId> = Vec::new();
+        for segment in &self.blocks[index].segments {
+            let region = &self.logical_regions[segment.region_idx];
+            for cfg in std::iter::once(&region.cfg).chain(&region.shared) {
+                if !ids.contains(&(cfg.id as PointId)) {
+                    ids.push(cfg.id as PointId);
+                }
+            }
+        }
+        ids
+    }
+
+    fn parse_reads<'a>(
+        &self,
+        reads: impl Iterator<Item = Option<&'a BlockRead>>,
+    ) -> Vec<DataPoint> {
         let mut reg_values: Vec<Option<CollectState<u16>>> = vec![None; self.logical_regions.len()];
         let mut bit_values: Vec<Option<CollectState<bool>>> =
             vec![None; self.logical_regions.len()];
 
-        for (block, read) in self.blocks.iter().zip(reads.iter()) {
+        for (block, read) in self.blocks.iter().zip(reads) {
+            let Some(read) = read else {
+                continue;
+            };
             match (block.register_type, read) {
                 (RegisterType::Coils, BlockRead::Coils(data))
                 | (RegisterType::DiscreteInputs, BlockRead::DiscreteInputs(data)) => {
@@ -251,29 +349,33 @@ impl Blocks {
 
         let mut out = Vec::with_capacity(self.logical_regions.len());
         for (idx, region) in self.logical_regions.iter().enumerate() {
-            let value = match region.cfg.register_type {
-                RegisterType::Coils | RegisterType::DiscreteInputs => bit_values[idx]
-                    .as_ref()
-                    .filter(|state| state.filled == region.cfg.quantity as usize)
-                    .map(|state| decode_bit_value(&region.cfg, &state.values)),
-                RegisterType::HoldingRegisters | RegisterType::InputRegisters => reg_values[idx]
-                    .as_ref()
-                    .filter(|state| state.filled == region.cfg.quantity as usize)
-                    .map(|state| decode_register_value(&region.cfg, &state.values)),
-            };
-            let Some(value) = value else {
-                continue;
-            };
-            out.push(DataPoint {
-                id: region.cfg.id as u32,
-                name: region.cfg.name,
-                value,
-                key: region.cfg.key,
-                translator: region.cfg.trans,
-                bits: region.cfg.warn_bits,
-                words: region.cfg.status_words,
-                unit: region.cfg.unit,
-            });
+            for cfg in std::iter::once(&region.cfg).chain(&region.shared) {
+                let value = match cfg.register_type {
+                    RegisterType::Coils | RegisterType::DiscreteInputs => bit_values[idx]
+                        .as_ref()
+                        .filter(|state| state.filled == cfg.quantity as usize)
+                        .map(|state| decode_bit_value(cfg, &state.values)),
+                    RegisterType::HoldingRegisters | RegisterType::InputRegisters => reg_values
+                        [idx]
+                        .as_ref()
+                        .filter(|state| state.filled == cfg.quantity as usize)
+                        .and_then(|state| decode_register_value(cfg, &state.values)),
+                };
+                let Some(value) = value else {
+                    continue;
+                };
+                out.push(DataPoint {
+                    id: cfg.id as u32,
+                    name: cfg.name,
+                    value,
+                    key: cfg.key,
+                    translator: cfg.trans,
+                    bits: cfg.warn_bits,
+                    words: cfg.status_words,
+                    unit: cfg.unit,
+                    quality: Quality::Good,
+                });
+            }
         }
         out
     }
@@ -290,6 +392,8 @@ pub(super) struct Block {
 #[derive(Debug)]
 struct LogicalRegion {
     cfg: ModbusConfig,
+    /// 与 `cfg` 读取同一寄存器的其他位点位
+    shared: Vec<ModbusConfig>,
 }
 
 #[derive(Debug)]
@@ -330,7 +434,7 @@ fn collect_segments<T: Copy + Default>(
     }
 }
 
-fn decode_bit_value(cfg: &ModbusConfig, data: &[bool]) -> Val {
+pub(super) fn decode_bit_value(cfg: &ModbusConfig, data: &[bool]) -> Val {
     if cfg.quantity == 1 {
         return Val::U8(if data.first().copied().unwrap_or(false) {
             1
@@ -345,8 +449,9 @@ fn decode_bit_value(cfg: &ModbusConfig, data: &[bool]) -> Val {
     )
 }
 
-fn decode_scalar(cfg: &ModbusConfig, data: &[u16]) -> Val {
-    match cfg.data_type {
+/// 解码单个值，BCD 码中出现非十进制数字时返回 `None`
+fn decode_scalar(cfg: &ModbusConfig, data: &[u16]) -> Option<Val> {
+    let val = match cfg.data_type {
         ModbusDataType::Bool => {
             let v = if data.first().copied().unwrap_or(0) != 0 {
                 1u8
@@ -378,10 +483,52 @@ fn decode_scalar(cfg: &ModbusConfig, data: &[u16]) -> Val {
             let v = apply_scale_offset(raw as f64, cfg);
             to_val_numeric(v)
         }
-    }
+        ModbusDataType::F32 => {
+            let raw = f32::from_bits(u32_with_order(data, cfg.byte_order));
+            let v = apply_scale_offset(raw as f64, cfg);
+            to_val_numeric(v)
+        }
+        ModbusDataType::F64 => {
+            let raw = f64::from_bits(u64_with_order(data, cfg.byte_order));
+            let v = apply_scale_offset(raw, cfg);
+            to_val_numeric(v)
+        }
+        ModbusDataType::U64 => {
+            let raw = u64_with_order(data, cfg.byte_order);
+            let v = apply_scale_offset(raw as f64, cfg);
+            to_val_numeric(v)
+        }
+        ModbusDataType::I64 => {
+            let raw = u64_with_order(data, cfg.byte_order) as i64;
+            let v = apply_scale_offset(raw as f64, cfg);
+            to_val_numeric(v)
+        }
+        ModbusDataType::String(_) => decode_string(data, cfg.byte_order),
+        ModbusDataType::Bcd16 => {
+            let raw = u16_with_order(data.first().copied().unwrap_or(0), cfg.byte_order);
+            let Some(raw) = bcd_to_decimal(raw as u32) else {
+                warn!("点位{}的值{:#06x}不是有效的BCD码", cfg.name, raw);
+                return None;
+            };
+            to_val_numeric(apply_scale_offset(raw as f64, cfg))
+        }
+        ModbusDataType::Bcd32 => {
+            let raw = u32_with_order(data, cfg.byte_order);
+            let Some(raw) = bcd_to_decimal(raw) else {
+                warn!("点位{}的值{:#010x}不是有效的BCD码", cfg.name, raw);
+                return None;
+            };
+            to_val_numeric(apply_scale_offset(raw as f64, cfg))
+        }
+    };
+    Some(val)
 }
 
-fn decode_register_value(cfg: &ModbusConfig, data: &[u16]) -> Val {
+pub(super) fn decode_register_value(cfg: &ModbusConfig, data: &[u16]) -> Option<Val> {
+    if let Some(bit) = cfg.bit {
+        let raw = u16_with_order(data.first().copied().unwrap_or(0), cfg.byte_order);
+        return Some(Val::U8(((raw >> bit) & 1) as u8));
+    }
     let item_width = cfg.data_type.register_width() as usize;
     if cfg.quantity as usize == item_width {
         return decode_scalar(cfg, data);
@@ -391,9 +538,9 @@ fn decode_register_value(cfg: &ModbusConfig, data: &[u16]) -> Val {
         if chunk.len() < item_width {
             break;
         }
-        out.push(decode_scalar(cfg, chunk));
+        out.push(decode_scalar(cfg, chunk)?);
     }
-    Val::List(out)
+    Some(Val::List(out))
 }
 
 fn u16_with_order(v: u16, order: Option<ByteOrder>) -> u16 {
@@ -404,24 +551,48 @@ fn u16_with_order(v: u16, order: Option<ByteOrder>) -> u16 {
 }
 
 fn u32_with_order(data: &[u16], order: Option<ByteOrder>) -> u32 {
-    let w0 = data.first().copied().unwrap_or(0);
-    let w1 = data.get(1).copied().unwrap_or(0);
-    match order {
-        Some(ByteOrder::CDAB) => ((w1 as u32) << 16) | (w0 as u32),
-        _ => ((w0 as u32) << 16) | (w1 as u32),
+    let words = [
+        data.first().copied().unwrap_or(0),
+        data.get(1).copied().unwrap_or(0),
+    ];
+    let [w0, w1] = order.map_or(words, |order| order.reorder_u32(words));
+    ((w0 as u32) << 16) | (w1 as u32)
+}
+
+/// 每个寄存器高字节在前（`BA` 时低字节在前），去掉末尾的 NUL 与空格
+fn decode_string(data: &[u16], order: Option<ByteOrder>) -> Val {
+    let bytes: Vec<u8> = data
+        .iter()
+        .flat_map(|word| u16_with_order(*word, order).to_be_bytes())
+        .collect();
+    let text = String::from_utf8_lossy(&bytes);
+    Val::Str(text.trim_end_matches(['\0', ' ']).to_owned())
+}
+
+fn u64_with_order(data: &[u16], order: Option<ByteOrder>) -> u64 {
+    let mut words = [0u16; 4];
+    for (word, value) in words.iter_mut().zip(data) {
+        *word = *value;
     }
+    let words = order.map_or(words, |order| order.reorder_u64(words));
+    words
+        .iter()
+        .fold(0u64, |acc, word| (acc << 16) | *word as u64)
 }
 
 fn apply_scale_offset(raw: f64, cfg: &ModbusConfig) -> f64 {
     raw * cfg.scale + cfg.offset
 }
 
+/// 整数值超出 32 位范围时以 F64 表示，F64 在 2^53 以内可以精确表示整数
 fn to_val_numeric(v: f64) -> Val {
     if v.fract().abs() < 1e-6 {
-        if v >= 0.0 {
+        if (0.0..=u32::MAX as f64).contains(&v) {
             Val::U32(v as u32)
-        } else {
+        } else if (i32::MIN as f64..0.0).contains(&v) {
             Val::I32(v as i32)
+        } else {
+            Val::F64(v.round())
         }
     } else {
         Val::F64((v * 1000.0).floor() / 1000.0)
@@ -431,7 +602,7 @@ fn to_val_numeric(v: f64) -> Val {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::modbus_conf::ModbusDataType;
+    use crate::config::modbus_conf::{ModbusDataType, ScanClass};
 
     fn cfg(
         register_type: RegisterType,
@@ -455,6 +626,12 @@ mod tests {
             trans: None,
             status_words: None,
             warn_bits: None,
+            bit: None,
+            scan_class: ScanClass::Normal,
+            select_address: None,
+            handshake_address: None,
+            pulse_ms: None,
+            epsilon: None,
         }
     }
 
@@ -490,6 +667,39 @@ mod tests {
         assert_eq!(blocks.blocks[1].len, 1);
     }
 
+    #[test]
+    fn build_blocks_merges_gaps_within_max_gap() {
+        let a = cfg(RegisterType::InputRegisters, 0, ModbusDataType::U16);
+        let b = cfg(RegisterType::InputRegisters, 3, ModbusDataType::U32);
+        let c = cfg(RegisterType::InputRegisters, 8, ModbusDataType::U16);
+        let blocks = Blocks::build(vec![a, b, c], 2, FrameLimits::default()).unwrap();
+        assert_eq!(blocks.blocks.len(), 2);
+        assert_eq!(blocks.blocks[0].start, 0);
+        assert_eq!(blocks.blocks[0].len, 5);
+        assert_eq!(blocks.blocks[1].start, 8);
+
+        let reads = [
+            BlockRead::InputRegisters(vec![7, 0xFFFF, 0xFFFF, 0, 42]),
+            BlockRead::InputRegisters(vec![9]),
+        ];
+        let values: Vec<Val> = blocks.parse(&reads).into_iter().map(|p| p.value).collect();
+        assert_eq!(values, [Val::U32(7), Val::U32(42), Val::U32(9)]);
+    }
+
+    #[test]
+    fn build_blocks_respects_frame_limits() {
+        let configs: Vec<ModbusConfig> = (0u16..100)
+            .map(|addr| cfg(RegisterType::InputRegisters, addr, ModbusDataType::U16))
+            .collect();
+        let limits = FrameLimits {
+            registers: 60,
+            bits: 2000,
+        };
+        let blocks = Blocks::build(configs, 0, limits).unwrap();
+        let lens: Vec<u16> = blocks.blocks.iter().map(|block| block.len).collect();
+        assert_eq!(lens, [60, 40]);
+    }
+
     #[test]
     fn build_blocks_splits_on_max_len() {
         let mut configs: Vec<ModbusConfig> = Vec::new();
@@ -516,7 +726,167 @@ mod tests {
 
         let val = decode_register_value(&cfg, &[10, 20, 30]);
 
-        assert_eq!(val, Val::List(vec![Val::U32(1), Val::U32(2), Val::U32(3)]));
+        assert_eq!(
+            val,
+            Some(Val::List(vec![Val::U32(1), Val::U32(2), Val::U32(3)]))
+        );
+    }
+
+    #[test]
+    fn decode_f32_honors_word_order() {
+        let mut point = cfg(RegisterType::HoldingRegisters, 0, ModbusDataType::F32);
+        let bits = 230.5f32.to_bits();
+        let (hi, lo) = ((bits >> 16) as u16, bits as u16);
+
+        assert_eq!(
+            decode_register_value(&point, &[hi, lo]),
+            Some(Val::F64(230.5))
+        );
+        point.byte_order = Some(ByteOrder::CDAB);
+        assert_eq!(
+            decode_register_value(&point, &[lo, hi]),
+            Some(Val::F64(230.5))
+        );
+        point.scale = 2.0;
+        assert_eq!(
+            decode_register_value(&point, &[lo, hi]),
+            Some(Val::U32(461))
+        );
+    }
+
+    #[test]
+    fn decode_32_bit_byte_orders() {
+        let mut point = cfg(RegisterType::HoldingRegisters, 0, ModbusDataType::U32);
+        let cases = [
+            (ByteOrder::ABCD, [0x1122, 0x3344]),
+            (ByteOrder::CDAB, [0x3344, 0x1122]),
+            (ByteOrder::DCBA, [0x4433, 0x2211]),
+            (ByteOrder::BADC, [0x2211, 0x4433]),
+        ];
+        for (order, words) in cases {
+            point.byte_order = Some(order);
+            assert_eq!(order.assemble_u32(0x1122_3344), words);
+            assert_eq!(
+                decode_register_value(&point, &words),
+                Some(Val::U32(0x1122_3344))
+            );
+        }
+    }
+
+    #[test]
+    fn decode_f64_honors_word_orders() {
+        let mut point = cfg(RegisterType::InputRegisters, 0, ModbusDataType::F64);
+        let big = ByteOrder::ABCDEFGH.assemble_u64(123456.789f64.to_bits());
+        assert_eq!(point.quantity, 4);
+        assert_eq!(
+            decode_register_value(&point, &big),
+            Some(Val::F64(123456.789))
+        );
+
+        for order in [
+            ByteOrder::CDAB,
+            ByteOrder::GHEFCDAB,
+            ByteOrder::BADCFEHG,
+            ByteOrder::HGFEDCBA,
+        ] {
+            let words = order.assemble_u64(123456.789f64.to_bits());
+            assert_ne!(words, big);
+            point.byte_order = Some(order);
+            assert_eq!(
+                decode_register_value(&point, &words),
+                Some(Val::F64(123456.789))
+            );
+        }
+    }
+
+    #[test]
+    fn decode_64_bit_counters_beyond_u32() {
+        let mut point = cfg(RegisterType::InputRegisters, 0, ModbusDataType::U64);
+        let kwh: u64 = 5_000_000_000;
+        let words = ByteOrder::ABCDEFGH.assemble_u64(kwh);
+        assert_eq!(decode_register_value(&point, &words), Some(Val::F64(5e9)));
+        point.scale = 0.001;
+        assert_eq!(
+            decode_register_value(&point, &words),
+            Some(Val::U32(5_000_000))
+        );
+
+        let mut point = cfg(RegisterType::InputRegisters, 0, ModbusDataType::I64);
+        point.byte_order = Some(ByteOrder::GHEFCDAB);
+        let words = ByteOrder::GHEFCDAB.assemble_u64(-42i64 as u64);
+        assert_eq!(decode_register_value(&point, &words), Some(Val::I32(-42)));
+    }
+
+    #[test]
+    fn decode_string_trims_padding() {
+        let mut point = cfg(RegisterType::HoldingRegisters, 0, ModbusDataType::String(4));
+        let words = [0x5356, 0x2D31, 0x3200, 0x0000];
+        assert_eq!(point.quantity, 4);
+        assert_eq!(
+            decode_register_value(&point, &words),
+            Some(Val::Str("SV-12".to_owned()))
+        );
+
+        point.byte_order = Some(ByteOrder::BA);
+        let swapped = words.map(u16::swap_bytes);
+        assert_eq!(
+            decode_register_value(&point, &swapped),
+            Some(Val::Str("SV-12".to_owned()))
+        );
+    }
+
+    #[test]
+    fn decode_bcd_before_scale() {
+        let mut point = cfg(RegisterType::HoldingRegisters, 0, ModbusDataType::Bcd16);
+        point.scale = 0.1;
+        assert_eq!(
+            decode_register_value(&point, &[0x2305]),
+            Some(Val::F64(230.5))
+        );
+        assert_eq!(decode_register_value(&point, &[0x12A4]), None);
+
+        let mut point = cfg(RegisterType::HoldingRegisters, 0, ModbusDataType::Bcd32);
+        assert_eq!(point.quantity, 2);
+        assert_eq!(
+            decode_register_value(&point, &[0x0012, 0x3456]),
+            Some(Val::U32(123456))
+        );
+        point.byte_order = Some(ByteOrder::CDAB);
+        assert_eq!(
+            decode_register_value(&point, &[0x3456, 0x0012]),
+            Some(Val::U32(123456))
+        );
+    }
+
+    #[test]
+    fn bit_points_share_one_register_read() {
+        let word = cfg(RegisterType::HoldingRegisters, 9, ModbusDataType::U16);
+        let bits: Vec<ModbusConfig> = [0u8, 3, 15]
+            .into_iter()
+            .map(|bit| {
+                let mut point = cfg(RegisterType::HoldingRegisters, 10, ModbusDataType::U16);
+                point.id = 10 + bit as u16;
+                point.bit = Some(bit);
+                point
+            })
+            .collect();
+        let mut configs = vec![word];
+        configs.extend(bits);
+        let blocks = Blocks::try_from(configs).unwrap();
+
+        assert_eq!(blocks.blocks.len(), 1);
+        assert_eq!(blocks.blocks[0].len, 2);
+        let parsed = blocks.parse(&[BlockRead::HoldingRegisters(vec![7, 0b1000_0000_0000_1000])]);
+        let values: Vec<(u32, Val)> = parsed.into_iter().map(|p| (p.id, p.value)).collect();
+        assert_eq!(
+            values,
+            vec![
+                (1, Val::U32(7)),
+                (10, Val::U8(0)),
+                (13, Val::U8(1)),
+                (25, Val::U8(1)),
+            ]
+        );
     }
 
     #[test]
@@ -572,4 +942,19 @@ mod tests {
 
         assert!(parsed.is_empty());
     }
+
+    #[test]
+    fn dump_shows_raw_words_and_decoded_points() {
+        let mut volts = cfg(RegisterType::HoldingRegisters, 0, ModbusDataType::U16);
+        volts.key = "volts";
+        volts.scale = 0.1;
+        let blocks = Blocks::try_from(vec![volts]).unwrap();
+
+        let dump = blocks.dump(0, &BlockRead::HoldingRegisters(vec![0x0898]));
+
+        assert_eq!(
+            dump,
+            "HoldingRegisters[0x0000..+1] 原始: [0x0898] 解析: volts=220"
+        );
+    }
 }
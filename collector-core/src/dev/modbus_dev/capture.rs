@@ -0,0 +1,375 @@
+//! 报文抓取与回放
+//!
+//! 抓取时按设备的读取计划逐块读取，每个请求的应答(或错误)连同解码后的点位值记为一行 JSON；
+//! 回放时按当前的点位表重新解码抓包中的应答并写入数据中心，与抓取时的解码结果比较。
+//! 现场问题可以带回离线排查，修改点位表后也能用同一份抓包核对字节序与缩放。
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::center::SharedPointCenter;
+use crate::config::Device;
+use crate::config::modbus_conf::{ModbusConfig, ModbusConfigs, RegisterType};
+use crate::core::point::{DataPoint, Quality, Val};
+use crate::dev::maintenance::MaintenanceOutput;
+use crate::dev::{DeviceError, Identifiable};
+
+use super::ModbusDev;
+use super::block::{decode_bit_value, decode_register_value};
+use super::oneshot::Session;
+use super::runner::{build_plan, read_raw};
+
+/// 抓包文件中的一行：一次读取请求及其应答
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// 收到应答的时刻，UNIX 毫秒
+    pub at: u64,
+    pub device: String,
+    pub register_type: RegisterType,
+    /// 协议地址，已按设备的 `address_base` 换算
+    pub address: u16,
+    pub quantity: u16,
+    #[serde(flatten)]
+    pub response: Response,
+    /// 抓取时解码的点位值，键为点位键
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, Val>,
+}
+
+/// 从站的应答
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Registers(Vec<u16>),
+    Bits(Vec<bool>),
+    /// 超时、异常码或连接错误
+    Error(String),
+}
+
+/// 按点位表解码完全落在应答范围内的点位
+pub(super) fn decode(
+    points: &[ModbusConfig],
+    register_type: RegisterType,
+    address: u16,
+    response: &Response,
+) -> Vec<DataPoint> {
+    points
+        .iter()
+        .filter(|cfg| cfg.register_type == register_type && cfg.register_address >= address)
+        .filter_map(|cfg| {
+            let offset = usize::from(cfg.register_address - address);
+            let range = offset..offset + usize::from(cfg.quantity);
+            let value = match response {
+                Response::Registers(registers) => {
+                    decode_register_value(cfg, registers.get(range)?)?
+                }
+                Response::Bits(bits) => decode_bit_value(cfg, bits.get(range)?),
+                Response::Error(_) => return None,
+            };
+            Some(DataPoint {
+                id: cfg.id as u32,
+                key: cfg.key,
+                name: cfg.name,
+                value,
+                translator: cfg.trans,
+                bits: cfg.warn_bits,
+                words: cfg.status_words,
+                unit: cfg.unit,
+                quality: Quality::Good,
+            })
+        })
+        .collect()
+}
+
+/// 抓包中的值按 JSON 读回，整数类型不一定与解码时相同，按序列化结果比较
+fn same(recorded: Option<&Val>, replayed: Option<&Val>) -> bool {
+    match (recorded, replayed) {
+        (Some(recorded), Some(replayed)) => {
+            serde_json::to_value(recorded).ok() == serde_json::to_value(replayed).ok()
+        }
+        (recorded, replayed) => recorded.is_none() && replayed.is_none(),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64)
+}
+
+/// 按设备的读取计划逐块抓取
+pub struct Recorder {
+    session: Session,
+    /// 各读取块的寄存器类型、起始地址与数量
+    requests: Vec<(RegisterType, u16, u16)>,
+}
+
+impl Recorder {
+    /// 读取计划与轮询时相同：同样的合并空洞、帧长限制与扫描等级划分
+    pub fn new(session: Session) -> Result<Self, DeviceError> {
+        let dev = session.dev();
+        let plan = build_plan(dev.protocol(), &dev.points())
+            .map_err(|err| DeviceError::DevRuntimeError(Box::new(err)))?;
+        let requests = plan
+            .blocks()
+            .flat_map(|blocks| &blocks.blocks)
+            .map(|block| (block.register_type, block.start, block.len))
+            .collect();
+        Ok(Self { session, requests })
+    }
+
+    pub fn id(&self) -> &str {
+        self.session.dev().id()
+    }
+
+    /// 每圈的请求数
+    pub fn request_count(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// 设备的轮询周期
+    pub fn interval(&self) -> Duration {
+        self.session.dev().protocol().interval()
+    }
+
+    /// 各请求的寄存器类型、起始地址与数量
+    pub(super) fn requests(&self) -> &[(RegisterType, u16, u16)] {
+        &self.requests
+    }
+
+    pub(super) fn points(&self) -> Arc<ModbusConfigs> {
+        self.session.dev().points()
+    }
+
+    /// 发出第 `index` 个请求，失败时返回错误应答
+    pub(super) async fn request(&mut self, index: usize) -> Response {
+        let (register_type, address, quantity) = self.requests[index];
+        let (dev, ctx) = self.session.parts();
+        let timeout = dev.protocol().timeout();
+        match read_raw(ctx, register_type, address, quantity, timeout).await {
+            Ok(MaintenanceOutput::Registers(registers)) => Response::Registers(registers),
+            Ok(MaintenanceOutput::Bits(bits)) => Response::Bits(bits),
+            Ok(MaintenanceOutput::Done) => Response::Error("空应答".to_owned()),
+            Err(err) => Response::Error(err),
+        }
+    }
+
+    /// 依次发出一圈请求，相邻请求按设备的 `request_interval` 间隔；失败的请求记为错误应答
+    pub async fn cycle(&mut self) -> Vec<Frame> {
+        let request_interval = self.session.dev().protocol().request_interval();
+        let points = self.points();
+        let mut frames = Vec::with_capacity(self.requests.len());
+        for index in 0..self.requests.len() {
+            if index > 0 && !request_interval.is_zero() {
+                tokio::time::sleep(request_interval).await;
+            }
+            let response = self.request(index).await;
+            let (register_type, address, quantity) = self.requests[index];
+            let values = decode(&points, register_type, address, &response)
+                .into_iter()
+                .map(|point| (point.key.to_owned(), point.value))
+                .collect();
+            frames.push(Frame {
+                at: now_ms(),
+                device: self.id().to_owned(),
+                register_type,
+                address,
+                quantity,
+                response,
+                values,
+            });
+        }
+        frames
+    }
+}
+
+/// 回放一帧的结果
+#[derive(Debug, Clone)]
+pub struct Replayed {
+    /// 按当前点位表解码并已写入数据中心的点位
+    pub points: Vec<DataPoint>,
+    /// 与抓取时解码结果不同的点位
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// 同一点位抓取时与回放时的解码结果，`None` 表示该次没有解码出这个点位
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub key: String,
+    pub recorded: Option<Val>,
+    pub replayed: Option<Val>,
+}
+
+/// 按设备当前的点位表回放抓包
+pub struct Replayer {
+    tables: HashMap<String, Arc<ModbusConfigs>>,
+    center: SharedPointCenter,
+}
+
+impl Replayer {
+    /// `devices` 须已加载点位表，地址换算、键前缀与缺省字节序与运行时相同
+    pub fn new(
+        devices: impl IntoIterator<Item = Device>,
+        center: SharedPointCenter,
+    ) -> Result<Self, DeviceError> {
+        let mut tables = HashMap::new();
+        for dev in devices {
+            let dev = ModbusDev::new(dev, center.clone())?;
+            tables.insert(dev.id().to_owned(), dev.points());
+        }
+        Ok(Self { tables, center })
+    }
+
+    /// 重新解码一帧并写入数据中心，抓包中的设备不在配置中时返回 `None`
+    pub fn replay(&self, frame: &Frame) -> Option<Replayed> {
+        let points = self.tables.get(&frame.device)?;
+        let points = decode(points, frame.register_type, frame.address, &frame.response);
+        let replayed: BTreeMap<&str, &Val> = points
+            .iter()
+            .map(|point| (point.key, &point.value))
+            .collect();
+        let keys: BTreeSet<&str> = frame
+            .values
+            .keys()
+            .map(String::as_str)
+            .chain(replayed.keys().copied())
+            .collect();
+        let mismatches = keys
+            .into_iter()
+            .filter_map(|key| {
+                let recorded = frame.values.get(key);
+                let replayed = replayed.get(key).copied();
+                (!same(recorded, replayed)).then(|| Mismatch {
+                    key: key.to_owned(),
+                    recorded: recorded.cloned(),
+                    replayed: replayed.cloned(),
+                })
+            })
+            .collect();
+        if !points.is_empty() {
+            self.center.ingest(&frame.device, points.clone());
+        }
+        Some(Replayed { points, mismatches })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::net::TcpListener;
+    use tokio_util::sync::CancellationToken;
+
+    use super::{Frame, Recorder, Replayer, Response};
+    use crate::center::{DataCenter, PointCenter};
+    use crate::config::Device;
+    use crate::dev::modbus_dev::oneshot::Session;
+    use crate::dev::modbus_dev::simulator::{Simulator, SimulatorOptions};
+
+    async fn device(dir: &std::path::Path, port: u16, scale: f64) -> Device {
+        let table = dir.join(format!("points-{scale}.json"));
+        std::fs::write(
+            &table,
+            format!(
+                r#"[{{ id: 1, name: "SOC", data_type: "U16", scale: {scale},
+                register_address: 40002, register_type: "HoldingRegisters", quantity: 1, key: "soc" }},
+                {{ id: 2, name: "运行", data_type: "Bool", register_address: 1,
+                register_type: "Coils", quantity: 1, key: "run" }}]"#
+            ),
+        )
+        .unwrap();
+        let json = format!(
+            r#"{{"id": "bms", "config": {{"com_type": "ModbusTCP", "ip": "127.0.0.1", "port": {port},
+            "slave": 1, "interval": 1000, "timeout": 500, "address_base": 1, "register_file": "{}"}}}}"#,
+            table.display()
+        );
+        let mut dev: Device = serde_json::from_str(&json).unwrap();
+        dev.load_protocol_configs().await.unwrap();
+        dev
+    }
+
+    #[tokio::test]
+    async fn recorded_frames_replay_through_the_point_table() {
+        let dir = std::env::temp_dir().join(format!("collector-capture-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let dev = device(&dir, port, 0.1).await;
+        let rescaled = device(&dir, port, 1.0).await;
+        let simulator = Simulator::load(
+            dir.join("points-0.1.json").to_str().unwrap(),
+            1,
+            None,
+            SimulatorOptions {
+                min: 10.0,
+                ..SimulatorOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        let token = CancellationToken::new();
+        tokio::spawn(simulator.serve(listener, token.clone()));
+
+        let mut recorder = Recorder::new(Session::connect(dev.clone()).await.unwrap()).unwrap();
+        assert_eq!(recorder.id(), "bms");
+        assert_eq!(recorder.request_count(), 2);
+        let frames = recorder.cycle().await;
+        token.cancel();
+        assert_eq!(frames.len(), 2);
+        let soc = frames
+            .iter()
+            .find(|frame| matches!(frame.response, Response::Registers(_)))
+            .unwrap();
+        assert_eq!(soc.address, 1);
+        assert!(soc.values.contains_key("soc"));
+
+        // 抓包逐行序列化后读回，再次序列化的结果不变
+        let lines: Vec<String> = frames
+            .iter()
+            .map(|frame| serde_json::to_string(frame).unwrap())
+            .collect();
+        let loaded: Vec<Frame> = lines
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        for (frame, line) in loaded.iter().zip(&lines) {
+            assert_eq!(&serde_json::to_string(frame).unwrap(), line);
+        }
+
+        let center = Arc::new(DataCenter::new(1));
+        let replayer = Replayer::new([dev], center.clone()).unwrap();
+        for frame in &loaded {
+            let replayed = replayer.replay(frame).unwrap();
+            assert!(replayed.mismatches.is_empty());
+        }
+        assert_eq!(
+            center
+                .read_by_key("bms", "soc")
+                .unwrap()
+                .value
+                .as_f64()
+                .unwrap(),
+            10.0
+        );
+
+        // 修改缩放后回放，解码结果不同的点位被报告
+        let replayer = Replayer::new([rescaled], Arc::new(DataCenter::new(1))).unwrap();
+        let replayed = replayer.replay(soc).unwrap();
+        assert_eq!(replayed.mismatches.len(), 1);
+        assert_eq!(replayed.mismatches[0].key, "soc");
+        assert_eq!(
+            replayed.mismatches[0].recorded,
+            Some(soc.values["soc"].clone())
+        );
+        let rescaled = replayed.mismatches[0].replayed.as_ref().unwrap();
+        assert_eq!(rescaled.as_f64().unwrap(), 100.0);
+
+        let mut other = soc.clone();
+        other.device = "pcs".to_owned();
+        assert!(replayer.replay(&other).is_none());
+    }
+}
@@ -1,32 +1,105 @@
+use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::sync::{Mutex, watch};
+use tokio::sync::{Mutex, mpsc, watch};
 use tokio::task::JoinHandle;
-use tokio::time;
 use tracing::{info, warn};
 
-use crate::center::{DataCenterError, SharedPointCenter};
-use crate::config::modbus_conf::ModbusConfigs;
+use crate::center::{DataCenterError, DownlinkCommand, SharedPointCenter};
+use crate::config::modbus_conf::{
+    self, ByteOrder, ModbusConfig, ModbusConfigs, RegisterType, XlsxOptions,
+};
 use crate::config::{self, Device};
-use crate::core::point::DownDataPoint;
 use crate::dev::modbus_dev::Protocol;
 use crate::dev::{
-    DeviceError, Executable, Identifiable, Lifecycle, LifecycleState,
+    self, DeviceError, DeviceExit, DevicePlan, Executable, Identifiable, Lifecycle, LifecycleState,
+    connect_limit::ConnectLimit,
     dev_config::{ModbusRtuConfig, ModbusTcpConfig},
+    diagnostics::{Diagnostics, DiagnosticsSnapshot},
+    maintenance::{self, Maintenance},
     state::SharedState,
 };
 
-use super::runner::ModbusRunner;
+use super::failover::Failover;
+use super::runner::{self, ModbusRunner};
 
 pub struct ModbusDev {
     id: String,
     protocol: Protocol,
-    configs: ModbusConfigs,
+    /// 当前的点位表，热更新时替换，运行中的任务在下一轮询周期换用
+    points_tx: watch::Sender<Arc<ModbusConfigs>>,
+    /// 热更新时读取点位表的选项
+    table: PointTable,
     state: SharedState,
     stop_tx: watch::Sender<bool>,
     stop_rx: watch::Receiver<bool>,
+    pause_tx: watch::Sender<bool>,
+    /// 交给运行中的任务的维护请求，每次启动时重建
+    maintenance_tx: Option<maintenance::RequestSender>,
     task: Mutex<Option<JoinHandle<()>>>,
     center: SharedPointCenter,
+    diagnostics: Diagnostics,
+    connect_limit: ConnectLimit,
+    /// 停止时等待后台任务结束的时间
+    stop_timeout: Duration,
+}
+
+/// 点位表的读取与换算选项
+pub(super) struct PointTable {
+    options: XlsxOptions,
+    /// 设备级字节序，作为点表中未指定字节序的点位的缺省值
+    default_order: Option<ByteOrder>,
+    address_base: u16,
+    key_prefix: Option<String>,
+}
+
+impl PointTable {
+    /// 不属于设备的点位表（如模拟器）：按缺省的工作表与列严格读取，不加键前缀
+    pub(super) fn standalone(address_base: u16, default_order: Option<ByteOrder>) -> Self {
+        Self {
+            options: XlsxOptions {
+                strict: true,
+                ..XlsxOptions::default()
+            },
+            default_order,
+            address_base,
+            key_prefix: None,
+        }
+    }
+
+    /// 读取点位表文件并换算，`dev_id` 用于日志
+    pub(super) async fn load(
+        &self,
+        path: &str,
+        dev_id: Option<String>,
+    ) -> Result<ModbusConfigs, DeviceError> {
+        let options = self.options.clone();
+        let configs = config::load_configs(
+            path.to_owned(),
+            dev_id,
+            move |path| modbus_conf::build_configs(path, &options),
+            |configs| configs,
+        )
+        .await
+        .map_err(DeviceError::PointTable)?;
+        self.prepare(configs)
+    }
+
+    /// 只保留启用的点位，补全字节序、加上键前缀并换算为协议地址
+    fn prepare(&self, configs: ModbusConfigs) -> Result<ModbusConfigs, DeviceError> {
+        configs
+            .into_iter()
+            .filter(|cfg| cfg.enable)
+            .map(|mut cfg| {
+                cfg.byte_order = cfg.byte_order.or(self.default_order);
+                if let Some(prefix) = &self.key_prefix {
+                    // 与点位表中的其他字符串一样泄漏为 'static
+                    cfg.key = format!("{prefix}{}", cfg.key).leak();
+                }
+                to_protocol_addresses(cfg, self.address_base)
+            })
+            .collect()
+    }
 }
 
 impl ModbusDev {
@@ -45,6 +118,23 @@ impl ModbusDev {
         let Some(configs) = dev.protocol_configs else {
             return Err(DeviceError::NotFoundConfigs(id));
         };
+        let default_order = match dev.config.byte_order.as_deref() {
+            Some(order) => Some(
+                ByteOrder::try_from(Some(order))
+                    .map_err(|_| DeviceError::InvalidByteOrder(order.to_owned()))?,
+            ),
+            None => None,
+        };
+        let table = PointTable {
+            options: XlsxOptions {
+                sheets: dev.config.sheets.clone(),
+                columns: dev.config.columns.clone(),
+                strict: dev.config.strict_point_tables == Some(true),
+            },
+            default_order,
+            address_base: dev.config.address_base.unwrap_or(0),
+            key_prefix: dev.config.key_prefix.clone(),
+        };
         let configs = match configs {
             config::ProtocolConfigs::Modbus(modbus_configs) => modbus_configs,
             #[cfg(target_os = "linux")]
@@ -54,10 +144,9 @@ impl ModbusDev {
             config::ProtocolConfigs::None => {
                 return Err(DeviceError::NotFoundConfigs(id));
             }
-        }
-        .into_iter()
-        .filter(|cfg| cfg.enable)
-        .collect();
+        };
+        let configs = table.prepare(configs)?;
+        let stop_timeout = dev::stop_timeout(dev.config.stop_timeout);
         let protocol = match com_type {
             config::ComType::ModbusTCP => {
                 let tcp_config = ModbusTcpConfig::try_from(dev.config)?;
@@ -73,18 +162,51 @@ impl ModbusDev {
         let state = SharedState::new(LifecycleState::New);
         let (stop_tx, stop_rx) = watch::channel(false);
         info!("加载{}配置成功!", id);
+        let diagnostics = Diagnostics::register(&id);
         Ok(ModbusDev {
             id,
             protocol,
+            points_tx: watch::Sender::new(Arc::new(configs)),
+            table,
             state,
-            configs,
             stop_tx,
             stop_rx,
+            pause_tx: watch::Sender::new(false),
+            maintenance_tx: None,
             task: Mutex::new(None),
             center,
+            diagnostics,
+            connect_limit: ConnectLimit::default(),
+            stop_timeout,
         })
     }
 
+    /// 与其他设备共享同时连接数限制
+    pub fn with_connect_limit(mut self, limit: ConnectLimit) -> Self {
+        self.connect_limit = limit;
+        self
+    }
+
+    /// 设备的通讯诊断计数
+    pub fn diagnostics(&self) -> DiagnosticsSnapshot {
+        self.diagnostics.snapshot()
+    }
+
+    pub(super) fn protocol(&self) -> &Protocol {
+        &self.protocol
+    }
+
+    /// 当前的点位表，地址已换算为协议地址
+    pub(super) fn points(&self) -> Arc<ModbusConfigs> {
+        self.points_tx.borrow().clone()
+    }
+
+    /// 按点位表的规则补全点位表之外的点位：缺省字节序与地址换算与点位表相同
+    pub(super) fn prepare_point(&self, cfg: ModbusConfig) -> Result<ModbusConfig, DeviceError> {
+        let mut configs = self.table.prepare(vec![cfg])?;
+        configs.pop().ok_or(DeviceError::Unsupported("禁用的点位"))
+    }
+
     /// 获取设备的生命周期状态
     /// # 返回值
     /// - `LifecycleState`: 设备的生命周期状态
@@ -99,7 +221,7 @@ impl ModbusDev {
     /// # 返回值
     /// - `bool`: 是否成功改变状态
     fn cas_state(&self, from: LifecycleState, to: LifecycleState) -> bool {
-        self.state.cas(from, to)
+        self.state.cas(&self.id, from, to)
     }
 
     /// 存储设备的生命周期状态
@@ -110,6 +232,27 @@ impl ModbusDev {
     }
 }
 
+/// 将点位表中按 `base` 编号的地址换算为协议地址，选择地址、握手地址一并换算
+fn to_protocol_addresses(mut cfg: ModbusConfig, base: u16) -> Result<ModbusConfig, DeviceError> {
+    let convert = |register_type: RegisterType, address: u16| {
+        register_type
+            .protocol_address(address, base)
+            .ok_or(DeviceError::InvalidAddress {
+                name: cfg.name,
+                address,
+                base,
+            })
+    };
+    cfg.register_address = convert(cfg.register_type, cfg.register_address)?;
+    if let Some(address) = cfg.select_address {
+        cfg.select_address = Some(convert(cfg.register_type, address)?);
+    }
+    if let Some(address) = cfg.handshake_address {
+        cfg.handshake_address = Some(convert(RegisterType::HoldingRegisters, address)?);
+    }
+    Ok(cfg)
+}
+
 impl Identifiable for ModbusDev {
     fn id(&self) -> &str {
         &self.id
@@ -132,11 +275,14 @@ impl Lifecycle for ModbusDev {
     async fn start(&mut self) -> Result<(), DeviceError> {
         //尝试将状态改为启动中，如果失败则不动作
         let ok = self.cas_state(LifecycleState::Ready, LifecycleState::Starting)
-            || self.cas_state(LifecycleState::Stopped, LifecycleState::Starting);
+            || self.cas_state(LifecycleState::Stopped, LifecycleState::Starting)
+            // 意外结束的任务由监督重新启动
+            || (self.exit().is_some()
+                && self.cas_state(LifecycleState::Failed, LifecycleState::Starting));
         if !ok {
             return Ok(());
         }
-        let (tx, rx) = tokio::sync::mpsc::channel::<Vec<DownDataPoint>>(16);
+        let (tx, rx) = tokio::sync::mpsc::channel::<DownlinkCommand>(16);
         //将设备注册到消息中心
         match self.center.attach_downlink(&self.id, tx.clone()) {
             Ok(()) => {}
@@ -158,19 +304,29 @@ impl Lifecycle for ModbusDev {
         if let Some(handle) = task_guard.take() {
             handle.abort();
         }
+        let (maintenance_tx, maintenance_rx) = mpsc::channel(maintenance::CAPACITY);
+        self.maintenance_tx = Some(maintenance_tx);
         let runner = ModbusRunner {
             id: self.id.clone(),
             protocol: self.protocol.clone(),
-            configs: self.configs.clone(),
+            configs: self.points_tx.borrow().as_ref().clone(),
+            points_rx: self.points_tx.subscribe(),
             state: self.state.clone(),
             stop_rx: self.stop_rx.clone(),
+            pause_rx: self.pause_tx.subscribe(),
             rx,
+            maintenance_rx,
             center: self.center.clone(),
+            diagnostics: self.diagnostics.clone(),
+            connect_limit: self.connect_limit.clone(),
+            failover: Failover::new(self.protocol.has_backup()),
         };
         //启动任务
-        let handle = tokio::spawn(async move {
-            runner.run().await;
-        });
+        let handle = tokio::spawn(dev::catch_panic(
+            self.id.clone(),
+            self.state.clone(),
+            runner.run(),
+        ));
         //把任务放回去
         *task_guard = Some(handle);
         Ok(())
@@ -195,14 +351,16 @@ impl Lifecycle for ModbusDev {
         //注销设备
         self.center.detach_downlink(&self.id);
         let mut task_guard = self.task.lock().await;
-        if let Some(mut handle) = task_guard.take() {
-            //等待任务结束
-            tokio::select! {
-                _ = time::sleep(Duration::from_secs(3)) => {
-                    handle.abort();
-                }
-                _ = &mut handle => {}
-            }
+        //等待任务结束
+        let aborted = match task_guard.take() {
+            Some(mut handle) => dev::join_or_abort(&mut handle, self.stop_timeout).await,
+            None => false,
+        };
+        info!("[{}] 通讯诊断: {:?}", self.id, self.diagnostics());
+        if aborted {
+            // 中止的任务来不及更新状态，否则无法再次启动
+            self.store_state(LifecycleState::Stopped);
+            return Err(DeviceError::ForceAborted(self.id.clone()));
         }
         Ok(())
     }
@@ -210,6 +368,175 @@ impl Lifecycle for ModbusDev {
     fn state(&self) -> LifecycleState {
         self.load_state()
     }
+
+    fn exit(&self) -> Option<DeviceExit> {
+        if *self.stop_rx.borrow() {
+            return None;
+        }
+        let task = self.task.try_lock().ok()?;
+        if !task.as_ref()?.is_finished() {
+            return None;
+        }
+        if self.load_state() == LifecycleState::Stopped {
+            return Some(DeviceExit::Completed);
+        }
+        // panic 的任务已由 catch_panic 标记为失败，其余未停止就结束的任务同样标记为失败
+        self.store_state(LifecycleState::Failed);
+        Some(DeviceExit::Failed)
+    }
+
+    fn set_paused(&self, paused: bool) -> Result<(), DeviceError> {
+        if self.pause_tx.send_replace(paused) != paused {
+            info!("[{}] {}轮询", self.id, if paused { "暂停" } else { "恢复" });
+        }
+        Ok(())
+    }
+
+    /// 读取点位表并检查能否构建读取块，通过后交给运行中的任务在下一轮询周期换用，不断开连接
+    async fn reload_points(&self, path: &str) -> Result<(), DeviceError> {
+        let configs = self.table.load(path, Some(self.id.clone())).await?;
+        runner::build_plan(&self.protocol, &configs)
+            .map_err(|err| DeviceError::PointTable(format!("{path}: {err}")))?;
+        info!(
+            "[{}] 已加载点位表 {}, 共{}个点位, 下一轮询周期生效",
+            self.id,
+            path,
+            configs.len()
+        );
+        self.points_tx.send_replace(Arc::new(configs));
+        Ok(())
+    }
+
+    fn plan(&self) -> Result<Option<DevicePlan>, DeviceError> {
+        let configs = self.points_tx.borrow().clone();
+        let plan = runner::build_plan(&self.protocol, &configs)
+            .map_err(|err| DeviceError::PointTable(err.to_string()))?;
+        Ok(Some(DevicePlan {
+            points: configs.len(),
+            blocks: plan.block_count(),
+        }))
+    }
+
+    fn maintain(&self, op: Maintenance, reply: maintenance::Reply) {
+        let accepted = match self.load_state() {
+            LifecycleState::Connected | LifecycleState::Running | LifecycleState::Paused => true,
+            // 连接中或连接失败等待退避时只接受重连，唤醒退避立即连接
+            LifecycleState::Connecting | LifecycleState::Failed => op == Maintenance::Reconnect,
+            _ => false,
+        };
+        let Some(tx) = self.maintenance_tx.as_ref().filter(|_| accepted) else {
+            let _ = reply.send(Err(DeviceError::NotRunning));
+            return;
+        };
+        match tx.try_send(maintenance::Request { op, reply }) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(request)) => {
+                let reason = "维护请求过多, 请稍后重试".to_owned();
+                let _ = request.reply.send(Err(DeviceError::Maintenance(reason)));
+            }
+            Err(mpsc::error::TrySendError::Closed(request)) => {
+                let _ = request.reply.send(Err(DeviceError::NotRunning));
+            }
+        }
+    }
 }
 
 impl Executable for ModbusDev {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use std::time::Duration;
+
+    use tokio::sync::oneshot;
+
+    use super::ModbusDev;
+    use crate::center::DataCenter;
+    use crate::config::modbus_conf::parse_json_configs;
+    use crate::config::{Device, ProtocolConfigs};
+    use crate::dev::maintenance::{Maintenance, MaintenanceOutput};
+    use crate::dev::{DeviceError, DevicePlan, Lifecycle, LifecycleState, events};
+
+    const POINT: &str = r#"{ id: 1, name: "电压", data_type: "U16", register_address: 0,
+        register_type: "HoldingRegisters", quantity: 1, key: "voltage" }"#;
+
+    #[tokio::test]
+    async fn point_table_is_reloaded_only_when_valid() {
+        let mut dev: Device = serde_json::from_str(
+            r#"{"id": "pcs", "config": {"com_type": "ModbusTCP", "ip": "127.0.0.1",
+                "port": 502, "slave": 1, "interval": 1000, "timeout": 1000}}"#,
+        )
+        .unwrap();
+        let configs = parse_json_configs(&format!("[{POINT}]")).unwrap();
+        dev.protocol_configs = Some(ProtocolConfigs::Modbus(configs));
+        let dev = ModbusDev::new(dev, Arc::new(DataCenter::new(8))).unwrap();
+        assert_eq!(
+            dev.plan().unwrap(),
+            Some(DevicePlan {
+                points: 1,
+                blocks: 1
+            })
+        );
+
+        let dir = std::env::temp_dir().join(format!("collector-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let table = dir.join("points.json");
+        let path = table.display().to_string();
+        let current = String::from(
+            r#"{ id: 2, name: "电流", data_type: "U16",
+            register_address: 1, register_type: "HoldingRegisters", quantity: 1, key: "current" }"#,
+        );
+        std::fs::write(&table, format!("[{POINT}, {current}]")).unwrap();
+        dev.reload_points(&path).await.unwrap();
+        assert_eq!(dev.points_tx.borrow().len(), 2);
+
+        // 重复的点位 ID 使点位表无效，保留原点位表
+        std::fs::write(&table, format!("[{POINT}, {POINT}]")).unwrap();
+        let err = dev.reload_points(&path).await.unwrap_err();
+        assert!(matches!(err, DeviceError::PointTable(_)));
+        assert_eq!(dev.points_tx.borrow().len(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconnect_during_backoff_connects_at_once() {
+        // 取一个无人监听的端口，连接立即被拒绝
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut dev: Device = serde_json::from_str(&format!(
+            r#"{{"id": "backoff-test", "config": {{"com_type": "ModbusTCP", "ip": "127.0.0.1",
+                "port": {port}, "slave": 1, "interval": 1000, "timeout": 1000}}}}"#
+        ))
+        .unwrap();
+        let configs = parse_json_configs(&format!("[{POINT}]")).unwrap();
+        dev.protocol_configs = Some(ProtocolConfigs::Modbus(configs));
+        let mut dev = ModbusDev::new(dev, Arc::new(DataCenter::new(8))).unwrap();
+        let mut events = events::subscribe();
+        let mut next = async || loop {
+            let event = events.recv().await.unwrap();
+            if event.device == "backoff-test" {
+                break event.to;
+            }
+        };
+        dev.init().unwrap();
+        dev.start().await.unwrap();
+        while next().await != LifecycleState::Failed {}
+
+        // 连接失败后退避 500ms，重连请求使其立即连接
+        let (reply, rx) = oneshot::channel();
+        dev.maintain(Maintenance::Reconnect, reply);
+        assert_eq!(rx.await.unwrap().unwrap(), MaintenanceOutput::Done);
+        let state = tokio::time::timeout(Duration::from_millis(200), next()).await;
+        assert_eq!(state.unwrap(), LifecycleState::Connecting);
+
+        // 其余维护操作在未连接时直接拒绝
+        let (reply, rx) = oneshot::channel();
+        dev.maintain(Maintenance::PollNow, reply);
+        assert!(matches!(rx.await.unwrap(), Err(DeviceError::NotRunning)));
+        dev.stop().await.unwrap();
+    }
+}
@@ -5,19 +5,37 @@ use smallvec::Array;
 use smallvec::SmallVec;
 use tokio::sync::watch;
 use tokio::time;
-use tokio_modbus::client::{Context, Writer};
+use tokio_modbus::ExceptionCode;
+use tokio_modbus::client::{Context, Reader, Writer};
 use tracing::warn;
 
 use crate::config::modbus_conf::{
-    ByteOrder, ModbusConfig, ModbusConfigs, ModbusDataType, RegisterType,
+    ByteOrder, ModbusConfig, ModbusConfigs, ModbusDataType, RegisterType, decimal_to_bcd,
 };
-use crate::core::point::{DownDataPoint, PointId, PointRef, Val, ValError};
+use crate::core::point::{DataPoint, DownDataPoint, PointId, PointRef, Val, ValError};
 
+use super::block::{BlockRead, Blocks};
 use super::error::ModbusDevError;
+use crate::dev::dev_config::FrameLimits;
+
+/// 写多个寄存器（0x10）单次最多 123 个
+const MAX_WRITE_REGISTERS: u16 = 123;
+/// 写多个线圈（0x0F）单次最多 1968 个
+const MAX_WRITE_COILS: u16 = 1968;
 
 pub(super) struct WritePlan {
     coils: Vec<(u16, SmallVec<[bool; 16]>)>,
     holding: Vec<(u16, SmallVec<[u16; 16]>)>,
+    /// 保持寄存器中的位点位，同一寄存器的多个位合并为一次掩码写
+    masks: Vec<(u16, BitMask)>,
+    /// 需要与握手寄存器读取在同一事务中完成的写入，逐个以 0x17 下发
+    exchanges: Vec<Exchange>,
+    /// 选择-执行控制点位，不与其他写入合并，逐个按选择、确认、执行的顺序下发
+    selects: Vec<SelectWrite>,
+    /// 脉冲输出的线圈，逐个合上、保持、断开
+    pulses: Vec<Pulse>,
+    /// 构建时被忽略的点位及原因，原子下发时据此整批拒绝
+    rejected: Vec<String>,
 }
 
 /// [`WritePlan::apply`] 的结果
@@ -26,6 +44,159 @@ pub(super) enum WriteOutcome {
     Completed,
     /// 写间隔等待期间收到停止信号，提前结束
     Stopped,
+    /// 设备没有接受写入（回读不一致或选择未确认），后续写块不再下发
+    Refused(String),
+}
+
+/// [`WritePlan::apply`] 的下发参数
+pub(super) struct WriteOptions {
+    /// 单次请求超时
+    pub io_timeout: Duration,
+    /// 每次写入之后的等待
+    pub interval: Duration,
+    /// 写入后回读校验
+    pub verify: bool,
+    /// 选择-执行控制等待选择确认的超时
+    pub select_timeout: Duration,
+}
+
+/// 掩码写寄存器（0x16）的掩码：结果 = (当前值 & and) | (or & !and)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BitMask {
+    and: u16,
+    or: u16,
+}
+
+impl Default for BitMask {
+    fn default() -> Self {
+        Self { and: 0xFFFF, or: 0 }
+    }
+}
+
+impl BitMask {
+    /// 将 `mask` 对应的位置为 `on`，不影响其他位
+    fn set(&mut self, mask: u16, on: bool) {
+        self.and &= !mask;
+        if on {
+            self.or |= mask;
+        } else {
+            self.or &= !mask;
+        }
+    }
+
+    fn apply(self, current: u16) -> u16 {
+        (current & self.and) | (self.or & !self.and)
+    }
+
+    /// 以 0x16 写入；从站不支持该功能码时回退为读-改-写，两次请求之间其他主站的写入会被覆盖
+    async fn write(
+        self,
+        ctx: &mut Context,
+        address: u16,
+        io_timeout: Duration,
+    ) -> Result<(), ModbusDevError> {
+        match time::timeout(
+            io_timeout,
+            ctx.masked_write_register(address, self.and, self.or),
+        )
+        .await??
+        {
+            Ok(()) => Ok(()),
+            Err(ExceptionCode::IllegalFunction) => {
+                let current = time::timeout(io_timeout, ctx.read_holding_registers(address, 1))
+                    .await???
+                    .first()
+                    .copied()
+                    .unwrap_or(0);
+                time::timeout(
+                    io_timeout,
+                    ctx.write_single_register(address, self.apply(current)),
+                )
+                .await???;
+                Ok(())
+            }
+            Err(code) => Err(code.into()),
+        }
+    }
+}
+
+/// 一次读写多个寄存器（0x17）事务：写入点位并读取握手寄存器
+struct Exchange {
+    address: u16,
+    values: SmallVec<[u16; 2]>,
+    read_address: u16,
+    read_len: u16,
+    /// 握手地址上的点位，没有配置点位时读到的值只记录日志
+    readback: Option<Blocks>,
+}
+
+impl Exchange {
+    fn new(
+        cfg: &ModbusConfig,
+        read_address: u16,
+        values: SmallVec<[u16; 2]>,
+        cfg_map: &HashMap<PointId, ModbusConfig>,
+    ) -> Self {
+        let configs: ModbusConfigs = cfg_map
+            .values()
+            .filter(|it| {
+                it.register_type == RegisterType::HoldingRegisters
+                    && it.register_address == read_address
+            })
+            .copied()
+            .collect();
+        let read_len = configs.iter().map(|it| it.quantity).max().unwrap_or(1);
+        let readback = (!configs.is_empty())
+            .then(|| Blocks::build(configs, 0, FrameLimits::default()).ok())
+            .flatten();
+        Self {
+            address: cfg.register_address,
+            values,
+            read_address,
+            read_len,
+            readback,
+        }
+    }
+
+    async fn exchange(
+        &self,
+        ctx: &mut Context,
+        io_timeout: Duration,
+    ) -> Result<Vec<DataPoint>, ModbusDevError> {
+        let data = time::timeout(
+            io_timeout,
+            ctx.read_write_multiple_registers(
+                self.read_address,
+                self.read_len,
+                self.address,
+                &self.values,
+            ),
+        )
+        .await???;
+        Ok(match &self.readback {
+            Some(blocks) => blocks.parse(&[BlockRead::HoldingRegisters(data)]),
+            None => {
+                warn!("握手地址{}未配置点位, 读到{:?}", self.read_address, data);
+                Vec::new()
+            }
+        })
+    }
+}
+
+/// 一次选择-执行（SBO）控制
+struct SelectWrite {
+    name: &'static str,
+    register_type: RegisterType,
+    select_address: u16,
+    address: u16,
+    /// 线圈点位为 0/1
+    values: SmallVec<[u16; 2]>,
+}
+
+/// 一次脉冲输出：合上线圈，保持 `width` 后断开
+struct Pulse {
+    address: u16,
+    width: Duration,
 }
 
 impl WritePlan {
@@ -34,18 +205,26 @@ impl WritePlan {
         cfg_map: &HashMap<PointId, ModbusConfig>,
         key_map: &HashMap<&'static str, PointId>,
         name_map: &HashMap<&'static str, PointId>,
+        limits: FrameLimits,
         dev_id: &str,
     ) -> Self {
         let mut coils: BTreeMap<u16, bool> = BTreeMap::new();
         let mut holding: BTreeMap<u16, u16> = BTreeMap::new();
+        let mut masks: BTreeMap<u16, BitMask> = BTreeMap::new();
+        let mut exchanges = Vec::new();
+        let mut selects = Vec::new();
+        let mut pulses = Vec::new();
+        let mut rejected = Vec::new();
 
         for entry in entries {
             let Some(id) = resolve_id(&entry.point, key_map, name_map) else {
                 warn!("[{}] 未找到点位配置, 忽略下发: {:?}", dev_id, entry.point);
+                rejected.push(format!("未找到点位配置: {:?}", entry.point));
                 continue;
             };
             let Some(cfg) = cfg_map.get(&id) else {
                 warn!("[{}] 未找到点位配置, 忽略下发: {}", dev_id, id);
+                rejected.push(format!("未找到点位配置: {}", id));
                 continue;
             };
             match cfg.register_type {
@@ -53,14 +232,59 @@ impl WritePlan {
                     let v: Result<bool, ValError> = (&entry.value).try_into();
                     let Ok(v) = v else {
                         warn!("[{}] 点位类型不支持下发到线圈: {}", dev_id, cfg.name);
+                        rejected.push(format!("点位类型不支持下发到线圈: {}", cfg.name));
+                        continue;
+                    };
+                    match (cfg.select_address, cfg.pulse_ms) {
+                        (Some(select_address), _) => selects.push(SelectWrite {
+                            name: cfg.name,
+                            register_type: cfg.register_type,
+                            select_address,
+                            address: cfg.register_address,
+                            values: SmallVec::from_slice(&[v as u16]),
+                        }),
+                        // 脉冲点位下发 OFF 时直接断开
+                        (None, Some(width)) if v => pulses.push(Pulse {
+                            address: cfg.register_address,
+                            width: Duration::from_millis(width.into()),
+                        }),
+                        _ => {
+                            coils.insert(cfg.register_address, v);
+                        }
+                    }
+                }
+                RegisterType::HoldingRegisters if let Some(bit) = cfg.bit => {
+                    let v: Result<bool, ValError> = (&entry.value).try_into();
+                    let Ok(v) = v else {
+                        warn!("[{}] 点位类型不支持下发到位: {}", dev_id, cfg.name);
+                        rejected.push(format!("点位类型不支持下发到位: {}", cfg.name));
                         continue;
                     };
-                    coils.insert(cfg.register_address, v);
+                    // 位号按解析时的字节序计，写入时换算回寄存器中的位置
+                    let mask = cfg
+                        .byte_order
+                        .map_or(1 << bit, |order| order.assemble_u16(1 << bit));
+                    masks.entry(cfg.register_address).or_default().set(mask, v);
                 }
                 RegisterType::HoldingRegisters => {
                     let Some(values) = encode_registers(cfg, &entry.value, dev_id) else {
+                        rejected.push(format!("点位值无法编码: {}", cfg.name));
                         continue;
                     };
+                    if let Some(select_address) = cfg.select_address {
+                        selects.push(SelectWrite {
+                            name: cfg.name,
+                            register_type: cfg.register_type,
+                            select_address,
+                            address: cfg.register_address,
+                            values,
+                        });
+                        continue;
+                    }
+                    if let Some(read_address) = cfg.handshake_address {
+                        exchanges.push(Exchange::new(cfg, read_address, values, cfg_map));
+                        continue;
+                    }
                     for (idx, v) in values.into_iter().enumerate() {
                         let addr = cfg.register_address.saturating_add(idx as u16);
                         holding.insert(addr, v);
@@ -68,34 +292,69 @@ impl WritePlan {
                 }
                 RegisterType::DiscreteInputs | RegisterType::InputRegisters => {
                     warn!("[{}] 只读寄存器不支持下发: {}", dev_id, cfg.name);
+                    rejected.push(format!("只读寄存器不支持下发: {}", cfg.name));
                 }
             }
         }
 
         WritePlan {
-            coils: merge_blocks::<[bool; 16]>(coils),
-            holding: merge_blocks::<[u16; 16]>(holding),
+            coils: merge_blocks::<[bool; 16]>(coils, limits.bits.min(MAX_WRITE_COILS)),
+            holding: merge_blocks::<[u16; 16]>(holding, limits.registers.min(MAX_WRITE_REGISTERS)),
+            masks: masks.into_iter().collect(),
+            exchanges,
+            selects,
+            pulses,
+            rejected,
         }
     }
 
+    /// 构建时被忽略的点位及原因
+    pub(super) fn rejected(&self) -> &[String] {
+        &self.rejected
+    }
+
+    /// 下发的写入步骤数：每个写块、掩码写、握手事务、选择-执行控制与脉冲输出各为一步
+    ///
+    /// 单个步骤在设备上要么生效要么不生效，多个步骤之间某一步失败时之前的步骤已经生效，
+    /// 原子下发因此只接受一个步骤的写入计划
+    pub(super) fn steps(&self) -> usize {
+        self.coils.len()
+            + self.holding.len()
+            + self.masks.len()
+            + self.exchanges.len()
+            + self.selects.len()
+            + self.pulses.len()
+    }
+
     /// 依次下发所有写块；每次实际写入之后都会等待一个 `interval`，
     /// 避免连续写入过于密集导致从站/网关来不及响应。
+    ///
+    /// `verify` 为 `true` 时每个写块等待间隔后回读，部分 PLC 会静默忽略超出范围的写入，
+    /// 脉冲输出不回读；0x17 事务中读到的握手点位追加到 `readback`
     pub(super) async fn apply(
         &self,
         ctx: &mut Context,
-        io_timeout: Duration,
         stop_rx: &mut watch::Receiver<bool>,
-        interval: Duration,
+        opts: &WriteOptions,
+        readback: &mut Vec<DataPoint>,
     ) -> Result<WriteOutcome, ModbusDevError> {
+        let io_timeout = opts.io_timeout;
         for (start, vals) in self.coils.iter() {
             if vals.len() == 1 {
                 time::timeout(io_timeout, ctx.write_single_coil(*start, vals[0])).await???;
             } else {
                 time::timeout(io_timeout, ctx.write_multiple_coils(*start, vals)).await???;
             }
-            if wait_interval(stop_rx, interval).await {
+            if wait_interval(stop_rx, opts.interval).await {
                 return Ok(WriteOutcome::Stopped);
             }
+            if opts.verify {
+                let actual = time::timeout(io_timeout, ctx.read_coils(*start, vals.len() as u16))
+                    .await???;
+                if let Some(reason) = first_mismatch(*start, vals, &actual) {
+                    return Ok(WriteOutcome::Refused(reason));
+                }
+            }
         }
         for (start, vals) in self.holding.iter() {
             if vals.len() == 1 {
@@ -103,12 +362,182 @@ impl WritePlan {
             } else {
                 time::timeout(io_timeout, ctx.write_multiple_registers(*start, vals)).await???;
             }
-            if wait_interval(stop_rx, interval).await {
+            if wait_interval(stop_rx, opts.interval).await {
+                return Ok(WriteOutcome::Stopped);
+            }
+            if opts.verify {
+                let actual = time::timeout(
+                    io_timeout,
+                    ctx.read_holding_registers(*start, vals.len() as u16),
+                )
+                .await???;
+                if let Some(reason) = first_mismatch(*start, vals, &actual) {
+                    return Ok(WriteOutcome::Refused(reason));
+                }
+            }
+        }
+        for (address, mask) in self.masks.iter() {
+            mask.write(ctx, *address, io_timeout).await?;
+            if wait_interval(stop_rx, opts.interval).await {
+                return Ok(WriteOutcome::Stopped);
+            }
+            if opts.verify {
+                let actual =
+                    time::timeout(io_timeout, ctx.read_holding_registers(*address, 1)).await???;
+                let expected: Vec<u16> = actual.iter().map(|v| mask.apply(*v)).collect();
+                if let Some(reason) = first_mismatch(*address, &expected, &actual) {
+                    return Ok(WriteOutcome::Refused(reason));
+                }
+            }
+        }
+        for exchange in &self.exchanges {
+            readback.extend(exchange.exchange(ctx, io_timeout).await?);
+            if wait_interval(stop_rx, opts.interval).await {
+                return Ok(WriteOutcome::Stopped);
+            }
+            if opts.verify {
+                let actual = time::timeout(
+                    io_timeout,
+                    ctx.read_holding_registers(exchange.address, exchange.values.len() as u16),
+                )
+                .await???;
+                if let Some(reason) = first_mismatch(exchange.address, &exchange.values, &actual) {
+                    return Ok(WriteOutcome::Refused(reason));
+                }
+            }
+        }
+        for select in &self.selects {
+            match select.operate(ctx, stop_rx, opts).await? {
+                WriteOutcome::Completed => {}
+                outcome => return Ok(outcome),
+            }
+        }
+        for pulse in &self.pulses {
+            time::timeout(io_timeout, ctx.write_single_coil(pulse.address, true)).await???;
+            // 保持期间收到停止信号也要先断开，不能让触点一直闭合
+            let stopped = wait_interval(stop_rx, pulse.width).await;
+            time::timeout(io_timeout, ctx.write_single_coil(pulse.address, false)).await???;
+            if stopped || wait_interval(stop_rx, opts.interval).await {
+                return Ok(WriteOutcome::Stopped);
+            }
+        }
+        Ok(WriteOutcome::Completed)
+    }
+}
+
+impl SelectWrite {
+    /// 写选择地址，回读确认已选中后在超时内执行；确认失败时写 0 取消选择
+    ///
+    /// 执行后的选择状态由设备自行复位
+    async fn operate(
+        &self,
+        ctx: &mut Context,
+        stop_rx: &mut watch::Receiver<bool>,
+        opts: &WriteOptions,
+    ) -> Result<WriteOutcome, ModbusDevError> {
+        let io_timeout = opts.io_timeout;
+        self.write_select(ctx, io_timeout, true).await?;
+        let deadline = time::Instant::now() + opts.select_timeout;
+        loop {
+            if wait_interval(stop_rx, opts.interval).await {
+                self.write_select(ctx, io_timeout, false).await?;
                 return Ok(WriteOutcome::Stopped);
             }
+            if self.read_selected(ctx, io_timeout).await? {
+                break;
+            }
+            if time::Instant::now() >= deadline {
+                self.write_select(ctx, io_timeout, false).await?;
+                return Ok(WriteOutcome::Refused(format!("{}选择未确认", self.name)));
+            }
+        }
+        match (self.register_type, self.values.as_slice()) {
+            (RegisterType::Coils, [value, ..]) => {
+                time::timeout(io_timeout, ctx.write_single_coil(self.address, *value != 0))
+                    .await???;
+            }
+            (_, [value]) => {
+                time::timeout(io_timeout, ctx.write_single_register(self.address, *value))
+                    .await???;
+            }
+            (_, values) => {
+                time::timeout(
+                    io_timeout,
+                    ctx.write_multiple_registers(self.address, values),
+                )
+                .await???;
+            }
+        }
+        if wait_interval(stop_rx, opts.interval).await {
+            return Ok(WriteOutcome::Stopped);
         }
         Ok(WriteOutcome::Completed)
     }
+
+    async fn write_select(
+        &self,
+        ctx: &mut Context,
+        io_timeout: Duration,
+        selected: bool,
+    ) -> Result<(), ModbusDevError> {
+        match self.register_type {
+            RegisterType::Coils => {
+                time::timeout(
+                    io_timeout,
+                    ctx.write_single_coil(self.select_address, selected),
+                )
+                .await???
+            }
+            _ => {
+                time::timeout(
+                    io_timeout,
+                    ctx.write_single_register(self.select_address, selected as u16),
+                )
+                .await???
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_selected(
+        &self,
+        ctx: &mut Context,
+        io_timeout: Duration,
+    ) -> Result<bool, ModbusDevError> {
+        Ok(match self.register_type {
+            RegisterType::Coils => {
+                time::timeout(io_timeout, ctx.read_coils(self.select_address, 1)).await??? == [true]
+            }
+            _ => {
+                time::timeout(
+                    io_timeout,
+                    ctx.read_holding_registers(self.select_address, 1),
+                )
+                .await???
+                    == [1]
+            }
+        })
+    }
+}
+
+/// 回读值与写入值第一处不一致的描述，一致时为 `None`
+fn first_mismatch<T: PartialEq + std::fmt::Debug>(
+    start: u16,
+    written: &[T],
+    actual: &[T],
+) -> Option<String> {
+    written
+        .iter()
+        .enumerate()
+        .find(|(index, value)| actual.get(*index) != Some(*value))
+        .map(|(index, value)| {
+            format!(
+                "地址{}写入{:?}, 回读{:?}",
+                start as usize + index,
+                value,
+                actual.get(index)
+            )
+        })
 }
 
 pub(super) fn stop_requested(stop_rx: &watch::Receiver<bool>) -> bool {
@@ -137,7 +566,7 @@ pub(super) fn build_name_map(configs: &ModbusConfigs) -> HashMap<&'static str, P
         .collect()
 }
 
-fn resolve_id(
+pub(super) fn resolve_id(
     point: &PointRef,
     key_map: &HashMap<&'static str, PointId>,
     name_map: &HashMap<&'static str, PointId>,
@@ -162,7 +591,8 @@ pub(super) fn build_cfg_map(configs: &ModbusConfigs) -> HashMap<PointId, ModbusC
     out
 }
 
-fn merge_blocks<A>(map: BTreeMap<u16, A::Item>) -> Vec<(u16, SmallVec<A>)>
+/// 合并地址连续的写入，单块不超过 `max_len`
+fn merge_blocks<A>(map: BTreeMap<u16, A::Item>, max_len: u16) -> Vec<(u16, SmallVec<A>)>
 where
     A: Array,
     A::Item: Copy,
@@ -178,7 +608,9 @@ where
                 cur_start = Some(addr);
                 cur_vals.push(val);
             }
-            (Some(_), Some(last)) if addr == last.saturating_add(1) => {
+            (Some(_), Some(last))
+                if addr == last.saturating_add(1) && cur_vals.len() < max_len as usize =>
+            {
                 cur_vals.push(val);
                 last_addr = Some(addr);
                 continue;
@@ -199,7 +631,11 @@ where
     out
 }
 
-fn encode_registers(cfg: &ModbusConfig, value: &Val, dev_id: &str) -> Option<SmallVec<[u16; 2]>> {
+pub(super) fn encode_registers(
+    cfg: &ModbusConfig,
+    value: &Val,
+    dev_id: &str,
+) -> Option<SmallVec<[u16; 2]>> {
     match cfg.data_type {
         ModbusDataType::Bool => {
             let v = value.try_into().ok()?;
@@ -219,6 +655,25 @@ fn encode_registers(cfg: &ModbusConfig, value: &Val, dev_id: &str) -> Option<Sma
         ModbusDataType::I32 => encode_double_register(cfg, value, dev_id, |raw, dev_id, name| {
             to_i32(raw, dev_id, name).map(|v| v as u32)
         }),
+        ModbusDataType::F32 => encode_double_register(cfg, value, dev_id, |raw, dev_id, name| {
+            to_f32(raw, dev_id, name).map(f32::to_bits)
+        }),
+        ModbusDataType::F64 => encode_quad_register(cfg, value, dev_id, |raw, dev_id, name| {
+            to_f64(raw, dev_id, name).map(f64::to_bits)
+        }),
+        ModbusDataType::U64 => encode_quad_register(cfg, value, dev_id, |raw, dev_id, name| {
+            to_u64(raw, dev_id, name)
+        }),
+        ModbusDataType::I64 => encode_quad_register(cfg, value, dev_id, |raw, dev_id, name| {
+            to_i64(raw, dev_id, name).map(|v| v as u64)
+        }),
+        ModbusDataType::String(len) => encode_string(cfg, value, len, dev_id),
+        ModbusDataType::Bcd16 => encode_single_register(cfg, value, dev_id, |raw, dev_id, name| {
+            to_bcd(raw, 9999, dev_id, name).map(|v| v as u16)
+        }),
+        ModbusDataType::Bcd32 => encode_double_register(cfg, value, dev_id, |raw, dev_id, name| {
+            to_bcd(raw, 99_999_999, dev_id, name)
+        }),
     }
 }
 
@@ -254,6 +709,53 @@ fn encode_double_register(
     Some(out)
 }
 
+fn encode_quad_register(
+    cfg: &ModbusConfig,
+    value: &Val,
+    dev_id: &str,
+    convert: impl FnOnce(f64, &str, &str) -> Option<u64>,
+) -> Option<SmallVec<[u16; 2]>> {
+    let raw = scale_to_raw(cfg, value, dev_id)?;
+    let v = convert(raw, dev_id, cfg.name)?;
+    let arr = cfg
+        .byte_order
+        .map_or(ByteOrder::ABCDEFGH.assemble_u64(v), |it| it.assemble_u64(v));
+    Some(SmallVec::from_slice(&arr))
+}
+
+/// 字符串按寄存器高字节在前写入，不足部分以 NUL 填充
+fn encode_string(
+    cfg: &ModbusConfig,
+    value: &Val,
+    len: u16,
+    dev_id: &str,
+) -> Option<SmallVec<[u16; 2]>> {
+    let Val::Str(text) = value else {
+        warn!(
+            "[{}] 字符串点位只能下发字符串, 忽略下发: {}",
+            dev_id, cfg.name
+        );
+        return None;
+    };
+    let capacity = len as usize * 2;
+    if text.len() > capacity {
+        warn!(
+            "[{}] 字符串超出{}字节, 忽略下发: {}",
+            dev_id, capacity, cfg.name
+        );
+        return None;
+    }
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.resize(capacity, 0);
+    let order = cfg.byte_order.unwrap_or(ByteOrder::AB);
+    Some(
+        bytes
+            .chunks(2)
+            .map(|pair| order.assemble_u16(u16::from_be_bytes([pair[0], pair[1]])))
+            .collect(),
+    )
+}
+
 fn scale_to_raw(cfg: &ModbusConfig, value: &Val, dev_id: &str) -> Option<f64> {
     let v: f64 = value.try_into().ok()?;
     if cfg.scale.abs() < 1e-12 {
@@ -298,3 +800,283 @@ fn to_i32(v: f64, dev_id: &str, name: &str) -> Option<i32> {
     }
     Some(r as i32)
 }
+
+fn to_u64(v: f64, dev_id: &str, name: &str) -> Option<u64> {
+    let r = v.round();
+    // u64::MAX 转为 f64 后向上取整为 2^64，需用开区间
+    if !(0.0..u64::MAX as f64).contains(&r) {
+        warn!("[{}] 点位值超出U64范围, 忽略下发: {}", dev_id, name);
+        return None;
+    }
+    Some(r as u64)
+}
+
+fn to_i64(v: f64, dev_id: &str, name: &str) -> Option<i64> {
+    let r = v.round();
+    if !(i64::MIN as f64..i64::MAX as f64).contains(&r) {
+        warn!("[{}] 点位值超出I64范围, 忽略下发: {}", dev_id, name);
+        return None;
+    }
+    Some(r as i64)
+}
+
+/// 按十进制数字转为 BCD 码，`max` 为可表示的最大值
+fn to_bcd(v: f64, max: u32, dev_id: &str, name: &str) -> Option<u32> {
+    let r = v.round();
+    if !(0.0..=max as f64).contains(&r) {
+        warn!("[{}] 点位值超出BCD范围, 忽略下发: {}", dev_id, name);
+        return None;
+    }
+    decimal_to_bcd(r as u32)
+}
+
+fn to_f32(v: f64, dev_id: &str, name: &str) -> Option<f32> {
+    if !v.is_finite() || v.abs() > f32::MAX as f64 {
+        warn!("[{}] 点位值超出F32范围, 忽略下发: {}", dev_id, name);
+        return None;
+    }
+    Some(v as f32)
+}
+
+fn to_f64(v: f64, dev_id: &str, name: &str) -> Option<f64> {
+    if !v.is_finite() {
+        warn!("[{}] 点位值超出F64范围, 忽略下发: {}", dev_id, name);
+        return None;
+    }
+    Some(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use super::{
+        BitMask, WritePlan, build_cfg_map, build_key_map, build_name_map, first_mismatch,
+        merge_blocks,
+    };
+    use crate::config::modbus_conf::parse_json_configs;
+    use crate::core::point::{DownDataPoint, Val};
+    use crate::dev::dev_config::FrameLimits;
+
+    #[test]
+    fn write_blocks_are_split_at_max_len() {
+        let holding: BTreeMap<u16, u16> = (0..5).chain(10..12).map(|addr| (addr, addr)).collect();
+
+        let blocks = merge_blocks::<[u16; 16]>(holding, 2);
+
+        let layout: Vec<(u16, Vec<u16>)> = blocks
+            .into_iter()
+            .map(|(start, vals)| (start, vals.to_vec()))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                (0, vec![0, 1]),
+                (2, vec![2, 3]),
+                (4, vec![4]),
+                (10, vec![10, 11])
+            ]
+        );
+    }
+
+    #[test]
+    fn adjacent_registers_are_one_write_step() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "有功设定", data_type: "U16", register_address: 10,
+              register_type: "HoldingRegisters", quantity: 1, key: "p_set" },
+            { id: 2, name: "无功设定", data_type: "U16", register_address: 11,
+              register_type: "HoldingRegisters", quantity: 1, key: "q_set" },
+            { id: 3, name: "功率因数", data_type: "U16", register_address: 20,
+              register_type: "HoldingRegisters", quantity: 1, key: "pf_set" },
+        ]"#,
+        )
+        .unwrap();
+        let plan = |keys: &[&str]| {
+            let entries = keys
+                .iter()
+                .map(|key| DownDataPoint::by_key((*key).into(), Val::U16(1)))
+                .collect();
+            WritePlan::build(
+                entries,
+                &build_cfg_map(&configs),
+                &build_key_map(&configs),
+                &build_name_map(&configs),
+                FrameLimits::default(),
+                "dev",
+            )
+        };
+
+        assert_eq!(plan(&["p_set", "q_set"]).steps(), 1);
+        assert_eq!(plan(&["p_set", "pf_set"]).steps(), 2);
+    }
+
+    #[test]
+    fn read_back_reports_first_mismatch() {
+        assert_eq!(first_mismatch(100, &[1u16, 2, 3], &[1, 2, 3]), None);
+        assert_eq!(
+            first_mismatch(100, &[1u16, 2, 3], &[1, 0, 3]).as_deref(),
+            Some("地址101写入2, 回读Some(0)")
+        );
+        assert!(first_mismatch(0, &[true, true], &[true]).is_some());
+    }
+
+    #[test]
+    fn select_points_are_kept_out_of_merged_blocks() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "断路器合闸", data_type: "Bool", register_address: 10,
+              register_type: "Coils", quantity: 1, key: "close", select_address: 20 },
+            { id: 2, name: "有功设定", data_type: "U16", register_address: 11,
+              register_type: "HoldingRegisters", quantity: 1, key: "p_set" },
+        ]"#,
+        )
+        .unwrap();
+        let entries = vec![
+            DownDataPoint::by_key("close".into(), Val::U8(1)),
+            DownDataPoint::by_key("p_set".into(), Val::U16(50)),
+        ];
+
+        let plan = WritePlan::build(
+            entries,
+            &build_cfg_map(&configs),
+            &build_key_map(&configs),
+            &build_name_map(&configs),
+            FrameLimits::default(),
+            "dev",
+        );
+
+        assert!(plan.rejected().is_empty());
+        assert!(plan.coils.is_empty());
+        assert_eq!(plan.holding.len(), 1);
+        assert_eq!(plan.selects.len(), 1);
+        assert_eq!(plan.steps(), 2);
+        assert_eq!(plan.selects[0].select_address, 20);
+        assert_eq!(plan.selects[0].values.as_slice(), [1]);
+
+        let invalid = parse_json_configs(
+            r#"[{ id: 1, name: "电压", data_type: "U16", register_address: 0,
+              register_type: "InputRegisters", quantity: 1, key: "v", select_address: 1 }]"#,
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn bits_in_one_register_share_a_mask() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "启动", data_type: "U16", register_address: 30,
+              register_type: "HoldingRegisters", quantity: 1, key: "start", bit: 0 },
+            { id: 2, name: "复位", data_type: "U16", register_address: 30,
+              register_type: "HoldingRegisters", quantity: 1, key: "reset", bit: 3 },
+        ]"#,
+        )
+        .unwrap();
+        let entries = vec![
+            DownDataPoint::by_key("start".into(), Val::U8(1)),
+            DownDataPoint::by_key("reset".into(), Val::U8(0)),
+        ];
+
+        let plan = WritePlan::build(
+            entries,
+            &build_cfg_map(&configs),
+            &build_key_map(&configs),
+            &build_name_map(&configs),
+            FrameLimits::default(),
+            "dev",
+        );
+
+        assert!(plan.rejected().is_empty());
+        assert!(plan.holding.is_empty());
+        assert_eq!(
+            plan.masks,
+            [(
+                30,
+                BitMask {
+                    and: !0b1001,
+                    or: 0b0001
+                }
+            )]
+        );
+        assert_eq!(plan.masks[0].1.apply(0b1110_1000), 0b1110_0001);
+    }
+
+    #[test]
+    fn handshake_points_are_written_with_a_paired_read() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "转速设定", data_type: "U16", register_address: 40,
+              register_type: "HoldingRegisters", quantity: 1, key: "speed", handshake_address: 41 },
+            { id: 2, name: "握手", data_type: "U32", register_address: 41,
+              register_type: "HoldingRegisters", quantity: 2, key: "ack" },
+        ]"#,
+        )
+        .unwrap();
+        let entries = vec![DownDataPoint::by_key("speed".into(), Val::U16(1500))];
+
+        let plan = WritePlan::build(
+            entries,
+            &build_cfg_map(&configs),
+            &build_key_map(&configs),
+            &build_name_map(&configs),
+            FrameLimits::default(),
+            "dev",
+        );
+
+        assert!(plan.holding.is_empty());
+        assert_eq!(plan.exchanges.len(), 1);
+        let exchange = &plan.exchanges[0];
+        assert_eq!(
+            (exchange.address, exchange.read_address, exchange.read_len),
+            (40, 41, 2)
+        );
+        assert_eq!(exchange.values.as_slice(), [1500]);
+        assert!(exchange.readback.is_some());
+
+        let invalid = parse_json_configs(
+            r#"[{ id: 1, name: "电压", data_type: "U16", register_address: 0,
+              register_type: "InputRegisters", quantity: 1, key: "v", handshake_address: 1 }]"#,
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn pulse_coils_are_written_on_then_off() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "启机", data_type: "Bool", register_address: 50,
+              register_type: "Coils", quantity: 1, key: "start", pulse_ms: 500 },
+            { id: 2, name: "停机", data_type: "Bool", register_address: 51,
+              register_type: "Coils", quantity: 1, key: "stop", pulse_ms: 500 },
+        ]"#,
+        )
+        .unwrap();
+        let entries = vec![
+            DownDataPoint::by_key("start".into(), Val::U8(1)),
+            DownDataPoint::by_key("stop".into(), Val::U8(0)),
+        ];
+
+        let plan = WritePlan::build(
+            entries,
+            &build_cfg_map(&configs),
+            &build_key_map(&configs),
+            &build_name_map(&configs),
+            FrameLimits::default(),
+            "dev",
+        );
+
+        assert_eq!(plan.pulses.len(), 1);
+        assert_eq!(plan.pulses[0].address, 50);
+        assert_eq!(plan.pulses[0].width, Duration::from_millis(500));
+        // 下发 OFF 按普通线圈写入
+        assert_eq!(plan.coils.len(), 1);
+        assert_eq!(plan.coils[0].0, 51);
+
+        let invalid = parse_json_configs(
+            r#"[{ id: 1, name: "电压", data_type: "U16", register_address: 0,
+              register_type: "HoldingRegisters", quantity: 1, key: "v", pulse_ms: 100 }]"#,
+        );
+        assert!(invalid.is_err());
+    }
+}
@@ -20,6 +20,16 @@ pub enum ModbusDevError {
     ModbusException(ExceptionCode),
     #[error("Build blocks error: {0}")]
     BlocksError(#[from] BuildBlocksError),
+    #[error("Invalid device identification response")]
+    InvalidIdentification,
+    #[error("TLS error: {0}")]
+    Tls(String),
+    #[error("Link {link} is already open with {expected}, got {actual}")]
+    LinkProfileMismatch {
+        link: String,
+        expected: String,
+        actual: String,
+    },
 }
 
 impl From<ExceptionCode> for ModbusDevError {
@@ -0,0 +1,108 @@
+//! 主备链路切换
+//!
+//! 当前链路连续 [`FAILOVER_AFTER`] 次连接失败后换用另一条链路；使用备用链路期间每隔 [`PRIMARY_RETRY`]
+//! 回试一次主链路，回试时备用链路上的轮询不中断，主链路连接成功后才切回。
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// 连续连接失败该次数后切换链路
+pub(super) const FAILOVER_AFTER: u32 = 3;
+/// 使用备用链路期间回试主链路的间隔
+pub(super) const PRIMARY_RETRY: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub(super) struct Failover {
+    has_backup: bool,
+    on_backup: bool,
+    /// 当前链路连续连接失败的次数
+    failures: u32,
+    /// 下一次回试主链路的时间，仅在使用备用链路时有效
+    retry_at: Option<Instant>,
+}
+
+impl Failover {
+    pub(super) fn new(has_backup: bool) -> Self {
+        Self {
+            has_backup,
+            on_backup: false,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    pub(super) fn on_backup(&self) -> bool {
+        self.on_backup
+    }
+
+    pub(super) fn connected(&mut self) {
+        self.failures = 0;
+    }
+
+    /// 当前链路连接失败，返回是否切换了链路
+    pub(super) fn failed(&mut self, now: Instant) -> bool {
+        if !self.has_backup {
+            return false;
+        }
+        self.failures += 1;
+        if self.failures < FAILOVER_AFTER {
+            return false;
+        }
+        self.failures = 0;
+        self.on_backup = !self.on_backup;
+        self.retry_at = self.on_backup.then(|| now + PRIMARY_RETRY);
+        true
+    }
+
+    /// 使用备用链路且到了回试主链路的时间
+    pub(super) fn retry_due(&self, now: Instant) -> bool {
+        self.on_backup && self.retry_at.is_some_and(|at| at <= now)
+    }
+
+    /// 回试主链路的结果，成功时切回主链路，失败时推迟下一次回试
+    pub(super) fn retried(&mut self, restored: bool, now: Instant) {
+        if restored {
+            self.on_backup = false;
+            self.failures = 0;
+            self.retry_at = None;
+        } else {
+            self.retry_at = Some(now + PRIMARY_RETRY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use super::{FAILOVER_AFTER, Failover, PRIMARY_RETRY};
+
+    #[test]
+    fn repeated_failures_switch_links_and_primary_is_retried() {
+        let now = Instant::now();
+        let mut single = Failover::new(false);
+        assert!((0..10).all(|_| !single.failed(now)));
+        assert!(!single.on_backup());
+
+        let mut failover = Failover::new(true);
+        for _ in 1..FAILOVER_AFTER {
+            assert!(!failover.failed(now));
+        }
+        failover.connected();
+        for _ in 1..FAILOVER_AFTER {
+            assert!(!failover.failed(now));
+        }
+        assert!(failover.failed(now));
+        assert!(failover.on_backup());
+
+        assert!(!failover.retry_due(now));
+        assert!(failover.retry_due(now + PRIMARY_RETRY));
+        failover.retried(false, now + PRIMARY_RETRY);
+        assert!(failover.on_backup());
+        assert!(!failover.retry_due(now + PRIMARY_RETRY));
+        failover.retried(true, now + PRIMARY_RETRY * 2);
+        assert!(!failover.on_backup());
+        assert!(!failover.retry_due(now + PRIMARY_RETRY * 3));
+    }
+}
@@ -0,0 +1,135 @@
+//! Read Device Identification
+//!
+//! 以功能码 0x2B、MEI 类型 0x0E 按流方式读取基本类别的标识对象：厂商名称、产品代码与主次版本号。
+//! 对象较多的设备分多次响应，按响应中的下一个对象 ID 继续读取。
+
+use std::borrow::Cow;
+
+use tokio_modbus::client::{Client, Context};
+use tokio_modbus::{Request, Response};
+
+use super::error::ModbusDevError;
+use crate::core::point::{DataPoint, Quality, Val};
+use crate::dev::identity::DeviceIdentity;
+
+const FUNCTION: u8 = 0x2B;
+const MEI_TYPE: u8 = 0x0E;
+/// 读取基本类别的标识对象
+const READ_BASIC: u8 = 0x01;
+
+/// 一次响应中的标识对象，`next` 为后续对象的起始 ID
+#[derive(Debug, PartialEq)]
+struct Page {
+    objects: Vec<(u8, String)>,
+    next: Option<u8>,
+}
+
+/// 解析去掉功能码后的响应数据
+fn parse(data: &[u8]) -> Option<Page> {
+    let (&[mei, _code, _conformity, more, next, count], mut rest) = data.split_first_chunk()?;
+    if mei != MEI_TYPE {
+        return None;
+    }
+    let mut objects = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let (&[id, len], tail) = rest.split_first_chunk()?;
+        let value = tail.get(..usize::from(len))?;
+        objects.push((id, String::from_utf8_lossy(value).trim().to_owned()));
+        rest = &tail[value.len()..];
+    }
+    Some(Page {
+        objects,
+        next: (more == 0xFF).then_some(next),
+    })
+}
+
+/// 读取设备标识，`device` 为设备 ID
+pub(super) async fn read(
+    ctx: &mut Context,
+    device: &str,
+) -> Result<DeviceIdentity, ModbusDevError> {
+    let mut identity = DeviceIdentity {
+        device: device.to_owned(),
+        ..DeviceIdentity::default()
+    };
+    let mut object_id = 0;
+    loop {
+        let request = Request::Custom(FUNCTION, Cow::Owned(vec![MEI_TYPE, READ_BASIC, object_id]));
+        let Response::Custom(FUNCTION, data) = ctx.call(request).await?? else {
+            return Err(ModbusDevError::InvalidIdentification);
+        };
+        let page = parse(&data).ok_or(ModbusDevError::InvalidIdentification)?;
+        for (id, value) in page.objects {
+            match id {
+                0x00 => identity.vendor = Some(value),
+                0x01 => identity.product_code = Some(value),
+                0x02 => identity.revision = Some(value),
+                _ => {}
+            }
+        }
+        match page.next {
+            // 下一个对象 ID 不前进时停止，避免异常设备导致死循环
+            Some(next) if next > object_id => object_id = next,
+            _ => return Ok(identity),
+        }
+    }
+}
+
+/// 标识对应的元数据点位，ID 紧挨通讯状态点位 0xFFFF 之前
+pub(super) fn points(identity: &DeviceIdentity) -> Vec<DataPoint> {
+    [
+        (0xFFFC, "厂商名称", "vendorName", &identity.vendor),
+        (0xFFFD, "产品代码", "productCode", &identity.product_code),
+        (0xFFFE, "版本", "revision", &identity.revision),
+    ]
+    .into_iter()
+    .filter_map(|(id, name, key, value)| {
+        value.as_ref().map(|value| DataPoint {
+            id,
+            name,
+            value: Val::Str(value.clone()),
+            key,
+            translator: None,
+            bits: None,
+            words: None,
+            unit: None,
+            quality: Quality::Good,
+        })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Page, parse};
+
+    #[test]
+    fn identification_response_is_parsed() {
+        let data = [
+            0x0E, 0x01, 0x01, 0xFF, 0x02, 0x02, //
+            0x00, 0x04, b'A', b'C', b'M', b'E', //
+            0x01, 0x03, b'P', b'C', b'S',
+        ];
+        assert_eq!(
+            parse(&data),
+            Some(Page {
+                objects: vec![(0x00, "ACME".into()), (0x01, "PCS".into())],
+                next: Some(0x02),
+            })
+        );
+
+        let last = [
+            0x0E, 0x01, 0x01, 0x00, 0x00, 0x01, 0x02, 0x04, b'V', b'1', b'.', b'2',
+        ];
+        assert_eq!(parse(&last).unwrap().next, None);
+    }
+
+    #[test]
+    fn truncated_response_is_rejected() {
+        assert_eq!(
+            parse(&[0x0E, 0x01, 0x01, 0x00, 0x00, 0x01, 0x00, 0x04, b'A']),
+            None
+        );
+        assert_eq!(parse(&[0x0D, 0x01, 0x01, 0x00, 0x00, 0x00]), None);
+    }
+}
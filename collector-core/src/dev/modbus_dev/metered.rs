@@ -0,0 +1,74 @@
+//! 带诊断计数的 Modbus 客户端
+//!
+//! 包装连接得到的 [`Context`]，每个请求计入设备的 [`Diagnostics`]。
+//! 请求在外层超时中被取消时计为超时；共享链路的往返时间包含排队等待总线的时间。
+
+use std::io;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+use tokio_modbus::client::{Client, Context};
+use tokio_modbus::prelude::SlaveContext;
+use tokio_modbus::{Request, Response, Slave};
+
+use crate::dev::diagnostics::Diagnostics;
+
+/// 为连接加上诊断计数
+pub(super) fn attach(inner: Context, diagnostics: Diagnostics) -> Context {
+    let client: Box<dyn Client> = Box::new(MeteredClient { inner, diagnostics });
+    Context::from(client)
+}
+
+#[derive(Debug)]
+struct MeteredClient {
+    inner: Context,
+    diagnostics: Diagnostics,
+}
+
+/// 请求未完成就被丢弃时计为超时
+struct Pending<'a> {
+    diagnostics: &'a Diagnostics,
+    done: bool,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.diagnostics.timeout();
+        }
+    }
+}
+
+impl SlaveContext for MeteredClient {
+    fn set_slave(&mut self, slave: Slave) {
+        self.inner.set_slave(slave);
+    }
+}
+
+#[async_trait]
+impl Client for MeteredClient {
+    async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+        self.diagnostics.request();
+        let mut pending = Pending {
+            diagnostics: &self.diagnostics,
+            done: false,
+        };
+        let started = Instant::now();
+        let result = self.inner.call(request).await;
+        pending.done = true;
+        match &result {
+            Ok(response) => self
+                .diagnostics
+                .response(started.elapsed(), response.is_err()),
+            Err(tokio_modbus::Error::Transport(err)) if err.kind() == io::ErrorKind::TimedOut => {
+                self.diagnostics.timeout()
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    async fn disconnect(&mut self) -> io::Result<()> {
+        self.inner.disconnect().await
+    }
+}
@@ -1,17 +1,106 @@
-mod backoff;
+pub mod bench;
 mod block;
+pub mod capture;
 mod device;
 mod downlink;
 mod error;
+mod failover;
+mod identification;
+mod metered;
+pub mod oneshot;
+mod paced;
+mod rs485;
 mod runner;
+mod shared;
+pub mod simulator;
+mod tls;
 
 pub use device::ModbusDev;
 pub use error::ModbusDevError;
 
-use crate::dev::dev_config::{ModbusRtuConfig, ModbusTcpConfig};
+use std::time::Duration;
+
+use tokio_serial::{DataBits, Parity, SerialPortBuilder, StopBits};
+
+use crate::dev::dev_config::{FrameLimits, ModbusRtuConfig, ModbusTcpConfig};
 
 #[derive(Clone)]
 pub(super) enum Protocol {
     Tcp(ModbusTcpConfig),
     Rtu(ModbusRtuConfig),
 }
+
+impl Protocol {
+    /// 是否配置了备用链路
+    fn has_backup(&self) -> bool {
+        match self {
+            Protocol::Tcp(cfg) => cfg.backup.is_some(),
+            Protocol::Rtu(cfg) => cfg.backup_tty.is_some(),
+        }
+    }
+
+    /// 连接与响应超时
+    fn timeout(&self) -> Duration {
+        match self {
+            Protocol::Tcp(cfg) => Duration::from_millis(cfg.timeout),
+            Protocol::Rtu(cfg) => Duration::from_millis(cfg.timeout),
+        }
+    }
+
+    /// 轮询周期
+    fn interval(&self) -> Duration {
+        match self {
+            Protocol::Tcp(cfg) => Duration::from_millis(cfg.interval),
+            Protocol::Rtu(cfg) => Duration::from_millis(cfg.interval),
+        }
+    }
+
+    /// 相邻两次请求之间的间隔
+    fn request_interval(&self) -> Duration {
+        match self {
+            Protocol::Tcp(cfg) => Duration::from_millis(cfg.request_interval),
+            Protocol::Rtu(cfg) => Duration::from_millis(cfg.request_interval),
+        }
+    }
+
+    /// 选择-执行控制等待选择确认的超时
+    fn select_timeout(&self) -> Duration {
+        match self {
+            Protocol::Tcp(cfg) => Duration::from_millis(cfg.select_timeout),
+            Protocol::Rtu(cfg) => Duration::from_millis(cfg.select_timeout),
+        }
+    }
+
+    fn limits(&self) -> FrameLimits {
+        match self {
+            Protocol::Tcp(cfg) => cfg.limits,
+            Protocol::Rtu(cfg) => cfg.limits,
+        }
+    }
+}
+
+/// 按配置的串口参数打开串口，无法识别的数据位、校验与停止位按 8N1 处理
+pub(crate) fn serial_port(
+    tty: &str,
+    baud_rate: u32,
+    data_bits: u8,
+    parity: &str,
+    stop_bits: u8,
+) -> SerialPortBuilder {
+    tokio_serial::new(tty, baud_rate)
+        .data_bits(match data_bits {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            _ => DataBits::Eight,
+        })
+        .parity(match parity.to_ascii_uppercase().as_str() {
+            "E" | "EVEN" => Parity::Even,
+            "O" | "ODD" => Parity::Odd,
+            _ => Parity::None,
+        })
+        .stop_bits(match stop_bits {
+            2 => StopBits::Two,
+            _ => StopBits::One,
+        })
+}
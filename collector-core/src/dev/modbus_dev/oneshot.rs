@@ -0,0 +1,265 @@
+//! 单次读写
+//!
+//! 调试时按设备配置连接设备，读取单个点位或点位表之外的地址、下发单个点位：不启动轮询，不写入数据中心。
+//! 地址换算、缺省字节序与解码规则与轮询时的点位表相同，下发的编码与检查与运行时下发相同，并总是回读校验。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio_modbus::client::Context;
+
+use crate::center::DataCenter;
+use crate::config::Device;
+use crate::config::modbus_conf::{ModbusConfig, ModbusDataType, RegisterType, ScanClass};
+use crate::core::point::{DataPoint, DownDataPoint, PointId, Quality, Val};
+use crate::dev::maintenance::MaintenanceOutput;
+use crate::dev::{DeviceError, Identifiable};
+
+use super::ModbusDev;
+use super::block::{decode_bit_value, decode_register_value};
+use super::downlink::{
+    WriteOptions, WriteOutcome, WritePlan, build_cfg_map, build_key_map, build_name_map,
+};
+use super::runner::read_raw;
+
+/// 与一个设备的连接
+pub struct Session {
+    dev: ModbusDev,
+    ctx: Context,
+}
+
+/// 编码后待下发的写入，见 [`Session::plan_write`]
+pub struct PlannedWrite(WritePlan);
+
+/// 单次读取的结果
+#[derive(Debug, Clone)]
+pub struct Reading {
+    /// 读到的原始寄存器或线圈
+    pub raw: MaintenanceOutput,
+    /// 按点位表规则解码的点位，BCD 码无效时为 `None`
+    pub point: Option<DataPoint>,
+}
+
+impl Session {
+    /// 按设备配置连接设备的主链路，设备配置须已加载点位表
+    pub async fn connect(dev: Device) -> Result<Self, DeviceError> {
+        let dev = ModbusDev::new(dev, Arc::new(DataCenter::new(1)))?;
+        let ctx = dev
+            .protocol()
+            .open(false)
+            .await
+            .map_err(|err| DeviceError::DevRuntimeError(Box::new(err)))?;
+        Ok(Self { dev, ctx })
+    }
+
+    pub(super) fn dev(&self) -> &ModbusDev {
+        &self.dev
+    }
+
+    /// 设备与连接，供 [`super::capture::Recorder`] 逐块读取
+    pub(super) fn parts(&mut self) -> (&ModbusDev, &mut Context) {
+        (&self.dev, &mut self.ctx)
+    }
+
+    /// 按键名或名称查找点位表中的点位
+    pub fn point(&self, name: &str) -> Option<ModbusConfig> {
+        self.dev
+            .points()
+            .iter()
+            .find(|cfg| cfg.key == name || cfg.name == name)
+            .copied()
+    }
+
+    /// 点位表之外的点位，`address` 按设备点位表的编号
+    pub fn adhoc_point(
+        &self,
+        register_type: RegisterType,
+        address: u16,
+        data_type: ModbusDataType,
+    ) -> Result<ModbusConfig, DeviceError> {
+        self.dev.prepare_point(ModbusConfig {
+            id: 0,
+            name: "-",
+            data_type,
+            unit: None,
+            remarks: None,
+            register_address: address,
+            register_type,
+            quantity: data_type.register_width(),
+            byte_order: None,
+            scale: 1.0,
+            offset: 0.0,
+            enable: true,
+            key: "",
+            trans: None,
+            status_words: None,
+            warn_bits: None,
+            bit: None,
+            scan_class: ScanClass::Normal,
+            select_address: None,
+            handshake_address: None,
+            pulse_ms: None,
+            epsilon: None,
+        })
+    }
+
+    /// 读取点位并解码
+    pub async fn read(&mut self, cfg: &ModbusConfig) -> Result<Reading, DeviceError> {
+        let timeout = self.dev.protocol().timeout();
+        let raw = read_raw(
+            &mut self.ctx,
+            cfg.register_type,
+            cfg.register_address,
+            cfg.quantity,
+            timeout,
+        )
+        .await
+        .map_err(DeviceError::Read)?;
+        let value = match &raw {
+            MaintenanceOutput::Bits(bits) => Some(decode_bit_value(cfg, bits)),
+            MaintenanceOutput::Registers(registers) => decode_register_value(cfg, registers),
+            MaintenanceOutput::Done => None,
+        };
+        let point = value.map(|value| DataPoint {
+            id: cfg.id as u32,
+            key: cfg.key,
+            name: cfg.name,
+            value,
+            translator: cfg.trans,
+            bits: cfg.warn_bits,
+            words: cfg.status_words,
+            unit: cfg.unit,
+            quality: Quality::Good,
+        });
+        Ok(Reading { raw, point })
+    }
+
+    /// 编码点位表中点位的下发：只读点位与超出数据类型范围的值被拒绝
+    pub fn plan_write(&self, cfg: &ModbusConfig, value: Val) -> Result<PlannedWrite, DeviceError> {
+        let points = self.dev.points();
+        let plan = WritePlan::build(
+            vec![DownDataPoint::by_id(cfg.id as PointId, value)],
+            &build_cfg_map(&points),
+            &build_key_map(&points),
+            &build_name_map(&points),
+            self.dev.protocol().limits(),
+            self.dev.id(),
+        );
+        if !plan.rejected().is_empty() {
+            return Err(DeviceError::Write(plan.rejected().join("; ")));
+        }
+        Ok(PlannedWrite(plan))
+    }
+
+    /// 下发并回读校验，设备没有接受写入时返回错误
+    pub async fn write(&mut self, write: PlannedWrite) -> Result<(), DeviceError> {
+        let protocol = self.dev.protocol();
+        let opts = WriteOptions {
+            io_timeout: protocol.timeout(),
+            interval: protocol.request_interval().max(Duration::from_millis(1)),
+            verify: true,
+            select_timeout: protocol.select_timeout(),
+        };
+        // 不会停止，发送端须保留到下发结束
+        let (_stop_tx, mut stop_rx) = watch::channel(false);
+        let mut readback = Vec::new();
+        match write
+            .0
+            .apply(&mut self.ctx, &mut stop_rx, &opts, &mut readback)
+            .await
+        {
+            Ok(WriteOutcome::Completed) => Ok(()),
+            Ok(WriteOutcome::Refused(reason)) => Err(DeviceError::Write(reason)),
+            Ok(WriteOutcome::Stopped) => Err(DeviceError::Write("下发被中止".to_owned())),
+            Err(err) => Err(DeviceError::Write(err.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::Session;
+    use crate::config::Device;
+    use crate::config::modbus_conf::{ModbusDataType, RegisterType};
+    use crate::core::point::Val;
+    use crate::dev::DeviceError;
+    use crate::dev::maintenance::MaintenanceOutput;
+
+    #[tokio::test]
+    async fn reads_points_with_point_table_rules() {
+        // 只应答读保持寄存器与写单个寄存器的从站，寄存器 n 的初值为 10(n+1)
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut registers: Vec<u16> = (1..=8).map(|n| 10 * n).collect();
+            let mut request = [0u8; 12];
+            while socket.read_exact(&mut request).await.is_ok() {
+                let address = usize::from(request[9]);
+                if request[7] == 0x06 {
+                    registers[address] = u16::from_be_bytes([request[10], request[11]]);
+                    socket.write_all(&request).await.unwrap();
+                    continue;
+                }
+                let count = request[11];
+                let mut response = vec![request[0], request[1], 0, 0, 0, 3 + 2 * count];
+                response.extend([request[6], request[7], 2 * count]);
+                for value in &registers[address..address + usize::from(count)] {
+                    response.extend(value.to_be_bytes());
+                }
+                socket.write_all(&response).await.unwrap();
+            }
+        });
+
+        let dir = std::env::temp_dir().join(format!("collector-oneshot-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let table = dir.join("points.json");
+        std::fs::write(
+            &table,
+            r#"[{ id: 1, name: "SOC", data_type: "U16", scale: 0.1,
+            register_address: 40002, register_type: "HoldingRegisters", quantity: 1, key: "soc" }]"#,
+        )
+        .unwrap();
+        let json = format!(
+            r#"{{"id": "bms", "config": {{"com_type": "ModbusTCP", "ip": "127.0.0.1", "port": {port},
+            "slave": 1, "interval": 1000, "timeout": 500, "address_base": 1, "register_file": "{}"}}}}"#,
+            table.display()
+        );
+        let mut dev: Device = serde_json::from_str(&json).unwrap();
+        dev.load_protocol_configs().await.unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let mut session = Session::connect(dev).await.unwrap();
+        assert!(session.point("missing").is_none());
+        let soc = session.point("SOC").unwrap();
+        assert_eq!(soc.register_address, 1);
+        let reading = session.read(&soc).await.unwrap();
+        assert_eq!(reading.raw, MaintenanceOutput::Registers(vec![20]));
+        assert_eq!(reading.point.unwrap().value.as_f64().unwrap(), 2.0);
+
+        let raw = session
+            .adhoc_point(RegisterType::HoldingRegisters, 40001, ModbusDataType::U32)
+            .unwrap();
+        assert_eq!(raw.register_address, 0);
+        let reading = session.read(&raw).await.unwrap();
+        assert_eq!(reading.raw, MaintenanceOutput::Registers(vec![10, 20]));
+        assert_eq!(
+            reading.point.unwrap().value.as_f64().unwrap(),
+            (10 << 16 | 20) as f64
+        );
+
+        // 下发按点位表缩放并回读校验，超出数据类型范围的值被拒绝
+        assert!(matches!(
+            session.plan_write(&soc, Val::F64(-1.0)),
+            Err(DeviceError::Write(_))
+        ));
+        let write = session.plan_write(&soc, Val::F64(2.5)).unwrap();
+        session.write(write).await.unwrap();
+        let reading = session.read(&soc).await.unwrap();
+        assert_eq!(reading.raw, MaintenanceOutput::Registers(vec![25]));
+    }
+}
@@ -0,0 +1,97 @@
+//! 请求限速
+//!
+//! 部分 PLC 连续收到请求会死机，包装连接使相邻两次请求之间至少间隔给定时间，
+//! 轮询、重试、回读校验与下发的每个请求都受限制。共享链路在链路层按帧间隔限速，不使用该包装。
+
+use std::io;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::{self, Instant};
+use tokio_modbus::client::{Client, Context};
+use tokio_modbus::prelude::SlaveContext;
+use tokio_modbus::{Request, Response, Slave};
+
+/// 为连接加上请求间的最小间隔
+pub(super) fn attach(inner: Context, delay: Duration) -> Context {
+    let client: Box<dyn Client> = Box::new(PacedClient {
+        inner,
+        delay,
+        last: None,
+    });
+    Context::from(client)
+}
+
+#[derive(Debug)]
+struct PacedClient {
+    inner: Context,
+    delay: Duration,
+    /// 上一个请求结束的时刻
+    last: Option<Instant>,
+}
+
+impl SlaveContext for PacedClient {
+    fn set_slave(&mut self, slave: Slave) {
+        self.inner.set_slave(slave);
+    }
+}
+
+#[async_trait]
+impl Client for PacedClient {
+    async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+        if let Some(last) = self.last.take() {
+            time::sleep_until(last + self.delay).await;
+        }
+        let result = self.inner.call(request).await;
+        self.last = Some(Instant::now());
+        result
+    }
+
+    async fn disconnect(&mut self) -> io::Result<()> {
+        self.inner.disconnect().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio::time::Instant;
+    use tokio_modbus::client::{Client, Context, Reader};
+    use tokio_modbus::prelude::SlaveContext;
+    use tokio_modbus::{Request, Response, Slave};
+
+    use super::attach;
+
+    #[derive(Debug)]
+    struct Echo;
+
+    impl SlaveContext for Echo {
+        fn set_slave(&mut self, _: Slave) {}
+    }
+
+    #[async_trait]
+    impl Client for Echo {
+        async fn call(&mut self, _: Request<'_>) -> tokio_modbus::Result<Response> {
+            Ok(Ok(Response::ReadHoldingRegisters(vec![0])))
+        }
+
+        async fn disconnect(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn consecutive_requests_are_spaced() {
+        let inner = Context::from(Box::new(Echo) as Box<dyn Client>);
+        let mut ctx = attach(inner, Duration::from_millis(30));
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            ctx.read_holding_registers(0, 1).await.unwrap().unwrap();
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+}
@@ -0,0 +1,159 @@
+//! RS-485 收发方向控制
+//!
+//! 没有自动收发切换电路的半双工收发器需要在发送期间置位 DE/RE：
+//! 写入前置位方向线，数据刷出后按波特率估算串口发完剩余字节的时间，再复位方向线进入接收。
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::{self, Sleep};
+use tokio_serial::{SerialPort, SerialStream};
+
+use crate::dev::dev_config::DirectionControl;
+
+/// 驱动 DE/RE 的方向线
+#[derive(Debug)]
+enum DirectionLine {
+    Rts,
+    #[cfg(target_os = "linux")]
+    Gpio(gpio_cdev::LineHandle),
+}
+
+impl DirectionLine {
+    fn open(control: &DirectionControl) -> io::Result<Self> {
+        match control {
+            DirectionControl::Rts => Ok(DirectionLine::Rts),
+            #[cfg(target_os = "linux")]
+            DirectionControl::Gpio { chip, line } => {
+                let handle = gpio_cdev::Chip::new(chip)
+                    .and_then(|mut chip| chip.get_line(*line))
+                    .and_then(|line| {
+                        line.request(gpio_cdev::LineRequestFlags::OUTPUT, 0, "collector-rs485")
+                    })
+                    .map_err(io::Error::other)?;
+                Ok(DirectionLine::Gpio(handle))
+            }
+            #[cfg(not(target_os = "linux"))]
+            DirectionControl::Gpio { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "当前平台不支持 GPIO 方向控制",
+            )),
+        }
+    }
+
+    fn set(&mut self, port: &mut SerialStream, transmit: bool) -> io::Result<()> {
+        match self {
+            DirectionLine::Rts => Ok(port.write_request_to_send(transmit)?),
+            #[cfg(target_os = "linux")]
+            DirectionLine::Gpio(handle) => handle
+                .set_value(u8::from(transmit))
+                .map_err(io::Error::other),
+        }
+    }
+}
+
+/// 发送期间置位方向线的串口
+#[derive(Debug)]
+pub(super) struct DirectedPort {
+    port: SerialStream,
+    line: DirectionLine,
+    /// 一个字符（11 位）的发送时间
+    char_time: Duration,
+    transmitting: bool,
+    /// 本次发送写入的字节数
+    written: usize,
+    drain: Option<Pin<Box<Sleep>>>,
+}
+
+impl DirectedPort {
+    pub(super) fn new(
+        mut port: SerialStream,
+        control: &DirectionControl,
+        baudrate: u32,
+    ) -> io::Result<Self> {
+        let mut line = DirectionLine::open(control)?;
+        line.set(&mut port, false)?;
+        Ok(Self {
+            port,
+            line,
+            char_time: char_time(baudrate),
+            transmitting: false,
+            written: 0,
+            drain: None,
+        })
+    }
+}
+
+/// 波特率对应的一个字符（起始位、8 数据位、校验位、停止位）的发送时间
+fn char_time(baudrate: u32) -> Duration {
+    Duration::from_micros(11_000_000_u64.div_ceil(u64::from(baudrate.max(1))))
+}
+
+impl AsyncRead for DirectedPort {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().port).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for DirectedPort {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        // 等待发完期间又有数据写入时保持发送状态
+        this.drain = None;
+        if !this.transmitting {
+            this.line.set(&mut this.port, true)?;
+            this.transmitting = true;
+            this.written = 0;
+        }
+        let n = ready!(Pin::new(&mut this.port).poll_write(cx, buf))?;
+        this.written += n;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(Pin::new(&mut this.port).poll_flush(cx))?;
+        if !this.transmitting {
+            return Poll::Ready(Ok(()));
+        }
+        let remaining = this.char_time * u32::try_from(this.written).unwrap_or(u32::MAX);
+        let drain = this
+            .drain
+            .get_or_insert_with(|| Box::pin(time::sleep(remaining)));
+        ready!(drain.as_mut().poll(cx));
+        this.drain = None;
+        this.transmitting = false;
+        this.line.set(&mut this.port, false)?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Pin::new(&mut self.get_mut().port).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::char_time;
+
+    #[test]
+    fn char_time_follows_baud_rate() {
+        assert_eq!(char_time(9600), Duration::from_micros(1146));
+        assert_eq!(char_time(1200), Duration::from_micros(9167));
+    }
+}
@@ -1,31 +1,59 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use std::time::Duration;
 
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::TcpStream;
 use tokio::sync::{mpsc, watch};
-use tokio::time;
-use tokio_modbus::Slave;
-use tokio_modbus::client::{Context, rtu, tcp};
+use tokio::time::{self, Instant};
+use tokio_modbus::client::{Context, Reader, rtu, tcp};
 use tokio_modbus::prelude::SlaveContext;
-use tokio_serial::{DataBits, Parity};
+use tokio_modbus::{ExceptionCode, Slave};
 use tracing::{info, warn};
 
-use crate::center::SharedPointCenter;
-use crate::config::modbus_conf::{ModbusConfig, ModbusConfigs};
-use crate::core::point::{DataPoint, DownDataPoint, PointId, PointRef, Val};
-use crate::dev::modbus_dev::Protocol;
-use crate::dev::modbus_dev::block::{BlockRead, Blocks};
+use crate::center::{self, DataCenterError, DownlinkReceiver, SharedPointCenter};
+use crate::config::modbus_conf::{ModbusConfig, ModbusConfigs, RegisterType, ScanClass};
+use crate::config::{ExceptionPolicy, ScanIntervals};
+use crate::core::point::{DataPoint, PointId, PointRef, Quality, Val};
+use crate::dev::modbus_dev::block::{BlockRead, Blocks, BuildBlocksError};
 use crate::dev::modbus_dev::downlink::{
-    WriteOutcome, WritePlan, build_cfg_map, build_key_map, build_name_map, stop_requested,
-    wait_interval,
+    WriteOptions, WriteOutcome, WritePlan, build_cfg_map, build_key_map, build_name_map,
+    resolve_id, stop_requested, wait_interval,
+};
+use crate::dev::modbus_dev::{Protocol, serial_port};
+use crate::dev::{
+    DeviceError, LifecycleState, backoff,
+    backoff::Backoff,
+    connect_limit::ConnectLimit,
+    dev_config::FrameLimits,
+    diagnostics::Diagnostics,
+    identity,
+    maintenance::{self, Maintenance, MaintenanceOutput},
+    state::SharedState,
 };
-use crate::dev::{LifecycleState, state::SharedState};
 
-use super::backoff::Backoff;
 use super::error::ModbusDevError;
+use super::failover::{FAILOVER_AFTER, Failover};
+use super::identification;
+use super::metered;
+use super::paced;
+use super::rs485::DirectedPort;
+use super::shared::{self, LinkKey};
+use super::tls;
 
 /// 连续读取失败（含超时）达到该阈值即判定连接不可用，触发重连
 const MAX_READ_FAILURES: u32 = 3;
 
+/// 读取失败后到重试之间的等待
+const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// 没有到期的扫描等级时的最长等待，限制空闲时的写延迟
+const IDLE_TICK: Duration = Duration::from_millis(20);
+/// 暂停期间检查下发与恢复的间隔
+const PAUSE_TICK: Duration = Duration::from_millis(200);
+/// 轮询时段外检查时段开始的间隔
+const SCHEDULE_TICK: Duration = Duration::from_secs(10);
+
 /// 三张点位查找表的打包引用，避免函数参数过多。
 struct PointMaps<'a> {
     cfg_map: &'a HashMap<PointId, ModbusConfig>,
@@ -35,7 +63,7 @@ struct PointMaps<'a> {
 
 /// `drain_writes` 的结果
 enum DrainOutcome {
-    /// 写队列已排空；`true` 表示本轮确实下发过至少一次写入
+    /// 写队列已排空；`true` 表示本轮确实下发过至少一次写入或按需读取
     Idle(bool),
     /// 写入失败，需要断线重连
     WriteFailed,
@@ -45,22 +73,65 @@ enum DrainOutcome {
     Stopped,
 }
 
+/// `run_connected` 结束的原因
+#[derive(Debug, PartialEq, Eq)]
+enum ConnectionEnd {
+    /// 连接失效、收到停止信号或下发通道关闭
+    Closed,
+    /// 点位表已更新，在同一连接上按新点位表继续轮询
+    PointsReloaded,
+    /// 维护请求立即重连
+    Reconnect,
+    /// 使用备用链路期间到了回试主链路的时间
+    RetryPrimary,
+    /// 轮询时段结束
+    OutsideSchedule,
+}
+
 /// round-robin 读取一圈 block 的结果
 enum ReadOutcome {
     /// 还未读满一圈，暂无可发布的数据
     Pending,
-    /// 读满一圈，得到解析后的数据点（可能为空）
-    Published(Vec<DataPoint>),
+    /// 读满一圈，得到解析后的数据点（可能为空）及按异常处理策略标记为坏质量的点位
+    Published {
+        points: Vec<DataPoint>,
+        bad: Vec<PointId>,
+    },
     /// 连续失败已达阈值，需要断线重连
     FailureThresholdReached,
 }
 
-/// round-robin 读取状态：当前游标、上一圈的槽位缓存、连续失败计数
+/// 块读取的参数
+struct ReadOptions {
+    timeout: Duration,
+    retries: u32,
+    exception_policy: ExceptionPolicy,
+    /// 设置后块读取失败不中断本圈，连续该圈数所有块都失败才重连
+    max_failed_cycles: Option<u32>,
+    /// 输出每个块的原始数据与解析结果
+    dump: bool,
+}
+
+/// 一个块在本圈中的读取结果
+enum Slot {
+    /// 尚未读取，或读取失败
+    Empty,
+    Read(BlockRead),
+    /// 从站返回异常码，按异常处理策略跳过
+    Skipped,
+    /// 读取失败，继续读取其余块
+    Failed,
+}
+
+/// round-robin 读取状态：当前游标、上一圈的槽位缓存
 struct ReadCursor {
     index: usize,
     block_count: usize,
-    slots: Vec<Option<BlockRead>>,
-    fail_streak: u32,
+    slots: Vec<Slot>,
+    /// 本圈中需要标记为坏质量的点位
+    bad: Vec<PointId>,
+    /// 所有块都读取失败的连续圈数
+    failed_cycles: u32,
 }
 
 impl ReadCursor {
@@ -68,17 +139,24 @@ impl ReadCursor {
         Self {
             index: 0,
             block_count,
-            slots: (0..block_count).map(|_| None).collect(),
-            fail_streak: 0,
+            slots: (0..block_count).map(|_| Slot::Empty).collect(),
+            bad: Vec::new(),
+            failed_cycles: 0,
         }
     }
 
     /// 读取下一个 block，读满一圈后统一发布，语义与原周期读取一致
+    ///
+    /// `fail_streak` 为连接上的连续失败计数，各扫描等级共用；重试后仍失败才计入。
+    /// 从站对跨越未公开空洞的块返回非法数据地址时，将块一分为二后重读，拆分结果保留到之后的轮询；
+    /// 其余异常码、以及无法再拆分的块按 `exception_policy` 处理，跳过的块不影响其他块的发布。
+    /// 设置了 `max_failed_cycles` 时读取失败的块按坏质量发布，不计入连续失败
     async fn advance(
         &mut self,
         ctx: &mut Context,
-        blocks: &Blocks,
-        timeout: Duration,
+        blocks: &mut Blocks,
+        opts: &ReadOptions,
+        fail_streak: &mut u32,
         id: &str,
     ) -> ReadOutcome {
         if self.block_count == 0 {
@@ -88,28 +166,54 @@ impl ReadCursor {
         let i = self.index;
         self.index = (self.index + 1) % self.block_count;
 
-        match time::timeout(timeout, blocks.request_one(ctx, i)).await {
+        let failure = match read_block(ctx, blocks, i, opts.timeout, opts.retries).await {
             Ok(Ok(read)) => {
-                self.fail_streak = 0;
-                self.slots[i] = Some(read);
+                *fail_streak = 0;
+                if opts.dump {
+                    info!("[{}] 块 {} {}", id, i, blocks.dump(i, &read));
+                }
+                self.slots[i] = Slot::Read(read);
+                None
             }
-            Ok(Err(err)) => {
-                self.fail_streak += 1;
-                warn!(
-                    "[{}] 读取失败 ({}/{}): {}",
-                    id, self.fail_streak, MAX_READ_FAILURES, err
+            // 拆分在守卫中完成，无法拆分时落入后续分支
+            Ok(Err(ModbusDevError::ModbusException(ExceptionCode::IllegalDataAddress)))
+                if blocks.split(i) =>
+            {
+                let (head, tail) = (&blocks.blocks[i], &blocks.blocks[i + 1]);
+                info!(
+                    "[{}] 块 {} 返回非法数据地址, 拆分为 [{:#06x}..+{}] 与 [{:#06x}..+{}]",
+                    id, i, head.start, head.len, tail.start, tail.len
                 );
-                if self.fail_streak >= MAX_READ_FAILURES {
-                    return ReadOutcome::FailureThresholdReached;
+                self.block_count += 1;
+                self.slots.insert(i + 1, Slot::Empty);
+                self.index = i;
+                return ReadOutcome::Pending;
+            }
+            Ok(Err(ModbusDevError::ModbusException(code)))
+                if opts.exception_policy != ExceptionPolicy::Reconnect =>
+            {
+                warn!("[{}] 块 {} 返回异常 {:?}, 跳过", id, i, code);
+                self.slots[i] = Slot::Skipped;
+                if opts.exception_policy == ExceptionPolicy::Bad {
+                    self.bad.extend(blocks.point_ids(i));
                 }
+                None
             }
-            Err(_) => {
-                self.fail_streak += 1;
+            Ok(Err(err)) => Some(format!("读取失败: {err}")),
+            Err(_) => Some("读取超时".to_owned()),
+        };
+        if let Some(reason) = failure {
+            if opts.max_failed_cycles.is_some() {
+                warn!("[{}] 块 {} {}, 继续读取其余块", id, i, reason);
+                self.slots[i] = Slot::Failed;
+                self.bad.extend(blocks.point_ids(i));
+            } else {
+                *fail_streak += 1;
                 warn!(
-                    "[{}] 读取超时 ({}/{}, 块 {})",
-                    id, self.fail_streak, MAX_READ_FAILURES, i
+                    "[{}] 块 {} {} ({}/{})",
+                    id, i, reason, fail_streak, MAX_READ_FAILURES
                 );
-                if self.fail_streak >= MAX_READ_FAILURES {
+                if *fail_streak >= MAX_READ_FAILURES {
                     return ReadOutcome::FailureThresholdReached;
                 }
             }
@@ -118,23 +222,199 @@ impl ReadCursor {
         if self.index != 0 {
             return ReadOutcome::Pending;
         }
-        // 读完一圈：取出所有槽位数据，take() 同时将槽位复位为 None
-        let reads: Vec<_> = self.slots.iter_mut().filter_map(|s| s.take()).collect();
-        if reads.len() != self.block_count {
+        // 读完一圈：取出所有槽位数据，同时将槽位复位
+        let slots: Vec<Slot> = self
+            .slots
+            .iter_mut()
+            .map(|slot| std::mem::replace(slot, Slot::Empty))
+            .collect();
+        let bad = std::mem::take(&mut self.bad);
+        if slots.iter().any(|slot| matches!(slot, Slot::Empty)) {
             return ReadOutcome::Pending;
         }
-        ReadOutcome::Published(blocks.parse(&reads))
+        if let Some(limit) = opts.max_failed_cycles {
+            if slots.iter().all(|slot| matches!(slot, Slot::Failed)) {
+                self.failed_cycles += 1;
+                warn!(
+                    "[{}] 本圈所有块读取失败 ({}/{})",
+                    id, self.failed_cycles, limit
+                );
+                if self.failed_cycles >= limit {
+                    return ReadOutcome::FailureThresholdReached;
+                }
+            } else {
+                self.failed_cycles = 0;
+            }
+        }
+        let reads: Vec<Option<BlockRead>> = slots
+            .into_iter()
+            .map(|slot| match slot {
+                Slot::Read(read) => Some(read),
+                _ => None,
+            })
+            .collect();
+        ReadOutcome::Published {
+            points: blocks.parse_partial(&reads),
+            bad,
+        }
+    }
+}
+
+/// 一个扫描等级的读取块，`period` 为空时连续轮询
+struct ScanGroup {
+    class: ScanClass,
+    period: Option<Duration>,
+    blocks: Blocks,
+}
+
+/// 按扫描等级与轮询周期分别构建读取块，不同等级、不同周期的点位不会合并到同一个块
+fn build_scan_groups(
+    configs: ModbusConfigs,
+    max_gap: u16,
+    limits: FrameLimits,
+    intervals: &ScanIntervals,
+) -> Result<Vec<ScanGroup>, BuildBlocksError> {
+    let mut classes: BTreeMap<(ScanClass, Option<u64>), ModbusConfigs> = BTreeMap::new();
+    for cfg in configs {
+        let period = intervals.period(cfg.scan_class, cfg.register_type);
+        classes
+            .entry((cfg.scan_class, period))
+            .or_default()
+            .push(cfg);
+    }
+    classes
+        .into_iter()
+        .map(|((class, period), configs)| {
+            Ok(ScanGroup {
+                class,
+                period: period.map(Duration::from_millis),
+                blocks: Blocks::build(configs, max_gap, limits)?,
+            })
+        })
+        .collect()
+}
+
+/// 由点位表构建的读取块与点位查找表
+pub(super) struct Plan {
+    groups: Vec<ScanGroup>,
+    cfg_map: HashMap<PointId, ModbusConfig>,
+    key_map: HashMap<&'static str, PointId>,
+    name_map: HashMap<&'static str, PointId>,
+}
+
+impl Plan {
+    /// 各扫描等级的读取块
+    pub(super) fn blocks(&self) -> impl Iterator<Item = &Blocks> {
+        self.groups.iter().map(|group| &group.blocks)
+    }
+
+    /// 各扫描等级的读取块总数
+    pub(super) fn block_count(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.blocks.blocks.len())
+            .sum()
+    }
+}
+
+/// 按设备的协议配置构建点位表的读取计划；RTU 广播地址没有应答，点位只用于下发
+pub(super) fn build_plan(
+    protocol: &Protocol,
+    configs: &ModbusConfigs,
+) -> Result<Plan, BuildBlocksError> {
+    let (max_gap, limits, intervals, broadcast) = match protocol {
+        Protocol::Tcp(cfg) => (cfg.max_gap, cfg.limits, &cfg.scan_intervals, false),
+        Protocol::Rtu(cfg) => (cfg.max_gap, cfg.limits, &cfg.scan_intervals, cfg.slave == 0),
+    };
+    let polled = if broadcast {
+        ModbusConfigs::new()
+    } else {
+        configs.clone()
+    };
+    Ok(Plan {
+        groups: build_scan_groups(polled, max_gap, limits, intervals)?,
+        cfg_map: build_cfg_map(configs),
+        key_map: build_key_map(configs),
+        name_map: build_name_map(configs),
+    })
+}
+
+/// 扫描等级在当前连接上的读取进度
+struct ScanState {
+    cursor: ReadCursor,
+    /// 下一个块可以读取的时刻
+    due: Instant,
+    /// 本圈开始读取的时刻
+    started: Instant,
+}
+
+impl ScanState {
+    fn new(group: &ScanGroup, now: Instant) -> Self {
+        Self {
+            cursor: ReadCursor::new(group.blocks.block_count()),
+            due: now,
+            started: now,
+        }
+    }
+
+    /// 读完一个块后更新到期时刻：一圈内的块立即可读，读完一圈后等到下一周期；
+    /// 周期已过时从当前时刻算起，避免周期过短的等级一直抢占其他等级
+    fn finish_block(&mut self, period: Option<Duration>, now: Instant) {
+        self.due = match period {
+            Some(period) if self.cursor.index == 0 => (self.started + period).max(now),
+            _ => now,
+        };
+    }
+}
+
+/// 读取一个块，失败或超时后等待 [`RETRY_DELAY`] 重试，至多重试 `retries` 次
+///
+/// 从站返回的异常码说明请求已送达，重试无意义，直接返回
+async fn read_block(
+    ctx: &mut Context,
+    blocks: &Blocks,
+    index: usize,
+    timeout: Duration,
+    retries: u32,
+) -> Result<Result<BlockRead, ModbusDevError>, time::error::Elapsed> {
+    let mut attempt = 0;
+    loop {
+        let result = time::timeout(timeout, blocks.request_one(ctx, index)).await;
+        if attempt >= retries
+            || matches!(result, Ok(Ok(_) | Err(ModbusDevError::ModbusException(_))))
+        {
+            return result;
+        }
+        attempt += 1;
+        time::sleep(RETRY_DELAY).await;
     }
 }
 
+/// 选出下一个读取的扫描等级：已到期的等级中到期最早的，同时到期时等级高的优先
+fn next_scan(states: &[ScanState], now: Instant) -> Option<usize> {
+    states
+        .iter()
+        .enumerate()
+        .filter(|(_, state)| state.cursor.block_count > 0 && state.due <= now)
+        .min_by_key(|(index, state)| (state.due, *index))
+        .map(|(index, _)| index)
+}
+
 pub(super) struct ModbusRunner {
     pub(super) id: String,
     pub(super) protocol: Protocol,
     pub(super) configs: ModbusConfigs,
+    /// 热更新的点位表，在轮询周期之间换用
+    pub(super) points_rx: watch::Receiver<Arc<ModbusConfigs>>,
     pub(super) state: SharedState,
     pub(super) stop_rx: watch::Receiver<bool>,
-    pub(super) rx: mpsc::Receiver<Vec<DownDataPoint>>,
+    pub(super) pause_rx: watch::Receiver<bool>,
+    pub(super) rx: DownlinkReceiver,
+    pub(super) maintenance_rx: maintenance::RequestReceiver,
     pub(super) center: SharedPointCenter,
+    pub(super) diagnostics: Diagnostics,
+    pub(super) connect_limit: ConnectLimit,
+    pub(super) failover: Failover,
 }
 
 impl ModbusRunner {
@@ -151,90 +431,209 @@ impl ModbusRunner {
                 bits: None,
                 words: None,
                 unit: None,
+                quality: Quality::Good,
             }],
         );
     }
 
     fn timeout(&self) -> Duration {
+        self.protocol.timeout()
+    }
+
+    fn request_interval(&self) -> Duration {
+        self.protocol.request_interval()
+    }
+
+    fn retries(&self) -> u32 {
         match &self.protocol {
-            Protocol::Tcp(cfg) => Duration::from_millis(cfg.timeout),
-            Protocol::Rtu(cfg) => Duration::from_millis(cfg.timeout),
+            Protocol::Tcp(cfg) => cfg.retries,
+            Protocol::Rtu(cfg) => cfg.retries,
         }
     }
 
-    fn request_interval(&self) -> Duration {
+    fn exception_policy(&self) -> ExceptionPolicy {
         match &self.protocol {
-            Protocol::Tcp(cfg) => Duration::from_millis(cfg.request_interval),
-            Protocol::Rtu(cfg) => Duration::from_millis(cfg.request_interval),
+            Protocol::Tcp(cfg) => cfg.exception_policy,
+            Protocol::Rtu(cfg) => cfg.exception_policy,
         }
     }
 
-    fn max_gap(&self) -> u16 {
+    fn substitute(&self) -> Option<Val> {
         match &self.protocol {
-            Protocol::Tcp(cfg) => cfg.max_gap,
-            Protocol::Rtu(cfg) => cfg.max_gap,
+            Protocol::Tcp(cfg) => cfg.substitute.map(Val::F64),
+            Protocol::Rtu(cfg) => cfg.substitute.map(Val::F64),
         }
     }
 
-    async fn connect(&self) -> Result<Context, ModbusDevError> {
+    /// 连接失效后将点位表中的点位标记为通讯故障，避免中断前的值看起来仍是最新的
+    fn set_points_comm_fail(&self) {
+        let ids: Vec<PointId> = self.configs.iter().map(|cfg| cfg.id as PointId).collect();
+        self.center
+            .mark_comm_fail(&self.id, &ids, self.substitute().as_ref());
+    }
+
+    fn max_failed_cycles(&self) -> Option<u32> {
         match &self.protocol {
-            Protocol::Tcp(cfg) => {
-                let addr = format!("{}:{}", cfg.ip, cfg.port).parse()?;
-                let mut ctx = time::timeout(self.timeout(), tcp::connect(addr)).await??;
-                ctx.set_slave(Slave(cfg.slave));
-                Ok(ctx)
+            Protocol::Tcp(cfg) => cfg.max_failed_cycles,
+            Protocol::Rtu(cfg) => cfg.max_failed_cycles,
+        }
+    }
+
+    fn debug_dump(&self) -> bool {
+        match &self.protocol {
+            Protocol::Tcp(cfg) => cfg.debug_dump,
+            Protocol::Rtu(cfg) => cfg.debug_dump,
+        }
+    }
+
+    fn verify_writes(&self) -> bool {
+        match &self.protocol {
+            Protocol::Tcp(cfg) => cfg.verify_writes,
+            Protocol::Rtu(cfg) => cfg.verify_writes,
+        }
+    }
+
+    fn start_jitter(&self) -> Duration {
+        match &self.protocol {
+            Protocol::Tcp(cfg) => cfg.start_jitter,
+            Protocol::Rtu(cfg) => cfg.start_jitter,
+        }
+    }
+
+    fn select_timeout(&self) -> Duration {
+        self.protocol.select_timeout()
+    }
+
+    fn limits(&self) -> FrameLimits {
+        self.protocol.limits()
+    }
+
+    /// 当前本地时间是否在轮询时段内
+    fn in_schedule(&self) -> bool {
+        let schedule = match &self.protocol {
+            Protocol::Tcp(cfg) => &cfg.schedule,
+            Protocol::Rtu(cfg) => &cfg.schedule,
+        };
+        schedule.is_always() || schedule.allows(chrono::Local::now().naive_local())
+    }
+
+    /// 是否使用共享链路：同一串口上的 RTU 设备总是共用串口
+    fn shared_link(&self) -> bool {
+        match &self.protocol {
+            Protocol::Tcp(cfg) => cfg.shared_connection,
+            Protocol::Rtu(_) => true,
+        }
+    }
+
+    /// 单次请求的外层超时；共享链路在取得总线后按 timeout 计时，排队等待不计入
+    fn request_timeout(&self) -> Duration {
+        if self.shared_link() {
+            Duration::MAX
+        } else {
+            self.timeout()
+        }
+    }
+
+    /// 建立连接，连接上的请求计入诊断计数
+    ///
+    /// 独占连接在计数之外限速，等待的时间不计入往返时间；共享链路由链路按帧间隔限速。
+    /// `backup` 为 `true` 时连接备用链路
+    async fn connect(&self, backup: bool) -> Result<Context, ModbusDevError> {
+        let ctx = metered::attach(self.protocol.open(backup).await?, self.diagnostics.clone());
+        match &self.protocol {
+            Protocol::Tcp(cfg) if !cfg.shared_connection && !cfg.min_request_delay.is_zero() => {
+                Ok(paced::attach(ctx, cfg.min_request_delay))
             }
-            Protocol::Rtu(cfg) => {
-                let mut builder = tokio_serial::new(cfg.serial_tty.as_str(), cfg.baudrate);
-                builder = builder
-                    .data_bits(match cfg.data_bits {
-                        5 => DataBits::Five,
-                        6 => DataBits::Six,
-                        7 => DataBits::Seven,
-                        _ => DataBits::Eight,
-                    })
-                    .parity(match cfg.parity.to_ascii_uppercase().as_str() {
-                        "E" | "EVEN" => Parity::Even,
-                        "O" | "ODD" => Parity::Odd,
-                        _ => Parity::None,
-                    })
-                    .stop_bits(match cfg.stop_bits {
-                        2 => tokio_serial::StopBits::Two,
-                        _ => tokio_serial::StopBits::One,
-                    })
-                    .timeout(self.timeout());
-                let port = tokio_serial::SerialStream::open(&builder)?;
-                let ctx = time::timeout(self.timeout(), async move {
-                    Ok::<_, ModbusDevError>(rtu::attach_slave(port, Slave(cfg.slave)))
-                })
-                .await??;
-                Ok(ctx)
+            _ => Ok(ctx),
+        }
+    }
+
+    /// 按配置读取设备标识并发布为元数据点位，失败时只记录警告，不影响轮询
+    async fn identify(&self, ctx: &mut Context) {
+        let Protocol::Tcp(cfg) = &self.protocol else {
+            return;
+        };
+        if !cfg.identify {
+            return;
+        }
+        match time::timeout(self.request_timeout(), identification::read(ctx, &self.id)).await {
+            Ok(Ok(identity)) => {
+                info!(
+                    "[{}] 设备标识: {} {} {}",
+                    self.id,
+                    identity.vendor.as_deref().unwrap_or("-"),
+                    identity.product_code.as_deref().unwrap_or("-"),
+                    identity.revision.as_deref().unwrap_or("-")
+                );
+                self.center
+                    .ingest(&self.id, identification::points(&identity));
+                identity::record(identity);
             }
+            Ok(Err(err)) => warn!("[{}] 读取设备标识失败: {}", self.id, err),
+            Err(_) => warn!("[{}] 读取设备标识超时", self.id),
         }
     }
 
-    /// 单请求调度器：每次节拍先排空写队列，再按 round-robin 读下一个块。
+    /// 单请求调度器：每次节拍先排空写队列，再从到期的扫描等级中按 round-robin 读下一个块。
     ///
-    /// 写延迟 ≤ request_interval，不随块数增长。
+    /// 写延迟 ≤ request_interval，不随块数增长；没有到期的扫描等级时不超过 [`IDLE_TICK`]。
     /// 写入之后、以及每次读取之后都会等待一个 request_interval，
     /// 避免写完立刻读、或读请求过于密集导致从站/网关来不及响应。
+    ///
+    /// 点位表更新后，等各扫描等级读完当前一圈再返回 [`ConnectionEnd::PointsReloaded`]，由调用方换用新点位表
     async fn run_connected(
         &mut self,
         ctx: &mut Context,
         stop_rx: &mut watch::Receiver<bool>,
-        blocks: &Blocks,
-        maps: PointMaps<'_>,
-    ) {
+        plan: &mut Plan,
+    ) -> ConnectionEnd {
+        let Plan {
+            groups,
+            cfg_map,
+            key_map,
+            name_map,
+        } = plan;
+        let maps = PointMaps {
+            cfg_map,
+            key_map,
+            name_map,
+        };
         self.state.store(&self.id, LifecycleState::Running);
-        let timeout = self.timeout();
+        let read_opts = ReadOptions {
+            timeout: self.request_timeout(),
+            retries: self.retries(),
+            exception_policy: self.exception_policy(),
+            max_failed_cycles: self.max_failed_cycles(),
+            dump: self.debug_dump(),
+        };
         let effective_interval = self.request_interval().max(Duration::from_millis(1));
 
-        let mut reader = ReadCursor::new(blocks.block_count());
+        let now = Instant::now();
+        let mut scans: Vec<ScanState> = groups
+            .iter()
+            .map(|group| ScanState::new(group, now))
+            .collect();
+        let mut fail_streak = 0;
 
         loop {
             if stop_requested(stop_rx) {
                 self.set_comm_fault(true);
-                return;
+                return ConnectionEnd::Closed;
+            }
+            if self.points_rx.has_changed().unwrap_or(false)
+                && scans.iter().all(|scan| scan.cursor.index == 0)
+            {
+                return ConnectionEnd::PointsReloaded;
+            }
+            if scans.iter().all(|scan| scan.cursor.index == 0) {
+                if !self.in_schedule() {
+                    info!("[{}] 轮询时段结束, 断开连接", self.id);
+                    self.set_comm_fault(true);
+                    return ConnectionEnd::OutsideSchedule;
+                }
+                if self.failover.retry_due(Instant::now()) {
+                    return ConnectionEnd::RetryPrimary;
+                }
             }
 
             match self
@@ -245,37 +644,91 @@ impl ModbusRunner {
                     // 写后至少让从站/网关喘一口气，避免写完立刻读导致超时
                     if wrote_any && wait_interval(stop_rx, effective_interval).await {
                         self.set_comm_fault(true);
-                        return;
+                        return ConnectionEnd::Closed;
                     }
                 }
                 DrainOutcome::WriteFailed => {
                     self.set_comm_fault(true);
-                    return;
+                    self.set_points_comm_fail();
+                    return ConnectionEnd::Closed;
                 }
-                DrainOutcome::ChannelClosed => return,
+                DrainOutcome::ChannelClosed => return ConnectionEnd::Closed,
                 DrainOutcome::Stopped => {
                     self.set_comm_fault(true);
-                    return;
+                    return ConnectionEnd::Closed;
                 }
             }
 
-            match reader.advance(ctx, blocks, timeout, &self.id).await {
-                ReadOutcome::Published(entries) => {
-                    if !entries.is_empty() {
-                        self.center.ingest(&self.id, entries);
+            if self.drain_maintenance(ctx, &mut scans).await {
+                self.set_comm_fault(true);
+                return ConnectionEnd::Reconnect;
+            }
+
+            // 暂停期间只处理下发与维护请求
+            if *self.pause_rx.borrow() {
+                self.state.store(&self.id, LifecycleState::Paused);
+                if wait_interval(stop_rx, PAUSE_TICK).await {
+                    self.set_comm_fault(true);
+                    return ConnectionEnd::Closed;
+                }
+                continue;
+            }
+            if self.state.load() == LifecycleState::Paused {
+                self.state.store(&self.id, LifecycleState::Running);
+            }
+
+            let now = Instant::now();
+            let Some(index) = next_scan(&scans, now) else {
+                let idle = scans
+                    .iter()
+                    .filter(|scan| scan.cursor.block_count > 0)
+                    .map(|scan| scan.due.saturating_duration_since(now))
+                    .min()
+                    .unwrap_or(IDLE_TICK);
+                if wait_interval(stop_rx, idle.clamp(Duration::from_millis(1), IDLE_TICK)).await {
+                    self.set_comm_fault(true);
+                    return ConnectionEnd::Closed;
+                }
+                continue;
+            };
+            let (group, scan) = (&mut groups[index], &mut scans[index]);
+            if scan.cursor.index == 0 {
+                scan.started = now;
+            }
+            let outcome = scan
+                .cursor
+                .advance(
+                    ctx,
+                    &mut group.blocks,
+                    &read_opts,
+                    &mut fail_streak,
+                    &self.id,
+                )
+                .await;
+            scan.finish_block(group.period, Instant::now());
+            match outcome {
+                ReadOutcome::Published { points, bad } => {
+                    if !points.is_empty() {
+                        self.diagnostics.poll();
+                        self.center.ingest(&self.id, points);
+                    }
+                    if !bad.is_empty() {
+                        self.center.mark_bad(&self.id, &bad);
                     }
                 }
                 ReadOutcome::Pending => {}
                 ReadOutcome::FailureThresholdReached => {
+                    warn!("[{}] 扫描等级{:?}连续读取失败", self.id, group.class);
                     self.set_comm_fault(true);
-                    return;
+                    self.set_points_comm_fail();
+                    return ConnectionEnd::Closed;
                 }
             }
 
             // 块间间隔：至少 1ms，防止 request_interval=0 时循环不挂起导致单核 100%
             if wait_interval(stop_rx, effective_interval).await {
                 self.set_comm_fault(true);
-                return;
+                return ConnectionEnd::Closed;
             }
         }
     }
@@ -291,11 +744,19 @@ impl ModbusRunner {
         stop_rx: &mut watch::Receiver<bool>,
         interval: Duration,
     ) -> DrainOutcome {
-        let timeout = self.timeout();
+        let timeout = self.request_timeout();
         let mut wrote_any = false;
         loop {
             match self.rx.try_recv() {
-                Ok(entries) => {
+                Ok(mut cmd) => {
+                    if let Some(read) = cmd.read.take() {
+                        let result = self.read_points(ctx, &read.points, maps, interval).await;
+                        read.reply(result);
+                        wrote_any = true;
+                        continue;
+                    }
+                    let atomic = cmd.atomic;
+                    let (entries, ack) = cmd.into_parts();
                     let items: Vec<String> = entries
                         .iter()
                         .map(|e| format!("{}: {}", resolve_name(&e.point, maps.cfg_map), e.value))
@@ -306,13 +767,54 @@ impl ModbusRunner {
                         maps.cfg_map,
                         maps.key_map,
                         maps.name_map,
+                        self.limits(),
                         &self.id,
                     );
-                    match plan.apply(ctx, timeout, stop_rx, interval).await {
-                        Ok(WriteOutcome::Completed) => {}
-                        Ok(WriteOutcome::Stopped) => return DrainOutcome::Stopped,
+                    // 原子下发：任一点位无法编码或需要多个写入步骤则整批拒绝，不写入任何寄存器
+                    if atomic {
+                        let reason = if !plan.rejected().is_empty() {
+                            Some(plan.rejected().join("; "))
+                        } else if plan.steps() > 1 {
+                            Some(format!(
+                                "原子下发须在一次写入内完成, 当前需{}次写入",
+                                plan.steps()
+                            ))
+                        } else {
+                            None
+                        };
+                        if let Some(reason) = reason {
+                            warn!("[{}] 原子下发被拒绝: {}", self.id, reason);
+                            center::reply(ack, Err(DataCenterError::Rejected(reason)));
+                            continue;
+                        }
+                    }
+                    let opts = WriteOptions {
+                        io_timeout: timeout,
+                        interval,
+                        verify: self.verify_writes(),
+                        select_timeout: self.select_timeout(),
+                    };
+                    let mut readback = Vec::new();
+                    let result = plan.apply(ctx, stop_rx, &opts, &mut readback).await;
+                    if !readback.is_empty() {
+                        self.center.ingest(&self.id, readback);
+                    }
+                    match result {
+                        Ok(WriteOutcome::Completed) => center::reply(ack, Ok(())),
+                        Ok(WriteOutcome::Refused(reason)) => {
+                            warn!("[{}] 设备未接受下发: {}", self.id, reason);
+                            center::reply(ack, Err(DataCenterError::WriteFailed(reason)));
+                        }
+                        Ok(WriteOutcome::Stopped) => {
+                            center::reply(
+                                ack,
+                                Err(DataCenterError::WriteFailed("设备停止".into())),
+                            );
+                            return DrainOutcome::Stopped;
+                        }
                         Err(err) => {
                             warn!("[{}] 下发失败, 准备重连: {}", self.id, err);
+                            center::reply(ack, Err(DataCenterError::WriteFailed(err.to_string())));
                             return DrainOutcome::WriteFailed;
                         }
                     }
@@ -328,65 +830,299 @@ impl ModbusRunner {
         }
     }
 
+    /// 处理已到达的维护请求，请求重连时返回 `true`
+    async fn drain_maintenance(&mut self, ctx: &mut Context, scans: &mut [ScanState]) -> bool {
+        while let Ok(request) = self.maintenance_rx.try_recv() {
+            info!("[{}] 维护: {:?}", self.id, request.op);
+            let result = match request.op {
+                Maintenance::Reconnect => {
+                    let _ = request.reply.send(Ok(MaintenanceOutput::Done));
+                    return true;
+                }
+                Maintenance::ResetDiagnostics => {
+                    self.diagnostics.reset();
+                    Ok(MaintenanceOutput::Done)
+                }
+                Maintenance::PollNow => {
+                    let now = Instant::now();
+                    for scan in scans.iter_mut() {
+                        scan.due = now;
+                    }
+                    Ok(MaintenanceOutput::Done)
+                }
+                Maintenance::DumpRegisters {
+                    register_type,
+                    address,
+                    count,
+                } => {
+                    self.dump_registers(ctx, register_type, address, count)
+                        .await
+                }
+            };
+            let _ = request.reply.send(result);
+        }
+        false
+    }
+
+    /// 未连接时处理已到达的维护请求：重连请求立即应答，其余请求回复设备未运行；
+    /// 有重连请求时返回 `true`，调用方不等待退避直接连接
+    fn reject_maintenance(&mut self) -> bool {
+        let mut reconnect = false;
+        while let Ok(request) = self.maintenance_rx.try_recv() {
+            reconnect |= answer_disconnected(&self.id, request);
+        }
+        reconnect
+    }
+
+    /// 读取原始寄存器或线圈，不解析、不更新缓存
+    async fn dump_registers(
+        &self,
+        ctx: &mut Context,
+        register_type: RegisterType,
+        address: u16,
+        count: u16,
+    ) -> Result<MaintenanceOutput, DeviceError> {
+        let timeout = self.request_timeout();
+        let output = read_raw(ctx, register_type, address, count, timeout)
+            .await
+            .map_err(DeviceError::Maintenance)?;
+        info!(
+            "[{}] {:?} {:#06x}..+{}: {:?}",
+            self.id, register_type, address, count, output
+        );
+        Ok(output)
+    }
+
+    /// 按需读取指定点位，不影响轮询进度，读到的值同时更新缓存
+    ///
+    /// 点位涉及多个读取块时，块间等待 `interval`
+    async fn read_points(
+        &self,
+        ctx: &mut Context,
+        points: &[PointRef],
+        maps: &PointMaps<'_>,
+        interval: Duration,
+    ) -> Result<Vec<DataPoint>, DataCenterError> {
+        let mut configs: ModbusConfigs = Vec::with_capacity(points.len());
+        for point in points {
+            let cfg = resolve_id(point, maps.key_map, maps.name_map)
+                .and_then(|id| maps.cfg_map.get(&id))
+                .ok_or_else(|| {
+                    DataCenterError::NotFoundPoint(self.id.clone(), format!("{point:?}"))
+                })?;
+            if !configs.iter().any(|it| it.id == cfg.id) {
+                configs.push(*cfg);
+            }
+        }
+        let blocks = Blocks::build(configs, 0, self.limits())
+            .map_err(|err| DataCenterError::ReadFailed(err.to_string()))?;
+        let mut reads = Vec::with_capacity(blocks.block_count());
+        for index in 0..blocks.block_count() {
+            if index > 0 {
+                time::sleep(interval).await;
+            }
+            let read = read_block(ctx, &blocks, index, self.request_timeout(), self.retries())
+                .await
+                .map_err(|_| DataCenterError::ReadFailed("读取超时".into()))?
+                .map_err(|err| DataCenterError::ReadFailed(err.to_string()))?;
+            reads.push(read);
+        }
+        let points = blocks.parse(&reads);
+        if !points.is_empty() {
+            self.center.ingest(&self.id, points.clone());
+        }
+        Ok(points)
+    }
+
+    /// 按点位表设置各点位的变化容差，未指定的点位按数据中心的全局容差比较
+    fn apply_epsilons(&self) {
+        for cfg in &self.configs {
+            self.center
+                .set_point_epsilon(&self.id, cfg.id as PointId, cfg.epsilon);
+        }
+    }
+
+    /// 换用热更新的点位表，新点位表无法构建读取块时保留原点位表
+    fn swap_points(&mut self, plan: &mut Plan) {
+        let configs = self.points_rx.borrow_and_update().as_ref().clone();
+        match build_plan(&self.protocol, &configs) {
+            Ok(new) => {
+                *plan = new;
+                self.configs = configs;
+                self.apply_epsilons();
+                info!(
+                    "[{}] 已换用新点位表, 共{}个点位",
+                    self.id,
+                    self.configs.len()
+                );
+            }
+            Err(err) => warn!(
+                "[{}] 新点位表构建读取块失败, 保留原点位表: {}",
+                self.id, err
+            ),
+        }
+    }
+
+    /// 回试主链路，备用链路上的连接保留到回试结束；主链路连接成功时返回新连接
+    async fn retry_primary(&mut self) -> Option<Context> {
+        let connected = self.connect(false).await;
+        self.failover.retried(connected.is_ok(), Instant::now());
+        match connected {
+            Ok(mut ctx) => {
+                info!("[{}] 主链路已恢复, 切回主链路", self.id);
+                self.diagnostics.set_backup_active(false);
+                self.identify(&mut ctx).await;
+                Some(ctx)
+            }
+            Err(err) => {
+                info!("[{}] 主链路仍不可用, 继续使用备用链路: {}", self.id, err);
+                None
+            }
+        }
+    }
+
     pub(super) async fn run(mut self) {
-        let cfg_map = build_cfg_map(&self.configs);
-        let key_map = build_key_map(&self.configs);
-        let name_map = build_name_map(&self.configs);
-        let blocks = match Blocks::build(self.configs.clone(), self.max_gap()) {
-            Ok(blocks) => blocks,
+        let mut plan = match build_plan(&self.protocol, &self.configs) {
+            Ok(plan) => plan,
             Err(err) => {
                 warn!("[{}] 构建读取块失败: {}", self.id, err);
-                self.state.store(&self.id, LifecycleState::Failed);
+                self.state
+                    .store_with_reason(&self.id, LifecycleState::Failed, err.to_string());
                 self.set_comm_fault(true);
                 return;
             }
         };
+        self.apply_epsilons();
         let mut stop_rx = self.stop_rx.clone();
         let mut backoff = Backoff::new(Duration::from_millis(500), Duration::from_secs(10));
+        let mut first_attempt = true;
+        let mut start_jitter = self.start_jitter();
+        let mut pause_rx = self.pause_rx.clone();
+        self.diagnostics.set_backup_active(false);
         loop {
             if stop_requested(&stop_rx) {
                 self.state.store(&self.id, LifecycleState::Stopped);
                 self.set_comm_fault(true);
                 return;
             }
+            // 暂停期间断开后不重连，恢复后再连接
+            if *pause_rx.borrow_and_update() {
+                self.state.store(&self.id, LifecycleState::Paused);
+                tokio::select! {
+                    _ = pause_rx.wait_for(|paused| !*paused) => {}
+                    _ = stop_rx.wait_for(|stop| *stop) => {}
+                }
+                continue;
+            }
+            // 轮询时段外不连接，时段开始后再连接，不计为重连
+            if !self.in_schedule() {
+                self.state
+                    .store_with_reason(&self.id, LifecycleState::Paused, "不在轮询时段");
+                first_attempt = true;
+                tokio::select! {
+                    _ = time::sleep(SCHEDULE_TICK) => {}
+                    _ = stop_rx.wait_for(|stop| *stop) => {}
+                }
+                continue;
+            }
+            if self.points_rx.has_changed().unwrap_or(false) {
+                self.swap_points(&mut plan);
+            }
             self.state.store(&self.id, LifecycleState::Connecting);
             self.set_comm_fault(true);
+            if !std::mem::take(&mut first_attempt) {
+                self.diagnostics.reconnect();
+            }
 
-            match self.connect().await {
+            // 等待连接许可期间也响应停止
+            let permit = tokio::select! {
+                permit = self.connect_limit.acquire() => permit,
+                _ = stop_rx.wait_for(|stop| *stop) => continue,
+            };
+            let connected = self.connect(self.failover.on_backup()).await;
+            drop(permit);
+            let reconnect_now = match connected {
                 Ok(mut ctx) => {
                     backoff.reset();
+                    self.failover.connected();
                     self.state.store(&self.id, LifecycleState::Connected);
                     self.set_comm_fault(false);
-                    self.run_connected(
-                        &mut ctx,
-                        &mut stop_rx,
-                        &blocks,
-                        PointMaps {
-                            cfg_map: &cfg_map,
-                            key_map: &key_map,
-                            name_map: &name_map,
-                        },
-                    )
-                    .await;
+                    self.identify(&mut ctx).await;
+                    // 只在第一次连接后等待，重连不再等待
+                    let delay = backoff::jitter(std::mem::take(&mut start_jitter));
+                    if !delay.is_zero() && wait_interval(&mut stop_rx, delay).await {
+                        continue;
+                    }
+                    let mut end = self.run_connected(&mut ctx, &mut stop_rx, &mut plan).await;
+                    loop {
+                        match end {
+                            ConnectionEnd::PointsReloaded => self.swap_points(&mut plan),
+                            ConnectionEnd::RetryPrimary => {
+                                if let Some(primary) = self.retry_primary().await {
+                                    ctx = primary;
+                                }
+                            }
+                            ConnectionEnd::Closed
+                            | ConnectionEnd::Reconnect
+                            | ConnectionEnd::OutsideSchedule => break,
+                        }
+                        end = self.run_connected(&mut ctx, &mut stop_rx, &mut plan).await;
+                    }
+                    // 连接断开前到达的维护请求不再有机会执行，立即应答而不是等到重连成功
+                    let reconnect = self.reject_maintenance();
+                    if matches!(
+                        end,
+                        ConnectionEnd::Reconnect | ConnectionEnd::OutsideSchedule
+                    ) {
+                        continue;
+                    }
+                    reconnect
                 }
                 Err(err) => {
-                    self.state.store(&self.id, LifecycleState::Failed);
+                    self.state
+                        .store_with_reason(&self.id, LifecycleState::Failed, err.to_string());
                     warn!("[{}] 连接失败, 准备重连: {}", self.id, err);
                     self.set_comm_fault(true);
+                    self.set_points_comm_fail();
+                    if self.failover.failed(Instant::now()) {
+                        let on_backup = self.failover.on_backup();
+                        warn!(
+                            "[{}] 连续{}次连接失败, 切换到{}链路",
+                            self.id,
+                            FAILOVER_AFTER,
+                            if on_backup { "备用" } else { "主" }
+                        );
+                        self.diagnostics.set_backup_active(on_backup);
+                        backoff.reset();
+                    }
+                    self.reject_maintenance()
                 }
-            }
+            };
             if stop_requested(&stop_rx) {
                 self.state.store(&self.id, LifecycleState::Stopped);
                 self.set_comm_fault(true);
                 return;
             }
-            let delay = backoff.next_delay();
-            tokio::select! {
-                _ = time::sleep(delay) => {}
-                _ = stop_rx.changed() => {
-                    if stop_requested(&stop_rx) {
-                        self.state.store(&self.id, LifecycleState::Stopped);
-                        return;
+            if reconnect_now {
+                continue;
+            }
+            // 退避期间收到重连请求时立即连接
+            let sleep = time::sleep(backoff.next_delay());
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+                    Some(request) = self.maintenance_rx.recv() => {
+                        if answer_disconnected(&self.id, request) {
+                            break;
+                        }
+                    }
+                    _ = stop_rx.changed() => {
+                        if stop_requested(&stop_rx) {
+                            self.state.store(&self.id, LifecycleState::Stopped);
+                            return;
+                        }
+                        break;
                     }
                 }
             }
@@ -394,6 +1130,55 @@ impl ModbusRunner {
     }
 }
 
+/// 未连接时应答维护请求，重连请求返回 `true`
+fn answer_disconnected(id: &str, request: maintenance::Request) -> bool {
+    info!("[{}] 维护: {:?}", id, request.op);
+    let reconnect = request.op == Maintenance::Reconnect;
+    let result = if reconnect {
+        Ok(MaintenanceOutput::Done)
+    } else {
+        Err(DeviceError::NotRunning)
+    };
+    let _ = request.reply.send(result);
+    reconnect
+}
+
+/// 读取原始寄存器或线圈，失败时返回原因
+pub(super) async fn read_raw(
+    ctx: &mut Context,
+    register_type: RegisterType,
+    address: u16,
+    count: u16,
+    timeout: Duration,
+) -> Result<MaintenanceOutput, String> {
+    let read = async {
+        match register_type {
+            RegisterType::Coils => ctx
+                .read_coils(address, count)
+                .await
+                .map(|r| r.map(MaintenanceOutput::Bits)),
+            RegisterType::DiscreteInputs => ctx
+                .read_discrete_inputs(address, count)
+                .await
+                .map(|r| r.map(MaintenanceOutput::Bits)),
+            RegisterType::HoldingRegisters => ctx
+                .read_holding_registers(address, count)
+                .await
+                .map(|r| r.map(MaintenanceOutput::Registers)),
+            RegisterType::InputRegisters => ctx
+                .read_input_registers(address, count)
+                .await
+                .map(|r| r.map(MaintenanceOutput::Registers)),
+        }
+    };
+    match time::timeout(timeout, read).await {
+        Ok(Ok(Ok(output))) => Ok(output),
+        Ok(Ok(Err(code))) => Err(format!("从站返回异常 {code:?}")),
+        Ok(Err(err)) => Err(err.to_string()),
+        Err(_) => Err("读取超时".to_owned()),
+    }
+}
+
 fn resolve_name<'a>(point: &'a PointRef, cfg_map: &'a HashMap<PointId, ModbusConfig>) -> &'a str {
     match point {
         PointRef::Key(k) | PointRef::Name(k) => cfg_map
@@ -404,3 +1189,405 @@ fn resolve_name<'a>(point: &'a PointRef, cfg_map: &'a HashMap<PointId, ModbusCon
         PointRef::Id(id) => cfg_map.get(id).map(|cfg| cfg.name).unwrap_or("unknown"),
     }
 }
+
+impl Protocol {
+    /// 打开主链路或备用链路的连接；共享链路上复用已打开的链路
+    pub(super) async fn open(&self, backup: bool) -> Result<Context, ModbusDevError> {
+        match self {
+            Protocol::Tcp(cfg) => {
+                let (ip, port) = match &cfg.backup {
+                    Some((ip, port)) if backup => (ip.as_str(), *port),
+                    _ => (cfg.ip.as_str(), cfg.port),
+                };
+                let addr = format!("{ip}:{port}").parse()?;
+                let open = || async move {
+                    let connect = async {
+                        let stream = TcpStream::connect(addr).await?;
+                        if let Some(idle) = cfg.keepalive {
+                            let keepalive = TcpKeepalive::new().with_time(idle).with_interval(idle);
+                            SockRef::from(&stream).set_tcp_keepalive(&keepalive)?;
+                        }
+                        match &cfg.tls {
+                            Some(opts) => tls::connect(stream, ip, opts).await,
+                            None => Ok(tcp::attach(stream)),
+                        }
+                    };
+                    time::timeout(self.timeout(), connect).await?
+                };
+                if cfg.shared_connection {
+                    return shared::connect(
+                        LinkKey::Tcp(addr),
+                        String::new(),
+                        Slave(cfg.slave),
+                        self.timeout(),
+                        cfg.min_request_delay,
+                        open,
+                    )
+                    .await;
+                }
+                let mut ctx = open().await?;
+                ctx.set_slave(Slave(cfg.slave));
+                Ok(ctx)
+            }
+            Protocol::Rtu(cfg) => {
+                let mut profile = format!(
+                    "{} {}{}{}",
+                    cfg.baudrate,
+                    cfg.data_bits,
+                    cfg.parity.to_ascii_uppercase(),
+                    cfg.stop_bits
+                );
+                if let Some(direction) = &cfg.direction {
+                    profile = format!("{profile} {direction}");
+                }
+                let tty = match &cfg.backup_tty {
+                    Some(tty) if backup => tty,
+                    _ => &cfg.serial_tty,
+                };
+                let open = || async move {
+                    let builder =
+                        serial_port(tty, cfg.baudrate, cfg.data_bits, &cfg.parity, cfg.stop_bits)
+                            .timeout(self.timeout());
+                    let port = tokio_serial::SerialStream::open(&builder)?;
+                    let ctx = match &cfg.direction {
+                        Some(direction) => {
+                            rtu::attach(DirectedPort::new(port, direction, cfg.baudrate)?)
+                        }
+                        None => rtu::attach(port),
+                    };
+                    Ok::<_, ModbusDevError>(ctx)
+                };
+                shared::connect(
+                    LinkKey::Serial(tty.clone()),
+                    profile,
+                    Slave(cfg.slave),
+                    self.timeout(),
+                    cfg.inter_frame_delay.max(cfg.min_request_delay),
+                    open,
+                )
+                .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use std::io;
+
+    use async_trait::async_trait;
+    use tokio_modbus::client::{Client, Context};
+    use tokio_modbus::prelude::SlaveContext;
+    use tokio_modbus::{ExceptionCode, Request, Response, Slave};
+
+    use super::{
+        ReadCursor, ReadOptions, ReadOutcome, ScanState, build_scan_groups, next_scan, read_block,
+    };
+    use crate::config::modbus_conf::{ScanClass, parse_json_configs};
+    use crate::config::{ExceptionPolicy, ScanIntervals};
+    use crate::dev::dev_config::FrameLimits;
+    use crate::dev::modbus_dev::block::{BlockRead, Blocks};
+
+    /// 前 `failures` 次请求返回传输错误、读取范围包含 `rejected` 地址时返回异常码的从站
+    #[derive(Debug, Default)]
+    struct FlakySlave {
+        failures: u32,
+        rejected: Option<u16>,
+        calls: u32,
+    }
+
+    impl SlaveContext for FlakySlave {
+        fn set_slave(&mut self, _: Slave) {}
+    }
+
+    #[async_trait]
+    impl Client for FlakySlave {
+        async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+            self.calls += 1;
+            if self.calls <= self.failures {
+                return Err(io::Error::from(io::ErrorKind::InvalidData).into());
+            }
+            match request {
+                Request::ReadHoldingRegisters(addr, count)
+                    if self
+                        .rejected
+                        .is_some_and(|rejected| (addr..addr + count).contains(&rejected)) =>
+                {
+                    Ok(Err(ExceptionCode::IllegalDataAddress))
+                }
+                Request::ReadHoldingRegisters(_, count) => {
+                    Ok(Ok(Response::ReadHoldingRegisters(vec![7; count.into()])))
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        async fn disconnect(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn scan_classes_are_polled_on_their_own_period() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "断路器", data_type: "U16", register_address: 0,
+              register_type: "InputRegisters", quantity: 1, key: "breaker", scan_class: "fast" },
+            { id: 2, name: "电压", data_type: "U16", register_address: 1,
+              register_type: "InputRegisters", quantity: 1, key: "voltage" },
+            { id: 3, name: "温度", data_type: "U16", register_address: 2,
+              register_type: "InputRegisters", quantity: 1, key: "temp", scan_class: "慢" },
+        ]"#,
+        )
+        .unwrap();
+        let intervals = ScanIntervals {
+            fast: Some(200),
+            normal: None,
+            slow: Some(30_000),
+            ..ScanIntervals::default()
+        };
+
+        let groups = build_scan_groups(configs, 10, FrameLimits::default(), &intervals).unwrap();
+
+        // 地址相邻但等级不同的点位分开读取
+        let classes: Vec<_> = groups.iter().map(|group| group.class).collect();
+        assert_eq!(
+            classes,
+            [ScanClass::Fast, ScanClass::Normal, ScanClass::Slow]
+        );
+        assert!(groups.iter().all(|group| group.blocks.block_count() == 1));
+
+        let start = Instant::now();
+        let mut states: Vec<ScanState> = groups
+            .iter()
+            .map(|group| ScanState::new(group, start))
+            .collect();
+        // 同时到期时高等级优先
+        assert_eq!(next_scan(&states, start), Some(0));
+        for (group, state) in groups.iter().zip(&mut states) {
+            state.finish_block(group.period, start);
+        }
+        assert_eq!(states[0].due, start + Duration::from_millis(200));
+        assert_eq!(states[2].due, start + Duration::from_secs(30));
+        // 未配置周期的等级连续轮询
+        assert_eq!(next_scan(&states, start), Some(1));
+        let later = start + Duration::from_millis(250);
+        states[1].finish_block(None, later);
+        assert_eq!(next_scan(&states, later), Some(0));
+    }
+
+    #[test]
+    fn register_types_are_polled_on_their_own_period() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "断路器", data_type: "Bool", register_address: 0,
+              register_type: "DiscreteInputs", quantity: 1, key: "breaker" },
+            { id: 2, name: "电压", data_type: "U16", register_address: 0,
+              register_type: "InputRegisters", quantity: 1, key: "voltage" },
+            { id: 3, name: "电流", data_type: "U16", register_address: 1,
+              register_type: "InputRegisters", quantity: 1, key: "current", scan_class: "fast" },
+        ]"#,
+        )
+        .unwrap();
+        let intervals = ScanIntervals {
+            fast: Some(100),
+            discrete_inputs: Some(500),
+            input_registers: Some(5000),
+            ..ScanIntervals::default()
+        };
+
+        let groups = build_scan_groups(configs, 10, FrameLimits::default(), &intervals).unwrap();
+
+        let periods: Vec<_> = groups
+            .iter()
+            .map(|group| (group.class, group.period))
+            .collect();
+        assert_eq!(
+            periods,
+            [
+                (ScanClass::Fast, Some(Duration::from_millis(100))),
+                (ScanClass::Normal, Some(Duration::from_millis(500))),
+                (ScanClass::Normal, Some(Duration::from_secs(5))),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_reads_are_retried() {
+        let configs = parse_json_configs(
+            r#"[{ id: 1, name: "电压", data_type: "U16", register_address: 0,
+                 register_type: "HoldingRegisters", quantity: 1, key: "voltage" }]"#,
+        )
+        .unwrap();
+        let blocks = Blocks::build(configs, 0, FrameLimits::default()).unwrap();
+        let timeout = Duration::from_secs(1);
+        let flaky = |failures| {
+            let client: Box<dyn Client> = Box::new(FlakySlave {
+                failures,
+                ..FlakySlave::default()
+            });
+            Context::from(client)
+        };
+
+        let mut ctx = flaky(2);
+        let read = read_block(&mut ctx, &blocks, 0, timeout, 2).await;
+        assert!(matches!(read, Ok(Ok(BlockRead::HoldingRegisters(data))) if data == [7]));
+
+        let mut ctx = flaky(2);
+        assert!(matches!(
+            read_block(&mut ctx, &blocks, 0, timeout, 1).await,
+            Ok(Err(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn exception_blocks_follow_policy() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "电压", data_type: "U16", register_address: 0,
+              register_type: "HoldingRegisters", quantity: 1, key: "voltage" },
+            { id: 2, name: "电流", data_type: "U16", register_address: 500,
+              register_type: "HoldingRegisters", quantity: 1, key: "current" },
+        ]"#,
+        )
+        .unwrap();
+        let mut blocks = Blocks::build(configs, 0, FrameLimits::default()).unwrap();
+        let client: Box<dyn Client> = Box::new(FlakySlave {
+            rejected: Some(500),
+            ..FlakySlave::default()
+        });
+        let mut ctx = Context::from(client);
+        let mut cursor = ReadCursor::new(blocks.block_count());
+        let opts = ReadOptions {
+            timeout: Duration::from_secs(1),
+            retries: 0,
+            exception_policy: ExceptionPolicy::Bad,
+            max_failed_cycles: None,
+            dump: false,
+        };
+        let mut fail_streak = 0;
+
+        let first = cursor
+            .advance(&mut ctx, &mut blocks, &opts, &mut fail_streak, "dev")
+            .await;
+        assert!(matches!(first, ReadOutcome::Pending));
+        let ReadOutcome::Published { points, bad } = cursor
+            .advance(&mut ctx, &mut blocks, &opts, &mut fail_streak, "dev")
+            .await
+        else {
+            panic!("一圈读完应发布");
+        };
+        assert_eq!(points.iter().map(|p| p.id).collect::<Vec<_>>(), [1]);
+        assert_eq!(bad, [2]);
+        assert_eq!(fail_streak, 0);
+    }
+
+    #[tokio::test]
+    async fn failed_blocks_do_not_stop_the_cycle() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "电压", data_type: "U16", register_address: 0,
+              register_type: "HoldingRegisters", quantity: 1, key: "voltage" },
+            { id: 2, name: "电流", data_type: "U16", register_address: 500,
+              register_type: "HoldingRegisters", quantity: 1, key: "current" },
+        ]"#,
+        )
+        .unwrap();
+        let mut blocks = Blocks::build(configs, 0, FrameLimits::default()).unwrap();
+        let client: Box<dyn Client> = Box::new(FlakySlave {
+            failures: 5,
+            ..FlakySlave::default()
+        });
+        let mut ctx = Context::from(client);
+        let mut cursor = ReadCursor::new(blocks.block_count());
+        let opts = ReadOptions {
+            timeout: Duration::from_secs(1),
+            retries: 0,
+            exception_policy: ExceptionPolicy::Reconnect,
+            max_failed_cycles: Some(3),
+            dump: false,
+        };
+        let mut fail_streak = 0;
+        let mut cycle = async |cursor: &mut ReadCursor| {
+            let first = cursor
+                .advance(&mut ctx, &mut blocks, &opts, &mut fail_streak, "dev")
+                .await;
+            assert!(matches!(first, ReadOutcome::Pending));
+            cursor
+                .advance(&mut ctx, &mut blocks, &opts, &mut fail_streak, "dev")
+                .await
+        };
+
+        // 前两圈所有块都失败，未达阈值时照常发布坏质量
+        for _ in 0..2 {
+            let ReadOutcome::Published { points, bad } = cycle(&mut cursor).await else {
+                panic!("未达阈值应继续发布");
+            };
+            assert!(points.is_empty());
+            assert_eq!(bad, [1, 2]);
+        }
+        // 第三圈只有第一个块失败，其余块照常发布，连续失败圈数清零
+        let ReadOutcome::Published { points, bad } = cycle(&mut cursor).await else {
+            panic!("部分块成功应发布");
+        };
+        assert_eq!(points.iter().map(|p| p.id).collect::<Vec<_>>(), [2]);
+        assert_eq!(bad, [1]);
+        assert_eq!(cursor.failed_cycles, 0);
+    }
+
+    #[tokio::test]
+    async fn blocks_spanning_holes_are_split() {
+        let configs = parse_json_configs(
+            r#"[
+            { id: 1, name: "电压", data_type: "U16", register_address: 0,
+              register_type: "HoldingRegisters", quantity: 1, key: "voltage" },
+            { id: 2, name: "电流", data_type: "U16", register_address: 2,
+              register_type: "HoldingRegisters", quantity: 1, key: "current" },
+        ]"#,
+        )
+        .unwrap();
+        let mut blocks = Blocks::build(configs, 1, FrameLimits::default()).unwrap();
+        assert_eq!(blocks.block_count(), 1);
+        let client: Box<dyn Client> = Box::new(FlakySlave {
+            rejected: Some(1),
+            ..FlakySlave::default()
+        });
+        let mut ctx = Context::from(client);
+        let mut cursor = ReadCursor::new(blocks.block_count());
+        let opts = ReadOptions {
+            timeout: Duration::from_secs(1),
+            retries: 0,
+            exception_policy: ExceptionPolicy::Reconnect,
+            max_failed_cycles: None,
+            dump: false,
+        };
+        let mut fail_streak = 0;
+
+        let first = cursor
+            .advance(&mut ctx, &mut blocks, &opts, &mut fail_streak, "dev")
+            .await;
+        assert!(matches!(first, ReadOutcome::Pending));
+        let layout: Vec<_> = blocks
+            .blocks
+            .iter()
+            .map(|block| (block.start, block.len))
+            .collect();
+        assert_eq!(layout, [(0, 1), (2, 1)]);
+
+        let mut outcome = ReadOutcome::Pending;
+        for _ in 0..2 {
+            outcome = cursor
+                .advance(&mut ctx, &mut blocks, &opts, &mut fail_streak, "dev")
+                .await;
+        }
+        let ReadOutcome::Published { points, bad } = outcome else {
+            panic!("拆分后一圈读完应发布");
+        };
+        assert_eq!(points.iter().map(|p| p.id).collect::<Vec<_>>(), [1, 2]);
+        assert!(bad.is_empty());
+    }
+}
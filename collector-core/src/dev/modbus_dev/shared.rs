@@ -0,0 +1,342 @@
+//! 共享的 Modbus 链路
+//!
+//! 同一串口上的 RTU 设备、以及开启 `shared_connection` 的同一网关后的 TCP 设备共用一条链路：
+//! 每个设备持有一个 [`SharedClient`]，请求前切换从站地址，请求之间由先进先出的互斥锁串行化，
+//! 各设备的请求按到达顺序轮流占用总线。最后一个设备释放后链路随之关闭。
+//!
+//! 超时按设备各自的 `timeout` 从取得总线时开始计时，排队等待的时间不计入。
+//! 设备的请求与总线上前一帧之间至少间隔该设备的帧间隔，慢速的串口转换器需要更长的静默时间。
+//! 链路出现传输错误、或连续若干次请求超时时关闭链路，
+//! 之后各设备的请求失败并按原有流程重连，第一个重连的设备重新打开链路。
+//!
+//! 串口上从站地址为 0 的设备是广播：只能写入，从站不应答，
+//! 发出请求后占用总线等待该设备的 `timeout` 作为从站处理的转换时延，然后按写入成功返回。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::{self, Instant};
+use tokio_modbus::client::{Client, Context};
+use tokio_modbus::prelude::SlaveContext;
+use tokio_modbus::{Request, Response, Slave};
+
+use super::error::ModbusDevError;
+
+/// 链路上连续超时的请求达到该数量即认为链路已失效，任一请求成功即清零
+const MAX_TIMEOUTS: u32 = 3;
+
+/// 链路的标识：TCP 地址或串口设备
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum LinkKey {
+    Tcp(SocketAddr),
+    Serial(String),
+}
+
+impl fmt::Display for LinkKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkKey::Tcp(addr) => write!(f, "{addr}"),
+            LinkKey::Serial(tty) => f.write_str(tty),
+        }
+    }
+}
+
+/// 链路标识到共享链路的映射，链路由使用它的设备持有
+static LINKS: LazyLock<Mutex<HashMap<LinkKey, Weak<Link>>>> = LazyLock::new(Default::default);
+
+#[derive(Debug)]
+struct Link {
+    /// 打开链路的参数，如串口的波特率与校验位；共用链路的设备必须一致
+    profile: String,
+    state: tokio::sync::Mutex<LinkState>,
+}
+
+#[derive(Debug, Default)]
+struct LinkState {
+    ctx: Option<Context>,
+    timeouts: u32,
+    /// 总线上前一个请求结束的时刻
+    last_frame: Option<Instant>,
+}
+
+/// 以 `slave` 的从站地址使用 `key` 上的共享链路，链路未打开时用 `open` 打开
+///
+/// `timeout` 为该设备单次请求的超时，`frame_gap` 为该设备请求前的最小静默时间
+pub(super) async fn connect<F, Fut>(
+    key: LinkKey,
+    profile: String,
+    slave: Slave,
+    timeout: Duration,
+    frame_gap: Duration,
+    open: F,
+) -> Result<Context, ModbusDevError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Context, ModbusDevError>>,
+{
+    let serial = matches!(key, LinkKey::Serial(_));
+    let link = {
+        let mut links = LINKS.lock().expect("shared links lock poisoned");
+        links.retain(|_, link| link.strong_count() > 0);
+        match links.get(&key).and_then(Weak::upgrade) {
+            Some(link) if link.profile != profile => {
+                return Err(ModbusDevError::LinkProfileMismatch {
+                    link: key.to_string(),
+                    expected: link.profile.clone(),
+                    actual: profile,
+                });
+            }
+            Some(link) => link,
+            None => {
+                let link = Arc::new(Link {
+                    profile,
+                    state: Default::default(),
+                });
+                links.insert(key, Arc::downgrade(&link));
+                link
+            }
+        }
+    };
+    {
+        let mut state = link.state.lock().await;
+        if state.ctx.is_none() {
+            *state = LinkState {
+                ctx: Some(open().await?),
+                ..LinkState::default()
+            };
+        }
+    }
+    let client: Box<dyn Client> = Box::new(SharedClient {
+        serial,
+        link,
+        slave,
+        timeout,
+        frame_gap,
+    });
+    Ok(Context::from(client))
+}
+
+/// 共享链路上的一个从站
+#[derive(Debug)]
+struct SharedClient {
+    /// 串口链路，从站地址 0 为广播
+    serial: bool,
+    link: Arc<Link>,
+    slave: Slave,
+    timeout: Duration,
+    frame_gap: Duration,
+}
+
+impl SlaveContext for SharedClient {
+    fn set_slave(&mut self, slave: Slave) {
+        self.slave = slave;
+    }
+}
+
+#[async_trait]
+impl Client for SharedClient {
+    async fn call(&mut self, request: Request<'_>) -> tokio_modbus::Result<Response> {
+        let mut state = self.link.state.lock().await;
+        let state = &mut *state;
+        let Some(ctx) = state.ctx.as_mut() else {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "共享链路已断开").into());
+        };
+        ctx.set_slave(self.slave);
+        if let Some(last) = state.last_frame {
+            time::sleep_until(last + self.frame_gap).await;
+        }
+        if self.serial && self.slave.is_broadcast() {
+            let Some(response) = broadcast_response(&request) else {
+                return Err(io::Error::new(io::ErrorKind::Unsupported, "广播只能写入").into());
+            };
+            // 没有应答，等满转换时延即认为已下发；期间收到的任何数据都忽略
+            let result = time::timeout(self.timeout, ctx.call(request)).await;
+            state.last_frame = Some(Instant::now());
+            if let Ok(Err(tokio_modbus::Error::Transport(err))) = result {
+                state.ctx = None;
+                return Err(err.into());
+            }
+            return Ok(Ok(response));
+        }
+        let result = time::timeout(self.timeout, ctx.call(request)).await;
+        state.last_frame = Some(Instant::now());
+        match result {
+            Ok(result) => {
+                match &result {
+                    Err(tokio_modbus::Error::Transport(_)) => state.ctx = None,
+                    _ => state.timeouts = 0,
+                }
+                result
+            }
+            Err(_) => {
+                state.timeouts += 1;
+                if state.timeouts >= MAX_TIMEOUTS {
+                    state.ctx = None;
+                }
+                Err(io::Error::from(io::ErrorKind::TimedOut).into())
+            }
+        }
+    }
+
+    /// 链路由其他设备共用，单个设备断开时不关闭
+    async fn disconnect(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 广播写入按从站正常应答时的响应返回，非写入请求为 `None`
+fn broadcast_response(request: &Request<'_>) -> Option<Response> {
+    Some(match request {
+        Request::WriteSingleCoil(address, value) => Response::WriteSingleCoil(*address, *value),
+        Request::WriteMultipleCoils(address, values) => {
+            Response::WriteMultipleCoils(*address, values.len() as u16)
+        }
+        Request::WriteSingleRegister(address, value) => {
+            Response::WriteSingleRegister(*address, *value)
+        }
+        Request::WriteMultipleRegisters(address, values) => {
+            Response::WriteMultipleRegisters(*address, values.len() as u16)
+        }
+        Request::MaskWriteRegister(address, and, or) => {
+            Response::MaskWriteRegister(*address, *and, *or)
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use tokio_modbus::Slave;
+    use tokio_modbus::client::{Reader, Writer, rtu, tcp};
+
+    use super::{LinkKey, connect};
+    use crate::dev::modbus_dev::ModbusDevError;
+
+    #[tokio::test]
+    async fn devices_on_one_link_share_a_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                sockets.push(socket);
+            }
+        });
+        let timeout = Duration::from_secs(1);
+        let open = || async move { Ok::<_, ModbusDevError>(tcp::connect(addr).await?) };
+
+        let first = connect(
+            LinkKey::Tcp(addr),
+            String::new(),
+            Slave(1),
+            timeout,
+            Duration::ZERO,
+            open,
+        )
+        .await
+        .unwrap();
+        let second = connect(
+            LinkKey::Tcp(addr),
+            String::new(),
+            Slave(2),
+            timeout,
+            Duration::ZERO,
+            open,
+        )
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        drop((first, second));
+        let _third = connect(
+            LinkKey::Tcp(addr),
+            String::new(),
+            Slave(3),
+            timeout,
+            Duration::ZERO,
+            open,
+        )
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn serial_settings_must_match() {
+        let key = LinkKey::Serial("/dev/ttyTEST".into());
+        let timeout = Duration::from_secs(1);
+        let open = || async {
+            let (client, _server) = tokio::io::duplex(64);
+            Ok::<_, ModbusDevError>(rtu::attach(client))
+        };
+
+        let _first = connect(
+            key.clone(),
+            "9600 8N1".into(),
+            Slave(1),
+            timeout,
+            Duration::ZERO,
+            open,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            connect(
+                key,
+                "19200 8N1".into(),
+                Slave(2),
+                timeout,
+                Duration::ZERO,
+                open
+            )
+            .await,
+            Err(ModbusDevError::LinkProfileMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn broadcast_writes_do_not_wait_for_a_response() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let open = move || async move { Ok::<_, ModbusDevError>(rtu::attach(client)) };
+        let mut ctx = connect(
+            LinkKey::Serial("/dev/ttyBROADCAST".into()),
+            "9600 8N1".into(),
+            Slave::broadcast(),
+            Duration::from_millis(50),
+            Duration::ZERO,
+            open,
+        )
+        .await
+        .unwrap();
+
+        ctx.write_single_register(1, 0x1234).await.unwrap().unwrap();
+        let mut frame = [0; 6];
+        server.read_exact(&mut frame).await.unwrap();
+        assert_eq!(frame, [0x00, 0x06, 0x00, 0x01, 0x12, 0x34]);
+
+        let read = ctx.read_holding_registers(1, 1).await;
+        assert!(matches!(
+            read,
+            Err(tokio_modbus::Error::Transport(err)) if err.kind() == io::ErrorKind::Unsupported
+        ));
+    }
+}
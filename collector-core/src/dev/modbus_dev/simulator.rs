@@ -0,0 +1,443 @@
+//! Modbus TCP 从站模拟器
+//!
+//! 按点位表预置寄存器并定时刷新，用于在没有设备的电脑上跑通完整的采集流程。地址换算与缺省字节序同设备的点位表，
+//! 寄存器按下发的编码规则写入：数值点位在 `min`~`max`（工程值）之间取值，布尔点位取真或假，字符串点位取其键名。
+//! 主站写入过的地址保持写入的值，不再刷新，下发后可以回读。
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future;
+use parking_lot::Mutex;
+use tokio::net::TcpListener;
+use tokio::time::Instant;
+use tokio_modbus::server::tcp::{Server, accept_tcp_connection};
+use tokio_modbus::{ExceptionCode, Request, Response, SlaveRequest};
+use tokio_util::sync::CancellationToken;
+use tracing::error;
+
+use crate::config::modbus_conf::{ByteOrder, ModbusConfig, ModbusDataType, RegisterType};
+use crate::core::point::Val;
+use crate::dev::DeviceError;
+use crate::dock::modbus::tables::RegisterTable;
+
+use super::device::PointTable;
+use super::downlink::encode_registers;
+
+/// 编码失败时日志中的设备名
+const LOG_NAME: &str = "模拟器";
+
+#[derive(Debug, thiserror::Error)]
+#[error("无效的取值规律:{0}, 可选 fixed/random/ramp")]
+pub struct InvalidWaveform(String);
+
+/// 点位的取值规律
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    /// 各点位取范围内互不相同的固定值
+    Fixed,
+    /// 每次刷新取范围内的随机值
+    Random,
+    /// 每个周期从最小值线性升到最大值，各点位错开相位
+    Ramp,
+}
+
+impl FromStr for Waveform {
+    type Err = InvalidWaveform;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fixed" => Ok(Waveform::Fixed),
+            "random" => Ok(Waveform::Random),
+            "ramp" => Ok(Waveform::Ramp),
+            other => Err(InvalidWaveform(other.to_owned())),
+        }
+    }
+}
+
+/// 第 `index` 个点位的相位（0~1），按黄金分割错开，相邻点位的值相差较大
+fn phase(index: usize) -> f64 {
+    (index as f64 * 0.618_033_988_75).fract()
+}
+
+impl Waveform {
+    /// 第 `index` 个点位在启动 `elapsed` 后在取值范围中的位置（0~1）
+    fn position(self, index: usize, elapsed: Duration, period: Duration) -> f64 {
+        match self {
+            Waveform::Fixed => phase(index),
+            Waveform::Random => getrandom::u64().unwrap_or(0) as f64 / u64::MAX as f64,
+            Waveform::Ramp => {
+                let cycles = elapsed.as_secs_f64() / period.as_secs_f64().max(0.001);
+                (cycles + phase(index)).fract()
+            }
+        }
+    }
+}
+
+/// 模拟器的取值选项
+#[derive(Debug, Clone)]
+pub struct SimulatorOptions {
+    pub waveform: Waveform,
+    /// 数值点位的最小工程值
+    pub min: f64,
+    /// 数值点位的最大工程值
+    pub max: f64,
+    /// 斜坡的周期
+    pub period: Duration,
+    /// 刷新间隔，固定值时不刷新
+    pub interval: Duration,
+}
+
+impl Default for SimulatorOptions {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Fixed,
+            min: 0.0,
+            max: 100.0,
+            period: Duration::from_secs(60),
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+struct State {
+    table: Mutex<RegisterTable>,
+    /// 主站写入过的地址，不再刷新
+    held: Mutex<BTreeSet<(RegisterType, u16)>>,
+}
+
+pub struct Simulator {
+    points: Vec<ModbusConfig>,
+    options: SimulatorOptions,
+    state: Arc<State>,
+}
+
+impl Simulator {
+    /// 读取点位表并预置寄存器，`address_base`、`byte_order` 与设备配置中的同名选项相同
+    pub async fn load(
+        path: &str,
+        address_base: u16,
+        byte_order: Option<ByteOrder>,
+        options: SimulatorOptions,
+    ) -> Result<Self, DeviceError> {
+        let points = PointTable::standalone(address_base, byte_order)
+            .load(path, None)
+            .await?;
+        Ok(Self::new(points, options))
+    }
+
+    /// 按已换算为协议地址的点位预置寄存器
+    pub fn new(points: Vec<ModbusConfig>, options: SimulatorOptions) -> Self {
+        let simulator = Self {
+            points,
+            options,
+            state: Arc::new(State {
+                table: Mutex::new(RegisterTable::new()),
+                held: Mutex::new(BTreeSet::new()),
+            }),
+        };
+        simulator.refresh(Duration::ZERO);
+        simulator
+    }
+
+    pub fn points(&self) -> &[ModbusConfig] {
+        &self.points
+    }
+
+    /// 按启动后经过的时间刷新各点位，跳过主站写入过的点位
+    fn refresh(&self, elapsed: Duration) {
+        let held = self.state.held.lock().clone();
+        let mut table = self.state.table.lock();
+        for (index, cfg) in self.points.iter().enumerate() {
+            let width = match cfg.register_type {
+                RegisterType::Coils | RegisterType::DiscreteInputs => 1,
+                _ => cfg.quantity.max(1),
+            };
+            let written = (0..width).any(|offset| {
+                cfg.register_address
+                    .checked_add(offset)
+                    .is_some_and(|address| held.contains(&(cfg.register_type, address)))
+            });
+            if written {
+                continue;
+            }
+            let position = self
+                .options
+                .waveform
+                .position(index, elapsed, self.options.period);
+            if let Some(registers) = encode_registers(cfg, &self.value(cfg, position), LOG_NAME) {
+                store(&mut table, cfg, &registers);
+            }
+        }
+    }
+
+    fn value(&self, cfg: &ModbusConfig, position: f64) -> Val {
+        match cfg.data_type {
+            ModbusDataType::Bool => Val::U16(u16::from(position >= 0.5)),
+            ModbusDataType::String(_) => Val::Str(cfg.key.to_owned()),
+            _ => Val::F64(self.options.min + (self.options.max - self.options.min) * position),
+        }
+    }
+
+    /// 在 `listener` 上提供 Modbus TCP 服务，响应任意从站地址，直到 `token` 取消
+    pub async fn serve(
+        self,
+        listener: TcpListener,
+        token: CancellationToken,
+    ) -> std::io::Result<()> {
+        let simulator = Arc::new(self);
+        if simulator.options.waveform != Waveform::Fixed {
+            let simulator = simulator.clone();
+            let token = token.clone();
+            tokio::spawn(async move {
+                let start = Instant::now();
+                let mut ticker = tokio::time::interval(simulator.options.interval);
+                loop {
+                    tokio::select! {
+                        _ = token.cancelled() => break,
+                        _ = ticker.tick() => simulator.refresh(start.elapsed()),
+                    }
+                }
+            });
+        }
+        let service = SimulatorService(simulator.state.clone());
+        let on_connected = |stream, socket_addr| {
+            let service = service.clone();
+            async move { accept_tcp_connection(stream, socket_addr, move |_| Ok(Some(service.clone()))) }
+        };
+        let abort = Box::pin(async move { token.cancelled().await });
+        Server::new(listener)
+            .serve_until(
+                &on_connected,
+                |err| error!("[{}] 连接错误: {}", LOG_NAME, err),
+                abort,
+            )
+            .await
+            .map(|_| ())
+    }
+}
+
+/// 按点位写入编码后的寄存器：位点位只改寄存器中的对应位
+fn store(table: &mut RegisterTable, cfg: &ModbusConfig, registers: &[u16]) {
+    let Some(&first) = registers.first() else {
+        return;
+    };
+    match (cfg.register_type, cfg.bit) {
+        (RegisterType::Coils | RegisterType::DiscreteInputs, _) => {
+            table.write_bool(cfg.register_type, cfg.register_address, first != 0);
+        }
+        (register_type, Some(bit)) => {
+            let word = table.read_u16(register_type, cfg.register_address);
+            let mask = 1u16 << bit;
+            let word = if first != 0 {
+                word | mask
+            } else {
+                word & !mask
+            };
+            table.write_u16(register_type, cfg.register_address, word);
+        }
+        (register_type, None) => {
+            for (offset, word) in registers.iter().enumerate() {
+                if let Some(address) = cfg.register_address.checked_add(offset as u16) {
+                    table.write_u16(register_type, address, *word);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SimulatorService(Arc<State>);
+
+impl tokio_modbus::server::Service for SimulatorService {
+    type Request = SlaveRequest<'static>;
+    type Response = Response;
+    type Exception = ExceptionCode;
+    type Future = future::Ready<Result<Self::Response, Self::Exception>>;
+
+    fn call(&self, req: Self::Request) -> Self::Future {
+        future::ready(self.handle(req.request))
+    }
+}
+
+impl SimulatorService {
+    /// 记录主站写入的地址
+    fn hold(&self, register_type: RegisterType, address: u16, count: usize) {
+        let mut held = self.0.held.lock();
+        for offset in 0..count {
+            if let Some(address) = address.checked_add(offset as u16) {
+                held.insert((register_type, address));
+            }
+        }
+    }
+
+    fn write_registers(&self, address: u16, words: &[u16]) {
+        let mut table = self.0.table.lock();
+        for (offset, word) in words.iter().enumerate() {
+            if let Some(address) = address.checked_add(offset as u16) {
+                table.write_u16(RegisterType::HoldingRegisters, address, *word);
+            }
+        }
+        drop(table);
+        self.hold(RegisterType::HoldingRegisters, address, words.len());
+    }
+
+    fn write_coils(&self, address: u16, coils: &[bool]) {
+        let mut table = self.0.table.lock();
+        for (offset, coil) in coils.iter().enumerate() {
+            if let Some(address) = address.checked_add(offset as u16) {
+                table.write_bool(RegisterType::Coils, address, *coil);
+            }
+        }
+        drop(table);
+        self.hold(RegisterType::Coils, address, coils.len());
+    }
+
+    fn handle(&self, req: Request<'static>) -> Result<Response, ExceptionCode> {
+        match req {
+            Request::ReadCoils(address, count) => Ok(Response::ReadCoils(
+                self.0.table.lock().read_coils(address, count),
+            )),
+            Request::ReadDiscreteInputs(address, count) => Ok(Response::ReadDiscreteInputs(
+                self.0.table.lock().read_discrete_inputs(address, count),
+            )),
+            Request::ReadHoldingRegisters(address, count) => Ok(Response::ReadHoldingRegisters(
+                self.0.table.lock().read_holding_registers(address, count),
+            )),
+            Request::ReadInputRegisters(address, count) => Ok(Response::ReadInputRegisters(
+                self.0.table.lock().read_input_registers(address, count),
+            )),
+            Request::WriteSingleCoil(address, coil) => {
+                self.write_coils(address, &[coil]);
+                Ok(Response::WriteSingleCoil(address, coil))
+            }
+            Request::WriteMultipleCoils(address, coils) => {
+                self.write_coils(address, &coils);
+                Ok(Response::WriteMultipleCoils(address, coils.len() as u16))
+            }
+            Request::WriteSingleRegister(address, word) => {
+                self.write_registers(address, &[word]);
+                Ok(Response::WriteSingleRegister(address, word))
+            }
+            Request::WriteMultipleRegisters(address, words) => {
+                self.write_registers(address, &words);
+                Ok(Response::WriteMultipleRegisters(
+                    address,
+                    words.len() as u16,
+                ))
+            }
+            Request::MaskWriteRegister(address, and_mask, or_mask) => {
+                let word = self
+                    .0
+                    .table
+                    .lock()
+                    .read_u16(RegisterType::HoldingRegisters, address);
+                self.write_registers(address, &[(word & and_mask) | (or_mask & !and_mask)]);
+                Ok(Response::MaskWriteRegister(address, and_mask, or_mask))
+            }
+            Request::ReadWriteMultipleRegisters(read_address, count, write_address, words) => {
+                self.write_registers(write_address, &words);
+                Ok(Response::ReadWriteMultipleRegisters(
+                    self.0
+                        .table
+                        .lock()
+                        .read_holding_registers(read_address, count),
+                ))
+            }
+            _ => Err(ExceptionCode::IllegalFunction),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+    use tokio_modbus::client::{Reader, Writer, tcp};
+    use tokio_util::sync::CancellationToken;
+
+    use super::{Simulator, SimulatorOptions, Waveform};
+    use crate::config::modbus_conf::{ModbusConfig, ModbusDataType, RegisterType, ScanClass};
+
+    fn point(id: u16, data_type: ModbusDataType, register_type: RegisterType) -> ModbusConfig {
+        ModbusConfig {
+            id,
+            name: "-",
+            data_type,
+            unit: None,
+            remarks: None,
+            register_address: id * 10,
+            register_type,
+            quantity: data_type.register_width(),
+            byte_order: None,
+            scale: 0.1,
+            offset: 0.0,
+            enable: true,
+            key: "sn",
+            trans: None,
+            status_words: None,
+            warn_bits: None,
+            bit: None,
+            scan_class: ScanClass::Normal,
+            select_address: None,
+            handshake_address: None,
+            pulse_ms: None,
+            epsilon: None,
+        }
+    }
+
+    #[test]
+    fn ramp_wraps_within_the_range() {
+        let period = Duration::from_secs(10);
+        assert_eq!(Waveform::Ramp.position(0, Duration::ZERO, period), 0.0);
+        assert!((Waveform::Ramp.position(0, Duration::from_secs(5), period) - 0.5).abs() < 1e-9);
+        assert!(Waveform::Ramp.position(0, Duration::from_secs(10), period) < 1e-9);
+        assert_ne!(
+            Waveform::Fixed.position(1, Duration::ZERO, period),
+            Waveform::Fixed.position(2, Duration::ZERO, period)
+        );
+        assert!("sine".parse::<Waveform>().is_err());
+    }
+
+    #[tokio::test]
+    async fn registers_are_served_and_writes_are_held() {
+        let options = SimulatorOptions {
+            waveform: Waveform::Ramp,
+            interval: Duration::from_millis(10),
+            ..SimulatorOptions::default()
+        };
+        let simulator = Simulator::new(
+            vec![
+                point(1, ModbusDataType::U16, RegisterType::HoldingRegisters),
+                point(2, ModbusDataType::String(2), RegisterType::InputRegisters),
+                point(3, ModbusDataType::Bool, RegisterType::Coils),
+            ],
+            options,
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let token = CancellationToken::new();
+        let server = tokio::spawn(simulator.serve(listener, token.clone()));
+
+        let mut ctx = tcp::connect(addr).await.unwrap();
+        // 字符串点位取键名；第 2 个点位的相位约为 0.24，启动后一分钟内为假
+        assert_eq!(
+            ctx.read_input_registers(20, 2).await.unwrap().unwrap(),
+            [0x736e, 0]
+        );
+        assert_eq!(ctx.read_coils(30, 1).await.unwrap().unwrap(), [false]);
+
+        ctx.write_single_register(10, 1234).await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            ctx.read_holding_registers(10, 1).await.unwrap().unwrap(),
+            [1234]
+        );
+
+        token.cancel();
+        server.await.unwrap().unwrap();
+    }
+}
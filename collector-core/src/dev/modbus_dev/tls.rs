@@ -0,0 +1,70 @@
+//! Modbus/TCP Security
+//!
+//! 按设备配置的 CA、客户端证书与私钥建立双向认证的 TLS 连接，再在其上运行 Modbus TCP。
+//! 证书文件在每次连接时重新读取，现场更换证书后重连即可生效。
+
+use std::sync::Arc;
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use tokio::net::TcpStream;
+use tokio_modbus::client::{Context, tcp};
+use tokio_rustls::TlsConnector;
+
+use super::error::ModbusDevError;
+use crate::config::TlsOptions;
+
+fn tls_error(err: impl std::fmt::Display) -> ModbusDevError {
+    ModbusDevError::Tls(err.to_string())
+}
+
+fn client_config(opts: &TlsOptions) -> Result<ClientConfig, ModbusDevError> {
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(&opts.ca_file).map_err(tls_error)? {
+        roots.add(cert.map_err(tls_error)?).map_err(tls_error)?;
+    }
+    let certs = CertificateDer::pem_file_iter(&opts.cert_file)
+        .map_err(tls_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(tls_error)?;
+    let key = PrivateKeyDer::from_pem_file(&opts.key_file).map_err(tls_error)?;
+    ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?
+        .with_root_certificates(roots)
+        .with_client_auth_cert(certs, key)
+        .map_err(tls_error)
+}
+
+/// 在已建立的 TCP 连接上握手，`ip` 在未配置 `server_name` 时用于校验设备证书
+pub(super) async fn connect(
+    stream: TcpStream,
+    ip: &str,
+    opts: &TlsOptions,
+) -> Result<Context, ModbusDevError> {
+    let connector = TlsConnector::from(Arc::new(client_config(opts)?));
+    let name = ServerName::try_from(opts.server_name.as_deref().unwrap_or(ip).to_owned())
+        .map_err(tls_error)?;
+    let stream = connector.connect(name, stream).await?;
+    Ok(tcp::attach(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::client_config;
+    use crate::config::TlsOptions;
+    use crate::dev::modbus_dev::ModbusDevError;
+
+    #[test]
+    fn missing_certificates_are_reported() {
+        let opts = TlsOptions {
+            ca_file: "/nonexistent/ca.pem".into(),
+            cert_file: "/nonexistent/client.pem".into(),
+            key_file: "/nonexistent/client.key".into(),
+            server_name: None,
+        };
+
+        assert!(matches!(client_config(&opts), Err(ModbusDevError::Tls(_))));
+    }
+}
@@ -0,0 +1,189 @@
+//! 轮询时段
+//!
+//! 按本地时间限制设备的轮询：只在允许的时段内连接与读取，维护时段内不读取，其余时间断开连接。
+//! 用于按流量计费的蜂窝链路与夜间断电的设备。
+//!
+//! 时段写作 `HH:MM-HH:MM`，可在前面加上星期或日期：`06:00-22:00`、`Mon-Fri 07:30-18:00`、
+//! `Sat,Sun 09:00-12:00`、`2026-10-20 08:00-12:00`。结束早于开始的时段跨过零点，
+//! 星期与日期指开始的那一天；结束时间可以写作 `24:00`。
+
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike, Weekday};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+#[derive(Debug, thiserror::Error)]
+#[error("无效的时段:{0}")]
+pub struct InvalidWindow(String);
+
+/// 时段适用的日子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Days {
+    Every,
+    /// 按星期，第 n 位表示周一起的第 n 天
+    Weekdays(u8),
+    Date(NaiveDate),
+}
+
+impl Days {
+    fn parse(value: &str) -> Option<Self> {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(Days::Date(date));
+        }
+        let mut mask = 0;
+        for part in value.split(',') {
+            let (first, last) = part.split_once('-').unwrap_or((part, part));
+            let first = first.trim().parse::<Weekday>().ok()?.num_days_from_monday();
+            let last = last.trim().parse::<Weekday>().ok()?.num_days_from_monday();
+            if first > last {
+                return None;
+            }
+            for day in first..=last {
+                mask |= 1 << day;
+            }
+        }
+        Some(Days::Weekdays(mask))
+    }
+
+    fn matches(self, date: NaiveDate) -> bool {
+        match self {
+            Days::Every => true,
+            Days::Weekdays(mask) => mask & (1 << date.weekday().num_days_from_monday()) != 0,
+            Days::Date(day) => day == date,
+        }
+    }
+}
+
+/// 一个时段，起止为一天中的分钟数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    days: Days,
+    start: u32,
+    end: u32,
+}
+
+/// 解析 `HH:MM`，允许 `24:00`
+fn minute_of_day(value: &str) -> Option<u32> {
+    let (hour, minute) = value.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+    let total = hour * 60 + minute;
+    (minute < 60 && total <= MINUTES_PER_DAY).then_some(total)
+}
+
+impl FromStr for TimeWindow {
+    type Err = InvalidWindow;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidWindow(value.to_owned());
+        let value = value.trim();
+        let (days, range) = match value.rsplit_once(' ') {
+            Some((days, range)) => (Days::parse(days.trim()).ok_or_else(invalid)?, range),
+            None => (Days::Every, value),
+        };
+        let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+        let (start, end) = (
+            minute_of_day(start).ok_or_else(invalid)?,
+            minute_of_day(end).ok_or_else(invalid)?,
+        );
+        if start == end || start == MINUTES_PER_DAY {
+            return Err(invalid());
+        }
+        Ok(Self { days, start, end })
+    }
+}
+
+impl TimeWindow {
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let minute = at.hour() * 60 + at.minute();
+        let date = at.date();
+        if self.start < self.end {
+            return self.days.matches(date) && (self.start..self.end).contains(&minute);
+        }
+        // 跨零点：开始当天的后半段与次日的前半段
+        (self.days.matches(date) && minute >= self.start)
+            || (minute < self.end && date.pred_opt().is_some_and(|day| self.days.matches(day)))
+    }
+}
+
+/// 设备的轮询时段
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    /// 允许轮询的时段，为空时全天允许
+    windows: Vec<TimeWindow>,
+    /// 不轮询的时段，优先于 `windows`
+    blackouts: Vec<TimeWindow>,
+}
+
+impl Schedule {
+    pub fn new(windows: &[String], blackouts: &[String]) -> Result<Self, InvalidWindow> {
+        let parse = |values: &[String]| -> Result<Vec<TimeWindow>, InvalidWindow> {
+            values.iter().map(|value| value.parse()).collect()
+        };
+        Ok(Self {
+            windows: parse(windows)?,
+            blackouts: parse(blackouts)?,
+        })
+    }
+
+    /// 未限制时段，任何时间都允许轮询
+    pub fn is_always(&self) -> bool {
+        self.windows.is_empty() && self.blackouts.is_empty()
+    }
+
+    /// 指定的本地时间是否允许轮询
+    pub fn allows(&self, at: NaiveDateTime) -> bool {
+        (self.windows.is_empty() || self.windows.iter().any(|window| window.contains(at)))
+            && !self.blackouts.iter().any(|window| window.contains(at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::{Schedule, TimeWindow};
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn windows_are_parsed() {
+        for invalid in [
+            "",
+            "06:00",
+            "06:00-06:00",
+            "25:00-26:00",
+            "06:60-07:00",
+            "Fri-Mon 06:00-07:00",
+            "Someday 06:00-07:00",
+        ] {
+            assert!(invalid.parse::<TimeWindow>().is_err(), "{invalid}");
+        }
+        let window: TimeWindow = "Mon-Wed,Fri 06:00-24:00".parse().unwrap();
+        // 2026-10-19 为周一
+        assert!(window.contains(at("2026-10-19 23:59")));
+        assert!(!window.contains(at("2026-10-22 12:00")));
+        assert!(window.contains(at("2026-10-23 06:00")));
+        assert!(!window.contains(at("2026-10-23 05:59")));
+    }
+
+    #[test]
+    fn overnight_windows_and_blackouts() {
+        let schedule = Schedule::new(
+            &["Fri 22:00-02:00".to_owned()],
+            &["2026-10-24 01:00-01:30".to_owned()],
+        )
+        .unwrap();
+        assert!(!schedule.is_always());
+        assert!(schedule.allows(at("2026-10-23 23:00")));
+        assert!(schedule.allows(at("2026-10-24 00:30")));
+        assert!(!schedule.allows(at("2026-10-24 01:10")));
+        assert!(!schedule.allows(at("2026-10-24 22:30")));
+        assert!(!schedule.allows(at("2026-10-23 00:30")));
+
+        let always = Schedule::new(&[], &[]).unwrap();
+        assert!(always.is_always() && always.allows(at("2026-10-24 01:10")));
+    }
+}
@@ -0,0 +1,96 @@
+//! 分组启动
+//!
+//! 设备可归入启动分组并声明依赖的分组，如先启动电表、再启动需要其数据的控制器。
+//! 按依赖把分组排成若干阶段，前一阶段的设备都已运行（或等待超时）后再启动下一阶段。
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use tracing::warn;
+
+/// 按依赖把分组排成启动阶段，`groups` 为分组名及其依赖的分组
+///
+/// 依赖不存在的分组时忽略该依赖；存在循环依赖的分组放在最后一个阶段同时启动
+pub(crate) fn phases(groups: &BTreeMap<String, BTreeSet<String>>) -> Vec<Vec<String>> {
+    let mut pending: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (group, deps) in groups {
+        let mut known = BTreeSet::new();
+        for dep in deps {
+            if dep == group {
+                continue;
+            }
+            if groups.contains_key(dep) {
+                known.insert(dep.as_str());
+            } else {
+                warn!("启动分组 {} 依赖的分组 {} 不存在, 已忽略", group, dep);
+            }
+        }
+        pending.insert(group, known);
+    }
+    let mut phases = Vec::new();
+    while !pending.is_empty() {
+        let ready: Vec<&str> = pending
+            .iter()
+            .filter(|(_, deps)| deps.is_empty())
+            .map(|(group, _)| *group)
+            .collect();
+        if ready.is_empty() {
+            let cyclic: Vec<String> = pending.keys().map(|group| (*group).to_owned()).collect();
+            warn!("启动分组存在循环依赖: {:?}, 将同时启动", cyclic);
+            phases.push(cyclic);
+            break;
+        }
+        for group in &ready {
+            pending.remove(group);
+        }
+        for deps in pending.values_mut() {
+            deps.retain(|dep| !ready.contains(dep));
+        }
+        phases.push(ready.into_iter().map(str::to_owned).collect());
+    }
+    phases
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::phases;
+
+    fn groups(deps: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        deps.iter()
+            .map(|(group, deps)| {
+                (
+                    (*group).to_owned(),
+                    deps.iter().map(|dep| (*dep).to_owned()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_start_after_their_dependencies() {
+        let groups = groups(&[
+            ("controller", &["meter", "pcs"]),
+            ("meter", &[]),
+            ("pcs", &["meter", "missing"]),
+            ("bms", &[]),
+        ]);
+        assert_eq!(
+            phases(&groups),
+            vec![
+                vec!["bms".to_owned(), "meter".to_owned()],
+                vec!["pcs".to_owned()],
+                vec!["controller".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn cyclic_groups_start_last() {
+        let groups = groups(&[("a", &["b"]), ("b", &["a"]), ("c", &[])]);
+        assert_eq!(
+            phases(&groups),
+            vec![vec!["c".to_owned()], vec!["a".to_owned(), "b".to_owned()]]
+        );
+    }
+}
@@ -4,6 +4,8 @@ use std::sync::atomic::{AtomicU8, Ordering};
 use tracing::info;
 
 use crate::dev::LifecycleState;
+use crate::dev::diagnostics::unix_millis;
+use crate::dev::events::{self, LifecycleEvent};
 
 /// 设备生命周期状态的共享原子封装。
 ///
@@ -23,21 +25,45 @@ impl SharedState {
         self.0.load(Ordering::Acquire).into()
     }
 
-    /// 仅当当前状态等于 `from` 时，原子地更新为 `to`。
+    /// 仅当当前状态等于 `from` 时，原子地更新为 `to`，成功时记录状态迁移日志、广播生命周期事件。
     ///
     /// 返回值表示这次状态迁移是否成功。
-    pub fn cas(&self, from: LifecycleState, to: LifecycleState) -> bool {
-        self.0
+    pub fn cas(&self, id: &str, from: LifecycleState, to: LifecycleState) -> bool {
+        let ok = self
+            .0
             .compare_exchange(from as u8, to as u8, Ordering::AcqRel, Ordering::Acquire)
-            .is_ok()
+            .is_ok();
+        if ok && from != to {
+            Self::emit(id, from, to, None);
+        }
+        ok
     }
 
-    /// 直接写入目标状态，并记录状态迁移日志。
+    /// 直接写入目标状态，并记录状态迁移日志、广播生命周期事件。
     pub fn store(&self, id: &str, to: LifecycleState) {
-        let from = self.load();
-        self.0.store(to as u8, Ordering::Release);
+        self.transition(id, to, None);
+    }
+
+    /// 同 [`store`](Self::store)，事件中附带状态变化的原因。
+    pub fn store_with_reason(&self, id: &str, to: LifecycleState, reason: impl Into<String>) {
+        self.transition(id, to, Some(reason.into()));
+    }
+
+    fn transition(&self, id: &str, to: LifecycleState, reason: Option<String>) {
+        let from: LifecycleState = self.0.swap(to as u8, Ordering::AcqRel).into();
         if from != to {
-            info!("[{}]{} -> {}", id, from, to);
+            Self::emit(id, from, to, reason);
         }
     }
+
+    fn emit(id: &str, from: LifecycleState, to: LifecycleState, reason: Option<String>) {
+        info!("[{}]{} -> {}", id, from, to);
+        events::emit(LifecycleEvent {
+            device: id.to_owned(),
+            from,
+            to,
+            timestamp_ms: unix_millis(),
+            reason,
+        });
+    }
 }
@@ -0,0 +1,85 @@
+//! 设备监督
+//!
+//! 设备的后台任务未经停止请求就结束（panic 或意外返回）时，按设备配置的重启策略重新启动，
+//! 两次重启之间按指数退避等待，重启次数记入设备状态。停止或移除设备时监督任务随之取消。
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use tokio::time;
+use tracing::{error, warn};
+
+use crate::config::RestartPolicy;
+use crate::dev::DeviceExit;
+use crate::dev::backoff::Backoff;
+use crate::dev::handle::DeviceHandle;
+
+/// 检查设备任务是否结束的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 启动设备，任务意外结束后按 `policy` 重启，每次重启计入设备的重启次数
+pub(crate) async fn supervise(device: DeviceHandle, policy: RestartPolicy) {
+    let restarts = device.restart_counter();
+    let mut backoff = Backoff::new(
+        Duration::from_millis(policy.backoff.unwrap_or(1000)),
+        Duration::from_millis(policy.max_backoff.unwrap_or(60_000)),
+    );
+    loop {
+        if let Err(err) = device.start().await {
+            error!("{}", err);
+        }
+        let exit = wait_exit(&device).await;
+        let id = device.id();
+        let count = restarts.load(Ordering::Relaxed);
+        if !policy.should_restart(exit, count) {
+            error!(
+                "[{}] 设备任务已结束({:?}), 已重启{}次, 不再重启",
+                id, exit, count
+            );
+            return;
+        }
+        let delay = backoff.next_delay();
+        warn!(
+            "[{}] 设备任务意外结束({:?}), {}ms 后第{}次重启",
+            id,
+            exit,
+            delay.as_millis(),
+            count + 1
+        );
+        time::sleep(delay).await;
+        restarts.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// 等待设备任务意外结束，返回结束方式
+async fn wait_exit(device: &DeviceHandle) -> DeviceExit {
+    loop {
+        time::sleep(CHECK_INTERVAL).await;
+        if let Some(exit) = device.exit().await {
+            return exit;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{RestartMode, RestartPolicy};
+    use crate::dev::DeviceExit;
+
+    #[test]
+    fn restart_policy_limits_retries() {
+        let on_failure = RestartPolicy {
+            max_retries: Some(2),
+            ..RestartPolicy::default()
+        };
+        assert!(on_failure.should_restart(DeviceExit::Failed, 1));
+        assert!(!on_failure.should_restart(DeviceExit::Failed, 2));
+        assert!(!on_failure.should_restart(DeviceExit::Completed, 0));
+
+        let always = RestartPolicy {
+            mode: RestartMode::Always,
+            ..RestartPolicy::default()
+        };
+        assert!(always.should_restart(DeviceExit::Completed, 100));
+    }
+}
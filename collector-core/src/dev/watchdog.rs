@@ -0,0 +1,193 @@
+//! 设备看门狗
+//!
+//! 设备停留在连接中/失败状态超过设定时间，或数据超过若干个采集周期未更新时，
+//! 强制停止并重新启动设备，同时输出告警日志。停止或移除设备时看门狗随之取消。
+//! 暂停（含轮询时段外）期间不检查数据更新，恢复后重新计时。
+
+use std::time::Duration;
+
+use tokio::time::{self, Instant};
+use tracing::error;
+
+use crate::dev::LifecycleState;
+use crate::dev::diagnostics::{device_diagnostics, unix_millis};
+use crate::dev::handle::DeviceHandle;
+
+/// 检查设备状态的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 看门狗的触发条件
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Limits {
+    /// 连接中/失败状态的最长持续时间
+    pub(crate) stuck: Option<Duration>,
+    /// 数据未更新的最长时间
+    pub(crate) stale: Option<Duration>,
+}
+
+impl Limits {
+    /// 未配置任何条件时不需要看门狗
+    pub(crate) fn new(watchdog: crate::config::Watchdog, interval: Option<u64>) -> Option<Self> {
+        let limits = Self {
+            stuck: watchdog.stuck_timeout.map(Duration::from_millis),
+            stale: watchdog
+                .stale_intervals
+                .zip(interval)
+                .map(|(n, interval)| Duration::from_millis(interval.saturating_mul(u64::from(n)))),
+        };
+        (limits.stuck.is_some() || limits.stale.is_some()).then_some(limits)
+    }
+}
+
+/// 看门狗对一个设备的观察
+struct Observer {
+    limits: Limits,
+    /// 进入连接中/失败状态的时刻
+    stuck_since: Option<Instant>,
+    /// 启动（或重启）的时刻，尚未读到数据时从该时刻算起
+    started: Instant,
+}
+
+impl Observer {
+    fn new(limits: Limits, now: Instant) -> Self {
+        Self {
+            limits,
+            stuck_since: None,
+            started: now,
+        }
+    }
+
+    /// 返回需要重启的原因，`last_poll` 为最近一次成功读取距今的时间
+    fn check(
+        &mut self,
+        state: LifecycleState,
+        last_poll: Option<Duration>,
+        now: Instant,
+    ) -> Option<String> {
+        if matches!(state, LifecycleState::Connecting | LifecycleState::Failed) {
+            let since = *self.stuck_since.get_or_insert(now);
+            if let Some(stuck) = self.limits.stuck
+                && now.duration_since(since) >= stuck
+            {
+                return Some(format!("{}状态持续超过{}ms", state, stuck.as_millis()));
+            }
+        } else {
+            self.stuck_since = None;
+        }
+        if state == LifecycleState::Paused {
+            self.started = now;
+            return None;
+        }
+        let stale = self.limits.stale?;
+        if state != LifecycleState::Running && state != LifecycleState::Connected {
+            return None;
+        }
+        let age = last_poll
+            .unwrap_or(Duration::MAX)
+            .min(now.duration_since(self.started));
+        (age >= stale).then(|| format!("数据超过{}ms未更新", stale.as_millis()))
+    }
+}
+
+/// 观察设备，满足触发条件时强制重启
+pub(crate) async fn watch(device: DeviceHandle, limits: Limits) {
+    let id = device.id();
+    let mut observer = Observer::new(limits, Instant::now());
+    loop {
+        time::sleep(CHECK_INTERVAL).await;
+        let state = device.state().await;
+        let last_poll = device_diagnostics(id)
+            .and_then(|diag| diag.last_poll_ms)
+            .map(|ms| Duration::from_millis(unix_millis().saturating_sub(ms)));
+        let Some(reason) = observer.check(state, last_poll, Instant::now()) else {
+            continue;
+        };
+        error!("[{}] 看门狗: {}, 强制重启设备", id, reason);
+        if let Err(err) = device.stop().await {
+            error!("{}", err);
+        }
+        if let Err(err) = device.start().await {
+            error!("{}", err);
+        }
+        observer = Observer::new(limits, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{Limits, Observer};
+    use crate::dev::LifecycleState;
+
+    #[test]
+    fn stuck_and_stale_devices_are_detected() {
+        let limits = Limits {
+            stuck: Some(Duration::from_secs(10)),
+            stale: Some(Duration::from_secs(30)),
+        };
+        let start = Instant::now();
+        let mut observer = Observer::new(limits, start);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(
+            observer
+                .check(LifecycleState::Connecting, None, at(1))
+                .is_none()
+        );
+        assert!(
+            observer
+                .check(LifecycleState::Failed, None, at(10))
+                .is_none()
+        );
+        assert!(
+            observer
+                .check(LifecycleState::Failed, None, at(11))
+                .is_some()
+        );
+
+        // 恢复连接后重新计时，数据按最近一次读取判断是否过期
+        assert!(
+            observer
+                .check(LifecycleState::Running, None, at(12))
+                .is_none()
+        );
+        assert!(
+            observer
+                .check(LifecycleState::Connecting, None, at(13))
+                .is_none()
+        );
+        let fresh = Some(Duration::from_secs(2));
+        assert!(
+            observer
+                .check(LifecycleState::Running, fresh, at(40))
+                .is_none()
+        );
+        let stale = Some(Duration::from_secs(31));
+        assert!(
+            observer
+                .check(LifecycleState::Running, stale, at(40))
+                .is_some()
+        );
+
+        // 暂停结束后从恢复时起计时
+        assert!(
+            observer
+                .check(LifecycleState::Paused, stale, at(100))
+                .is_none()
+        );
+        let paused = Some(Duration::from_secs(90));
+        assert!(
+            observer
+                .check(LifecycleState::Running, paused, at(120))
+                .is_none()
+        );
+        assert!(
+            observer
+                .check(LifecycleState::Running, paused, at(131))
+                .is_some()
+        );
+    }
+}
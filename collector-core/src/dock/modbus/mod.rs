@@ -1,4 +1,4 @@
 mod server;
-mod tables;
+pub(crate) mod tables;
 
 pub use server::{ModbusServer, ModbusServerError};
@@ -199,6 +199,7 @@ impl ModbusServer {
                                             RegValue::Bool(b) => new_tbl.write_bool(cfg.register_type, cfg.register_address, b),
                                             RegValue::Word(w) => new_tbl.write_u16(cfg.register_type, cfg.register_address, w),
                                             RegValue::DWord(dw) => new_tbl.write_u16_pair(cfg.register_type, cfg.register_address, dw),
+                                            RegValue::QWord(qw) => new_tbl.write_u16_quad(cfg.register_type, cfg.register_address, qw),
                                         }
                                     }
                                 }
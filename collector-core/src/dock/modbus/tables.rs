@@ -51,6 +51,23 @@ impl RegisterTable {
         }
     }
 
+    pub fn write_u16_quad(&mut self, reg_type: RegisterType, addr: u16, vals: [u16; 4]) {
+        for (offset, val) in vals.into_iter().enumerate() {
+            if let Some(next) = addr.checked_add(offset as u16) {
+                self.write_u16(reg_type, next, val);
+            }
+        }
+    }
+
+    pub fn read_u16(&self, reg_type: RegisterType, addr: u16) -> u16 {
+        let registers = match reg_type {
+            RegisterType::HoldingRegisters => &self.holding_registers,
+            RegisterType::InputRegisters => &self.input_registers,
+            _ => return 0,
+        };
+        registers.get(&addr).copied().unwrap_or(0)
+    }
+
     pub fn read_coils(&self, addr: u16, cnt: u16) -> Vec<bool> {
         (addr..addr.saturating_add(cnt))
             .map(|a| self.coils.get(&a).copied().unwrap_or(false))
@@ -1,5 +1,7 @@
 pub mod center;
 pub mod config;
+#[cfg(unix)]
+pub mod control;
 pub mod core;
 pub mod dev;
 pub mod dock;
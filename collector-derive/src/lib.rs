@@ -277,6 +277,10 @@ fn infer_data_type(ty: &Type) -> Option<Ident> {
                 "i16" => Some(Ident::new("I16", segment.ident.span())),
                 "u32" => Some(Ident::new("U32", segment.ident.span())),
                 "i32" => Some(Ident::new("I32", segment.ident.span())),
+                "f32" => Some(Ident::new("F32", segment.ident.span())),
+                "f64" => Some(Ident::new("F64", segment.ident.span())),
+                "u64" => Some(Ident::new("U64", segment.ident.span())),
+                "i64" => Some(Ident::new("I64", segment.ident.span())),
                 _ => None,
             }
         }),
@@ -286,7 +290,8 @@ fn infer_data_type(ty: &Type) -> Option<Ident> {
 
 fn default_quantity(data_type: &Ident) -> u16 {
     match data_type.to_string().as_str() {
-        "U32" | "I32" => 2,
+        "U32" | "I32" | "F32" => 2,
+        "F64" | "U64" | "I64" => 4,
         _ => 1,
     }
 }
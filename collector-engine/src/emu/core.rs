@@ -8,7 +8,7 @@ use crate::{
     strategy::{Schedule, Strategy},
 };
 use collector_core::{
-    center::{DataCenterError, SharedPointCenter},
+    center::{self, DataCenterError, DownlinkCommand, DownlinkReceiver, SharedPointCenter},
     core::point::DownDataPoint,
     dev::{DeviceError, Executable, Identifiable, Lifecycle, LifecycleState, state::SharedState},
     utils::database::get_database,
@@ -61,7 +61,7 @@ impl Emu {
     }
 
     fn cas_state(&self, from: LifecycleState, to: LifecycleState) -> bool {
-        self.state.cas(from, to)
+        self.state.cas(&self.id, from, to)
     }
 
     fn store_state(&self, to: LifecycleState) {
@@ -86,7 +86,7 @@ impl Lifecycle for Emu {
         if !ok {
             return Ok(());
         }
-        let (tx, rx) = tokio::sync::mpsc::channel::<Vec<DownDataPoint>>(16);
+        let (tx, rx) = tokio::sync::mpsc::channel::<DownlinkCommand>(16);
         //将设备注册到消息中心
         match self.center.attach_downlink(&self.id, tx.clone()) {
             Ok(()) => {}
@@ -186,7 +186,7 @@ async fn wait_for_stop(stop_rx: &mut watch::Receiver<bool>) {
 }
 
 async fn run_downlink(
-    mut rx: tokio::sync::mpsc::Receiver<Vec<DownDataPoint>>,
+    mut rx: DownlinkReceiver,
     commands: Arc<AsyncMutex<Vec<Box<dyn Command>>>>,
     strategies: Arc<AsyncMutex<Vec<Box<dyn Strategy>>>>,
     mut stop_rx: watch::Receiver<bool>,
@@ -195,24 +195,41 @@ async fn run_downlink(
         tokio::select! {
             _ = wait_for_stop(&mut stop_rx) => break,
             msg = rx.recv() => {
-                if let Some(entries) = msg {
-                    down(&entries, commands.clone(), strategies.clone()).await;
+                if let Some(mut cmd) = msg {
+                    if let Some(read) = cmd.read.take() {
+                        read.reply(Err(DataCenterError::ReadFailed("模拟设备不支持按需读取".into())));
+                        continue;
+                    }
+                    let atomic = cmd.atomic;
+                    let (entries, ack) = cmd.into_parts();
+                    // 原子下发：各处理器逐个处理点位，出错时无法整批回滚，只接受单个点位
+                    if atomic && entries.len() > 1 {
+                        let reason = format!("原子下发只能包含一个点位, 当前{}个", entries.len());
+                        tracing::warn!("原子下发被拒绝: {}", reason);
+                        center::reply(ack, Err(DataCenterError::Rejected(reason)));
+                        continue;
+                    }
+                    let result = down(&entries, commands.clone(), strategies.clone()).await;
+                    center::reply(ack, result.map_err(DataCenterError::WriteFailed));
                 }
             }
         }
     }
 }
 
+/// 依次交给各命令与策略处理下行点位，返回各处理器的出错原因
 async fn down(
     points: &[DownDataPoint],
     commands: Arc<AsyncMutex<Vec<Box<dyn Command>>>>,
     strategies: Arc<AsyncMutex<Vec<Box<dyn Strategy>>>>,
-) {
+) -> Result<(), String> {
+    let mut errors = Vec::new();
     {
         let commands = commands.lock().await;
         for cmd in commands.iter() {
             if let Err(e) = cmd.down(points).await {
                 tracing::error!("[{}] 处理下行点位出错: {}", cmd.name(), e);
+                errors.push(format!("{}: {}", cmd.name(), e));
             }
         }
     }
@@ -222,9 +239,15 @@ async fn down(
         for strategy in strategies.iter() {
             if let Err(e) = strategy.down(points).await {
                 tracing::error!("[{}] 处理下行点位出错: {}", strategy.name(), e);
+                errors.push(format!("{}: {}", strategy.name(), e));
             }
         }
     }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
 }
 
 async fn run_strategy(
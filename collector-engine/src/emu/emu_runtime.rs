@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use collector_core::{
     center::SharedPointCenter,
-    core::point::{DataPoint, DownDataPoint, Val},
+    core::point::{DataPoint, DownDataPoint, Quality, Val},
     runtime::{
         core::get_runtime,
         emu::{EmuPermission, OperationMode},
@@ -100,6 +100,7 @@ fn operation_mode(data: u8) -> DataPoint {
         bits: None,
         words: None,
         unit: None,
+        quality: Quality::Good,
     }
 }
 
@@ -113,6 +114,7 @@ fn permission(data: u8) -> DataPoint {
         bits: None,
         words: None,
         unit: None,
+        quality: Quality::Good,
     }
 }
 
@@ -126,6 +128,7 @@ fn health_status(data: u8) -> DataPoint {
         bits: None,
         words: None,
         unit: None,
+        quality: Quality::Good,
     }
 }
 
@@ -139,6 +142,7 @@ fn charge_soc_limit(data: f64) -> DataPoint {
         bits: None,
         words: None,
         unit: None,
+        quality: Quality::Good,
     }
 }
 
@@ -152,5 +156,6 @@ fn discharge_soc_limit(data: f64) -> DataPoint {
         bits: None,
         words: None,
         unit: None,
+        quality: Quality::Good,
     }
 }
@@ -3,7 +3,7 @@ use std::time::Duration;
 use chrono::{Datelike, Timelike};
 use collector_core::{
     center::SharedPointCenter,
-    core::point::{DataPoint, DownDataPoint, PointRef, Val},
+    core::point::{DataPoint, DownDataPoint, PointRef, Quality, Val},
     down,
     runtime::core::get_runtime,
 };
@@ -194,6 +194,7 @@ impl PlannedCurve {
             bits: None,
             words: None,
             unit: None,
+            quality: Quality::Good,
         }
     }
 }
@@ -7,7 +7,7 @@ use crate::{
 use async_trait::async_trait;
 use collector_core::{
     center::{DataCenterError, SharedPointCenter},
-    core::point::{DataPoint, DownDataPoint, PointRef, Val},
+    core::point::{DataPoint, DownDataPoint, PointRef, Quality, Val},
     down,
 };
 use parking_lot::RwLock;
@@ -79,6 +79,7 @@ impl Tms {
                 bits: None,
                 words: None,
                 unit: None,
+                quality: Quality::Good,
             })),
         }
     }
@@ -20,6 +20,7 @@ fn val_to_lua(lua: &Lua, val: &Val) -> mlua::Result<Value> {
         Val::U16(v) => Ok(Value::Number(*v as f64)),
         Val::U32(v) => Ok(Value::Number(*v as f64)),
         Val::F64(v) => Ok(Value::Number(*v)),
+        Val::Str(v) => Ok(Value::String(lua.create_string(v)?)),
         Val::List(items) => {
             let t = lua.create_table()?;
             for (i, item) in items.iter().enumerate() {